@@ -0,0 +1,170 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::json;
+use structopt::clap;
+
+/// How the errors of the CLI are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => eyre::bail!("invalid error format: {}", other),
+        }
+    }
+}
+
+impl ErrorFormat {
+    /// Reads the error format from the raw arguments, before they are parsed,
+    /// so that the errors of the arguments parsing are also formatted.
+    pub fn from_args<I: IntoIterator<Item = OsString>>(args: I) -> ErrorFormat {
+        let args: Vec<_> = args.into_iter().filter_map(|arg| arg.into_string().ok()).collect();
+        args.iter()
+            .enumerate()
+            .find_map(|(i, arg)| match arg.strip_prefix("--error-format=") {
+                Some(format) => format.parse().ok(),
+                None if arg == "--error-format" => args.get(i + 1)?.parse().ok(),
+                None => None,
+            })
+            .unwrap_or(ErrorFormat::Text)
+    }
+}
+
+/// The classes of errors, each one exits the process with its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The arguments of the command line are invalid.
+    Arguments,
+    /// The request is invalid for the index, reported by milli as a user error.
+    User,
+    /// The documents or the files given to the command can't be parsed.
+    Parse,
+    /// A file, a network resource or the index can't be read or written.
+    Io,
+    /// The index is corrupted or milli failed unexpectedly.
+    Internal,
+    Other,
+}
+
+impl ErrorClass {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorClass::Other => 1,
+            ErrorClass::Arguments => 2,
+            ErrorClass::User => 3,
+            ErrorClass::Parse => 4,
+            ErrorClass::Io => 5,
+            ErrorClass::Internal => 6,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ErrorClass::Arguments => "arguments",
+            ErrorClass::User => "user",
+            ErrorClass::Parse => "parse",
+            ErrorClass::Io => "io",
+            ErrorClass::Internal => "internal",
+            ErrorClass::Other => "other",
+        }
+    }
+}
+
+/// Returns the class of the error and a code describing it more precisely,
+/// the first error of the chain recognized gives them.
+fn classify(report: &eyre::Report) -> (ErrorClass, String) {
+    for cause in report.chain() {
+        if let Some(error) = cause.downcast_ref::<milli::Error>() {
+            return match error {
+                milli::Error::UserError(error) => (ErrorClass::User, variant_code(error)),
+                milli::Error::InternalError(error) => (ErrorClass::Internal, variant_code(error)),
+                milli::Error::IoError(error) => (ErrorClass::Io, variant_code(&error.kind())),
+            };
+        } else if let Some(error) = cause.downcast_ref::<milli::documents::Error>() {
+            return (ErrorClass::Parse, variant_code(error));
+        } else if cause.is::<serde_json::Error>() {
+            return (ErrorClass::Parse, String::from("invalid_json"));
+        } else if cause.is::<csv::Error>() {
+            return (ErrorClass::Parse, String::from("invalid_csv"));
+        } else if let Some(error) = cause.downcast_ref::<std::io::Error>() {
+            return (ErrorClass::Io, variant_code(&error.kind()));
+        } else if cause.is::<reqwest::Error>() {
+            return (ErrorClass::Io, String::from("http_error"));
+        } else if let Some(error) = cause.downcast_ref::<heed::Error>() {
+            return (ErrorClass::Internal, variant_code(error));
+        }
+    }
+    (ErrorClass::Other, String::from("unexpected_error"))
+}
+
+/// Returns the name of the enum variant of the error in snake case,
+/// read from its debug representation, `InvalidFilter(..)` gives `invalid_filter`.
+fn variant_code(error: &dyn fmt::Debug) -> String {
+    let debug = format!("{:?}", error);
+    let name = debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default();
+
+    let mut code = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            code.push('_');
+        }
+        code.extend(c.to_lowercase());
+    }
+    code
+}
+
+/// Writes the error to stderr in the given format and exits with the code of its class.
+pub fn exit_with_report(report: eyre::Report, format: ErrorFormat) -> ! {
+    let (class, code) = classify(&report);
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {:?}", report),
+        ErrorFormat::Json => {
+            let context: Vec<_> = report.chain().skip(1).map(|cause| cause.to_string()).collect();
+            let error = json!({
+                "class": class.name(),
+                "code": code,
+                "message": report.to_string(),
+                "context": context,
+            });
+            eprintln!("{}", error);
+        }
+    }
+    std::process::exit(class.exit_code())
+}
+
+/// Writes the error of the arguments parsing to stderr in the given format and exits,
+/// the help and version messages are printed as is.
+pub fn exit_with_clap_error(error: clap::Error, format: ErrorFormat) -> ! {
+    use clap::ErrorKind::{HelpDisplayed, VersionDisplayed};
+
+    if matches!(error.kind, HelpDisplayed | VersionDisplayed) {
+        error.exit()
+    }
+
+    let class = ErrorClass::Arguments;
+    match format {
+        ErrorFormat::Text => eprintln!("{}", error.message),
+        ErrorFormat::Json => {
+            let message = error.message.lines().next().unwrap_or_default();
+            let message = message.trim_start_matches("error: ");
+            let error = json!({
+                "class": class.name(),
+                "code": variant_code(&error.kind),
+                "message": message,
+                "context": error.info.unwrap_or_default(),
+            });
+            eprintln!("{}", error);
+        }
+    }
+    std::process::exit(class.exit_code())
+}
@@ -1,21 +1,34 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
-use std::io::{stdin, BufRead, BufReader, Cursor, Read, Write};
+use std::io::{stdin, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use byte_unit::Byte;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::{cursor, terminal};
 use eyre::Result;
+use flate2::read::GzDecoder;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use milli::documents::{dump_documents, DumpCompression};
+use milli::facet::FacetNormalization;
 use milli::update::UpdateIndexingStep::{
     ComputeIdsAndMergeDocuments, IndexDocuments, MergeDataIntoFinalDatabase, RemapDocumentAddition,
 };
-use milli::update::{IndexDocumentsConfig, IndexDocumentsMethod, IndexerConfig};
-use milli::Index;
+use milli::update::{
+    ArrayMergePolicy, DocumentIdValidation, IndexDocumentsConfig, IndexDocumentsMethod,
+    IndexerConfig, Setting,
+};
+use milli::{Index, QueryRule, StemmingLanguage, TermsDroppingOrder, TypoTolerance, WarmupLevel};
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use structopt::StructOpt;
 
+use crate::error::{exit_with_clap_error, exit_with_report, ErrorFormat};
+
+mod error;
+
 #[cfg(target_os = "linux")]
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
@@ -30,6 +43,9 @@ struct Cli {
     /// Verbose mode (-v, -vv, -vvv, etc.)
     #[structopt(short, long, parse(from_occurrences))]
     verbose: usize,
+    /// Write the errors as text or as JSON objects with an error code and their context.
+    #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+    error_format: ErrorFormat,
     #[structopt(subcommand)]
     subcommand: Command,
 }
@@ -45,6 +61,31 @@ enum Command {
         #[structopt(subcommand)]
         cmd: Settings,
     },
+    /// Dump the indexed words with their number of documents, one per line.
+    Words(Words),
+    Analyze {
+        #[structopt(subcommand)]
+        cmd: Analyze,
+    },
+    /// Regenerate the words, facets, prefixes and geo databases from the stored documents.
+    Reindex,
+    /// Remove the deleted documents left in the words and facets databases.
+    Vacuum,
+    /// Read the databases used by the searches to pull them into the page cache.
+    Warmup {
+        /// The databases to read: "fsts", "search" or "full".
+        #[structopt(long, default_value = "search", parse(try_from_str = parse_warmup_level))]
+        level: WarmupLevel,
+    },
+}
+
+fn parse_warmup_level(level: &str) -> Result<WarmupLevel> {
+    match level {
+        "fsts" => Ok(WarmupLevel::Fsts),
+        "search" => Ok(WarmupLevel::Search),
+        "full" => Ok(WarmupLevel::Full),
+        other => eyre::bail!("invalid warmup level: {}", other),
+    }
 }
 
 impl Performer for Command {
@@ -53,6 +94,31 @@ impl Performer for Command {
             Command::Documents { cmd } => cmd.perform(index),
             Command::Search(cmd) => cmd.perform(index),
             Command::Settings { cmd } => cmd.perform(index),
+            Command::Words(cmd) => cmd.perform(index),
+            Command::Analyze { cmd } => cmd.perform(index),
+            Command::Reindex => reindex(index),
+            Command::Vacuum => {
+                let mut txn = index.write_txn()?;
+                let result = milli::update::Vacuum::new(&mut txn, &index).execute()?;
+                txn.commit()?;
+                eprintln!(
+                    "removed {} orphan documents, cleaned {} entries and deleted {} entries",
+                    result.orphan_documents, result.cleaned_entries, result.deleted_entries,
+                );
+                Ok(())
+            }
+            Command::Warmup { level } => {
+                let txn = index.read_txn()?;
+                let before = Instant::now();
+                let stats = index.warmup(&txn, level)?;
+                eprintln!(
+                    "read {} entries ({}) in {:.02?}",
+                    stats.entries,
+                    Byte::from_bytes(stats.bytes as u128).get_appropriate_unit(true),
+                    before.elapsed(),
+                );
+                Ok(())
+            }
         }
     }
 }
@@ -61,6 +127,14 @@ impl Performer for Command {
 enum Settings {
     Update(SettingsUpdate),
     Show,
+    /// Write the settings of the index to a JSON file.
+    Export {
+        path: PathBuf,
+    },
+    /// Apply the settings found in a JSON file to the index.
+    Import {
+        path: PathBuf,
+    },
 }
 
 impl Settings {
@@ -119,20 +193,423 @@ impl Performer for Settings {
         match self {
             Settings::Update(update) => update.perform(index),
             Settings::Show => self.show(index),
+            Settings::Export { path } => {
+                let txn = index.read_txn()?;
+                let snapshot = SettingsSnapshot::from_index(&index, &txn)?;
+                let file = File::create(path)?;
+                serde_json::to_writer_pretty(file, &snapshot)?;
+                Ok(())
+            }
+            Settings::Import { path } => {
+                let file = File::open(path)?;
+                let snapshot: SettingsSnapshot = serde_json::from_reader(BufReader::new(file))?;
+                snapshot.apply(index)
+            }
+        }
+    }
+}
+
+/// A serializable copy of every setting of an index, settings that are not present in the
+/// snapshot are left untouched when it is applied and `null` ones are reset.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+struct SettingsSnapshot {
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    displayed_attributes: Setting<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    searchable_attributes: Setting<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    filterable_attributes: Setting<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    sortable_attributes: Setting<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    criteria: Setting<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    stop_words: Setting<BTreeSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    distinct_attribute: Setting<String>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    synonyms: Setting<HashMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    document_id_validation: Setting<DocumentIdValidation>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    terms_dropping_order: Setting<TermsDroppingOrder>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    typo_tolerance: Setting<TypoTolerance>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    stemming_language: Setting<StemmingLanguage>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    decompounding_dictionary: Setting<BTreeSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    diacritics_sensitive: Setting<bool>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    case_sensitive: Setting<bool>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    hierarchical_facets: Setting<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    date_fields: Setting<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    facet_normalization: Setting<HashMap<String, FacetNormalization>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    query_rules: Setting<Vec<QueryRule>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    numbers_normalization: Setting<bool>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    field_languages: Setting<HashMap<String, StemmingLanguage>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    array_merge_policies: Setting<HashMap<String, ArrayMergePolicy>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    exact_attributes: Setting<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    exact_words: Setting<BTreeSet<String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    primary_key: Setting<String>,
+}
+
+impl SettingsSnapshot {
+    fn from_index(index: &Index, txn: &heed::RoTxn) -> Result<Self> {
+        fn or_reset<T>(value: Option<T>) -> Setting<T> {
+            value.map_or(Setting::Reset, Setting::Set)
+        }
+
+        let displayed_attributes = index
+            .displayed_fields(txn)?
+            .map(|fields| fields.into_iter().map(String::from).collect());
+        let searchable_attributes = index
+            .searchable_fields(txn)?
+            .map(|fields| fields.into_iter().map(String::from).collect());
+        let stop_words = index
+            .stop_words(txn)?
+            .map(|stop_words| -> Result<BTreeSet<_>> {
+                Ok(stop_words.stream().into_strs()?.into_iter().collect())
+            })
+            .transpose()?;
+        // in milli each word in the synonyms map were split on their separator. Since we lost
+        // this information we are going to put space between words.
+        let synonyms: HashMap<_, Vec<_>> = index
+            .synonyms(txn)?
+            .iter()
+            .map(|(key, values)| {
+                (key.join(" "), values.iter().map(|value| value.join(" ")).collect())
+            })
+            .collect();
+        let decompounding_dictionary = index
+            .decompounding_dictionary(txn)?
+            .map(|words| -> Result<BTreeSet<_>> {
+                Ok(words.stream().into_strs()?.into_iter().collect())
+            })
+            .transpose()?;
+        let exact_words = index
+            .exact_words(txn)?
+            .map(|words| -> Result<BTreeSet<_>> {
+                Ok(words.stream().into_strs()?.into_iter().collect())
+            })
+            .transpose()?;
+
+        Ok(SettingsSnapshot {
+            displayed_attributes: or_reset(displayed_attributes),
+            searchable_attributes: or_reset(searchable_attributes),
+            filterable_attributes: Setting::Set(index.filterable_fields(txn)?),
+            sortable_attributes: Setting::Set(index.sortable_fields(txn)?),
+            criteria: Setting::Set(
+                index.criteria(txn)?.into_iter().map(|c| c.to_string()).collect(),
+            ),
+            stop_words: or_reset(stop_words),
+            distinct_attribute: or_reset(index.distinct_field(txn)?.map(String::from)),
+            synonyms: Setting::Set(synonyms),
+            document_id_validation: Setting::Set(index.document_id_validation(txn)?),
+            terms_dropping_order: Setting::Set(index.terms_dropping_order(txn)?),
+            typo_tolerance: Setting::Set(index.typo_tolerance(txn)?),
+            stemming_language: or_reset(index.stemming_language(txn)?),
+            decompounding_dictionary: or_reset(decompounding_dictionary),
+            diacritics_sensitive: Setting::Set(index.diacritics_sensitive(txn)?),
+            case_sensitive: Setting::Set(index.case_sensitive(txn)?),
+            hierarchical_facets: Setting::Set(index.hierarchical_facets(txn)?),
+            date_fields: Setting::Set(index.date_fields(txn)?),
+            facet_normalization: Setting::Set(index.facet_normalization(txn)?),
+            query_rules: Setting::Set(index.query_rules(txn)?),
+            numbers_normalization: Setting::Set(index.numbers_normalization(txn)?),
+            field_languages: Setting::Set(index.field_languages(txn)?),
+            array_merge_policies: Setting::Set(index.array_merge_policies(txn)?),
+            exact_attributes: Setting::Set(index.exact_attributes(txn)?),
+            exact_words: or_reset(exact_words),
+            primary_key: or_reset(index.primary_key(txn)?.map(String::from)),
+        })
+    }
+
+    fn apply(self, index: Index) -> Result<()> {
+        let mut txn = index.write_txn()?;
+        let config = IndexerConfig { log_every_n: Some(100), ..Default::default() };
+
+        // The primary key can't be changed once documents are indexed, we only
+        // send it when it differs from the current one to get a meaningful error.
+        let current_primary_key = index.primary_key(&txn)?.map(String::from);
+        let primary_key = match self.primary_key {
+            Setting::Set(primary_key) if Some(&primary_key) == current_primary_key.as_ref() => {
+                Setting::NotSet
+            }
+            Setting::Reset if current_primary_key.is_none() => Setting::NotSet,
+            otherwise => otherwise,
+        };
+
+        let mut update = milli::update::Settings::new(&mut txn, &index, &config);
+
+        match self.displayed_attributes {
+            Setting::Set(fields) => update.set_displayed_fields(fields),
+            Setting::Reset => update.reset_displayed_fields(),
+            Setting::NotSet => (),
+        }
+        match self.searchable_attributes {
+            Setting::Set(fields) => update.set_searchable_fields(fields),
+            Setting::Reset => update.reset_searchable_fields(),
+            Setting::NotSet => (),
+        }
+        match self.filterable_attributes {
+            Setting::Set(fields) => update.set_filterable_fields(fields),
+            Setting::Reset => update.reset_filterable_fields(),
+            Setting::NotSet => (),
+        }
+        match self.sortable_attributes {
+            Setting::Set(fields) => update.set_sortable_fields(fields),
+            Setting::Reset => update.reset_sortable_fields(),
+            Setting::NotSet => (),
+        }
+        match self.criteria {
+            Setting::Set(criteria) => update.set_criteria(criteria),
+            Setting::Reset => update.reset_criteria(),
+            Setting::NotSet => (),
+        }
+        match self.stop_words {
+            Setting::Set(stop_words) => update.set_stop_words(stop_words),
+            Setting::Reset => update.reset_stop_words(),
+            Setting::NotSet => (),
+        }
+        match self.distinct_attribute {
+            Setting::Set(field) => update.set_distinct_field(field),
+            Setting::Reset => update.reset_distinct_field(),
+            Setting::NotSet => (),
+        }
+        match self.synonyms {
+            Setting::Set(synonyms) => update.set_synonyms(synonyms),
+            Setting::Reset => update.reset_synonyms(),
+            Setting::NotSet => (),
+        }
+        match self.document_id_validation {
+            Setting::Set(validation) => update.set_document_id_validation(validation),
+            Setting::Reset => update.reset_document_id_validation(),
+            Setting::NotSet => (),
         }
+        match self.terms_dropping_order {
+            Setting::Set(order) => update.set_terms_dropping_order(order),
+            Setting::Reset => update.reset_terms_dropping_order(),
+            Setting::NotSet => (),
+        }
+        match self.typo_tolerance {
+            Setting::Set(typo_tolerance) => update.set_typo_tolerance(typo_tolerance),
+            Setting::Reset => update.reset_typo_tolerance(),
+            Setting::NotSet => (),
+        }
+        match self.stemming_language {
+            Setting::Set(language) => update.set_stemming_language(language),
+            Setting::Reset => update.reset_stemming_language(),
+            Setting::NotSet => (),
+        }
+        match self.decompounding_dictionary {
+            Setting::Set(words) => update.set_decompounding_dictionary(words),
+            Setting::Reset => update.reset_decompounding_dictionary(),
+            Setting::NotSet => (),
+        }
+        match self.diacritics_sensitive {
+            Setting::Set(sensitive) => update.set_diacritics_sensitive(sensitive),
+            Setting::Reset => update.reset_diacritics_sensitive(),
+            Setting::NotSet => (),
+        }
+        match self.case_sensitive {
+            Setting::Set(sensitive) => update.set_case_sensitive(sensitive),
+            Setting::Reset => update.reset_case_sensitive(),
+            Setting::NotSet => (),
+        }
+        match self.hierarchical_facets {
+            Setting::Set(fields) => update.set_hierarchical_facets(fields),
+            Setting::Reset => update.reset_hierarchical_facets(),
+            Setting::NotSet => (),
+        }
+        match self.date_fields {
+            Setting::Set(fields) => update.set_date_fields(fields),
+            Setting::Reset => update.reset_date_fields(),
+            Setting::NotSet => (),
+        }
+        match self.facet_normalization {
+            Setting::Set(normalization) => update.set_facet_normalization(normalization),
+            Setting::Reset => update.reset_facet_normalization(),
+            Setting::NotSet => (),
+        }
+        match self.query_rules {
+            Setting::Set(rules) => update.set_query_rules(rules),
+            Setting::Reset => update.reset_query_rules(),
+            Setting::NotSet => (),
+        }
+        match self.numbers_normalization {
+            Setting::Set(normalization) => update.set_numbers_normalization(normalization),
+            Setting::Reset => update.reset_numbers_normalization(),
+            Setting::NotSet => (),
+        }
+        match self.field_languages {
+            Setting::Set(languages) => update.set_field_languages(languages),
+            Setting::Reset => update.reset_field_languages(),
+            Setting::NotSet => (),
+        }
+        match self.array_merge_policies {
+            Setting::Set(policies) => update.set_array_merge_policies(policies),
+            Setting::Reset => update.reset_array_merge_policies(),
+            Setting::NotSet => (),
+        }
+        match self.exact_attributes {
+            Setting::Set(fields) => update.set_exact_attributes(fields),
+            Setting::Reset => update.reset_exact_attributes(),
+            Setting::NotSet => (),
+        }
+        match self.exact_words {
+            Setting::Set(words) => update.set_exact_words(words),
+            Setting::Reset => update.reset_exact_words(),
+            Setting::NotSet => (),
+        }
+        match primary_key {
+            Setting::Set(primary_key) => update.set_primary_key(primary_key),
+            Setting::Reset => update.reset_primary_key(),
+            Setting::NotSet => (),
+        }
+
+        let mut bars = Vec::new();
+        let progesses = MultiProgress::new();
+        for _ in 0..4 {
+            let bar = ProgressBar::hidden();
+            let bar = progesses.add(bar);
+            bars.push(bar);
+        }
+
+        std::thread::spawn(move || {
+            progesses.join().unwrap();
+        });
+
+        update.execute(|step| indexing_callback(step, &bars))?;
+
+        txn.commit()?;
+        Ok(())
     }
 }
 
 #[derive(Debug, StructOpt)]
 enum Documents {
     Add(DocumentAddition),
+    /// Delete all the documents of the index, the settings are kept.
+    Clear(DocumentsClear),
+    /// Delete the documents with the given external ids.
+    Delete(DocumentsDeletion),
+    /// Write all the documents of the index as JSON lines.
+    Dump(DocumentsDump),
 }
 
 impl Performer for Documents {
     fn perform(self, index: Index) -> Result<()> {
         match self {
             Self::Add(addition) => addition.perform(index),
+            Self::Clear(clear) => clear.perform(index),
+            Self::Delete(deletion) => deletion.perform(index),
+            Self::Dump(dump) => dump.perform(index),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct DocumentsClear {}
+
+impl Performer for DocumentsClear {
+    fn perform(self, index: Index) -> Result<()> {
+        let mut txn = index.write_txn()?;
+        let result = milli::update::ClearDocuments::new(&mut txn, &index).execute()?;
+        txn.commit()?;
+
+        println!(
+            "deleted {} documents, cleared {} databases, reclaimed {}",
+            result.deleted_documents,
+            result.cleared_databases,
+            Byte::from_bytes(result.reclaimed_bytes as u128).get_appropriate_unit(true),
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct DocumentsDeletion {
+    /// The external ids of the documents to delete.
+    ids: Vec<String>,
+    /// Path of a file containing the external ids to delete, one per line.
+    #[structopt(short, long)]
+    path: Option<PathBuf>,
+}
+
+impl Performer for DocumentsDeletion {
+    fn perform(self, index: Index) -> Result<()> {
+        let mut ids = self.ids;
+        if let Some(path) = &self.path {
+            for line in BufReader::new(File::open(path)?).lines() {
+                let line = line?;
+                let id = line.trim();
+                if !id.is_empty() {
+                    ids.push(id.to_string());
+                }
+            }
         }
+        if ids.is_empty() {
+            eyre::bail!("no document ids to delete, give them as arguments or with --path");
+        }
+
+        let mut txn = index.write_txn()?;
+        let mut deletion = milli::update::DeleteDocuments::new(&mut txn, &index)?;
+        for id in &ids {
+            deletion.delete_external_id(id);
+        }
+        let result = deletion.execute()?;
+        txn.commit()?;
+
+        if !result.not_found_external_ids.is_empty() {
+            eprintln!(
+                "warning: {} documents were not found: {}",
+                result.not_found_external_ids.len(),
+                result.not_found_external_ids.join(", ")
+            );
+        }
+        println!(
+            "deleted {} documents, {} remaining",
+            result.deleted_documents, result.remaining_documents
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct DocumentsDump {
+    /// Path of the dump file, if not present, the documents are written to stdout.
+    #[structopt(short, long)]
+    path: Option<PathBuf>,
+    /// Compress the dump with gzip or zstd.
+    #[structopt(long, possible_values = &["gzip", "zstd"])]
+    compress: Option<DumpCompression>,
+}
+
+impl Performer for DocumentsDump {
+    fn perform(self, index: Index) -> Result<()> {
+        let writer: Box<dyn Write> = match &self.path {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(std::io::stdout()),
+        };
+
+        let txn = index.read_txn()?;
+        let count = dump_documents(&index, &txn, BufWriter::new(writer), self.compress)?;
+        eprintln!("dumped {} documents", count);
+        Ok(())
     }
 }
 
@@ -150,9 +627,19 @@ fn setup(opt: &Cli) -> Result<()> {
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let command = Cli::from_args();
+fn main() {
+    let error_format = ErrorFormat::from_args(std::env::args_os());
+    let command = match Cli::from_iter_safe(std::env::args_os()) {
+        Ok(command) => command,
+        Err(error) => exit_with_clap_error(error, error_format),
+    };
+
+    if let Err(report) = run(command) {
+        exit_with_report(report, error_format);
+    }
+}
 
+fn run(command: Cli) -> Result<()> {
     setup(&command)?;
 
     let mut options = heed::EnvOpenOptions::new();
@@ -188,36 +675,64 @@ impl FromStr for DocumentAdditionFormat {
 struct DocumentAddition {
     #[structopt(short, long, default_value = "json", possible_values = &["csv", "jsonl", "json"])]
     format: DocumentAdditionFormat,
-    /// Path to the update file, if not present, will read from stdin.
+    /// Path to the update file, if neither a path nor an url is present, will read from stdin.
     #[structopt(short, long)]
     path: Option<PathBuf>,
+    /// URL of the update file, the response is streamed into the update.
+    #[structopt(long, conflicts_with = "path")]
+    url: Option<String>,
+    /// Decompress the update file with gzip.
+    #[structopt(long)]
+    gzip: bool,
     /// Whether to generate missing document ids.
     #[structopt(short, long)]
     autogen_docids: bool,
     /// Whether to update or replace the documents if they already exist.
     #[structopt(short, long)]
     update_documents: bool,
+    /// Only validate the documents and report the errors found, nothing is written to the index.
+    #[structopt(long)]
+    dry_run: bool,
+    /// Skip the invalid documents instead of rejecting the whole update.
+    #[structopt(long)]
+    continue_on_error: bool,
+    /// Report the documents sharing the same id in the update.
+    #[structopt(long)]
+    report_duplicates: bool,
+    /// Replace the invalid UTF-8 sequences of the documents instead of failing.
+    #[structopt(long)]
+    lossy_utf8: bool,
 }
 
 impl Performer for DocumentAddition {
     fn perform(self, index: milli::Index) -> Result<()> {
-        let reader: Box<dyn Read> = match self.path {
-            Some(ref path) => {
+        let reader: Box<dyn Read> = match (&self.path, &self.url) {
+            (Some(path), _) => {
                 let file = File::open(path)?;
                 Box::new(file)
             }
-            None => Box::new(stdin()),
+            (None, Some(url)) => {
+                let response = reqwest::blocking::get(url)?.error_for_status()?;
+                Box::new(response)
+            }
+            (None, None) => Box::new(stdin()),
         };
+        let reader: Box<dyn Read> =
+            if self.gzip { Box::new(GzDecoder::new(reader)) } else { reader };
 
         println!("parsing documents...");
 
-        let documents = match self.format {
-            DocumentAdditionFormat::Csv => documents_from_csv(reader)?,
-            DocumentAdditionFormat::Json => documents_from_json(reader)?,
-            DocumentAdditionFormat::Jsonl => documents_from_jsonl(reader)?,
+        let (mut documents, replaced_utf8_sequences) = match self.format {
+            DocumentAdditionFormat::Csv => documents_from_csv(reader, self.lossy_utf8)?,
+            DocumentAdditionFormat::Json => documents_from_json(reader, self.lossy_utf8)?,
+            DocumentAdditionFormat::Jsonl => documents_from_jsonl(reader, self.lossy_utf8)?,
         };
+        if replaced_utf8_sequences > 0 {
+            eprintln!("warning: replaced {} invalid UTF-8 sequences.", replaced_utf8_sequences);
+        }
 
-        let reader = milli::documents::DocumentBatchReader::from_reader(Cursor::new(documents))?;
+        documents.seek(SeekFrom::Start(0))?;
+        let reader = milli::documents::DocumentBatchReader::from_reader(documents)?;
 
         println!("Adding {} documents to the index.", reader.len());
 
@@ -232,6 +747,8 @@ impl Performer for DocumentAddition {
         let indexing_config = IndexDocumentsConfig {
             update_method,
             autogenerate_docids: self.autogen_docids,
+            continue_on_error: self.continue_on_error,
+            report_duplicates: self.report_duplicates,
             ..Default::default()
         };
         let mut bars = Vec::new();
@@ -248,6 +765,20 @@ impl Performer for DocumentAddition {
             indexing_config,
             |step| indexing_callback(step, &bars),
         );
+
+        if self.dry_run {
+            let errors = addition.validate_documents(reader)?;
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            // The transaction is aborted when dropped, nothing is written to the index.
+            if !errors.is_empty() {
+                eyre::bail!("found {} invalid documents", errors.len());
+            }
+            println!("all the documents are valid.");
+            return Ok(());
+        }
+
         addition.add_documents(reader)?;
 
         std::thread::spawn(move || {
@@ -258,6 +789,9 @@ impl Performer for DocumentAddition {
 
         txn.commit()?;
 
+        for skipped in &result.skipped_documents_errors {
+            eprintln!("skipped document {}: {}", skipped.position, skipped.error);
+        }
         println!("{:?}", result);
         Ok(())
     }
@@ -305,36 +839,48 @@ fn indexing_callback(step: milli::update::UpdateIndexingStep, bars: &[ProgressBa
     bar.enable_steady_tick(200);
 }
 
-fn documents_from_jsonl(reader: impl Read) -> Result<Vec<u8>> {
-    let mut writer = Cursor::new(Vec::new());
+fn documents_from_jsonl(reader: impl Read, lossy_utf8: bool) -> Result<(File, usize)> {
+    // The batch is written to a temporary file to not hold all the documents in memory.
+    let mut writer = BufWriter::new(tempfile::tempfile()?);
     let mut documents = milli::documents::DocumentBatchBuilder::new(&mut writer)?;
+    documents.set_lossy_utf8(lossy_utf8);
 
-    let mut buf = String::new();
+    let mut buf = Vec::new();
     let mut reader = BufReader::new(reader);
 
-    while reader.read_line(&mut buf)? > 0 {
-        documents.extend_from_json(&mut buf.as_bytes())?;
+    while reader.read_until(b'\n', &mut buf)? > 0 {
+        documents.extend_from_json(&mut buf.as_slice())?;
+        buf.clear();
     }
+    let replaced_utf8_sequences = documents.replaced_utf8_sequences();
     documents.finish()?;
 
-    Ok(writer.into_inner())
+    Ok((writer.into_inner()?, replaced_utf8_sequences))
 }
 
-fn documents_from_json(reader: impl Read) -> Result<Vec<u8>> {
-    let mut writer = Cursor::new(Vec::new());
+fn documents_from_json(reader: impl Read, lossy_utf8: bool) -> Result<(File, usize)> {
+    let mut writer = BufWriter::new(tempfile::tempfile()?);
     let mut documents = milli::documents::DocumentBatchBuilder::new(&mut writer)?;
+    documents.set_lossy_utf8(lossy_utf8);
 
     documents.extend_from_json(reader)?;
+    let replaced_utf8_sequences = documents.replaced_utf8_sequences();
     documents.finish()?;
 
-    Ok(writer.into_inner())
+    Ok((writer.into_inner()?, replaced_utf8_sequences))
 }
 
-fn documents_from_csv(reader: impl Read) -> Result<Vec<u8>> {
-    let mut writer = Cursor::new(Vec::new());
-    milli::documents::DocumentBatchBuilder::from_csv(reader, &mut writer)?.finish()?;
+fn documents_from_csv(reader: impl Read, lossy_utf8: bool) -> Result<(File, usize)> {
+    let mut writer = BufWriter::new(tempfile::tempfile()?);
+    let documents = if lossy_utf8 {
+        milli::documents::DocumentBatchBuilder::from_csv_lossy(reader, &mut writer)?
+    } else {
+        milli::documents::DocumentBatchBuilder::from_csv(reader, &mut writer)?
+    };
+    let replaced_utf8_sequences = documents.replaced_utf8_sequences();
+    documents.finish()?;
 
-    Ok(writer.into_inner())
+    Ok((writer.into_inner()?, replaced_utf8_sequences))
 }
 
 #[derive(Debug, StructOpt)]
@@ -342,17 +888,38 @@ struct Search {
     query: Option<String>,
     #[structopt(short, long)]
     filter: Option<String>,
+    /// The sort expressions separated by commas, e.g. `price:asc,rating:desc`,
+    /// the following expressions break the ties of the previous ones.
+    #[structopt(long)]
+    sort: Option<String>,
     #[structopt(short, long)]
     offset: Option<usize>,
     #[structopt(short, long)]
     limit: Option<usize>,
     #[structopt(short, long, conflicts_with = "query")]
     interactive: bool,
+    /// Search again on every keystroke, the last word being searched as a prefix.
+    #[structopt(long, conflicts_with_all = &["query", "interactive"])]
+    as_you_type: bool,
+    /// The time to wait after the last keystroke before searching, in milliseconds.
+    #[structopt(long, default_value = "50")]
+    debounce: u64,
+    /// Path to a file of queries to execute, one JSON object per line with optional
+    /// `query`, `filter`, `sort`, `offset` and `limit` fields.
+    #[structopt(long, conflicts_with_all = &["query", "interactive"])]
+    queries_file: Option<PathBuf>,
 }
 
 impl Performer for Search {
     fn perform(self, index: milli::Index) -> Result<()> {
-        if self.interactive {
+        if let Some(ref path) = self.queries_file {
+            Self::perform_queries_file(&index, path)?;
+        } else if self.as_you_type {
+            terminal::enable_raw_mode()?;
+            let result = self.perform_as_you_type(&index);
+            terminal::disable_raw_mode()?;
+            result?;
+        } else if self.interactive {
             let stdin = std::io::stdin();
             let mut lines = stdin.lock().lines();
             loop {
@@ -365,6 +932,7 @@ impl Performer for Search {
                             &index,
                             &Some(line),
                             &self.filter,
+                            &self.sort,
                             &self.offset,
                             &self.limit,
                         )?;
@@ -385,6 +953,7 @@ impl Performer for Search {
                 &index,
                 &self.query,
                 &self.filter,
+                &self.sort,
                 &self.offset,
                 &self.limit,
             )?;
@@ -402,15 +971,156 @@ impl Performer for Search {
 }
 
 impl Search {
+    /// The maximum number of hits displayed at once in the search-as-you-type mode.
+    const AS_YOU_TYPE_MAX_HITS: usize = 10;
+
+    fn perform_as_you_type(&self, index: &milli::Index) -> Result<()> {
+        let debounce = Duration::from_millis(self.debounce);
+        let limit =
+            self.limit.unwrap_or(Self::AS_YOU_TYPE_MAX_HITS).min(Self::AS_YOU_TYPE_MAX_HITS);
+        let mut query = String::new();
+        let mut last_keystroke: Option<Instant> = None;
+
+        Self::render_as_you_type(&query, &[], None)?;
+        loop {
+            if event::poll(Duration::from_millis(10))? {
+                if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
+                    match code {
+                        KeyCode::Esc | KeyCode::Enter => break,
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Char(c) => query.push(c),
+                        KeyCode::Backspace => {
+                            query.pop();
+                        }
+                        _ => continue,
+                    }
+                    last_keystroke = Some(Instant::now());
+                }
+            }
+
+            // We only search once the user stopped typing for the debounce duration.
+            if last_keystroke.map_or(false, |instant| instant.elapsed() >= debounce) {
+                last_keystroke = None;
+                let now = Instant::now();
+                let jsons = Self::perform_single_search(
+                    index,
+                    &Some(query.clone()),
+                    &self.filter,
+                    &self.sort,
+                    &self.offset,
+                    &Some(limit),
+                )?;
+                Self::render_as_you_type(&query, &jsons, Some(now.elapsed()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_as_you_type(
+        query: &str,
+        hits: &[Map<String, Value>],
+        time: Option<Duration>,
+    ) -> Result<()> {
+        let (width, _) = terminal::size()?;
+        let mut stdout = std::io::stdout();
+        crossterm::execute!(
+            stdout,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        // The terminal is in raw mode, we must explicitly return to the start of the lines.
+        write!(stdout, "> {}\r\n", query)?;
+        if let Some(time) = time {
+            write!(stdout, "{} hits in {:.02?}\r\n", hits.len(), time)?;
+        }
+        for hit in hits {
+            let line = serde_json::to_string(hit)?;
+            let line: String = line.chars().take(width as usize).collect();
+            write!(stdout, "{}\r\n", line)?;
+        }
+
+        // We put the cursor back at the end of the query.
+        crossterm::execute!(stdout, cursor::MoveTo(2 + query.chars().count() as u16, 0))?;
+        stdout.flush()?;
+        Ok(())
+    }
+
     fn perform_single_search(
         index: &milli::Index,
         query: &Option<String>,
         filter: &Option<String>,
+        sort: &Option<String>,
         offset: &Option<usize>,
         limit: &Option<usize>,
     ) -> Result<Vec<Map<String, Value>>> {
         let txn = index.env.read_txn()?;
-        let mut search = index.search(&txn);
+        let sort = sort.clone().map(|sort| vec![sort]);
+        let (jsons, _) =
+            Self::perform_search_in_txn(index, &txn, query, filter, &sort, offset, limit)?;
+        Ok(jsons)
+    }
+
+    /// Executes every query of the file, one JSON object per line, in the same read transaction
+    /// and writes the results on stdout, one JSON object per line too.
+    fn perform_queries_file(index: &milli::Index, path: &PathBuf) -> Result<()> {
+        let txn = index.env.read_txn()?;
+        let file = BufReader::new(File::open(path)?);
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+
+        for line in file.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let now = Instant::now();
+            let result = serde_json::from_str::<BatchQuery>(&line)
+                .map_err(eyre::Error::from)
+                .and_then(|query| {
+                    Self::perform_search_in_txn(
+                        index,
+                        &txn,
+                        &query.query,
+                        &query.filter,
+                        &query.sort,
+                        &query.offset,
+                        &query.limit,
+                    )
+                    .map(|result| (query, result))
+                });
+
+            let output = match result {
+                Ok((query, (hits, nb_hits))) => serde_json::json!({
+                    "query": query.query,
+                    "hits": hits,
+                    "nbHits": nb_hits,
+                    "processingTimeMs": now.elapsed().as_millis() as u64,
+                }),
+                // A failing query must not stop the evaluation of the following ones.
+                Err(error) => serde_json::json!({ "error": error.to_string() }),
+            };
+
+            serde_json::to_writer(&mut stdout, &output)?;
+            writeln!(stdout)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the displayed documents and the number of candidates.
+    fn perform_search_in_txn(
+        index: &milli::Index,
+        txn: &heed::RoTxn,
+        query: &Option<String>,
+        filter: &Option<String>,
+        sort: &Option<Vec<String>>,
+        offset: &Option<usize>,
+        limit: &Option<usize>,
+    ) -> Result<(Vec<Map<String, Value>>, u64)> {
+        let mut search = index.search(txn);
 
         if let Some(ref query) = query {
             search.query(query);
@@ -422,6 +1132,15 @@ impl Search {
             }
         }
 
+        // Every sort string can contain several expressions separated by commas.
+        if let Some(sort) = sort {
+            let mut sort_criteria = Vec::new();
+            for sort in sort {
+                sort_criteria.extend(milli::parse_sort_criteria(sort).map_err(milli::Error::from)?);
+            }
+            search.sort_criteria(sort_criteria);
+        }
+
         if let Some(offset) = offset {
             search.offset(*offset);
         }
@@ -432,20 +1151,104 @@ impl Search {
 
         let result = search.execute()?;
 
-        let fields_ids_map = index.fields_ids_map(&txn)?;
+        let fields_ids_map = index.fields_ids_map(txn)?;
         let displayed_fields =
-            index.displayed_fields_ids(&txn)?.unwrap_or_else(|| fields_ids_map.ids().collect());
-        let documents = index.documents(&txn, result.documents_ids)?;
+            index.displayed_fields_ids(txn)?.unwrap_or_else(|| fields_ids_map.ids().collect());
+        let documents = index.documents(txn, result.documents_ids)?;
         let mut jsons = Vec::new();
-        for (_, obkv) in documents {
-            let json = milli::obkv_to_json(&displayed_fields, &fields_ids_map, obkv)?;
+        for (_, document) in documents {
+            let json = milli::obkv_to_json(&displayed_fields, &fields_ids_map, document.obkv())?;
             jsons.push(json);
         }
 
-        Ok(jsons)
+        Ok((jsons, result.candidates.len()))
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct Words {
+    /// Only dump the words starting with this prefix.
+    #[structopt(long, default_value = "")]
+    prefix: String,
+    /// Only dump the words in at least this number of documents.
+    #[structopt(long, default_value = "0")]
+    min_frequency: u64,
+}
+
+impl Performer for Words {
+    fn perform(self, index: milli::Index) -> Result<()> {
+        let rtxn = index.read_txn()?;
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        for result in index.word_frequencies(&rtxn, &self.prefix, self.min_frequency)? {
+            let (word, count) = result?;
+            writeln!(stdout, "{}\t{}", word, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, StructOpt)]
+enum Analyze {
+    /// Suggest the words present in too many documents to be relevant as stop words.
+    StopWords {
+        /// The ratio of the documents, between 0 and 1, a word must be present in.
+        #[structopt(long, default_value = "0.5")]
+        threshold: f64,
+    },
+}
+
+impl Performer for Analyze {
+    fn perform(self, index: milli::Index) -> Result<()> {
+        match self {
+            Analyze::StopWords { threshold } => {
+                let rtxn = index.read_txn()?;
+                let stdout = std::io::stdout();
+                let mut stdout = stdout.lock();
+                for (word, ratio) in index.stop_words_candidates(&rtxn, threshold)? {
+                    writeln!(stdout, "{}\t{:.2}%", word, ratio * 100.0)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
+fn reindex(index: Index) -> Result<()> {
+    let mut txn = index.write_txn()?;
+    let config = IndexerConfig { log_every_n: Some(100), ..Default::default() };
+
+    let mut bars = Vec::new();
+    let progesses = MultiProgress::new();
+    for _ in 0..4 {
+        let bar = ProgressBar::hidden();
+        let bar = progesses.add(bar);
+        bars.push(bar);
+    }
+
+    std::thread::spawn(move || {
+        progesses.join().unwrap();
+    });
+
+    let count = milli::update::Reindex::new(&mut txn, &index, &config)
+        .execute(|step| indexing_callback(step, &bars))?;
+
+    txn.commit()?;
+    eprintln!("reindexed {} documents", count);
+    Ok(())
+}
+
+/// A query of a queries file, every parameter is optional.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchQuery {
+    query: Option<String>,
+    filter: Option<String>,
+    sort: Option<Vec<String>>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
 #[derive(Debug, StructOpt)]
 struct SettingsUpdate {
     #[structopt(long)]
@@ -52,6 +52,7 @@ pub struct Error<'a> {
 pub enum ErrorKind<'a> {
     ReservedGeo(&'a str),
     Geo,
+    GeoPolygon,
     MisusedGeo,
     InvalidPrimary,
     ExpectedEof,
@@ -139,6 +140,9 @@ impl<'a> Display for Error<'a> {
             ErrorKind::Geo => {
                 writeln!(f, "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`.")?
             }
+            ErrorKind::GeoPolygon => {
+                writeln!(f, "The `_geoPolygon` filter expects at least three points: `_geoPolygon([[latitude, longitude], ...])`.")?
+            }
             ErrorKind::ReservedGeo(name) => {
                 writeln!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance) built-in rule to filter on `_geo` coordinates.", name.escape_debug())?
             }
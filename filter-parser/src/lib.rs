@@ -6,7 +6,7 @@
 //! or             = and (~ "OR" ~ and)
 //! and            = not (~ "AND" not)*
 //! not            = ("NOT" ~ not) | primary
-//! primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | condition | to
+//! primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | geoPolygon | condition | to
 //! condition      = value ("==" | ">" ...) value
 //! to             = value value TO value
 //! value          = WS* ~ ( word | singleQuoted | doubleQuoted) ~ WS*
@@ -14,6 +14,8 @@
 //! doubleQuoted   = "\"" .* all but double quotes "\""
 //! word           = (alphanumeric | _ | - | .)+
 //! geoRadius      = WS* ~ "_geoRadius(" ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ "," float ~ WS* ~ ")"
+//! geoPolygon     = WS* ~ "_geoPolygon(" ~ WS* ~ "[" ~ point ~ ("," ~ point)* ~ "]" ~ WS* ~ ")"
+//! point          = WS* ~ "[" ~ WS* ~ float ~ WS* ~ "," ~ WS* ~ float ~ WS* ~ "]" ~ WS*
 //! ```
 //!
 //! Other BNF grammar used to handle some specific errors:
@@ -39,7 +41,7 @@ mod condition;
 mod error;
 mod value;
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display};
 use std::ops::Deref;
 use std::str::FromStr;
 
@@ -119,6 +121,8 @@ pub enum FilterCondition<'a> {
     And(Box<Self>, Box<Self>),
     GeoLowerThan { point: [Token<'a>; 2], radius: Token<'a> },
     GeoGreaterThan { point: [Token<'a>; 2], radius: Token<'a> },
+    GeoInsidePolygon { points: Vec<[Token<'a>; 2]> },
+    GeoOutsidePolygon { points: Vec<[Token<'a>; 2]> },
 }
 
 impl<'a> FilterCondition<'a> {
@@ -136,6 +140,12 @@ impl<'a> FilterCondition<'a> {
             }
             FilterCondition::GeoLowerThan { point: [point, _], .. } if depth == 0 => Some(point),
             FilterCondition::GeoGreaterThan { point: [point, _], .. } if depth == 0 => Some(point),
+            FilterCondition::GeoInsidePolygon { points } if depth == 0 => {
+                points.first().map(|[lat, _]| lat)
+            }
+            FilterCondition::GeoOutsidePolygon { points } if depth == 0 => {
+                points.first().map(|[lat, _]| lat)
+            }
             _ => None,
         }
     }
@@ -155,6 +165,8 @@ impl<'a> FilterCondition<'a> {
             And(a, b) => Or(a.negate().into(), b.negate().into()),
             GeoLowerThan { point, radius } => GeoGreaterThan { point, radius },
             GeoGreaterThan { point, radius } => GeoLowerThan { point, radius },
+            GeoInsidePolygon { points } => GeoOutsidePolygon { points },
+            GeoOutsidePolygon { points } => GeoInsidePolygon { points },
         }
     }
 
@@ -167,6 +179,68 @@ impl<'a> FilterCondition<'a> {
     }
 }
 
+/// Writes the filter back as an expression that parses into the same filter,
+/// the values being always quoted.
+impl<'a> Display for FilterCondition<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterCondition::Condition { fid, op } => {
+                let fid = fid.value();
+                match op {
+                    Condition::GreaterThan(v) => write!(f, "\"{}\" > \"{}\"", fid, v.value()),
+                    Condition::GreaterThanOrEqual(v) => {
+                        write!(f, "\"{}\" >= \"{}\"", fid, v.value())
+                    }
+                    Condition::Equal(v) => write!(f, "\"{}\" = \"{}\"", fid, v.value()),
+                    Condition::NotEqual(v) => write!(f, "\"{}\" != \"{}\"", fid, v.value()),
+                    Condition::LowerThan(v) => write!(f, "\"{}\" < \"{}\"", fid, v.value()),
+                    Condition::LowerThanOrEqual(v) => {
+                        write!(f, "\"{}\" <= \"{}\"", fid, v.value())
+                    }
+                    Condition::Between { from, to } => {
+                        write!(f, "\"{}\" \"{}\" TO \"{}\"", fid, from.value(), to.value())
+                    }
+                }
+            }
+            FilterCondition::Or(lhs, rhs) => write!(f, "{} OR {}", lhs, rhs),
+            FilterCondition::And(lhs, rhs) => {
+                // the `OR` has a lower precedence than the `AND`
+                match lhs.as_ref() {
+                    FilterCondition::Or(..) => write!(f, "({})", lhs)?,
+                    lhs => write!(f, "{}", lhs)?,
+                }
+                f.write_str(" AND ")?;
+                match rhs.as_ref() {
+                    FilterCondition::Or(..) => write!(f, "({})", rhs),
+                    rhs => write!(f, "{}", rhs),
+                }
+            }
+            FilterCondition::GeoLowerThan { point: [lat, lng], radius } => {
+                write!(f, "_geoRadius({}, {}, {})", lat.value(), lng.value(), radius.value())
+            }
+            FilterCondition::GeoGreaterThan { point: [lat, lng], radius } => {
+                write!(f, "NOT _geoRadius({}, {}, {})", lat.value(), lng.value(), radius.value())
+            }
+            FilterCondition::GeoInsidePolygon { points } => write_geo_polygon(f, points),
+            FilterCondition::GeoOutsidePolygon { points } => {
+                f.write_str("NOT ")?;
+                write_geo_polygon(f, points)
+            }
+        }
+    }
+}
+
+fn write_geo_polygon(f: &mut fmt::Formatter, points: &[[Token; 2]]) -> fmt::Result {
+    f.write_str("_geoPolygon([")?;
+    for (i, [lat, lng]) in points.iter().enumerate() {
+        if i != 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "[{}, {}]", lat.value(), lng.value())?;
+    }
+    f.write_str("])")
+}
+
 /// remove OPTIONAL whitespaces before AND after the provided parser.
 fn ws<'a, O>(inner: impl FnMut(Span<'a>) -> IResult<O>) -> impl FnMut(Span<'a>) -> IResult<O> {
     delimited(multispace0, inner, multispace0)
@@ -226,6 +300,33 @@ fn parse_geo_radius(input: Span) -> IResult<FilterCondition> {
     Ok((input, res))
 }
 
+/// geoPolygon     = WS* ~ "_geoPolygon([" ~ point ~ ("," ~ point)* ~ "])"
+/// point          = WS* ~ "[" ~ float ~ "," ~ float ~ "]" ~ WS*
+/// If we parse `_geoPolygon` we MUST parse the rest of the expression.
+fn parse_geo_polygon(input: Span) -> IResult<FilterCondition> {
+    let point = delimited(char('['), separated_list1(tag(","), ws(recognize_float)), char(']'));
+    // we want to forbid space BEFORE the _geoPolygon but not after
+    let parsed = preceded(
+        tuple((multispace0, tag("_geoPolygon"))),
+        // if we were able to parse `_geoPolygon` and can't parse the rest of the input we return a failure
+        cut(delimited(
+            char('('),
+            ws(delimited(char('['), separated_list1(tag(","), ws(point)), char(']'))),
+            char(')'),
+        )),
+    )(input)
+    .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::GeoPolygon)));
+
+    let (input, points) = parsed?;
+
+    if points.len() < 3 || points.iter().any(|point| point.len() != 2) {
+        return Err(nom::Err::Failure(Error::new_from_kind(input, ErrorKind::GeoPolygon)));
+    }
+
+    let points = points.into_iter().map(|point| [point[0].into(), point[1].into()]).collect();
+    Ok((input, FilterCondition::GeoInsidePolygon { points }))
+}
+
 /// geoPoint      = WS* ~ "_geoPoint(float ~ "," ~ float ~ "," float)
 fn parse_geo_point(input: Span) -> IResult<FilterCondition> {
     // we want to forbid space BEFORE the _geoPoint but not after
@@ -240,7 +341,7 @@ fn parse_geo_point(input: Span) -> IResult<FilterCondition> {
     Err(nom::Err::Failure(Error::new_from_kind(input, ErrorKind::ReservedGeo("_geoPoint"))))
 }
 
-/// primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | condition | to
+/// primary        = (WS* ~ "("  expression ")" ~ WS*) | geoRadius | geoPolygon | condition | to
 fn parse_primary(input: Span) -> IResult<FilterCondition> {
     alt((
         // if we find a first parenthesis, then we must parse an expression and find the closing parenthesis
@@ -252,6 +353,7 @@ fn parse_primary(input: Span) -> IResult<FilterCondition> {
             }),
         ),
         parse_geo_radius,
+        parse_geo_polygon,
         parse_condition,
         parse_to,
         // the next lines are only for error handling and are written at the end to have the less possible performance impact
@@ -464,6 +566,38 @@ pub mod tests {
                     radius: rtok("NOT _geoRadius(12, 13, ", "14"),
                 },
             ),
+            (
+                "_geoPolygon([[12, 13], [14, 15], [16, 13]])",
+                Fc::GeoInsidePolygon {
+                    points: vec![
+                        [rtok("_geoPolygon([[", "12"), rtok("_geoPolygon([[12, ", "13")],
+                        [
+                            rtok("_geoPolygon([[12, 13], [", "14"),
+                            rtok("_geoPolygon([[12, 13], [14, ", "15"),
+                        ],
+                        [
+                            rtok("_geoPolygon([[12, 13], [14, 15], [", "16"),
+                            rtok("_geoPolygon([[12, 13], [14, 15], [16, ", "13"),
+                        ],
+                    ],
+                },
+            ),
+            (
+                "NOT _geoPolygon([[12, 13], [14, 15], [16, 13]])",
+                Fc::GeoOutsidePolygon {
+                    points: vec![
+                        [rtok("NOT _geoPolygon([[", "12"), rtok("NOT _geoPolygon([[12, ", "13")],
+                        [
+                            rtok("NOT _geoPolygon([[12, 13], [", "14"),
+                            rtok("NOT _geoPolygon([[12, 13], [14, ", "15"),
+                        ],
+                        [
+                            rtok("NOT _geoPolygon([[12, 13], [14, 15], [", "16"),
+                            rtok("NOT _geoPolygon([[12, 13], [14, 15], [16, ", "13"),
+                        ],
+                    ],
+                },
+            ),
             // test simple `or` and `and`
             (
                 "channel = ponce AND 'dog race' != 'bernese mountain'",
@@ -588,6 +722,9 @@ pub mod tests {
             ("channel = Ponce OR", "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `TO` or `_geoRadius` but instead got nothing."),
             ("_geoRadius", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
             ("_geoRadius = 12", "The `_geoRadius` filter expects three arguments: `_geoRadius(latitude, longitude, radius)`."),
+            ("_geoPolygon", "The `_geoPolygon` filter expects at least three points: `_geoPolygon([[latitude, longitude], ...])`."),
+            ("_geoPolygon([[12, 13], [14, 15]])", "The `_geoPolygon` filter expects at least three points: `_geoPolygon([[latitude, longitude], ...])`."),
+            ("_geoPolygon([[12, 13], [14, 15, 16], [17, 18]])", "The `_geoPolygon` filter expects at least three points: `_geoPolygon([[latitude, longitude], ...])`."),
             ("_geoPoint(12, 13, 14)", "`_geoPoint` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance) built-in rule to filter on `_geo` coordinates."),
             ("position <= _geoPoint(12, 13, 14)", "`_geoPoint` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance) built-in rule to filter on `_geo` coordinates."),
             ("position <= _geoRadius(12, 13, 14)", "The `_geoRadius` filter is an operation and can't be used as a value."),
@@ -611,6 +748,30 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn display() {
+        let test_case = [
+            ("channel = Ponce", "\"channel\" = \"Ponce\""),
+            ("'dog race' != 'bernese mountain'", "\"dog race\" != \"bernese mountain\""),
+            ("NOT subscribers 100 TO 1000", "\"subscribers\" < \"100\" OR \"subscribers\" > \"1000\""),
+            ("subscribers 100 TO 1000", "\"subscribers\" \"100\" TO \"1000\""),
+            (
+                "channel = ponce AND (subscribers > 1000 OR subscribers <= 10)",
+                "\"channel\" = \"ponce\" AND (\"subscribers\" > \"1000\" OR \"subscribers\" <= \"10\")",
+            ),
+            ("NOT _geoRadius(12, 13, 14)", "NOT _geoRadius(12, 13, 14)"),
+            ("_geoPolygon([[1, 2], [3, 4], [5, 6]])", "_geoPolygon([[1, 2], [3, 4], [5, 6]])"),
+        ];
+
+        for (input, expected) in test_case {
+            let filter = FilterCondition::parse(input).unwrap().unwrap();
+            let displayed = filter.to_string();
+            assert_eq!(displayed, expected);
+            // the displayed filter must be parsed as the same filter
+            assert_eq!(FilterCondition::parse(&displayed).unwrap(), Some(filter));
+        }
+    }
+
     #[test]
     fn depth() {
         let filter = FilterCondition::parse("account_ids=1 OR account_ids=2 OR account_ids=3 OR account_ids=4 OR account_ids=5 OR account_ids=6").unwrap().unwrap();
@@ -25,8 +25,8 @@ use milli::update::{
     ClearDocuments, IndexDocumentsConfig, IndexDocumentsMethod, IndexerConfig, Setting,
 };
 use milli::{
-    obkv_to_json, CompressionType, Filter as MilliFilter, FilterCondition, Index, MatchingWords,
-    SearchResult, SortError,
+    obkv_to_json, obkv_to_json_writer, CompressionType, Filter as MilliFilter, FilterCondition,
+    Index, MatchingWords, SearchResult, SortError,
 };
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
@@ -136,6 +136,10 @@ pub struct IndexerOpt {
     /// Any value higher than 65535 will be clamped.
     #[structopt(long)]
     pub max_positions_per_attributes: Option<u32>,
+
+    /// Compress the stored documents with a dictionary trained on the first documents added.
+    #[structopt(long)]
+    pub compress_documents: bool,
 }
 
 struct Highlighter<'a, A> {
@@ -333,6 +337,7 @@ async fn main() -> anyhow::Result<()> {
         max_nb_chunks: opt.indexer.max_nb_chunks,
         chunk_compression_level: opt.indexer.chunk_compression_level,
         max_positions_per_attributes: opt.indexer.max_positions_per_attributes,
+        compress_documents: opt.indexer.compress_documents,
         thread_pool: Some(pool),
         log_every_n: Some(opt.indexer.log_every_n),
         max_memory: Some(opt.indexer.max_memory.get_bytes() as usize),
@@ -794,15 +799,24 @@ async fn main() -> anyhow::Result<()> {
                 search.sort_criteria(vec![sort.parse().map_err(SortError::from).unwrap()]);
             }
 
-            let SearchResult { matching_words, candidates, documents_ids } =
-                search.execute().unwrap();
+            if query.facet_distribution == Some(true) {
+                search.facets_distribution(None);
+            }
+
+            let SearchResult {
+                matching_words,
+                candidates,
+                documents_ids,
+                facets_distribution,
+                geo_distances,
+                ..
+            } = search.execute().unwrap();
 
             let number_of_candidates = candidates.len();
-            let facets = if query.facet_distribution == Some(true) {
-                Some(index.facets_distribution(&rtxn).candidates(candidates).execute().unwrap())
-            } else {
-                None
-            };
+            let facets = facets_distribution.map(|distribution| {
+                let iter = distribution.into_iter();
+                iter.map(|(name, values)| (name, values.into_iter().collect())).collect()
+            });
 
             let mut documents = Vec::new();
             let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
@@ -818,8 +832,9 @@ async fn main() -> anyhow::Result<()> {
             let stop_words = fst::Set::default();
             let highlighter = Highlighter::new(&stop_words);
 
-            for (_id, obkv) in index.documents(&rtxn, documents_ids).unwrap() {
-                let mut object = obkv_to_json(&displayed_fields, &fields_ids_map, obkv).unwrap();
+            for (id, document) in index.documents(&rtxn, documents_ids).unwrap() {
+                let mut object =
+                    obkv_to_json(&displayed_fields, &fields_ids_map, document.obkv()).unwrap();
                 if !disable_highlighting {
                     highlighter.highlight_record(
                         &mut object,
@@ -828,6 +843,10 @@ async fn main() -> anyhow::Result<()> {
                     );
                 }
 
+                if let Some(distance) = geo_distances.as_ref().and_then(|d| d.get(&id)) {
+                    object.insert("_geoDistance".to_string(), (*distance as u64).into());
+                }
+
                 documents.push(object);
             }
 
@@ -856,13 +875,21 @@ async fn main() -> anyhow::Result<()> {
             match external_documents_ids.get(&id) {
                 Some(document_id) => {
                     let document_id = document_id as u32;
-                    let (_, obkv) =
+                    let (_, stored) =
                         index.documents(&rtxn, Some(document_id)).unwrap().pop().unwrap();
-                    let document = obkv_to_json(&displayed_fields, &fields_ids_map, obkv).unwrap();
+                    // The document is not modified, we can write the stored JSON values as-is.
+                    let mut document = Vec::new();
+                    obkv_to_json_writer(
+                        &displayed_fields,
+                        &fields_ids_map,
+                        stored.obkv(),
+                        &mut document,
+                    )
+                    .unwrap();
 
                     Response::builder()
                         .header("Content-Type", "application/json")
-                        .body(serde_json::to_string(&document).unwrap())
+                        .body(String::from_utf8(document).unwrap())
                 }
                 None => Response::builder()
                     .status(404)
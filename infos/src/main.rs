@@ -5,6 +5,7 @@ use std::{fmt, io, str};
 use anyhow::Context;
 use byte_unit::Byte;
 use heed::EnvOpenOptions;
+use milli::documents::DumpCompression;
 use milli::facet::FacetType;
 use milli::index::db_name::*;
 use milli::{FieldId, Index};
@@ -18,6 +19,7 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 const ALL_DATABASE_NAMES: &[&str] = &[
     MAIN,
     WORD_DOCIDS,
+    EXACT_WORD_DOCIDS,
     WORD_PREFIX_DOCIDS,
     DOCID_WORD_POSITIONS,
     WORD_PAIR_PROXIMITY_DOCIDS,
@@ -26,6 +28,7 @@ const ALL_DATABASE_NAMES: &[&str] = &[
     WORD_PREFIX_POSITION_DOCIDS,
     FIELD_ID_WORD_COUNT_DOCIDS,
     FACET_ID_F64_DOCIDS,
+    FACET_ID_I128_DOCIDS,
     FACET_ID_STRING_DOCIDS,
     FIELD_ID_DOCID_FACET_F64S,
     FIELD_ID_DOCID_FACET_STRINGS,
@@ -34,6 +37,7 @@ const ALL_DATABASE_NAMES: &[&str] = &[
 
 const POSTINGS_DATABASE_NAMES: &[&str] = &[
     WORD_DOCIDS,
+    EXACT_WORD_DOCIDS,
     WORD_PREFIX_DOCIDS,
     DOCID_WORD_POSITIONS,
     WORD_PAIR_PROXIMITY_DOCIDS,
@@ -243,6 +247,10 @@ enum Command {
     ExportDocuments {
         /// If defined, only retrieve the documents that corresponds to these internal ids.
         internal_documents_ids: Vec<u32>,
+
+        /// Compress the output with gzip or zstd.
+        #[structopt(long, possible_values = &["gzip", "zstd"])]
+        compress: Option<DumpCompression>,
     },
 }
 
@@ -305,8 +313,8 @@ fn main() -> anyhow::Result<()> {
         }
         ExportWordsFst => export_words_fst(&index, &rtxn),
         ExportWordsPrefixFst => export_words_prefix_fst(&index, &rtxn),
-        ExportDocuments { internal_documents_ids } => {
-            export_documents(&index, &rtxn, internal_documents_ids)
+        ExportDocuments { internal_documents_ids, compress } => {
+            export_documents(&index, &rtxn, internal_documents_ids, compress)
         }
     }
 }
@@ -375,6 +383,7 @@ fn biggest_value_sizes(index: &Index, rtxn: &heed::RoTxn, limit: usize) -> anyho
         env: _env,
         main,
         word_docids,
+        exact_word_docids: _,
         word_prefix_docids,
         docid_word_positions,
         word_pair_proximity_docids,
@@ -383,10 +392,13 @@ fn biggest_value_sizes(index: &Index, rtxn: &heed::RoTxn, limit: usize) -> anyho
         word_prefix_position_docids,
         field_id_word_count_docids,
         facet_id_f64_docids,
+        facet_id_i128_docids: _,
         facet_id_string_docids,
         field_id_docid_facet_f64s: _,
         field_id_docid_facet_strings: _,
         documents,
+        // The other fields of the index are not databases.
+        ..
     } = index;
 
     let main_name = "main";
@@ -854,35 +866,33 @@ fn export_documents(
     index: &Index,
     rtxn: &heed::RoTxn,
     internal_ids: Vec<u32>,
+    compression: Option<DumpCompression>,
 ) -> anyhow::Result<()> {
     use std::io::{BufWriter, Write as _};
 
-    use milli::{obkv_to_json, BEU32};
+    use milli::documents::DumpWriter;
+    use milli::obkv_to_json_writer;
 
     let stdout = io::stdout();
-    let mut out = BufWriter::new(stdout);
+    let mut out = DumpWriter::new(BufWriter::new(stdout), compression)?;
 
     let fields_ids_map = index.fields_ids_map(rtxn)?;
     let displayed_fields: Vec<_> = fields_ids_map.iter().map(|(id, _name)| id).collect();
 
+    // The stored documents may be compressed or encrypted, we read them through the index.
     let iter: Box<dyn Iterator<Item = _>> = if internal_ids.is_empty() {
-        Box::new(index.documents.iter(rtxn)?.map(|result| result.map(|(_id, obkv)| obkv)))
+        Box::new(index.all_documents(rtxn)?)
     } else {
-        Box::new(
-            internal_ids
-                .into_iter()
-                .flat_map(|id| index.documents.get(rtxn, &BEU32::new(id)).transpose()),
-        )
+        Box::new(index.iter_documents(rtxn, internal_ids))
     };
 
     for result in iter {
-        let obkv = result?;
-        let document = obkv_to_json(&displayed_fields, &fields_ids_map, obkv)?;
-        serde_json::to_writer(&mut out, &document)?;
+        let (_id, document) = result?;
+        obkv_to_json_writer(&displayed_fields, &fields_ids_map, document.obkv(), &mut out)?;
         writeln!(&mut out)?;
     }
 
-    out.into_inner()?;
+    out.finish()?.into_inner()?;
 
     Ok(())
 }
@@ -929,15 +939,15 @@ fn average_number_of_words_by_doc(index: &Index, rtxn: &heed::RoTxn) -> anyhow::
 
 fn average_number_of_positions_by_word(index: &Index, rtxn: &heed::RoTxn) -> anyhow::Result<()> {
     use heed::types::DecodeIgnore;
-    use milli::BoRoaringBitmapCodec;
+    use milli::DeltaPositionsLenCodec;
 
     let mut values_length = Vec::new();
     let mut count = 0;
 
     let db = index.docid_word_positions.as_polymorph();
-    for result in db.iter::<_, DecodeIgnore, BoRoaringBitmapCodec>(rtxn)? {
-        let ((), val) = result?;
-        values_length.push(val.len() as u32);
+    for result in db.iter::<_, DecodeIgnore, DeltaPositionsLenCodec>(rtxn)? {
+        let ((), len) = result?;
+        values_length.push(len as u32);
         count += 1;
     }
 
@@ -956,6 +966,7 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
         env: _env,
         main,
         word_docids,
+        exact_word_docids,
         word_prefix_docids,
         docid_word_positions,
         word_pair_proximity_docids,
@@ -964,10 +975,13 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
         word_prefix_position_docids,
         field_id_word_count_docids,
         facet_id_f64_docids,
+        facet_id_i128_docids,
         facet_id_string_docids,
         field_id_docid_facet_f64s,
         field_id_docid_facet_strings,
         documents,
+        // The other fields of the index are not databases.
+        ..
     } = index;
 
     let names = if names.is_empty() {
@@ -981,6 +995,7 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
             MAIN => &main,
             WORD_PREFIX_DOCIDS => word_prefix_docids.as_polymorph(),
             WORD_DOCIDS => word_docids.as_polymorph(),
+            EXACT_WORD_DOCIDS => exact_word_docids.as_polymorph(),
             DOCID_WORD_POSITIONS => docid_word_positions.as_polymorph(),
             WORD_PAIR_PROXIMITY_DOCIDS => word_pair_proximity_docids.as_polymorph(),
             WORD_PREFIX_PAIR_PROXIMITY_DOCIDS => word_prefix_pair_proximity_docids.as_polymorph(),
@@ -988,6 +1003,7 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
             WORD_PREFIX_POSITION_DOCIDS => word_prefix_position_docids.as_polymorph(),
             FIELD_ID_WORD_COUNT_DOCIDS => field_id_word_count_docids.as_polymorph(),
             FACET_ID_F64_DOCIDS => facet_id_f64_docids.as_polymorph(),
+            FACET_ID_I128_DOCIDS => facet_id_i128_docids.as_polymorph(),
             FACET_ID_STRING_DOCIDS => facet_id_string_docids.as_polymorph(),
             FIELD_ID_DOCID_FACET_F64S => field_id_docid_facet_f64s.as_polymorph(),
             FIELD_ID_DOCID_FACET_STRINGS => field_id_docid_facet_strings.as_polymorph(),
@@ -1019,7 +1035,7 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
 fn database_stats(index: &Index, rtxn: &heed::RoTxn, name: &str) -> anyhow::Result<()> {
     use heed::types::ByteSlice;
     use heed::{BytesDecode, Error};
-    use milli::{BoRoaringBitmapCodec, CboRoaringBitmapCodec, RoaringBitmapCodec};
+    use milli::{CboRoaringBitmapCodec, DeltaPositionsCodec, RoaringBitmapCodec};
     use roaring::RoaringBitmap;
 
     fn compute_stats<'a, DC: BytesDecode<'a, DItem = RoaringBitmap>>(
@@ -1076,13 +1092,17 @@ fn database_stats(index: &Index, rtxn: &heed::RoTxn, name: &str) -> anyhow::Resu
             let db = index.word_docids.as_polymorph();
             compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
         }
+        EXACT_WORD_DOCIDS => {
+            let db = index.exact_word_docids.as_polymorph();
+            compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
+        }
         WORD_PREFIX_DOCIDS => {
             let db = index.word_prefix_docids.as_polymorph();
             compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
         }
         DOCID_WORD_POSITIONS => {
             let db = index.docid_word_positions.as_polymorph();
-            compute_stats::<BoRoaringBitmapCodec>(*db, rtxn, name)
+            compute_stats::<DeltaPositionsCodec>(*db, rtxn, name)
         }
         WORD_PAIR_PROXIMITY_DOCIDS => {
             let db = index.word_pair_proximity_docids.as_polymorph();
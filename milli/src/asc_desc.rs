@@ -141,6 +141,15 @@ impl AscDesc {
     }
 }
 
+impl fmt::Display for AscDesc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AscDesc::Asc(member) => write!(f, "{}:asc", member),
+            AscDesc::Desc(member) => write!(f, "{}:desc", member),
+        }
+    }
+}
+
 impl FromStr for AscDesc {
     type Err = AscDescError;
 
@@ -153,6 +162,32 @@ impl FromStr for AscDesc {
     }
 }
 
+/// Parses a list of sort expressions separated by commas, e.g. `price:asc,rating:desc`,
+/// the first expression sorts the documents and the following ones break the ties.
+///
+/// The commas inside of the parentheses of a `_geoPoint(latitude, longitude)` don't
+/// separate the expressions, the empty expressions are ignored.
+pub fn parse_sort_criteria(text: &str) -> Result<Vec<AscDesc>, SortError> {
+    let mut criteria = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ','))) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                let expression = text[start..i].trim();
+                if !expression.is_empty() {
+                    criteria.push(expression.parse::<AscDesc>()?);
+                }
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    Ok(criteria)
+}
+
 #[derive(Debug)]
 pub enum SortError {
     InvalidLatitude,
@@ -318,4 +353,18 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_multiple_sort_criteria() {
+        let criteria =
+            parse_sort_criteria("price:asc, rating:desc,_geoPoint(42, 59):asc,").unwrap();
+        assert_eq!(
+            criteria,
+            vec![Asc(Field(S("price"))), Desc(Field(S("rating"))), Asc(Geo([42., 59.]))]
+        );
+        assert!(parse_sort_criteria("").unwrap().is_empty());
+
+        let error = parse_sort_criteria("price:asc,rating").unwrap_err();
+        assert_eq!(error.to_string(), SortError::InvalidName { name: S("rating") }.to_string());
+    }
 }
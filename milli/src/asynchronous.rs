@@ -0,0 +1,171 @@
+//! An asynchronous facade over an [`Index`], for the servers running on tokio.
+//!
+//! Every operation runs on the blocking pool of the current tokio runtime and opens,
+//! commits or aborts its own transaction, so that no transaction crosses an `await` point.
+
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use heed::{RoTxn, RwTxn};
+
+use crate::documents::DocumentBatchReader;
+use crate::update::{
+    DocumentAdditionResult, IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings,
+};
+use crate::{Index, Result, Search, SearchResult};
+
+/// A cheaply clonable handle to an [`Index`] whose operations return futures.
+#[derive(Clone)]
+pub struct AsyncIndex {
+    index: Index,
+    indexer_config: Arc<IndexerConfig>,
+}
+
+impl AsyncIndex {
+    pub fn new(index: Index, indexer_config: Arc<IndexerConfig>) -> AsyncIndex {
+        AsyncIndex { index, indexer_config }
+    }
+
+    /// Returns the underlying index, to call the synchronous methods.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Runs the closure with a new read transaction on the blocking pool.
+    pub async fn read<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Index, &RoTxn) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let index = self.index.clone();
+        spawn_blocking(move || {
+            let rtxn = index.read_txn()?;
+            f(&index, &rtxn)
+        })
+        .await
+    }
+
+    /// Runs the closure with a new write transaction on the blocking pool,
+    /// the transaction is committed if the closure succeeds and aborted otherwise.
+    pub async fn write<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Index, &mut RwTxn) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let index = self.index.clone();
+        spawn_blocking(move || {
+            let mut wtxn = index.write_txn()?;
+            let output = f(&index, &mut wtxn)?;
+            wtxn.commit()?;
+            Ok(output)
+        })
+        .await
+    }
+
+    /// Executes the search configured by the closure.
+    pub async fn search<F>(&self, configure: F) -> Result<SearchResult>
+    where
+        F: FnOnce(&mut Search) + Send + 'static,
+    {
+        self.read(move |index, rtxn| {
+            let mut search = Search::new(rtxn, index);
+            configure(&mut search);
+            search.execute()
+        })
+        .await
+    }
+
+    /// Indexes the batch of documents and commits the update.
+    pub async fn add_documents<R>(
+        &self,
+        config: IndexDocumentsConfig,
+        documents: DocumentBatchReader<R>,
+    ) -> Result<DocumentAdditionResult>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        let indexer_config = self.indexer_config.clone();
+        self.write(move |index, wtxn| {
+            let mut builder = IndexDocuments::new(wtxn, index, &indexer_config, config, |_| ());
+            builder.add_documents(documents)?;
+            builder.execute()
+        })
+        .await
+    }
+
+    /// Applies the settings configured by the closure and commits the update.
+    pub async fn update_settings<F>(&self, configure: F) -> Result<()>
+    where
+        F: FnOnce(&mut Settings) + Send + 'static,
+    {
+        let indexer_config = self.indexer_config.clone();
+        self.write(move |index, wtxn| {
+            let mut builder = Settings::new(wtxn, index, &indexer_config);
+            configure(&mut builder);
+            builder.execute(|_| ())
+        })
+        .await
+    }
+}
+
+/// Runs the function on the blocking pool, resuming the panic of the function if any.
+async fn spawn_blocking<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(error) => panic!("blocking task failed: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use heed::EnvOpenOptions;
+    use maplit::hashset;
+
+    use super::*;
+
+    #[test]
+    fn search_and_update() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let index = AsyncIndex::new(index, Arc::new(IndexerConfig::default()));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            index
+                .update_settings(|settings| {
+                    settings.set_filterable_fields(hashset! { S("age") });
+                })
+                .await
+                .unwrap();
+
+            let content = documents!([
+                { "id": 0, "name": "kevin", "age": 20 },
+                { "id": 1, "name": "kevina", "age": 21 },
+                { "id": 2, "name": "benoit", "age": 22 }
+            ]);
+            let result =
+                index.add_documents(IndexDocumentsConfig::default(), content).await.unwrap();
+            assert_eq!(result.number_of_documents, 3);
+
+            let result = index
+                .search(|search| {
+                    search.query("kevin");
+                })
+                .await
+                .unwrap();
+            assert_eq!(result.documents_ids, vec![0, 1]);
+
+            let filterable =
+                index.read(|index, rtxn| Ok(index.filterable_fields(rtxn)?)).await.unwrap();
+            assert_eq!(filterable, hashset! { S("age") });
+        });
+    }
+}
@@ -0,0 +1,76 @@
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use linked_hash_map::LinkedHashMap;
+use time::OffsetDateTime;
+
+/// A least recently used cache of values computed from a version of the index.
+///
+/// The version is the last update date of the index as seen by the read transaction,
+/// the entries computed from another version are never returned and are evicted when
+/// a value of a new version is inserted.
+pub(crate) struct LruCache<K, V> {
+    capacity: usize,
+    inner: Mutex<(Option<OffsetDateTime>, LinkedHashMap<K, V>)>,
+}
+
+impl<K: Hash + Eq, V: Clone> LruCache<K, V> {
+    pub(crate) fn new(capacity: usize) -> LruCache<K, V> {
+        LruCache { capacity, inner: Mutex::new((None, LinkedHashMap::new())) }
+    }
+
+    pub(crate) fn get(&self, version: OffsetDateTime, key: &K) -> Option<V> {
+        let mut inner = self.inner.lock().unwrap();
+        let (cache_version, entries) = &mut *inner;
+        if *cache_version != Some(version) {
+            return None;
+        }
+        entries.get_refresh(key).cloned()
+    }
+
+    pub(crate) fn insert(&self, version: OffsetDateTime, key: K, value: V) {
+        let mut inner = self.inner.lock().unwrap();
+        let (cache_version, entries) = &mut *inner;
+        if *cache_version != Some(version) {
+            *cache_version = Some(version);
+            entries.clear();
+        }
+        entries.insert(key, value);
+        while entries.len() > self.capacity {
+            entries.pop_front();
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.inner.lock().unwrap().1.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn least_recently_used_and_versions() {
+        let cache = LruCache::new(2);
+        let version = OffsetDateTime::now_utc();
+        cache.insert(version, "a", 1);
+        cache.insert(version, "b", 2);
+        assert_eq!(cache.get(version, &"a"), Some(1));
+        // "b" is the least recently used entry.
+        cache.insert(version, "c", 3);
+        assert_eq!(cache.get(version, &"b"), None);
+        assert_eq!(cache.get(version, &"a"), Some(1));
+        assert_eq!(cache.get(version, &"c"), Some(3));
+
+        let next_version = version + Duration::SECOND;
+        assert_eq!(cache.get(next_version, &"a"), None);
+        cache.insert(next_version, "d", 4);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(version, &"d"), None);
+        assert_eq!(cache.get(next_version, &"d"), Some(4));
+    }
+}
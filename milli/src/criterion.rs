@@ -12,6 +12,7 @@ pub enum CriterionError {
     ReservedName { name: String },
     ReservedNameForSort { name: String },
     ReservedNameForFilter { name: String },
+    UnknownCustomCriterion { name: String },
 }
 
 impl fmt::Display for CriterionError {
@@ -41,6 +42,13 @@ impl fmt::Display for CriterionError {
                     name, name
                 )
             }
+            Self::UnknownCustomCriterion { name } => {
+                write!(
+                    f,
+                    "`{}:custom` ranking rule has no custom criterion registered under this name",
+                    name
+                )
+            }
         }
     }
 }
@@ -72,6 +80,8 @@ pub enum Criterion {
     Asc(String),
     /// Sorted by the decreasing value of the field specified.
     Desc(String),
+    /// Sorted by the custom criterion registered under this name at search time.
+    Custom(String),
 }
 
 impl Criterion {
@@ -95,17 +105,41 @@ impl FromStr for Criterion {
             "attribute" => Ok(Criterion::Attribute),
             "sort" => Ok(Criterion::Sort),
             "exactness" => Ok(Criterion::Exactness),
-            text => match AscDesc::from_str(text)? {
-                AscDesc::Asc(Member::Field(field)) => Ok(Criterion::Asc(field)),
-                AscDesc::Desc(Member::Field(field)) => Ok(Criterion::Desc(field)),
-                AscDesc::Asc(Member::Geo(_)) | AscDesc::Desc(Member::Geo(_)) => {
-                    Err(CriterionError::ReservedNameForSort { name: "_geoPoint".to_string() })?
+            text => match text.rsplit_once(':') {
+                Some((name, "custom")) if !name.is_empty() => {
+                    Ok(Criterion::Custom(name.to_string()))
                 }
+                _ => match AscDesc::from_str(text)? {
+                    AscDesc::Asc(Member::Field(field)) => Ok(Criterion::Asc(field)),
+                    AscDesc::Desc(Member::Field(field)) => Ok(Criterion::Desc(field)),
+                    AscDesc::Asc(Member::Geo(_)) | AscDesc::Desc(Member::Geo(_)) => {
+                        Err(CriterionError::ReservedNameForSort { name: "_geoPoint".to_string() })?
+                    }
+                },
             },
         }
     }
 }
 
+/// The order in which the words criterion drops the query terms when
+/// the documents don't contain all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TermsDroppingOrder {
+    /// The terms are dropped from the end of the query.
+    Last,
+    /// The terms are dropped from the start of the query.
+    First,
+    /// The terms that are present in the most documents are dropped first.
+    Frequency,
+}
+
+impl Default for TermsDroppingOrder {
+    fn default() -> Self {
+        Self::Last
+    }
+}
+
 pub fn default_criteria() -> Vec<Criterion> {
     vec![
         Criterion::Words,
@@ -130,6 +164,7 @@ impl fmt::Display for Criterion {
             Exactness => f.write_str("exactness"),
             Asc(attr) => write!(f, "{}:asc", attr),
             Desc(attr) => write!(f, "{}:desc", attr),
+            Custom(name) => write!(f, "{}:custom", name),
         }
     }
 }
@@ -156,6 +191,8 @@ mod tests {
             ("truc:machin:desc", Criterion::Desc(S("truc:machin"))),
             ("hello-world!:desc", Criterion::Desc(S("hello-world!"))),
             ("it's spacy over there:asc", Criterion::Asc(S("it's spacy over there"))),
+            ("popularity:custom", Criterion::Custom(S("popularity"))),
+            ("price:desc:custom", Criterion::Custom(S("price:desc"))),
         ];
 
         for (input, expected) in valid_criteria {
@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::error::InternalError;
+use crate::{FieldId, FieldsIdsMap, Result};
+
+/// The obkv of a stored document, borrowed from the LMDB pages for the lifetime of the
+/// transaction or decoded when the documents are compressed or encrypted.
+#[derive(Debug, Clone)]
+pub struct StoredDocument<'t> {
+    bytes: Cow<'t, [u8]>,
+}
+
+impl<'t> StoredDocument<'t> {
+    pub(crate) fn new(bytes: Cow<'t, [u8]>) -> StoredDocument<'t> {
+        StoredDocument { bytes }
+    }
+
+    /// Returns the obkv of the document.
+    pub fn obkv(&self) -> obkv::KvReaderU16<'_> {
+        obkv::KvReaderU16::new(&self.bytes)
+    }
+
+    /// Returns the raw JSON bytes of the field, `None` if the document doesn't have it.
+    pub fn get(&self, field_id: FieldId) -> Option<&[u8]> {
+        self.obkv().get(field_id)
+    }
+
+    /// Returns the fields of the document with their raw JSON bytes, ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = (FieldId, &[u8])> {
+        self.obkv().iter()
+    }
+
+    /// Returns the bytes of the obkv of the document.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A stored document whose fields are read by name and only deserialized when accessed,
+/// instead of converting the whole document with `obkv_to_json`.
+#[derive(Clone, Copy)]
+pub struct Document<'t> {
+    fields_ids_map: &'t FieldsIdsMap,
+    obkv: obkv::KvReaderU16<'t>,
+}
+
+impl<'t> Document<'t> {
+    pub fn new(fields_ids_map: &'t FieldsIdsMap, obkv: obkv::KvReaderU16<'t>) -> Document<'t> {
+        Document { fields_ids_map, obkv }
+    }
+
+    /// Returns the underlying obkv of the document.
+    pub fn obkv(&self) -> obkv::KvReaderU16<'t> {
+        self.obkv
+    }
+
+    /// Returns the raw JSON bytes of the field, `None` if the document doesn't have it.
+    pub fn get_raw(&self, name: &str) -> Option<&'t [u8]> {
+        self.fields_ids_map.id(name).and_then(|id| self.obkv.get(id))
+    }
+
+    /// Returns the string value of the field, borrowed from the document when it
+    /// contains no escaped characters, `None` if the field is missing or isn't a string.
+    pub fn get_str(&self, name: &str) -> Option<Cow<'t, str>> {
+        let bytes = self.get_raw(name)?;
+        match serde_json::from_slice::<&str>(bytes) {
+            Ok(string) => Some(Cow::Borrowed(string)),
+            Err(_) => serde_json::from_slice::<String>(bytes).ok().map(Cow::Owned),
+        }
+    }
+
+    /// Returns the number value of the field, `None` if the field is missing or isn't a number.
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get_raw(name).and_then(|bytes| serde_json::from_slice(bytes).ok())
+    }
+
+    /// Returns the JSON value of the field, `None` if the document doesn't have it.
+    pub fn get_json(&self, name: &str) -> Result<Option<Value>> {
+        match self.get_raw(name) {
+            Some(bytes) => {
+                Ok(Some(serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig};
+
+    #[test]
+    fn typed_getters() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin", "quote": "say \"hello\"", "age": 20, "tags": ["a", "b"] }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let (_, stored) = index.documents(&rtxn, Some(0)).unwrap().remove(0);
+        let document = Document::new(&fields_ids_map, stored.obkv());
+
+        assert!(matches!(document.get_str("name"), Some(Cow::Borrowed("kevin"))));
+        assert_eq!(document.get_str("quote").as_deref(), Some("say \"hello\""));
+        assert_eq!(document.get_str("age"), None);
+        assert_eq!(document.get_f64("age"), Some(20.0));
+        assert_eq!(document.get_f64("name"), None);
+        assert_eq!(document.get_json("tags").unwrap(), Some(json!(["a", "b"])));
+        assert_eq!(document.get_json("unknown").unwrap(), None);
+    }
+}
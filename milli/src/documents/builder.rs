@@ -1,12 +1,13 @@
 use std::collections::BTreeMap;
 use std::io;
-use std::io::{Cursor, Write};
+use std::io::{BufReader, Cursor, Read, Write};
+use std::str;
 
 use byteorder::{BigEndian, WriteBytesExt};
-use serde::Deserializer;
+use serde::{Deserializer, Serialize};
 use serde_json::Value;
 
-use super::serde::DocumentVisitor;
+use super::serde::{DocumentSerializer, DocumentVisitor};
 use super::{ByteCounter, DocumentsBatchIndex, DocumentsMetadata, Error};
 use crate::FieldId;
 
@@ -35,6 +36,8 @@ pub struct DocumentBatchBuilder<W> {
     value_buffer: Vec<u8>,
     values: BTreeMap<FieldId, Value>,
     count: usize,
+    lossy_utf8: bool,
+    replaced_utf8_sequences: usize,
 }
 
 impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
@@ -51,9 +54,22 @@ impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
             value_buffer: Vec::new(),
             values: BTreeMap::new(),
             count: 0,
+            lossy_utf8: false,
+            replaced_utf8_sequences: 0,
         })
     }
 
+    /// Replaces the invalid UTF-8 sequences of the json documents with the `U+FFFD` replacement
+    /// character instead of failing.
+    pub fn set_lossy_utf8(&mut self, lossy_utf8: bool) {
+        self.lossy_utf8 = lossy_utf8;
+    }
+
+    /// Returns the number of invalid UTF-8 sequences that have been replaced so far.
+    pub fn replaced_utf8_sequences(&self) -> usize {
+        self.replaced_utf8_sequences
+    }
+
     /// Returns the number of documents that have been written to the builder.
     pub fn len(&self) -> usize {
         self.count
@@ -78,8 +94,22 @@ impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
     }
 
     /// Extends the builder with json documents from a reader.
+    ///
+    /// The documents of a top-level array are written one by one while the array is parsed,
+    /// the whole input is never held in memory.
     pub fn extend_from_json<R: io::Read>(&mut self, reader: R) -> Result<(), Error> {
-        let mut de = serde_json::Deserializer::from_reader(reader);
+        if self.lossy_utf8 {
+            let mut reader = LossyUtf8Reader::new(reader);
+            let result = self.extend_from_valid_json(&mut reader);
+            self.replaced_utf8_sequences += reader.replaced;
+            return result;
+        }
+
+        self.extend_from_valid_json(reader)
+    }
+
+    fn extend_from_valid_json<R: io::Read>(&mut self, reader: R) -> Result<(), Error> {
+        let mut de = serde_json::Deserializer::from_reader(BufReader::new(reader));
 
         let mut visitor = DocumentVisitor {
             inner: &mut self.inner,
@@ -93,44 +123,125 @@ impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
         de.deserialize_any(&mut visitor).map_err(Error::JsonError)?
     }
 
+    /// Appends a document serialized from a map or a struct, the value of each field is
+    /// serialized as JSON directly in the batch, without going through a `serde_json::Value`.
+    pub fn append<T: Serialize>(&mut self, document: &T) -> Result<(), Error> {
+        self.value_buffer.clear();
+        let mut fields = BTreeMap::new();
+        document.serialize(&mut DocumentSerializer {
+            index: &mut self.index,
+            buffer: &mut self.value_buffer,
+            fields: &mut fields,
+            key: None,
+        })?;
+
+        self.obkv_buffer.clear();
+        let mut writer = obkv::KvWriter::new(&mut self.obkv_buffer);
+        for (fid, range) in fields {
+            writer.insert(fid, &self.value_buffer[range])?;
+        }
+
+        self.inner.write_u32::<BigEndian>(self.obkv_buffer.len() as u32)?;
+        self.inner.write_all(&self.obkv_buffer)?;
+
+        self.count += 1;
+
+        Ok(())
+    }
+
     /// Creates a builder from a reader of CSV documents.
     ///
     /// Since all fields in a csv documents are guaranteed to be ordered, we are able to perform
     /// optimisations, and extending from another CSV is not allowed.
     pub fn from_csv<R: io::Read>(reader: R, writer: W) -> Result<Self, Error> {
+        Self::from_csv_inner(reader, writer, None)
+    }
+
+    /// Creates a builder from a reader of CSV documents, inferring the type of the columns
+    /// whose header isn't annotated from their values in the first `sample_size` rows.
+    ///
+    /// A column whose sampled values are all numbers, or all booleans, gets this type, and a
+    /// string type otherwise. The values of the next rows that don't have the inferred type
+    /// are kept as strings.
+    pub fn from_csv_with_inference<R: io::Read>(
+        reader: R,
+        writer: W,
+        sample_size: usize,
+    ) -> Result<Self, Error> {
+        Self::from_csv_inner(reader, writer, Some(sample_size))
+    }
+
+    fn from_csv_inner<R: io::Read>(
+        reader: R,
+        writer: W,
+        sample_size: Option<usize>,
+    ) -> Result<Self, Error> {
         let mut this = Self::new(writer)?;
         // Ensure that this is the first and only addition made with this builder
         debug_assert!(this.index.is_empty());
 
         let mut records = csv::Reader::from_reader(reader);
 
-        let headers = records
+        let headers: Vec<_> = records
             .headers()?
             .into_iter()
             .map(parse_csv_header)
             .map(|(k, t)| (this.index.insert(&k), t))
-            .collect::<BTreeMap<_, _>>();
+            .collect();
+
+        // The sampled records are kept to be written once the types are known.
+        let mut records = records.into_records();
+        let mut sample = Vec::new();
+        if let Some(sample_size) = sample_size {
+            for record in records.by_ref().take(sample_size) {
+                sample.push(record?);
+            }
+        }
+
+        let headers: Vec<_> = headers
+            .into_iter()
+            .enumerate()
+            .map(|(i, (fid, ty))| match ty {
+                // The values of the annotated columns must have their type.
+                Some(ty) => (fid, ty, true),
+                None if sample_size.is_some() => {
+                    let values = sample.iter().filter_map(|record| record.get(i));
+                    (fid, infer_csv_type(values), false)
+                }
+                None => (fid, AllowedType::String, true),
+            })
+            .collect();
 
-        for (i, record) in records.into_records().enumerate() {
+        let sample = sample.into_iter().map(Ok);
+        for (i, record) in sample.chain(records).enumerate() {
             let record = record?;
             this.obkv_buffer.clear();
             let mut writer = obkv::KvWriter::new(&mut this.obkv_buffer);
-            for (value, (fid, ty)) in record.into_iter().zip(headers.iter()) {
+            for (value, (fid, ty, strict)) in record.into_iter().zip(headers.iter()) {
                 let value = match ty {
                     AllowedType::Number => {
                         if value.trim().is_empty() {
                             Value::Null
                         } else {
-                            value.trim().parse::<f64>().map(Value::from).map_err(|error| {
-                                Error::ParseFloat {
-                                    error,
-                                    // +1 for the header offset.
-                                    line: i + 1,
-                                    value: value.to_string(),
+                            match value.trim().parse::<f64>() {
+                                Ok(number) => Value::from(number),
+                                Err(_) if !strict => Value::String(value.to_string()),
+                                Err(error) => {
+                                    return Err(Error::ParseFloat {
+                                        error,
+                                        // +1 for the header offset.
+                                        line: i + 1,
+                                        value: value.to_string(),
+                                    });
                                 }
-                            })?
+                            }
                         }
                     }
+                    AllowedType::Boolean if value.trim().is_empty() => Value::Null,
+                    AllowedType::Boolean => match parse_csv_bool(value) {
+                        Some(boolean) => Value::Bool(boolean),
+                        None => Value::String(value.to_string()),
+                    },
                     AllowedType::String => {
                         if value.is_empty() {
                             Value::Null
@@ -153,24 +264,150 @@ impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
 
         Ok(this)
     }
+
+    /// Creates a builder from a reader of CSV documents, replacing the invalid UTF-8 sequences
+    /// with the `U+FFFD` replacement character instead of failing.
+    pub fn from_csv_lossy<R: io::Read>(reader: R, writer: W) -> Result<Self, Error> {
+        let mut reader = LossyUtf8Reader::new(reader);
+        let mut this = Self::from_csv(&mut reader, writer)?;
+        this.lossy_utf8 = true;
+        this.replaced_utf8_sequences = reader.replaced;
+
+        Ok(this)
+    }
+}
+
+/// A reader decoding the bytes of the inner reader as UTF-8 like `String::from_utf8_lossy`
+/// does, chunk by chunk, and counting the number of invalid sequences that were replaced.
+struct LossyUtf8Reader<R> {
+    inner: R,
+    eof: bool,
+    /// The bytes read from the inner reader that are not decoded yet,
+    /// the start of a character split between two reads.
+    input: Vec<u8>,
+    output: Vec<u8>,
+    output_position: usize,
+    replaced: usize,
+}
+
+impl<R: io::Read> LossyUtf8Reader<R> {
+    fn new(inner: R) -> LossyUtf8Reader<R> {
+        LossyUtf8Reader {
+            inner,
+            eof: false,
+            input: Vec::new(),
+            output: Vec::new(),
+            output_position: 0,
+            replaced: 0,
+        }
+    }
+
+    /// Reads a chunk of the inner reader and decodes it into the output buffer.
+    fn fill_output(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 8192];
+        let read = loop {
+            match self.inner.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        self.eof = read == 0;
+        self.input.extend_from_slice(&chunk[..read]);
+
+        self.output.clear();
+        self.output_position = 0;
+        let mut bytes = &self.input[..];
+        while !bytes.is_empty() {
+            match str::from_utf8(bytes) {
+                Ok(_) => {
+                    self.output.extend_from_slice(bytes);
+                    bytes = &[];
+                }
+                Err(error) => {
+                    let (valid, invalid) = bytes.split_at(error.valid_up_to());
+                    self.output.extend_from_slice(valid);
+                    let len = match error.error_len() {
+                        Some(len) => len,
+                        // The sequence may be completed by the next chunk.
+                        None if !self.eof => {
+                            bytes = invalid;
+                            break;
+                        }
+                        None => invalid.len(),
+                    };
+                    bytes = &invalid[len..];
+                    let mut replacement = [0; 3];
+                    let replacement = char::REPLACEMENT_CHARACTER.encode_utf8(&mut replacement);
+                    self.output.extend_from_slice(replacement.as_bytes());
+                    self.replaced += 1;
+                }
+            }
+        }
+
+        let consumed = self.input.len() - bytes.len();
+        self.input.drain(..consumed);
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+impl<R: io::Read> Read for LossyUtf8Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_position == self.output.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            self.fill_output()?;
+        }
+
+        let available = &self.output[self.output_position..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.output_position += len;
+        Ok(len)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum AllowedType {
     String,
     Number,
+    Boolean,
 }
 
-fn parse_csv_header(header: &str) -> (String, AllowedType) {
+/// Returns the name of the field and its type, `None` if the header isn't annotated.
+fn parse_csv_header(header: &str) -> (String, Option<AllowedType>) {
     // if there are several separators we only split on the last one.
     match header.rsplit_once(':') {
         Some((field_name, field_type)) => match field_type {
-            "string" => (field_name.to_string(), AllowedType::String),
-            "number" => (field_name.to_string(), AllowedType::Number),
+            "string" => (field_name.to_string(), Some(AllowedType::String)),
+            "number" => (field_name.to_string(), Some(AllowedType::Number)),
             // if the pattern isn't reconized, we keep the whole field.
-            _otherwise => (header.to_string(), AllowedType::String),
+            _otherwise => (header.to_string(), None),
         },
-        None => (header.to_string(), AllowedType::String),
+        None => (header.to_string(), None),
+    }
+}
+
+/// Returns the type shared by all the non-empty values, a string if they are all empty.
+fn infer_csv_type<'a>(values: impl Iterator<Item = &'a str>) -> AllowedType {
+    let values: Vec<_> = values.map(str::trim).filter(|value| !value.is_empty()).collect();
+    if values.is_empty() {
+        AllowedType::String
+    } else if values.iter().all(|value| value.parse::<f64>().is_ok()) {
+        AllowedType::Number
+    } else if values.iter().all(|value| parse_csv_bool(value).is_some()) {
+        AllowedType::Boolean
+    } else {
+        AllowedType::String
+    }
+}
+
+fn parse_csv_bool(value: &str) -> Option<bool> {
+    match value.trim() {
+        value if value.eq_ignore_ascii_case("true") => Some(true),
+        value if value.eq_ignore_ascii_case("false") => Some(false),
+        _otherwise => None,
     }
 }
 
@@ -235,6 +472,50 @@ mod test {
         assert!(reader.next_document_with_index().unwrap().is_none());
     }
 
+    #[test]
+    fn append_serializable_documents() {
+        #[derive(Serialize)]
+        struct Movie {
+            id: u32,
+            title: String,
+            genres: Vec<&'static str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            rating: Option<f64>,
+        }
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        let movie =
+            Movie { id: 1, title: "Carol".to_string(), genres: vec!["Romance"], rating: None };
+        builder.append(&movie).unwrap();
+        let movie =
+            Movie { id: 2, title: "Wonder Woman".to_string(), genres: vec![], rating: Some(7.5) };
+        builder.append(&movie).unwrap();
+        builder.append(&json!({ "id": 3, "title": "Life of Pi", "extra": { "a": null } })).unwrap();
+
+        assert!(builder.append(&json!([1, 2])).is_err());
+        assert!(builder.append(&maplit::btreemap! { 1 => "a" }).is_err());
+        assert_eq!(builder.len(), 3);
+
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let mut reader = DocumentBatchReader::from_reader(cursor).unwrap();
+
+        let mut documents = Vec::new();
+        while let Some((index, document)) = reader.next_document_with_index().unwrap() {
+            documents.push(obkv_to_value(&document, index));
+        }
+
+        assert_eq!(
+            documents,
+            vec![
+                json!({ "id": 1, "title": "Carol", "genres": ["Romance"] }),
+                json!({ "id": 2, "title": "Wonder Woman", "genres": [], "rating": 7.5 }),
+                json!({ "id": 3, "title": "Life of Pi", "extra": { "a": null } }),
+            ]
+        );
+    }
+
     #[test]
     fn add_documents_seq_json() {
         let mut cursor = Cursor::new(Vec::new());
@@ -321,6 +602,111 @@ mod test {
         assert!(reader.next_document_with_index().unwrap().is_none());
     }
 
+    #[test]
+    fn csv_type_inference() {
+        let documents = "id,price:string,rating,available,name,empty\n\
+1,10,4.5,true,foo,\n\
+2,20, 3 ,FALSE,12,\n\
+3,30,,true,bar,\n\
+4,40,n/a,maybe,baz,";
+
+        let mut buf = Vec::new();
+        DocumentBatchBuilder::from_csv_with_inference(
+            documents.as_bytes(),
+            Cursor::new(&mut buf),
+            3,
+        )
+        .unwrap()
+        .finish()
+        .unwrap();
+        let mut reader = DocumentBatchReader::from_reader(Cursor::new(buf)).unwrap();
+        let mut values = Vec::new();
+        while let Some((index, doc)) = reader.next_document_with_index().unwrap() {
+            values.push(obkv_to_value(&doc, index));
+        }
+
+        assert_eq!(
+            values,
+            vec![
+                json!({ "id": 1.0, "price": "10", "rating": 4.5, "available": true, "name": "foo", "empty": null }),
+                json!({ "id": 2.0, "price": "20", "rating": 3.0, "available": false, "name": "12", "empty": null }),
+                json!({ "id": 3.0, "price": "30", "rating": null, "available": true, "name": "bar", "empty": null }),
+                // The values that don't have the type inferred from the sample are kept as strings.
+                json!({ "id": 4.0, "price": "40", "rating": "n/a", "available": "maybe", "name": "baz", "empty": null }),
+            ]
+        );
+
+        // The annotated columns are still checked.
+        let documents = "id:number\n1\nfoo";
+        let result = DocumentBatchBuilder::from_csv_with_inference(
+            documents.as_bytes(),
+            Cursor::new(Vec::new()),
+            1,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn lossy_utf8_json() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        assert!(builder.extend_from_json(&b"{\"id\": 1, \"name\": \"caf\xe9\"}"[..]).is_err());
+
+        builder.set_lossy_utf8(true);
+        builder
+            .extend_from_json(&b"[{\"id\": 1, \"name\": \"caf\xe9 \xf0\x9f\"}, {\"id\": 2}]"[..])
+            .unwrap();
+        assert_eq!(builder.replaced_utf8_sequences(), 2);
+        builder.finish().unwrap();
+
+        cursor.set_position(0);
+        let mut reader = DocumentBatchReader::from_reader(cursor).unwrap();
+        let (index, doc) = reader.next_document_with_index().unwrap().unwrap();
+        let val = obkv_to_value(&doc, index);
+        assert_eq!(val, json!({ "id": 1, "name": "caf\u{FFFD} \u{FFFD}" }));
+    }
+
+    #[test]
+    fn lossy_utf8_csv() {
+        let documents = &b"city,country\n\"Bogot\xe1\",\"Colombia\""[..];
+
+        let mut buf = Vec::new();
+        let builder =
+            DocumentBatchBuilder::from_csv_lossy(documents, Cursor::new(&mut buf)).unwrap();
+        assert_eq!(builder.replaced_utf8_sequences(), 1);
+        builder.finish().unwrap();
+
+        let mut reader = DocumentBatchReader::from_reader(Cursor::new(buf)).unwrap();
+        let (index, doc) = reader.next_document_with_index().unwrap().unwrap();
+        let val = obkv_to_value(&doc, index);
+        assert_eq!(val, json!({ "city": "Bogot\u{FFFD}", "country": "Colombia" }));
+    }
+
+    #[test]
+    fn lossy_utf8_reader_split_sequences() {
+        // A reader returning a single byte at a time splits every multi-bytes character.
+        struct ByteByByte<'a>(&'a [u8]);
+
+        impl Read for ByteByByte<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.split_first() {
+                    Some((byte, rest)) if !buf.is_empty() => {
+                        buf[0] = *byte;
+                        self.0 = rest;
+                        Ok(1)
+                    }
+                    _ => Ok(0),
+                }
+            }
+        }
+
+        let mut reader = LossyUtf8Reader::new(ByteByByte(b"caf\xc3\xa9 \xe9 \xf0\x9f"));
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "caf\u{e9} \u{FFFD} \u{FFFD}");
+        assert_eq!(reader.replaced, 2);
+    }
+
     #[test]
     fn coma_in_field() {
         let documents = r#"city,country,pop
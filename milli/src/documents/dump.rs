@@ -0,0 +1,193 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use flate2::write::GzEncoder;
+use heed::RoTxn;
+
+use crate::{obkv_to_json_writer, FieldId, Index, Result};
+
+/// The zstd level used to compress the dumps, the default level of zstd.
+#[cfg(feature = "compression")]
+const DUMP_ZSTD_LEVEL: i32 = 3;
+
+/// The compression of a documents dump.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DumpCompression {
+    Gzip,
+    #[cfg(feature = "compression")]
+    Zstd,
+}
+
+impl fmt::Display for DumpCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DumpCompression::Gzip => f.write_str("gzip"),
+            #[cfg(feature = "compression")]
+            DumpCompression::Zstd => f.write_str("zstd"),
+        }
+    }
+}
+
+impl FromStr for DumpCompression {
+    type Err = InvalidDumpCompression;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("gzip") {
+            return Ok(DumpCompression::Gzip);
+        }
+        #[cfg(feature = "compression")]
+        if s.trim().eq_ignore_ascii_case("zstd") {
+            return Ok(DumpCompression::Zstd);
+        }
+        Err(InvalidDumpCompression)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidDumpCompression;
+
+impl fmt::Display for InvalidDumpCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "compression")]
+        let valid = r#""gzip" or "zstd""#;
+        #[cfg(not(feature = "compression"))]
+        let valid = r#""gzip""#;
+        write!(f, "Invalid dump compression, must be {}", valid)
+    }
+}
+
+impl Error for InvalidDumpCompression {}
+
+/// A writer compressing what is written into the inner writer, if a compression is given.
+///
+/// The compressed stream is only complete once [`DumpWriter::finish`] has been called.
+pub enum DumpWriter<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "compression")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> DumpWriter<W> {
+    pub fn new(writer: W, compression: Option<DumpCompression>) -> io::Result<DumpWriter<W>> {
+        match compression {
+            None => Ok(DumpWriter::Plain(writer)),
+            Some(DumpCompression::Gzip) => {
+                Ok(DumpWriter::Gzip(GzEncoder::new(writer, flate2::Compression::default())))
+            }
+            #[cfg(feature = "compression")]
+            Some(DumpCompression::Zstd) => {
+                Ok(DumpWriter::Zstd(zstd::Encoder::new(writer, DUMP_ZSTD_LEVEL)?))
+            }
+        }
+    }
+
+    /// Writes the end of the compressed stream and returns the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            DumpWriter::Plain(writer) => Ok(writer),
+            DumpWriter::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "compression")]
+            DumpWriter::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for DumpWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            DumpWriter::Plain(writer) => writer.write(buf),
+            DumpWriter::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "compression")]
+            DumpWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            DumpWriter::Plain(writer) => writer.flush(),
+            DumpWriter::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "compression")]
+            DumpWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Writes every document of the index, with all of its fields, as JSON lines,
+/// compressed with the given compression if any.
+///
+/// Returns the number of documents written.
+pub fn dump_documents<W: Write>(
+    index: &Index,
+    rtxn: &RoTxn,
+    writer: W,
+    compression: Option<DumpCompression>,
+) -> Result<u64> {
+    let fields_ids_map = index.fields_ids_map(rtxn)?;
+    let fields: Vec<FieldId> = fields_ids_map.iter().map(|(id, _name)| id).collect();
+
+    let mut writer = DumpWriter::new(writer, compression)?;
+    let mut count = 0;
+    for result in index.all_documents(rtxn)? {
+        let (_docid, document) = result?;
+        obkv_to_json_writer(&fields, &fields_ids_map, document.obkv(), &mut writer)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.finish()?.flush()?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn compressed_dumps() {
+        let index = TempIndex::new();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "hello world" },
+                { "id": 1, "title": "hello there", "tags": ["a", "b"] },
+            ]))
+            .unwrap();
+        let expected = "{\"id\":0,\"title\":\"hello world\"}\n\
+                        {\"id\":1,\"title\":\"hello there\",\"tags\":[\"a\",\"b\"]}\n";
+
+        let rtxn = index.read_txn().unwrap();
+        let mut plain = Vec::new();
+        assert_eq!(dump_documents(&index, &rtxn, &mut plain, None).unwrap(), 2);
+        assert_eq!(String::from_utf8(plain).unwrap(), expected);
+
+        let mut gzip = Vec::new();
+        dump_documents(&index, &rtxn, &mut gzip, Some(DumpCompression::Gzip)).unwrap();
+        let mut decompressed = String::new();
+        GzDecoder::new(&gzip[..]).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, expected);
+
+        #[cfg(feature = "compression")]
+        {
+            let mut zstd = Vec::new();
+            dump_documents(&index, &rtxn, &mut zstd, Some(DumpCompression::Zstd)).unwrap();
+            let decompressed = zstd::stream::decode_all(&zstd[..]).unwrap();
+            assert_eq!(String::from_utf8(decompressed).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn parse_dump_compression() {
+        assert_eq!("gzip".parse(), Ok(DumpCompression::Gzip));
+        #[cfg(feature = "compression")]
+        assert_eq!(" ZSTD".parse(), Ok(DumpCompression::Zstd));
+        assert_eq!("brotli".parse::<DumpCompression>(), Err(InvalidDumpCompression));
+    }
+}
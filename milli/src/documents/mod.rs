@@ -1,4 +1,5 @@
 mod builder;
+mod dump;
 /// The documents module defines an intermediary document format that milli uses for indexation, and
 /// provides an API to easily build and read such documents.
 ///
@@ -13,6 +14,7 @@ use std::io;
 use ::serde::{Deserialize, Serialize};
 use bimap::BiHashMap;
 pub use builder::DocumentBatchBuilder;
+pub use dump::{dump_documents, DumpCompression, DumpWriter, InvalidDumpCompression};
 pub use reader::DocumentBatchReader;
 
 use crate::FieldId;
@@ -49,6 +51,10 @@ impl DocumentsBatchIndex {
     pub fn name(&self, id: FieldId) -> Option<&String> {
         self.0.get_by_left(&id)
     }
+
+    pub fn id(&self, name: &str) -> Option<FieldId> {
+        self.0.get_by_right(name).cloned()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,7 +88,11 @@ impl<W: io::Write> io::Write for ByteCounter<W> {
 
 #[derive(Debug)]
 pub enum Error {
-    ParseFloat { error: std::num::ParseFloatError, line: usize, value: String },
+    ParseFloat {
+        error: std::num::ParseFloatError,
+        line: usize,
+        value: String,
+    },
     InvalidDocumentFormat,
     Custom(String),
     JsonError(serde_json::Error),
@@ -90,6 +100,12 @@ pub enum Error {
     Serialize(bincode::Error),
     Io(io::Error),
     DocumentTooLarge,
+    /// The batch is corrupted at the given byte offset, in the given document if any.
+    InvalidBatch {
+        offset: u64,
+        document: Option<usize>,
+        message: String,
+    },
 }
 
 impl From<csv::Error> for Error {
@@ -127,6 +143,12 @@ impl fmt::Display for Error {
             Error::JsonError(err) => write!(f, "Couldn't serialize document value: {}", err),
             Error::Io(e) => write!(f, "{}", e),
             Error::DocumentTooLarge => f.write_str("Provided document is too large (>2Gib)"),
+            Error::InvalidBatch { offset, document: Some(document), message } => {
+                write!(f, "Invalid document {} at byte {}: {}", document, offset, message)
+            }
+            Error::InvalidBatch { offset, document: None, message } => {
+                write!(f, "Invalid documents batch at byte {}: {}", offset, message)
+            }
             Error::Serialize(e) => write!(f, "{}", e),
             Error::CsvError(e) => write!(f, "{}", e),
         }
@@ -293,6 +315,49 @@ mod test {
         assert_eq!(nested, json!({ "toto": ["hello"] }));
     }
 
+    #[test]
+    fn validate_batch() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        let docs = serde_json::to_vec(&json!([{ "id": 1 }, { "id": 2, "name": "kevin" }])).unwrap();
+        builder.extend_from_json(Cursor::new(docs)).unwrap();
+        builder.finish().unwrap();
+        let bytes = cursor.into_inner();
+
+        let mut documents = DocumentBatchReader::from_reader(Cursor::new(bytes.clone())).unwrap();
+        documents.next_document_with_index().unwrap().unwrap();
+        documents.validate().unwrap();
+        // The reader is rewound to the first document.
+        assert!(documents.next_document_with_index().unwrap().is_some());
+        assert!(documents.next_document_with_index().unwrap().is_some());
+        assert!(documents.next_document_with_index().unwrap().is_none());
+
+        // The length of the first document overlaps the metadata.
+        let mut corrupted = bytes.clone();
+        corrupted[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        let mut documents = DocumentBatchReader::from_reader(Cursor::new(corrupted)).unwrap();
+        assert!(matches!(
+            documents.validate(),
+            Err(Error::InvalidBatch { offset: 8, document: Some(0), .. })
+        ));
+
+        // The field id of the first document is not in the index.
+        let mut corrupted = bytes.clone();
+        corrupted[12..14].copy_from_slice(&u16::MAX.to_be_bytes());
+        let mut documents = DocumentBatchReader::from_reader(Cursor::new(corrupted)).unwrap();
+        assert!(matches!(
+            documents.validate(),
+            Err(Error::InvalidBatch { offset: 12, document: Some(0), .. })
+        ));
+
+        // The value of the first document is not JSON.
+        let mut corrupted = bytes;
+        corrupted[18] = b'{';
+        let mut documents = DocumentBatchReader::from_reader(Cursor::new(corrupted)).unwrap();
+        let error = documents.validate().unwrap_err();
+        assert!(error.to_string().starts_with("Invalid document 0 at byte 12:"), "{}", error);
+    }
+
     #[test]
     fn out_of_order_fields() {
         let _documents = documents!([
@@ -2,8 +2,9 @@ use std::io;
 use std::io::{BufReader, Read};
 use std::mem::size_of;
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use obkv::KvReader;
+use serde::de::IgnoredAny;
 
 use super::{DocumentsBatchIndex, DocumentsMetadata, Error};
 use crate::FieldId;
@@ -16,6 +17,7 @@ use crate::FieldId;
 pub struct DocumentBatchReader<R> {
     reader: BufReader<R>,
     metadata: DocumentsMetadata,
+    metadata_offset: u64,
     buffer: Vec<u8>,
     seen_documents: usize,
 }
@@ -28,8 +30,8 @@ impl<R: io::Read + io::Seek> DocumentBatchReader<R> {
     pub fn from_reader(mut reader: R) -> Result<Self, Error> {
         let mut buffer = Vec::new();
 
-        let meta_offset = reader.read_u64::<BigEndian>()?;
-        reader.seek(io::SeekFrom::Start(meta_offset))?;
+        let metadata_offset = reader.read_u64::<BigEndian>()?;
+        reader.seek(io::SeekFrom::Start(metadata_offset))?;
         reader.read_to_end(&mut buffer)?;
         let metadata: DocumentsMetadata = bincode::deserialize(&buffer)?;
 
@@ -38,7 +40,7 @@ impl<R: io::Read + io::Seek> DocumentBatchReader<R> {
 
         let reader = BufReader::new(reader);
 
-        Ok(Self { reader, metadata, buffer, seen_documents: 0 })
+        Ok(Self { reader, metadata, metadata_offset, buffer, seen_documents: 0 })
     }
 
     /// Returns the next document in the reader, and wraps it in an `obkv::KvReader`, along with a
@@ -59,6 +61,58 @@ impl<R: io::Read + io::Seek> DocumentBatchReader<R> {
         }
     }
 
+    /// Checks that the documents fill the batch up to its metadata, that they are well-formed
+    /// obkvs with increasing field ids known by the index and JSON values, and rewinds the
+    /// reader to the first document.
+    ///
+    /// The field names are already known to be valid UTF-8 once the reader is constructed.
+    pub fn validate(&mut self) -> Result<(), Error> {
+        let header_size = size_of::<u64>() as u64;
+        if self.metadata_offset < header_size {
+            let message = format!("the metadata offset {} is in the header", self.metadata_offset);
+            return Err(Error::InvalidBatch { offset: 0, document: None, message });
+        }
+
+        self.reader.seek(io::SeekFrom::Start(header_size))?;
+        let mut offset = header_size;
+        for document in 0..self.metadata.count {
+            if offset + size_of::<u32>() as u64 > self.metadata_offset {
+                let message = format!(
+                    "the batch contains {} documents but {} are announced",
+                    document, self.metadata.count
+                );
+                return Err(Error::InvalidBatch { offset, document: Some(document), message });
+            }
+            let length = self.reader.read_u32::<BigEndian>()? as u64;
+            let start = offset + size_of::<u32>() as u64;
+            if start + length > self.metadata_offset {
+                let message = format!("the document length {} overlaps the metadata", length);
+                return Err(Error::InvalidBatch { offset, document: Some(document), message });
+            }
+
+            self.buffer.resize(length as usize, 0);
+            self.reader.read_exact(&mut self.buffer)?;
+            if let Err((position, message)) = validate_obkv(&self.buffer, &self.metadata.index) {
+                let offset = start + position as u64;
+                return Err(Error::InvalidBatch { offset, document: Some(document), message });
+            }
+            offset = start + length;
+        }
+
+        if offset != self.metadata_offset {
+            let message = format!(
+                "{} unexpected bytes after the last document",
+                self.metadata_offset.saturating_sub(offset)
+            );
+            return Err(Error::InvalidBatch { offset, document: None, message });
+        }
+
+        self.reader.seek(io::SeekFrom::Start(header_size))?;
+        self.seen_documents = 0;
+
+        Ok(())
+    }
+
     /// Return the fields index for the documents batch.
     pub fn index(&self) -> &DocumentsBatchIndex {
         &self.metadata.index
@@ -73,3 +127,49 @@ impl<R: io::Read + io::Seek> DocumentBatchReader<R> {
         self.len() == 0
     }
 }
+
+/// Checks the entries of an obkv, a field id, the length of the value and the value,
+/// returns the position of the invalid entry in the document and the reason.
+fn validate_obkv(bytes: &[u8], index: &DocumentsBatchIndex) -> Result<(), (usize, String)> {
+    let mut position = 0;
+    let mut last_field_id = None;
+    while position < bytes.len() {
+        let entry = &bytes[position..];
+        if entry.len() < size_of::<FieldId>() + size_of::<u32>() {
+            return Err((position, String::from("truncated field header")));
+        }
+        let field_id = BigEndian::read_u16(entry);
+        if last_field_id.map_or(false, |last| field_id <= last) {
+            return Err((
+                position,
+                format!("the field id {} is not in increasing order", field_id),
+            ));
+        }
+        if index.name(field_id).is_none() {
+            return Err((position, format!("the field id {} is not in the index", field_id)));
+        }
+
+        let value_start = size_of::<FieldId>() + size_of::<u32>();
+        let length = BigEndian::read_u32(&entry[size_of::<FieldId>()..]) as usize;
+        let value = match entry.get(value_start..value_start + length) {
+            Some(value) => value,
+            None => {
+                return Err((
+                    position,
+                    format!("the value of the field id {} is truncated", field_id),
+                ))
+            }
+        };
+        if let Err(error) = serde_json::from_slice::<IgnoredAny>(value) {
+            return Err((
+                position,
+                format!("the value of the field id {} is not JSON: {}", field_id, error),
+            ));
+        }
+
+        last_field_id = Some(field_id);
+        position += value_start + length;
+    }
+
+    Ok(())
+}
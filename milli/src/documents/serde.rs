@@ -1,9 +1,11 @@
 use std::collections::BTreeMap;
 use std::fmt;
 use std::io::{Cursor, Write};
+use std::ops::Range;
 
 use byteorder::WriteBytesExt;
 use serde::de::{DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -132,3 +134,188 @@ where
         deserializer.deserialize_map(self)
     }
 }
+
+/// Serializes a document, a map or a struct, by writing the JSON value of each of its fields
+/// in the buffer, the fields are keyed by their field id in the batch.
+pub struct DocumentSerializer<'a> {
+    pub index: &'a mut DocumentsBatchIndex,
+    pub buffer: &'a mut Vec<u8>,
+    pub fields: &'a mut BTreeMap<FieldId, Range<usize>>,
+    /// The field of the map entry whose value is being serialized.
+    pub key: Option<FieldId>,
+}
+
+impl DocumentSerializer<'_> {
+    fn serialize_field_value<T: ?Sized + Serialize>(
+        &mut self,
+        fid: FieldId,
+        value: &T,
+    ) -> Result<(), serde_json::Error> {
+        let start = self.buffer.len();
+        serde_json::to_writer(&mut *self.buffer, value)?;
+        self.fields.insert(fid, start..self.buffer.len());
+        Ok(())
+    }
+}
+
+fn not_a_document() -> serde_json::Error {
+    ser::Error::custom("a document must be a map or a struct")
+}
+
+macro_rules! reject {
+    ($($method:ident($($ty:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Self::Error> {
+                Err(not_a_document())
+            }
+        )*
+    };
+}
+
+impl<'a, 'b> Serializer for &'b mut DocumentSerializer<'a> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    type SerializeSeq = Impossible<(), serde_json::Error>;
+    type SerializeTuple = Impossible<(), serde_json::Error>;
+    type SerializeTupleStruct = Impossible<(), serde_json::Error>;
+    type SerializeTupleVariant = Impossible<(), serde_json::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), serde_json::Error>;
+
+    reject! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Self::Ok, Self::Error> {
+        Err(not_a_document())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(not_a_document())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(not_a_document())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(not_a_document())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(not_a_document())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(not_a_document())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(not_a_document())
+    }
+}
+
+impl<'a, 'b> SerializeMap for &'b mut DocumentSerializer<'a> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        match serde_json::to_value(key)? {
+            Value::String(name) => {
+                self.key = Some(self.index.insert(&name));
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("the keys of a document must be strings")),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        match self.key.take() {
+            Some(fid) => self.serialize_field_value(fid, value),
+            None => Err(ser::Error::custom("a value was serialized before its key")),
+        }
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> SerializeStruct for &'b mut DocumentSerializer<'a> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let fid = self.index.insert(key);
+        self.serialize_field_value(fid, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+#[cfg(feature = "compression")]
+use std::convert::TryInto;
+#[cfg(feature = "encryption")]
+use std::sync::Arc;
+
+#[cfg(feature = "compression")]
+use zstd::bulk::{Compressor, Decompressor};
+#[cfg(feature = "compression")]
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+
+#[cfg(feature = "encryption")]
+use crate::encryption::EncryptionKey;
+#[cfg(feature = "compression")]
+use crate::error::SerializationError;
+#[cfg(feature = "compression")]
+use crate::index::db_name;
+use crate::Result;
+
+/// The zstd level used to compress the documents.
+#[cfg(feature = "compression")]
+const COMPRESSION_LEVEL: i32 = 3;
+/// The maximum size of the trained dictionary, the size recommended by zstd.
+#[cfg(feature = "compression")]
+const DICTIONARY_MAX_SIZE: usize = 110 * 1024;
+/// The maximum size of the documents the dictionary is trained on.
+#[cfg(feature = "compression")]
+pub(crate) const SAMPLES_MAX_SIZE: usize = 100 * DICTIONARY_MAX_SIZE;
+
+/// Converts the obkvs of the documents into the bytes stored in the documents database
+/// and back, compressing them with the dictionary and encrypting them with the key of the index.
+pub(crate) struct DocumentsCodec {
+    #[cfg(feature = "compression")]
+    dictionary: Option<(EncoderDictionary<'static>, DecoderDictionary<'static>)>,
+    #[cfg(feature = "encryption")]
+    encryption_key: Option<Arc<EncryptionKey>>,
+}
+
+impl DocumentsCodec {
+    pub(crate) fn new(
+        #[cfg(feature = "compression")] dictionary: Option<&[u8]>,
+        #[cfg(feature = "encryption")] encryption_key: Option<Arc<EncryptionKey>>,
+    ) -> DocumentsCodec {
+        #[cfg(feature = "compression")]
+        let dictionary = dictionary.map(|dictionary| {
+            let encoder = EncoderDictionary::copy(dictionary, COMPRESSION_LEVEL);
+            let decoder = DecoderDictionary::copy(dictionary);
+            (encoder, decoder)
+        });
+
+        DocumentsCodec {
+            #[cfg(feature = "compression")]
+            dictionary,
+            #[cfg(feature = "encryption")]
+            encryption_key,
+        }
+    }
+
+    pub(crate) fn encode<'a>(&self, obkv: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        #[allow(unused_mut)]
+        let mut bytes = Cow::Borrowed(obkv);
+
+        // The compressed bytes are prefixed by the length of the
+        // obkv to know the size of the buffer to decompress into.
+        #[cfg(feature = "compression")]
+        if let Some((encoder, _)) = &self.dictionary {
+            let compressed = Compressor::with_prepared_dictionary(encoder)?.compress(obkv)?;
+            let mut buffer = Vec::with_capacity(4 + compressed.len());
+            buffer.extend_from_slice(&(obkv.len() as u32).to_be_bytes());
+            buffer.extend_from_slice(&compressed);
+            bytes = Cow::Owned(buffer);
+        }
+
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.encryption_key {
+            bytes = Cow::Owned(key.encrypt(&bytes)?);
+        }
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        #[allow(unused_mut)]
+        let mut bytes = Cow::Borrowed(bytes);
+
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.encryption_key {
+            bytes = Cow::Owned(key.decrypt(&bytes)?);
+        }
+
+        #[cfg(feature = "compression")]
+        if let Some((_, decoder)) = &self.dictionary {
+            let (length, compressed) = match bytes.get(..4) {
+                Some(length) => (u32::from_be_bytes(length.try_into().unwrap()), &bytes[4..]),
+                None => {
+                    return Err(
+                        SerializationError::Decoding { db_name: Some(db_name::DOCUMENTS) }.into()
+                    )
+                }
+            };
+            let mut decompressor = Decompressor::with_prepared_dictionary(decoder)?;
+            bytes = Cow::Owned(decompressor.decompress(compressed, length as usize)?);
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Trains a compression dictionary on the given documents,
+/// returns `None` if there aren't enough documents to train it.
+#[cfg(feature = "compression")]
+pub(crate) fn train_dictionary(samples: &[Vec<u8>]) -> Option<Vec<u8>> {
+    let total_size: usize = samples.iter().map(Vec::len).sum();
+    let max_size = DICTIONARY_MAX_SIZE.min(total_size / 10);
+    match zstd::dict::from_samples(samples, max_size) {
+        Ok(dictionary) => Some(dictionary),
+        Err(error) => {
+            log::warn!("Could not train the documents compression dictionary: {}", error);
+            None
+        }
+    }
+}
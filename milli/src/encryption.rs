@@ -0,0 +1,207 @@
+//! The at-rest encryption of the documents, enabled by the `encryption` feature.
+//!
+//! The values of the documents database are encrypted with XChaCha20-Poly1305 before being
+//! written to LMDB, the search structures (words, positions, facets) stay in plaintext.
+
+use std::fmt;
+use std::io;
+
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use crate::error::UserError;
+use crate::Result;
+
+const NONCE_LENGTH: usize = 24;
+/// The value encrypted and stored in the index to verify the key it is opened with.
+const KEY_CHECK_PLAINTEXT: &[u8] = b"milli-encryption-key-check";
+
+/// The key used to encrypt the documents of an index, the same key
+/// must be given every time the index is opened.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    cipher: XChaCha20Poly1305,
+}
+
+impl EncryptionKey {
+    pub fn new(key: [u8; 32]) -> EncryptionKey {
+        EncryptionKey { cipher: XChaCha20Poly1305::new(Key::from_slice(&key)) }
+    }
+
+    /// Encrypts the bytes with a random nonce, the nonce is prepended to the ciphertext.
+    pub(crate) fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LENGTH];
+        getrandom::getrandom(&mut nonce).map_err(io::Error::from)?;
+
+        let ciphertext = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|_| UserError::InvalidEncryptionKey)?;
+
+        let mut bytes = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+
+    /// Decrypts the bytes produced by `encrypt`, fails if they were encrypted with another key.
+    pub(crate) fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        if bytes.len() < NONCE_LENGTH {
+            return Err(UserError::InvalidEncryptionKey.into());
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+        match self.cipher.decrypt(XNonce::from_slice(nonce), ciphertext) {
+            Ok(plaintext) => Ok(plaintext),
+            Err(_) => Err(UserError::InvalidEncryptionKey.into()),
+        }
+    }
+
+    /// Returns the value stored in the index to later verify that it is opened with this key.
+    pub(crate) fn key_check(&self) -> Result<Vec<u8>> {
+        self.encrypt(KEY_CHECK_PLAINTEXT)
+    }
+
+    /// Verifies that the value returned by `key_check` was produced with this key.
+    pub(crate) fn verify_key_check(&self, key_check: &[u8]) -> Result<()> {
+        match self.decrypt(key_check)? {
+            plaintext if plaintext == KEY_CHECK_PLAINTEXT => Ok(()),
+            _ => Err(UserError::InvalidEncryptionKey.into()),
+        }
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EncryptionKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heed::types::ByteSlice;
+    use heed::EnvOpenOptions;
+
+    use super::*;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig};
+    use crate::{Error, Index, BEU32};
+
+    #[test]
+    fn encrypt_decrypt() {
+        let key = EncryptionKey::new([1; 32]);
+        let bytes = key.encrypt(b"hello").unwrap();
+        assert_ne!(&bytes[NONCE_LENGTH..], b"hello");
+        assert_eq!(key.decrypt(&bytes).unwrap(), b"hello");
+
+        let other = EncryptionKey::new([2; 32]);
+        assert!(matches!(
+            other.decrypt(&bytes),
+            Err(Error::UserError(UserError::InvalidEncryptionKey))
+        ));
+    }
+
+    #[test]
+    fn encrypted_documents() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let mut index = Index::new(options, &path).unwrap();
+        index.set_encryption_key(EncryptionKey::new([1; 32]));
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // the stored value is not the plain obkv anymore.
+        {
+            let rtxn = index.read_txn().unwrap();
+            let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+            let name_id = fields_ids_map.id("name").unwrap();
+            let raw = index
+                .documents
+                .remap_data_type::<ByteSlice>()
+                .get(&rtxn, &BEU32::new(0))
+                .unwrap()
+                .unwrap();
+            assert!(!raw.windows(5).any(|window| window == b"kevin"));
+
+            let (_, document) = index.documents(&rtxn, Some(0)).unwrap().remove(0);
+            assert_eq!(document.get(name_id), Some(&b"\"kevin\""[..]));
+        }
+
+        // the documents can't be read with another key.
+        index.set_encryption_key(EncryptionKey::new([2; 32]));
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.documents(&rtxn, Some(0)).is_err());
+    }
+
+    #[test]
+    fn encryption_key_check() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut encrypted_index = index.clone();
+        encrypted_index.set_encryption_key(EncryptionKey::new([1; 32]));
+        let mut wtxn = encrypted_index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder =
+            IndexDocuments::new(&mut wtxn, &encrypted_index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // the encrypted documents can't be read nor written without the key.
+        let rtxn = index.read_txn().unwrap();
+        assert!(matches!(
+            index.documents(&rtxn, Some(0)),
+            Err(Error::UserError(UserError::MissingEncryptionKey))
+        ));
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 2, "name": "kevina" }]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        assert!(matches!(
+            builder.execute(),
+            Err(Error::UserError(UserError::MissingEncryptionKey))
+        ));
+        drop(wtxn);
+
+        // the key can't be set on an index that already has unencrypted documents.
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let mut encrypted_index = index.clone();
+        encrypted_index.set_encryption_key(EncryptionKey::new([1; 32]));
+        let mut wtxn = encrypted_index.write_txn().unwrap();
+        let content = documents!([{ "id": 2, "name": "kevina" }]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder =
+            IndexDocuments::new(&mut wtxn, &encrypted_index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        assert!(matches!(
+            builder.execute(),
+            Err(Error::UserError(UserError::UnencryptedDocuments))
+        ));
+    }
+}
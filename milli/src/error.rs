@@ -12,7 +12,7 @@ use crate::{CriterionError, DocumentId, FieldId, SortError};
 pub type Object = Map<String, Value>;
 
 pub fn is_reserved_keyword(keyword: &str) -> bool {
-    ["_geo", "_geoDistance", "_geoPoint", "_geoRadius"].contains(&keyword)
+    ["_geo", "_geoDistance", "_geoPoint", "_geoPolygon", "_geoRadius"].contains(&keyword)
 }
 
 #[derive(Debug)]
@@ -36,6 +36,7 @@ pub enum InternalError {
     SerdeJson(serde_json::Error),
     Serialization(SerializationError),
     Store(MdbError),
+    UnknownDatabase { db_name: String },
     Utf8(str::Utf8Error),
 }
 
@@ -55,23 +56,75 @@ pub enum FieldIdMapMissingEntry {
 #[derive(Debug)]
 pub enum UserError {
     AttributeLimitReached,
+    CompressedDocumentsUnsupported,
     CriterionError(CriterionError),
     DocumentLimitReached,
-    InvalidDocumentId { document_id: Value },
-    InvalidFacetsDistribution { invalid_facets_name: BTreeSet<String> },
-    InvalidGeoField { document_id: Value, object: Value },
+    DocumentTooDeep {
+        document_id: String,
+        max_depth: usize,
+    },
+    DocumentTooManyFields {
+        document_id: String,
+        max_fields: usize,
+    },
+    EncryptedDocumentsUnsupported,
+    InvalidDocumentId {
+        document_id: Value,
+    },
+    InvalidEncryptionKey,
+    InvalidDocuments {
+        errors: Vec<UserError>,
+        omitted_errors: usize,
+    },
+    InvalidRelaxedDocumentId {
+        document_id: Value,
+        max_bytes: usize,
+    },
+    InvalidFacetsDistribution {
+        invalid_facets_name: BTreeSet<String>,
+    },
+    InvalidGeoField {
+        document_id: Value,
+        object: Value,
+    },
+    InvalidGeohashPrecision(usize),
+    InvalidGroupByField {
+        field: String,
+        valid_fields: BTreeSet<String>,
+    },
     InvalidFilter(String),
-    InvalidSortableAttribute { field: String, valid_fields: BTreeSet<String> },
+    InvalidHistogramBuckets(String),
+    InvalidSortableAttribute {
+        field: String,
+        expression: String,
+        valid_fields: BTreeSet<String>,
+        suggestion: Option<String>,
+    },
     SortRankingRuleMissing,
     InvalidStoreFile,
+    MatchesPositionWithoutDocuments,
     MaxDatabaseSizeReached,
-    MissingDocumentId { primary_key: String, document: Object },
+    MissingDocumentId {
+        primary_key: String,
+        document: Object,
+    },
+    MissingEncryptionKey,
     MissingPrimaryKey,
+    MultiplePrimaryKeyCandidatesFound {
+        candidates: Vec<String>,
+    },
     NoSpaceLeftOnDevice,
     PrimaryKeyCannotBeChanged(String),
     SerdeJson(serde_json::Error),
     SortError(SortError),
-    UnknownInternalDocumentId { document_id: DocumentId },
+    UnencryptedDocuments,
+    UnknownInternalDocumentId {
+        document_id: DocumentId,
+    },
+    UnsupportedIndexFormat {
+        version: u32,
+        supported: u32,
+    },
 }
 
 impl From<io::Error> for Error {
@@ -202,6 +255,7 @@ impl fmt::Display for InternalError {
             Self::SerdeJson(error) => error.fmt(f),
             Self::DatabaseClosing => HeedError::DatabaseClosing.fmt(f),
             Self::Store(error) => error.fmt(f),
+            Self::UnknownDatabase { db_name } => write!(f, "Unknown database `{}`.", db_name),
             Self::Utf8(error) => error.fmt(f),
         }
     }
@@ -213,9 +267,43 @@ impl fmt::Display for UserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::InvalidFilter(error) => f.write_str(error),
+            Self::InvalidHistogramBuckets(reason) => {
+                write!(f, "Invalid histogram buckets, {}.", reason)
+            }
+            Self::InvalidGeohashPrecision(precision) => write!(
+                f,
+                "Invalid geohash precision `{}`, it must be between 1 and {}.",
+                precision,
+                crate::search::MAX_GEOHASH_PRECISION
+            ),
+            Self::InvalidGroupByField { field, valid_fields } => {
+                let valid_names =
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+                write!(
+                    f,
+                    "Attribute `{}` is not filterable, the results cannot be grouped by it. \
+Available filterable attributes are: `{}`.",
+                    field, valid_names
+                )
+            }
             Self::AttributeLimitReached => f.write_str("A document cannot contain more than 65,535 fields."),
+            Self::CompressedDocumentsUnsupported => f.write_str(
+                "The documents of the index are compressed, \
+milli must be built with the `compression` feature to read them.",
+            ),
             Self::CriterionError(error) => write!(f, "{}", error),
             Self::DocumentLimitReached => f.write_str("Maximum number of documents reached."),
+            Self::DocumentTooDeep { document_id, max_depth } => write!(
+                f,
+                "The document with the id: `{}` contains a field nested more than {} levels deep.",
+                document_id, max_depth
+            ),
+            Self::DocumentTooManyFields { document_id, max_fields } => write!(
+                f,
+                "The document with the id: `{}` contains more than {} fields, \
+counting the fields of its nested objects.",
+                document_id, max_fields
+            ),
             Self::InvalidFacetsDistribution { invalid_facets_name } => {
                 let name_list =
                     invalid_facets_name.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
@@ -253,15 +341,46 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
                     document_id
                 )
             }
-            Self::InvalidSortableAttribute { field, valid_fields } => {
+            Self::InvalidDocuments { errors, omitted_errors } => {
+                write!(f, "{} documents are invalid:", errors.len() + omitted_errors)?;
+                for error in errors {
+                    write!(f, "\n- {}", error)?;
+                }
+                if *omitted_errors > 0 {
+                    write!(f, "\nand {} more errors.", omitted_errors)?;
+                }
+                Ok(())
+            }
+            Self::InvalidRelaxedDocumentId { document_id, max_bytes } => {
+                let document_id = match document_id {
+                    Value::String(id) => id.clone(),
+                    _ => document_id.to_string(),
+                };
+                write!(
+                    f,
+                    "Document identifier `{}` is invalid. \
+A document identifier can be of type integer or a non-empty string of at most {} bytes \
+without control characters.",
+                    document_id, max_bytes
+                )
+            }
+            Self::InvalidSortableAttribute { field, expression, valid_fields, suggestion } => {
                 let valid_names =
                     valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
                 write!(
                     f,
-                    "Attribute `{}` is not sortable. Available sortable attributes are: `{}`.",
-                    field, valid_names
-                )
+                    "Attribute `{}` of the sort expression `{}` is not sortable. \
+Available sortable attributes are: `{}`.",
+                    field, expression, valid_names
+                )?;
+                match suggestion {
+                    Some(suggestion) => write!(f, " Did you mean `{}`?", suggestion),
+                    None => Ok(()),
+                }
             }
+            Self::MatchesPositionWithoutDocuments => f.write_str(
+                "The matches positions can't be computed when the documents fetching is skipped.",
+            ),
             Self::SortRankingRuleMissing => f.write_str(
                 "The sort ranking rule must be specified in the \
 ranking rules settings to use the sort parameter at search time.",
@@ -270,10 +389,32 @@ ranking rules settings to use the sort parameter at search time.",
                 let json = serde_json::to_string(document).unwrap();
                 write!(f, "Document doesn't have a `{}` attribute: `{}`.", primary_key, json)
             }
-            Self::MissingPrimaryKey => f.write_str("The primary key inference process failed because the engine did not find any fields containing `id` substring in their name. If your document identifier does not contain any `id` substring, you can set the primary key of the index."),
+            Self::MissingPrimaryKey => f.write_str("The primary key inference process failed because the engine did not find any field named `id` or ending with `id` (like `user_id` or `userId`). If your document identifier is named differently, you can set the primary key of the index."),
+            Self::MultiplePrimaryKeyCandidatesFound { candidates } => {
+                write!(
+                    f,
+                    "The primary key inference process failed because the engine found multiple fields that could be the primary key: `{}`. You must set the primary key of the index.",
+                    candidates.join("`, `")
+                )
+            }
             Self::MaxDatabaseSizeReached => f.write_str("Maximum database size has been reached."),
             Self::NoSpaceLeftOnDevice => f.write_str("There is no more space left on the device. Consider increasing the size of the disk/partition."),
             Self::InvalidStoreFile => f.write_str("The database file is in an invalid state."),
+            Self::InvalidEncryptionKey => {
+                f.write_str("A document could not be decrypted with the encryption key of the index.")
+            }
+            Self::EncryptedDocumentsUnsupported => f.write_str(
+                "The documents of the index are encrypted, \
+milli must be built with the `encryption` feature to read them.",
+            ),
+            Self::MissingEncryptionKey => f.write_str(
+                "The documents of the index are encrypted, \
+the index must be given its encryption key to read or write them.",
+            ),
+            Self::UnencryptedDocuments => f.write_str(
+                "The index already contains unencrypted documents, \
+the encryption key must be set before any document is written.",
+            ),
             Self::PrimaryKeyCannotBeChanged(primary_key) => {
                 write!(f, "Index already has a primary key: `{}`.", primary_key)
             }
@@ -282,6 +423,12 @@ ranking rules settings to use the sort parameter at search time.",
             Self::UnknownInternalDocumentId { document_id } => {
                 write!(f, "An unknown internal document id have been used: `{}`.", document_id)
             }
+            Self::UnsupportedIndexFormat { version, supported } => write!(
+                f,
+                "The index has been written with the format version {} \
+but this version of milli only supports up to the format version {}.",
+                version, supported
+            ),
         }
     }
 }
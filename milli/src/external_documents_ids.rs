@@ -5,6 +5,7 @@ use std::{fmt, str};
 
 use fst::map::IndexedValue;
 use fst::{IntoStreamer, Streamer};
+use roaring::RoaringBitmap;
 
 const DELETED_ID: u64 = u64::MAX;
 
@@ -41,6 +42,30 @@ impl<'a> ExternalDocumentsIds<'a> {
         }
     }
 
+    /// Returns the ids of the documents whose external id starts with the prefix.
+    pub fn prefix_ids<A: AsRef<[u8]>>(&self, prefix: A) -> RoaringBitmap {
+        let prefix = prefix.as_ref();
+        let union_op = fst::map::OpBuilder::new()
+            .add(self.hard.range().ge(prefix))
+            .add(self.soft.range().ge(prefix))
+            .r#union();
+
+        let mut ids = RoaringBitmap::new();
+        let mut iter = union_op.into_stream();
+        while let Some((external_id, marked_docids)) = iter.next() {
+            // The ids are sorted, no id starts with the prefix after the first that doesn't.
+            if !external_id.starts_with(prefix) {
+                break;
+            }
+            let id = indexed_last_value(marked_docids).unwrap();
+            if id != DELETED_ID {
+                ids.insert(id.try_into().unwrap());
+            }
+        }
+
+        ids
+    }
+
     pub fn delete_ids<A: AsRef<[u8]>>(&mut self, other: fst::Set<A>) -> fst::Result<()> {
         let other = fst::Map::from(other.into_fst());
         let union_op = self.soft.op().add(&other).r#union();
@@ -214,4 +239,32 @@ mod tests {
         external_documents_ids.insert_ids(&new_ids).unwrap();
         assert_eq!(external_documents_ids.get("30"), Some(2));
     }
+
+    #[test]
+    fn prefix_ids() {
+        let mut external_documents_ids = ExternalDocumentsIds::default();
+
+        let new_ids = fst::Map::from_iter(vec![
+            ("tenant1:a", 0),
+            ("tenant1:b", 1),
+            ("tenant2:a", 2),
+            ("tenant42:a", 3),
+            ("tenant42:b", 4),
+        ])
+        .unwrap();
+        external_documents_ids.insert_ids(&new_ids).unwrap();
+
+        // The deleted ids are in the soft map.
+        let deleted_ids = fst::Set::from_iter(vec!["tenant42:b"]).unwrap();
+        external_documents_ids.delete_ids(deleted_ids).unwrap();
+        let new_ids = fst::Map::from_iter(vec![("tenant42:c", 5)]).unwrap();
+        external_documents_ids.insert_ids(&new_ids).unwrap();
+
+        let ids: Vec<_> = external_documents_ids.prefix_ids("tenant42:").iter().collect();
+        assert_eq!(ids, vec![3, 5]);
+        let ids: Vec<_> = external_documents_ids.prefix_ids("tenant1").iter().collect();
+        assert_eq!(ids, vec![0, 1]);
+        assert!(external_documents_ids.prefix_ids("tenant3").is_empty());
+        assert_eq!(external_documents_ids.prefix_ids("").len(), 5);
+    }
 }
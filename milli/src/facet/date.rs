@@ -0,0 +1,60 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// The size of the buckets of the distribution of a date facet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DateGranularity {
+    Year,
+    Month,
+    Day,
+    Hour,
+}
+
+/// Returns the unix timestamp, in seconds, of the given RFC 3339 date, e.g. `2021-03-14T10:00:00Z`.
+pub fn parse_date(value: &str) -> Option<f64> {
+    OffsetDateTime::parse(value.trim(), &Rfc3339).ok().map(|date| date.unix_timestamp() as f64)
+}
+
+/// Returns the name of the bucket of the given granularity containing the unix timestamp,
+/// in UTC, e.g. `2021-03` for a month, the names sort in the chronological order.
+pub fn date_bucket(timestamp: f64, granularity: DateGranularity) -> Option<String> {
+    let date = OffsetDateTime::from_unix_timestamp(timestamp.floor() as i64).ok()?;
+
+    let mut bucket = format!("{:04}", date.year());
+    if granularity != DateGranularity::Year {
+        write!(bucket, "-{:02}", date.month() as u8).ok()?;
+    }
+    if matches!(granularity, DateGranularity::Day | DateGranularity::Hour) {
+        write!(bucket, "-{:02}", date.day()).ok()?;
+    }
+    if granularity == DateGranularity::Hour {
+        write!(bucket, "T{:02}", date.hour()).ok()?;
+    }
+
+    Some(bucket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_of_a_date() {
+        let timestamp = parse_date("2021-03-14T10:42:00+02:00").unwrap();
+        assert_eq!(timestamp, 1615711320.0);
+
+        let bucket = |granularity| date_bucket(timestamp, granularity).unwrap();
+        assert_eq!(bucket(DateGranularity::Year), "2021");
+        assert_eq!(bucket(DateGranularity::Month), "2021-03");
+        assert_eq!(bucket(DateGranularity::Day), "2021-03-14");
+        // The buckets are in UTC.
+        assert_eq!(bucket(DateGranularity::Hour), "2021-03-14T08");
+
+        assert_eq!(parse_date("2021-03-14"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// How the string values of a faceted field are normalized into the keys they are
+/// filtered, sorted and grouped by, the original values are always kept for display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetNormalization {
+    /// The values are lowercased, `Café` and `café` are the same value.
+    Lowercase,
+    /// The values are only trimmed, their case and diacritics are significant.
+    Preserve,
+    /// The values are lowercased and their diacritics removed, `Café` and `cafe`
+    /// are the same value.
+    Fold,
+}
+
+impl Default for FacetNormalization {
+    fn default() -> FacetNormalization {
+        FacetNormalization::Lowercase
+    }
+}
+
+impl FacetNormalization {
+    /// Returns the key of the given facet string value.
+    pub fn normalize(&self, value: &str) -> String {
+        let value = value.trim();
+        match self {
+            FacetNormalization::Lowercase => value.to_lowercase(),
+            FacetNormalization::Preserve => value.to_string(),
+            FacetNormalization::Fold => {
+                value.nfd().filter(|c| !is_combining_mark(*c)).collect::<String>().to_lowercase()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_facet_values() {
+        assert_eq!(FacetNormalization::Lowercase.normalize(" Café "), "café");
+        assert_eq!(FacetNormalization::Preserve.normalize(" Café "), "Café");
+        assert_eq!(FacetNormalization::Fold.normalize(" Café "), "cafe");
+        assert_eq!(FacetNormalization::Fold.normalize("Ÿ ØRNE"), "y ørne");
+    }
+}
@@ -0,0 +1,64 @@
+/// The separator between the levels of a hierarchical facet value, e.g. `Books > SciFi`.
+pub const HIERARCHY_SEPARATOR: char = '>';
+
+/// The separator between the depth and the normalized path of a level-aware facet key.
+const DEPTH_SEPARATOR: char = '\u{1f}';
+
+/// Returns the prefix shared by the level-aware keys of the paths of the given depth,
+/// the roots of the hierarchy have a depth of one.
+pub fn hierarchy_depth_prefix(depth: usize) -> String {
+    format!("{}{}", depth, DEPTH_SEPARATOR)
+}
+
+/// Returns the level-aware normalized key of the given path, `None` if it is empty.
+pub fn hierarchy_level_key(path: &str) -> Option<String> {
+    hierarchy_levels(path).pop().map(|(key, _)| key)
+}
+
+/// Returns the level-aware normalized key and the original value of each level of the
+/// given path, from the root to the path itself, i.e. `Books > SciFi` gives `Books`
+/// then `Books > SciFi`.
+///
+/// Indexing every level of a path makes a filter on a level select its whole subtree.
+pub fn hierarchy_levels(path: &str) -> Vec<(String, String)> {
+    let mut levels = Vec::new();
+    let mut normalized = String::new();
+    let mut original = String::new();
+
+    let parts = path.split(HIERARCHY_SEPARATOR).map(str::trim).filter(|part| !part.is_empty());
+    for (i, part) in parts.enumerate() {
+        if i != 0 {
+            normalized.push_str(" > ");
+            original.push_str(" > ");
+        }
+        normalized.push_str(&part.to_lowercase());
+        original.push_str(part);
+
+        let key = format!("{}{}", hierarchy_depth_prefix(i + 1), normalized);
+        levels.push((key, original.clone()));
+    }
+
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+
+    #[test]
+    fn levels_of_a_path() {
+        let levels = hierarchy_levels(" Books >SciFi > Cyberpunk");
+        let expected = vec![
+            (S("1\u{1f}books"), S("Books")),
+            (S("2\u{1f}books > scifi"), S("Books > SciFi")),
+            (S("3\u{1f}books > scifi > cyberpunk"), S("Books > SciFi > Cyberpunk")),
+        ];
+        assert_eq!(levels, expected);
+
+        assert!(levels[1].0.starts_with(&hierarchy_depth_prefix(2)));
+        assert_eq!(hierarchy_level_key("books>SCIFI"), Some(levels[1].0.clone()));
+        assert_eq!(hierarchy_level_key(" > "), None);
+    }
+}
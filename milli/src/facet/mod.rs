@@ -1,6 +1,18 @@
+mod date;
+mod facet_normalization;
 mod facet_type;
 mod facet_value;
+mod hierarchy;
+mod nested;
 pub mod value_encoding;
 
+pub use self::date::{date_bucket, parse_date, DateGranularity};
+pub use self::facet_normalization::FacetNormalization;
 pub use self::facet_type::FacetType;
 pub use self::facet_value::FacetValue;
+pub use self::hierarchy::{
+    hierarchy_depth_prefix, hierarchy_level_key, hierarchy_levels, HIERARCHY_SEPARATOR,
+};
+pub use self::nested::{
+    facet_paths, is_facet_path, select_facet_path_values, FacetPath, FacetPaths,
+};
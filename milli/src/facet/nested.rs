@@ -0,0 +1,122 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::{FieldId, FieldsIdsMap};
+
+/// A faceted field whose name is a dotted path into the nested objects of a document
+/// field, e.g. `genres.name` selects the `name` of the `genres` objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetPath {
+    /// The id of the document field containing the nested objects.
+    pub root: FieldId,
+    /// The keys to follow in the nested objects of the root field.
+    pub keys: Vec<String>,
+}
+
+/// The faceted paths indexed by the field id of the whole dotted name.
+pub type FacetPaths = HashMap<FieldId, FacetPath>;
+
+/// Returns the id of the document field the given faceted field name is a path into
+/// and the keys of the path, `None` if the name is not a path into a known field.
+fn split_path<'a>(fields_ids_map: &FieldsIdsMap, name: &'a str) -> Option<(FieldId, &'a str)> {
+    // The shortest known prefix is the document field, the longer ones can be other paths.
+    name.match_indices('.').find_map(|(i, _)| {
+        let (root, keys) = (&name[..i], &name[i + 1..]);
+        fields_ids_map.id(root).map(|root| (root, keys))
+    })
+}
+
+/// Returns whether the faceted field name is a path into a known field of the documents.
+pub fn is_facet_path(fields_ids_map: &FieldsIdsMap, name: &str) -> bool {
+    split_path(fields_ids_map, name).map_or(false, |(_, keys)| !keys.is_empty())
+}
+
+/// Returns the faceted fields that are paths into the nested objects of the documents.
+///
+/// The faceted paths must have been inserted in the fields ids map beforehand.
+pub fn facet_paths(fields_ids_map: &FieldsIdsMap, faceted_fields: &HashSet<String>) -> FacetPaths {
+    let mut paths = HashMap::new();
+    for name in faceted_fields {
+        if let (Some(id), Some((root, keys))) =
+            (fields_ids_map.id(name), split_path(fields_ids_map, name))
+        {
+            if !keys.is_empty() {
+                let keys = keys.split('.').map(ToOwned::to_owned).collect();
+                paths.insert(id, FacetPath { root, keys });
+            }
+        }
+    }
+    paths
+}
+
+/// Returns the values selected by the keys of the path in the given root field value,
+/// the arrays are traversed at any depth and the selected arrays are flattened.
+pub fn select_facet_path_values(value: &Value, keys: &[String]) -> Value {
+    fn inner(value: &Value, keys: &[String], output: &mut Vec<Value>) {
+        match (value, keys) {
+            (Value::Array(values), _) => values.iter().for_each(|v| inner(v, keys, output)),
+            (Value::Object(object), [key, tail @ ..]) => {
+                if let Some(value) = object.get(key) {
+                    inner(value, tail, output);
+                }
+            }
+            (value, []) => output.push(value.clone()),
+            (_, _) => (),
+        }
+    }
+
+    let mut output = Vec::new();
+    inner(value, keys, &mut output);
+    Value::Array(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn facet_paths_of_known_fields() {
+        let mut fields_ids_map = FieldsIdsMap::new();
+        let genres = fields_ids_map.insert("genres").unwrap();
+        let genres_name = fields_ids_map.insert("genres.name").unwrap();
+        let title = fields_ids_map.insert("title").unwrap();
+        fields_ids_map.insert("author.name").unwrap();
+
+        assert!(is_facet_path(&fields_ids_map, "genres.name"));
+        assert!(!is_facet_path(&fields_ids_map, "author.name"));
+        assert!(!is_facet_path(&fields_ids_map, "title"));
+
+        let faceted_fields = hashset! { S("genres.name"), S("title"), S("author.name") };
+        let paths = facet_paths(&fields_ids_map, &faceted_fields);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[&genres_name], FacetPath { root: genres, keys: vec![S("name")] });
+        assert!(!paths.contains_key(&title));
+    }
+
+    #[test]
+    fn select_nested_values() {
+        let value = json!([
+            { "name": "Drama", "tags": ["sad", "long"] },
+            { "name": "Comedy", "tags": "fun" },
+            { "id": 3 },
+            [{ "name": "Drama" }],
+        ]);
+
+        let keys = [S("name")];
+        let selected = select_facet_path_values(&value, &keys);
+        assert_eq!(selected, json!(["Drama", "Comedy", "Drama"]));
+
+        let keys = [S("tags")];
+        let selected = select_facet_path_values(&value, &keys);
+        assert_eq!(selected, json!(["sad", "long", "fun"]));
+
+        let value = json!({ "info": { "lang": "en" } });
+        let keys = [S("info"), S("lang")];
+        assert_eq!(select_facet_path_values(&value, &keys), json!(["en"]));
+    }
+}
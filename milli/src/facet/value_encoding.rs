@@ -13,6 +13,34 @@ pub fn f64_into_bytes(float: f64) -> Option<[u8; 8]> {
     None
 }
 
+/// Encodes an `i64` into bytes that are ordered like the integers.
+#[inline]
+pub fn i64_into_bytes(integer: i64) -> [u8; 8] {
+    xor_first_bit(integer.to_be_bytes())
+}
+
+/// Decodes an `i64` encoded by `i64_into_bytes`.
+#[inline]
+pub fn i64_from_bytes(bytes: [u8; 8]) -> i64 {
+    i64::from_be_bytes(xor_first_bit(bytes))
+}
+
+/// Encodes an `i128`, that holds every `i64` and `u64`,
+/// into bytes that are ordered like the integers.
+#[inline]
+pub fn i128_into_bytes(integer: i128) -> [u8; 16] {
+    let mut bytes = integer.to_be_bytes();
+    bytes[0] ^= 0x80;
+    bytes
+}
+
+/// Decodes an `i128` encoded by `i128_into_bytes`.
+#[inline]
+pub fn i128_from_bytes(mut bytes: [u8; 16]) -> i128 {
+    bytes[0] ^= 0x80;
+    i128::from_be_bytes(bytes)
+}
+
 #[inline]
 fn xor_first_bit(mut x: [u8; 8]) -> [u8; 8] {
     x[0] ^= 0x80;
@@ -46,4 +74,27 @@ mod tests {
         let vec: Vec<_> = [a, b, c, d, e].iter().cloned().map(f64_into_bytes).collect();
         assert!(is_sorted(&vec), "{:?}", vec);
     }
+
+    #[test]
+    fn ordered_i64_bytes() {
+        let integers =
+            [i64::MIN, -9_007_199_254_740_993, -1, 0, 1, 9_007_199_254_740_993, i64::MAX];
+        let vec: Vec<_> = integers.iter().cloned().map(i64_into_bytes).collect();
+        assert!(is_sorted(&vec), "{:?}", vec);
+
+        for integer in integers.iter().cloned() {
+            assert_eq!(i64_from_bytes(i64_into_bytes(integer)), integer);
+        }
+    }
+
+    #[test]
+    fn ordered_i128_bytes() {
+        let integers = [i128::MIN, i64::MIN as i128, -1, 0, 1, u64::MAX as i128, i128::MAX];
+        let vec: Vec<_> = integers.iter().cloned().map(i128_into_bytes).collect();
+        assert!(is_sorted(&vec), "{:?}", vec);
+
+        for integer in integers.iter().cloned() {
+            assert_eq!(i128_from_bytes(i128_into_bytes(integer)), integer);
+        }
+    }
 }
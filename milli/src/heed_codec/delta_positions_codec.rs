@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use roaring::RoaringBitmap;
+
+/// Encodes the positions of a word in a document as the differences between the
+/// consecutive positions, bit-packed with the number of bits of the largest difference.
+///
+/// The encoding starts with the number of positions and the first position, both as
+/// varints, followed by the width in bits of the differences and the packed differences.
+pub struct DeltaPositionsCodec;
+
+impl DeltaPositionsCodec {
+    pub fn serialize_into(positions: &RoaringBitmap, out: &mut Vec<u8>) {
+        write_varint(positions.len() as u32, out);
+        let first = match positions.iter().next() {
+            Some(first) => first,
+            None => return,
+        };
+        write_varint(first, out);
+
+        let width = deltas(positions).max().map_or(0, |max| 32 - max.leading_zeros());
+        out.push(width as u8);
+
+        let mut buffer = 0u64;
+        let mut buffered_bits = 0;
+        for delta in deltas(positions) {
+            buffer |= (delta as u64) << buffered_bits;
+            buffered_bits += width;
+            while buffered_bits >= 8 {
+                out.push(buffer as u8);
+                buffer >>= 8;
+                buffered_bits -= 8;
+            }
+        }
+        if buffered_bits > 0 {
+            out.push(buffer as u8);
+        }
+    }
+}
+
+impl heed::BytesDecode<'_> for DeltaPositionsCodec {
+    type DItem = RoaringBitmap;
+
+    fn bytes_decode(bytes: &[u8]) -> Option<Self::DItem> {
+        let mut bitmap = RoaringBitmap::new();
+        let (len, bytes) = read_varint(bytes)?;
+        if len == 0 {
+            return Some(bitmap);
+        }
+
+        let (mut position, bytes) = read_varint(bytes)?;
+        bitmap.push(position);
+
+        let (&width, mut packed) = bytes.split_first()?;
+        let width = width as u32;
+        if width > 32 || (width == 0 && len > 1) {
+            return None;
+        }
+
+        let mask = (1u64 << width) - 1;
+        let mut buffer = 0u64;
+        let mut buffered_bits = 0;
+        for _ in 1..len {
+            while buffered_bits < width {
+                let (&byte, rest) = packed.split_first()?;
+                buffer |= (byte as u64) << buffered_bits;
+                buffered_bits += 8;
+                packed = rest;
+            }
+            let delta = (buffer & mask) as u32;
+            buffer >>= width;
+            buffered_bits -= width;
+            position = position.checked_add(delta)?;
+            bitmap.push(position);
+        }
+
+        Some(bitmap)
+    }
+}
+
+impl heed::BytesEncode<'_> for DeltaPositionsCodec {
+    type EItem = RoaringBitmap;
+
+    fn bytes_encode(item: &Self::EItem) -> Option<Cow<[u8]>> {
+        let mut out = Vec::new();
+        DeltaPositionsCodec::serialize_into(item, &mut out);
+        Some(Cow::Owned(out))
+    }
+}
+
+/// Only decodes the number of positions of a [`DeltaPositionsCodec`] entry.
+pub struct DeltaPositionsLenCodec;
+
+impl heed::BytesDecode<'_> for DeltaPositionsLenCodec {
+    type DItem = u64;
+
+    fn bytes_decode(bytes: &[u8]) -> Option<Self::DItem> {
+        read_varint(bytes).map(|(len, _)| len as u64)
+    }
+}
+
+fn deltas(positions: &RoaringBitmap) -> impl Iterator<Item = u32> + '_ {
+    positions.iter().zip(positions.iter().skip(1)).map(|(previous, next)| next - previous)
+}
+
+fn write_varint(mut value: u32, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((u32::try_from(value).ok()?, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use heed::{BytesDecode, BytesEncode};
+
+    use super::*;
+
+    #[test]
+    fn verify_encoding_decoding() {
+        let inputs = vec![
+            RoaringBitmap::new(),
+            RoaringBitmap::from_iter(vec![42]),
+            RoaringBitmap::from_iter(vec![0, 1, 2, 3]),
+            RoaringBitmap::from_iter(vec![3, 9, 65_536, 65_540, 131_075, u32::MAX]),
+            RoaringBitmap::from_iter((0..1000).map(|i| i * 7 + 65_536)),
+        ];
+
+        for input in inputs {
+            let bytes = DeltaPositionsCodec::bytes_encode(&input).unwrap();
+            let output = DeltaPositionsCodec::bytes_decode(&bytes).unwrap();
+            assert_eq!(input, output);
+            assert_eq!(DeltaPositionsLenCodec::bytes_decode(&bytes), Some(input.len()));
+        }
+
+        assert!(DeltaPositionsCodec::bytes_decode(&[3, 0]).is_none());
+    }
+
+    #[test]
+    fn smaller_than_raw_positions() {
+        // A word appearing every ten words of the first attribute.
+        let input = RoaringBitmap::from_iter((0..100).map(|i| i * 10));
+        let bytes = DeltaPositionsCodec::bytes_encode(&input).unwrap();
+        // The count, the first position, the width and 99 deltas of 4 bits.
+        assert_eq!(bytes.len(), 1 + 1 + 1 + 50);
+        assert!(bytes.len() * 7 < input.len() as usize * std::mem::size_of::<u32>());
+    }
+}
@@ -0,0 +1,31 @@
+use std::borrow::Cow;
+
+use crate::facet::value_encoding::{i128_from_bytes, i128_into_bytes};
+use crate::{try_split_array_at, FieldId};
+
+pub struct FieldIdI128Codec;
+
+impl<'a> heed::BytesDecode<'a> for FieldIdI128Codec {
+    type DItem = (FieldId, i128);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
+        let (field_id_bytes, bytes) = try_split_array_at(bytes)?;
+        let field_id = u16::from_be_bytes(field_id_bytes);
+
+        let (integer_bytes, _) = try_split_array_at(bytes)?;
+        let integer = i128_from_bytes(integer_bytes);
+
+        Some((field_id, integer))
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for FieldIdI128Codec {
+    type EItem = (FieldId, i128);
+
+    fn bytes_encode((field_id, integer): &Self::EItem) -> Option<Cow<[u8]>> {
+        let mut bytes = Vec::with_capacity(2 + 16);
+        bytes.extend_from_slice(&field_id.to_be_bytes());
+        bytes.extend_from_slice(&i128_into_bytes(*integer));
+        Some(Cow::Owned(bytes))
+    }
+}
@@ -1,47 +1,85 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::mem::size_of;
 use std::path::Path;
+use std::str;
+use std::sync::Arc;
 
+use fst::automaton::{Automaton, Str as StrAutomaton};
+use fst::{IntoStreamer, Streamer};
 use heed::flags::Flags;
 use heed::types::*;
-use heed::{Database, PolyDatabase, RoTxn, RwTxn};
+use heed::{BytesDecode, BytesEncode, Database, PolyDatabase, RoTxn, RwTxn};
 use roaring::RoaringBitmap;
 use rstar::RTree;
 use time::OffsetDateTime;
 
-use crate::error::{InternalError, UserError};
+use crate::cache::LruCache;
+use crate::documents_codec::DocumentsCodec;
+#[cfg(feature = "encryption")]
+use crate::encryption::EncryptionKey;
+use crate::error::{FieldIdMapMissingEntry, InternalError, SerializationError, UserError};
+use crate::facet::value_encoding::i64_from_bytes;
+use crate::facet::FacetNormalization;
 use crate::fields_ids_map::FieldsIdsMap;
 use crate::heed_codec::facet::{
     FacetLevelValueF64Codec, FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec,
-    FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec,
+    FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec, FieldIdI128Codec,
 };
+use crate::update::index_documents::{create_writer, writer_into_reader};
+use crate::update::{ArrayMergePolicy, DocumentIdValidation, UpdateId};
 use crate::{
-    default_criteria, BEU32StrCodec, BoRoaringBitmapCodec, CboRoaringBitmapCodec, Criterion,
-    DocumentId, ExternalDocumentsIds, FacetDistribution, FieldDistribution, FieldId,
-    FieldIdWordCountCodec, GeoPoint, ObkvCodec, Result, RoaringBitmapCodec, RoaringBitmapLenCodec,
-    Search, StrBEU32Codec, StrStrU8Codec, BEU32,
+    default_criteria, relative_from_absolute_position, try_split_array_at, BEU32StrCodec,
+    BoRoaringBitmapCodec, CboRoaringBitmapCodec, CboRoaringBitmapLenCodec, Criterion,
+    DeltaPositionsCodec, DocumentId, ExternalDocumentsIds, FacetDistribution, FacetHistogram,
+    FacetOptions, FacetOrder, FieldDistribution, FieldId, FieldIdWordCountCodec, GeoClusters,
+    GeoPoint, HistogramBuckets, ObkvCodec, QueryRule, Result, RoaringBitmapCodec,
+    RoaringBitmapLenCodec, Search, SearchContext, SearchQuery, SearchResult, StemmingLanguage,
+    StoredDocument, StrBEU32Codec, StrStrU8Codec, TermsDroppingOrder, TypoTolerance, BEU32,
 };
 
 pub mod main_key {
+    pub const ARRAY_MERGE_POLICIES_KEY: &str = "array-merge-policies";
+    pub const CASE_SENSITIVE_KEY: &str = "case-sensitive";
     pub const CRITERIA_KEY: &str = "criteria";
+    pub const DATE_FIELDS_KEY: &str = "date-fields";
+    pub const DECOMPOUNDING_DICTIONARY_KEY: &str = "decompounding-dictionary";
+    pub const DIACRITICS_SENSITIVE_KEY: &str = "diacritics-sensitive";
     pub const DISPLAYED_FIELDS_KEY: &str = "displayed-fields";
     pub const DISTINCT_FIELD_KEY: &str = "distinct-field-key";
+    pub const DOCUMENTS_COMPRESSION_DICTIONARY_KEY: &str = "documents-compression-dictionary";
     pub const DOCUMENTS_IDS_KEY: &str = "documents-ids";
+    pub const DOCUMENT_ID_VALIDATION_KEY: &str = "document-id-validation";
+    pub const EXACT_ATTRIBUTES_KEY: &str = "exact-attributes";
+    pub const EXACT_WORDS_KEY: &str = "exact-words";
+    pub const FACET_NORMALIZATION_KEY: &str = "facet-normalization";
     pub const FILTERABLE_FIELDS_KEY: &str = "filterable-fields";
+    pub const FORMAT_VERSION_KEY: &str = "format-version";
     pub const SORTABLE_FIELDS_KEY: &str = "sortable-fields";
+    pub const ENCRYPTION_KEY_CHECK_KEY: &str = "encryption-key-check";
     pub const FIELD_DISTRIBUTION_KEY: &str = "fields-distribution";
+    pub const FIELD_LANGUAGES_KEY: &str = "field-languages";
     pub const FIELDS_IDS_MAP_KEY: &str = "fields-ids-map";
     pub const GEO_FACETED_DOCUMENTS_IDS_KEY: &str = "geo-faceted-documents-ids";
     pub const GEO_RTREE_KEY: &str = "geo-rtree";
     pub const HARD_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "hard-external-documents-ids";
+    pub const HIERARCHICAL_FACETS_KEY: &str = "hierarchical-facets";
+    pub const LAST_JOURNALED_UPDATE_KEY: &str = "last-journaled-update";
     pub const NUMBER_FACETED_DOCUMENTS_IDS_PREFIX: &str = "number-faceted-documents-ids";
+    pub const NUMBERS_NORMALIZATION_KEY: &str = "numbers-normalization";
+    pub const PHONETIC_MATCHING_KEY: &str = "phonetic-matching";
+    pub const PHONETIC_WORDS_FST_KEY: &str = "phonetic-words-fst";
     pub const PRIMARY_KEY_KEY: &str = "primary-key";
+    pub const QUERY_RULES_KEY: &str = "query-rules";
     pub const SEARCHABLE_FIELDS_KEY: &str = "searchable-fields";
     pub const SOFT_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "soft-external-documents-ids";
+    pub const STEMMING_LANGUAGE_KEY: &str = "stemming-language";
     pub const STOP_WORDS_KEY: &str = "stop-words";
     pub const STRING_FACETED_DOCUMENTS_IDS_PREFIX: &str = "string-faceted-documents-ids";
     pub const SYNONYMS_KEY: &str = "synonyms";
+    pub const TERMS_DROPPING_ORDER_KEY: &str = "terms-dropping-order";
+    pub const TYPO_TOLERANCE_KEY: &str = "typo-tolerance";
     pub const WORDS_FST_KEY: &str = "words-fst";
     pub const WORDS_PREFIXES_FST_KEY: &str = "words-prefixes-fst";
     pub const CREATED_AT_KEY: &str = "created-at";
@@ -51,6 +89,7 @@ pub mod main_key {
 pub mod db_name {
     pub const MAIN: &str = "main";
     pub const WORD_DOCIDS: &str = "word-docids";
+    pub const EXACT_WORD_DOCIDS: &str = "exact-word-docids";
     pub const WORD_PREFIX_DOCIDS: &str = "word-prefix-docids";
     pub const DOCID_WORD_POSITIONS: &str = "docid-word-positions";
     pub const WORD_PAIR_PROXIMITY_DOCIDS: &str = "word-pair-proximity-docids";
@@ -59,12 +98,105 @@ pub mod db_name {
     pub const WORD_PREFIX_POSITION_DOCIDS: &str = "word-prefix-position-docids";
     pub const FIELD_ID_WORD_COUNT_DOCIDS: &str = "field-id-word-count-docids";
     pub const FACET_ID_F64_DOCIDS: &str = "facet-id-f64-docids";
+    /// The integers were stored as `i64` before the format version 2, the database keeps its
+    /// LMDB name as the migration rewrites its keys in place, renaming it would need
+    /// one more named database in the environments of the existing indexes.
+    pub const FACET_ID_I128_DOCIDS: &str = "facet-id-i64-docids";
     pub const FACET_ID_STRING_DOCIDS: &str = "facet-id-string-docids";
     pub const FIELD_ID_DOCID_FACET_F64S: &str = "field-id-docid-facet-f64s";
     pub const FIELD_ID_DOCID_FACET_STRINGS: &str = "field-id-docid-facet-strings";
     pub const DOCUMENTS: &str = "documents";
 }
 
+/// The tuning of the LMDB environment of an index.
+///
+/// Disabling the synchronization of the data and of the metadata speeds up the bulk
+/// indexing, at the cost of losing the last committed transactions if the system crashes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LmdbOptions {
+    /// Don't flush the system buffers to disk when committing a transaction (`MDB_NOSYNC`).
+    pub no_sync: bool,
+    /// Only flush the data pages on commit, not the meta page (`MDB_NOMETASYNC`).
+    pub no_meta_sync: bool,
+    /// Use a writeable memory map for the writes (`MDB_WRITEMAP`).
+    pub write_map: bool,
+    /// The maximum number of concurrent read transactions, LMDB defaults to 126.
+    pub max_readers: Option<u32>,
+    /// The maximum number of named databases, it can't be lower than the number milli uses.
+    pub max_dbs: Option<u32>,
+}
+
+/// The statistics of an indexed word, returned by `Index::term_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TermStats {
+    /// The number of documents containing the word.
+    pub document_frequency: u64,
+    /// The number of times the word appears in all the documents.
+    pub occurrences: u64,
+    /// The number of times the word appears in each field.
+    pub fields_occurrences: BTreeMap<String, u64>,
+}
+
+/// How much of an index [`Index::warmup`] reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WarmupLevel {
+    /// The words and prefixes FSTs, read to derive the words of every query.
+    Fsts,
+    /// The FSTs, the docids of the words and prefixes and the level 0 of the facets,
+    /// the databases read by most of the searches.
+    Search,
+    /// Every database of the index, including the documents.
+    Full,
+}
+
+/// The entries read by [`Index::warmup`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WarmupStats {
+    pub entries: u64,
+    /// The size of the keys and values of the entries.
+    pub bytes: u64,
+}
+
+/// The size of the memory pages, reading one byte of a page pulls it in the page cache.
+const PAGE_SIZE: usize = 4096;
+
+impl WarmupStats {
+    fn touch(&mut self, key: &[u8], value: &[u8]) {
+        for bytes in [key, value].iter() {
+            for byte in bytes.iter().step_by(PAGE_SIZE) {
+                // SAFETY: the byte is a valid reference, the volatile read
+                // only prevents the compiler from removing the read.
+                unsafe { std::ptr::read_volatile(byte) };
+            }
+        }
+        self.entries += 1;
+        self.bytes += (key.len() + value.len()) as u64;
+    }
+
+    fn touch_entries<'t>(
+        &mut self,
+        entries: impl Iterator<Item = heed::Result<(&'t [u8], &'t [u8])>>,
+    ) -> heed::Result<()> {
+        for entry in entries {
+            let (key, value) = entry?;
+            self.touch(key, value);
+        }
+        Ok(())
+    }
+}
+
+/// The number of named databases of an index.
+const NUMBER_OF_DBS: u32 = 16;
+
+/// The version of the format of the databases written by this version of milli,
+/// the indexes written without a format version are migrated when opened.
+///
+/// - `1`: the positions of the `docid_word_positions` database are delta-encoded
+///   and bit-packed instead of being stored as roaring bitmaps.
+/// - `2`: the integers of the `facet_id_i128_docids` database are stored as `i128`
+///   instead of `i64`, to also hold the `u64` values.
+pub const FORMAT_VERSION: u32 = 2;
+
 #[derive(Clone)]
 pub struct Index {
     /// The LMDB environment which this index is associated with.
@@ -75,11 +207,13 @@ pub struct Index {
 
     /// A word and all the documents ids containing the word.
     pub word_docids: Database<Str, RoaringBitmapCodec>,
+    /// A word of the exact attributes and all the documents ids containing the word in them.
+    pub exact_word_docids: Database<Str, RoaringBitmapCodec>,
     /// A prefix of word and all the documents ids containing this prefix.
     pub word_prefix_docids: Database<Str, RoaringBitmapCodec>,
 
     /// Maps a word and a document id (u32) to all the positions where the given word appears.
-    pub docid_word_positions: Database<BEU32StrCodec, BoRoaringBitmapCodec>,
+    pub docid_word_positions: Database<BEU32StrCodec, DeltaPositionsCodec>,
 
     /// Maps the proximity between a pair of words with all the docids where this relation appears.
     pub word_pair_proximity_docids: Database<StrStrU8Codec, CboRoaringBitmapCodec>,
@@ -95,6 +229,9 @@ pub struct Index {
 
     /// Maps the facet field id, level and the number with the docids that corresponds to it.
     pub facet_id_f64_docids: Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+    /// Maps the facet field id and the exact integer, an `i64` or an `u64` stored
+    /// as an `i128`, with the docids that corresponds to it.
+    pub facet_id_i128_docids: Database<FieldIdI128Codec, CboRoaringBitmapCodec>,
     /// Maps the facet field id and the string with the original string and docids that corresponds to it.
     pub facet_id_string_docids: Database<FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec>,
 
@@ -103,20 +240,59 @@ pub struct Index {
     /// Maps the document id, the facet field id and the strings.
     pub field_id_docid_facet_strings: Database<FieldDocIdFacetStringCodec, Str>,
 
-    /// Maps the document id to the document as an obkv store.
+    /// Maps the document id to the document as an obkv store,
+    /// the values must be read with `Index::documents` when the index is encrypted.
     pub documents: Database<OwnedType<BEU32>, ObkvCodec>,
+
+    /// The key used to encrypt the values of the documents database.
+    #[cfg(feature = "encryption")]
+    encryption_key: Option<Arc<EncryptionKey>>,
+
+    /// The candidates of the short prefix queries, shared by the clones of the index.
+    pub(crate) prefix_cache: Option<Arc<LruCache<(String, u8), RoaringBitmap>>>,
+    /// The documents matching the filters, keyed by their normalized expression.
+    pub(crate) filter_cache: Option<Arc<LruCache<String, RoaringBitmap>>>,
+    /// The distributions of the facets over all the documents, keyed
+    /// by the field id and the prefix of the hierarchical level.
+    pub(crate) facet_distribution_cache:
+        Option<Arc<LruCache<(FieldId, Option<String>), BTreeMap<String, u64>>>>,
 }
 
 impl Index {
-    pub fn new<P: AsRef<Path>>(mut options: heed::EnvOpenOptions, path: P) -> Result<Index> {
+    pub fn new<P: AsRef<Path>>(options: heed::EnvOpenOptions, path: P) -> Result<Index> {
+        Index::with_lmdb_options(options, path, LmdbOptions::default())
+    }
+
+    /// Opens or creates the index with the given tuning of the LMDB environment.
+    pub fn with_lmdb_options<P: AsRef<Path>>(
+        mut options: heed::EnvOpenOptions,
+        path: P,
+        lmdb_options: LmdbOptions,
+    ) -> Result<Index> {
         use db_name::*;
 
-        options.max_dbs(14);
-        unsafe { options.flag(Flags::MdbAlwaysFreePages) };
+        options.max_dbs(lmdb_options.max_dbs.map_or(NUMBER_OF_DBS, |n| n.max(NUMBER_OF_DBS)));
+        if let Some(max_readers) = lmdb_options.max_readers {
+            options.max_readers(max_readers);
+        }
+
+        unsafe {
+            options.flag(Flags::MdbAlwaysFreePages);
+            if lmdb_options.no_sync {
+                options.flag(Flags::MdbNoSync);
+            }
+            if lmdb_options.no_meta_sync {
+                options.flag(Flags::MdbNoMetaSync);
+            }
+            if lmdb_options.write_map {
+                options.flag(Flags::MdbWriteMap);
+            }
+        }
 
         let env = options.open(path)?;
         let main = env.create_poly_database(Some(MAIN))?;
         let word_docids = env.create_database(Some(WORD_DOCIDS))?;
+        let exact_word_docids = env.create_database(Some(EXACT_WORD_DOCIDS))?;
         let word_prefix_docids = env.create_database(Some(WORD_PREFIX_DOCIDS))?;
         let docid_word_positions = env.create_database(Some(DOCID_WORD_POSITIONS))?;
         let word_pair_proximity_docids = env.create_database(Some(WORD_PAIR_PROXIMITY_DOCIDS))?;
@@ -126,6 +302,7 @@ impl Index {
         let field_id_word_count_docids = env.create_database(Some(FIELD_ID_WORD_COUNT_DOCIDS))?;
         let word_prefix_position_docids = env.create_database(Some(WORD_PREFIX_POSITION_DOCIDS))?;
         let facet_id_f64_docids = env.create_database(Some(FACET_ID_F64_DOCIDS))?;
+        let facet_id_i128_docids = env.create_database(Some(FACET_ID_I128_DOCIDS))?;
         let facet_id_string_docids = env.create_database(Some(FACET_ID_STRING_DOCIDS))?;
         let field_id_docid_facet_f64s = env.create_database(Some(FIELD_ID_DOCID_FACET_F64S))?;
         let field_id_docid_facet_strings =
@@ -133,11 +310,13 @@ impl Index {
         let documents = env.create_database(Some(DOCUMENTS))?;
 
         Index::initialize_creation_dates(&env, main)?;
+        Index::initialize_format_version(&env, main, docid_word_positions, facet_id_i128_docids)?;
 
         Ok(Index {
             env,
             main,
             word_docids,
+            exact_word_docids,
             word_prefix_docids,
             docid_word_positions,
             word_pair_proximity_docids,
@@ -146,13 +325,121 @@ impl Index {
             word_prefix_position_docids,
             field_id_word_count_docids,
             facet_id_f64_docids,
+            facet_id_i128_docids,
             facet_id_string_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             documents,
+            #[cfg(feature = "encryption")]
+            encryption_key: None,
+            prefix_cache: None,
+            filter_cache: None,
+            facet_distribution_cache: None,
         })
     }
 
+    /// Encrypts the documents written to the index and decrypts the documents read from it
+    /// with the given key, it must be set before any document is written to the index.
+    ///
+    /// The index records that its documents are encrypted when the first one is written,
+    /// reading or writing them then fails without the same key.
+    #[cfg(feature = "encryption")]
+    pub fn set_encryption_key(&mut self, key: EncryptionKey) {
+        self.encryption_key = Some(Arc::new(key));
+    }
+
+    /// Caches the candidates of the prefix queries of up to three characters, the ones
+    /// search-as-you-type recomputes on every keystroke, `0` disables the cache.
+    ///
+    /// The entries are dropped when the index is updated.
+    pub fn set_prefix_cache_capacity(&mut self, capacity: usize) {
+        self.prefix_cache = match capacity {
+            0 => None,
+            capacity => Some(Arc::new(LruCache::new(capacity))),
+        };
+    }
+
+    /// Caches the documents matching the filters and their sub-filters, for the filters
+    /// repeated by every search like the tenant ones, `0` disables the cache.
+    ///
+    /// The entries are dropped when the index is updated.
+    pub fn set_filter_cache_capacity(&mut self, capacity: usize) {
+        self.filter_cache = match capacity {
+            0 => None,
+            capacity => Some(Arc::new(LruCache::new(capacity))),
+        };
+    }
+
+    /// Caches the distributions of the facets over all the documents, the ones of the
+    /// placeholder searches, for the given number of facets, `0` disables the cache.
+    ///
+    /// The entries are dropped when the index is updated.
+    pub fn set_facet_distribution_cache_capacity(&mut self, capacity: usize) {
+        self.facet_distribution_cache = match capacity {
+            0 => None,
+            capacity => Some(Arc::new(LruCache::new(capacity))),
+        };
+    }
+
+    /// Returns the codec converting the obkvs of the documents
+    /// into the bytes stored in the documents database.
+    pub(crate) fn documents_codec(&self, rtxn: &RoTxn) -> Result<DocumentsCodec> {
+        let dictionary = self.documents_compression_dictionary(rtxn)?;
+        #[cfg(not(feature = "compression"))]
+        if dictionary.is_some() {
+            return Err(UserError::CompressedDocumentsUnsupported.into());
+        }
+
+        // The documents are only decrypted once the index recorded that they are encrypted,
+        // the key must be the one they were encrypted with.
+        let key_check = self.encryption_key_check(rtxn)?;
+        #[cfg(not(feature = "encryption"))]
+        if key_check.is_some() {
+            return Err(UserError::EncryptedDocumentsUnsupported.into());
+        }
+        #[cfg(feature = "encryption")]
+        let encryption_key = match (key_check, &self.encryption_key) {
+            (Some(key_check), Some(key)) => {
+                key.verify_key_check(key_check)?;
+                Some(key.clone())
+            }
+            (Some(_), None) => return Err(UserError::MissingEncryptionKey.into()),
+            (None, _) => None,
+        };
+
+        Ok(DocumentsCodec::new(
+            #[cfg(feature = "compression")]
+            dictionary,
+            #[cfg(feature = "encryption")]
+            encryption_key,
+        ))
+    }
+
+    /// Records that the documents are encrypted with the key of the index, must be called
+    /// before the documents are written. Fails if the index already has unencrypted documents.
+    #[cfg(feature = "encryption")]
+    pub(crate) fn put_encryption_key_check(&self, wtxn: &mut RwTxn) -> Result<()> {
+        let key = match &self.encryption_key {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+        if self.encryption_key_check(wtxn)?.is_some() {
+            return Ok(());
+        }
+        if !self.documents.is_empty(wtxn)? {
+            return Err(UserError::UnencryptedDocuments.into());
+        }
+
+        let key_check = key.key_check()?;
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::ENCRYPTION_KEY_CHECK_KEY, &key_check)?;
+        Ok(())
+    }
+
+    /// Returns the value encrypted with the key of the documents, `None` if they aren't encrypted.
+    fn encryption_key_check<'t>(&self, rtxn: &'t RoTxn) -> heed::Result<Option<&'t [u8]>> {
+        self.main.get::<_, Str, ByteSlice>(rtxn, main_key::ENCRYPTION_KEY_CHECK_KEY)
+    }
+
     fn initialize_creation_dates(env: &heed::Env, main: PolyDatabase) -> heed::Result<()> {
         let mut txn = env.write_txn()?;
         // The db was just created, we update its metadata with the relevant information.
@@ -174,6 +461,46 @@ impl Index {
         Ok(())
     }
 
+    /// Writes the format version of the index, migrating the databases of the indexes
+    /// written with an older format and refusing the ones written by a newer milli.
+    fn initialize_format_version(
+        env: &heed::Env,
+        main: PolyDatabase,
+        docid_word_positions: Database<BEU32StrCodec, DeltaPositionsCodec>,
+        facet_id_i128_docids: Database<FieldIdI128Codec, CboRoaringBitmapCodec>,
+    ) -> Result<()> {
+        let mut txn = env.write_txn()?;
+        let version = main.get::<_, Str, SerdeJson<u32>>(&txn, main_key::FORMAT_VERSION_KEY)?;
+        match version {
+            Some(version) if version == FORMAT_VERSION => return Ok(()),
+            Some(version) if version > FORMAT_VERSION => {
+                return Err(UserError::UnsupportedIndexFormat {
+                    version,
+                    supported: FORMAT_VERSION,
+                }
+                .into())
+            }
+            _ => (),
+        }
+
+        // The indexes without a format version are at the version 0.
+        let version = version.unwrap_or(0);
+        if version < 1 {
+            migrate_docid_word_positions(&mut txn, docid_word_positions)?;
+        }
+        if version < 2 {
+            migrate_facet_id_i128_docids(&mut txn, facet_id_i128_docids)?;
+        }
+
+        main.put::<_, Str, SerdeJson<u32>>(
+            &mut txn,
+            main_key::FORMAT_VERSION_KEY,
+            &FORMAT_VERSION,
+        )?;
+        txn.commit()?;
+        Ok(())
+    }
+
     /// Create a write transaction to be able to write into the index.
     pub fn write_txn(&self) -> heed::Result<RwTxn> {
         self.env.write_txn()
@@ -189,6 +516,11 @@ impl Index {
         self.env.path()
     }
 
+    /// Returns the size in bytes of the database file of this `Index` on disk.
+    pub fn on_disk_size(&self) -> Result<u64> {
+        Ok(self.path().join("data.mdb").metadata()?.len())
+    }
+
     /// Returns an `EnvClosingEvent` that can be used to wait for the closing event,
     /// multiple threads can wait on this event.
     ///
@@ -546,6 +878,224 @@ impl Index {
         Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
     }
 
+    /* facet normalization */
+
+    /// Writes the normalization of the string values of the faceted fields.
+    pub(crate) fn put_facet_normalization(
+        &self,
+        wtxn: &mut RwTxn,
+        normalization: &HashMap<String, FacetNormalization>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(
+            wtxn,
+            main_key::FACET_NORMALIZATION_KEY,
+            normalization,
+        )
+    }
+
+    /// Deletes the normalization of the faceted fields in the database.
+    pub(crate) fn delete_facet_normalization(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::FACET_NORMALIZATION_KEY)
+    }
+
+    /// Returns the normalization of the string values of the faceted fields, the
+    /// fields that are not part of it are normalized with the default normalization.
+    pub fn facet_normalization(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<String, FacetNormalization>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::FACET_NORMALIZATION_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `facet_normalization`, but returns ids instead.
+    pub fn facet_normalization_ids(
+        &self,
+        rtxn: &RoTxn,
+    ) -> Result<HashMap<FieldId, FacetNormalization>> {
+        let normalization = self.facet_normalization(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(normalization
+            .into_iter()
+            .filter_map(|(name, normalization)| Some((fields_ids_map.id(&name)?, normalization)))
+            .collect())
+    }
+
+    /* field languages */
+
+    /// Writes the languages of the searchable fields.
+    pub(crate) fn put_field_languages(
+        &self,
+        wtxn: &mut RwTxn,
+        languages: &HashMap<String, StemmingLanguage>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::FIELD_LANGUAGES_KEY, languages)
+    }
+
+    /// Deletes the languages of the searchable fields.
+    pub(crate) fn delete_field_languages(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::FIELD_LANGUAGES_KEY)
+    }
+
+    /// Returns the languages the words of the searchable fields are stemmed with, the
+    /// fields that are not part of it are stemmed with the stemming language of the index.
+    pub fn field_languages(&self, rtxn: &RoTxn) -> heed::Result<HashMap<String, StemmingLanguage>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::FIELD_LANGUAGES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `field_languages`, but returns ids instead.
+    pub fn field_languages_ids(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<FieldId, StemmingLanguage>> {
+        let languages = self.field_languages(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(languages
+            .into_iter()
+            .filter_map(|(name, language)| Some((fields_ids_map.id(&name)?, language)))
+            .collect())
+    }
+
+    /* array merge policies */
+
+    /// Writes how the arrays of the fields are merged when the documents are updated.
+    pub(crate) fn put_array_merge_policies(
+        &self,
+        wtxn: &mut RwTxn,
+        policies: &HashMap<String, ArrayMergePolicy>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::ARRAY_MERGE_POLICIES_KEY, policies)
+    }
+
+    /// Deletes the array merge policies of the fields.
+    pub(crate) fn delete_array_merge_policies(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::ARRAY_MERGE_POLICIES_KEY)
+    }
+
+    /// Returns how the arrays of the fields are merged when the documents are updated,
+    /// the arrays of the fields that are not part of it are replaced.
+    pub fn array_merge_policies(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<String, ArrayMergePolicy>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::ARRAY_MERGE_POLICIES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `array_merge_policies`, but returns ids instead.
+    pub fn array_merge_policies_ids(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<FieldId, ArrayMergePolicy>> {
+        let policies = self.array_merge_policies(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(policies
+            .into_iter()
+            .filter_map(|(name, policy)| Some((fields_ids_map.id(&name)?, policy)))
+            .collect())
+    }
+
+    /* date fields */
+
+    /// Writes the names of the faceted fields whose values are dates.
+    pub(crate) fn put_date_fields(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &HashSet<String>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::DATE_FIELDS_KEY, fields)
+    }
+
+    /// Deletes the date fields names in the database.
+    pub(crate) fn delete_date_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::DATE_FIELDS_KEY)
+    }
+
+    /// Returns the names of the faceted fields whose values are dates.
+    pub fn date_fields(&self, rtxn: &RoTxn) -> heed::Result<HashSet<String>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::DATE_FIELDS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `date_fields`, but returns ids instead.
+    pub fn date_fields_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let fields = self.date_fields(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
+    }
+
+    /* exact attributes */
+
+    /// Writes the names of the searchable fields whose words only match the query words exactly.
+    pub(crate) fn put_exact_attributes(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &HashSet<String>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::EXACT_ATTRIBUTES_KEY, fields)
+    }
+
+    /// Deletes the exact attributes names in the database.
+    pub(crate) fn delete_exact_attributes(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::EXACT_ATTRIBUTES_KEY)
+    }
+
+    /// Returns the names of the searchable fields whose words never match through typos
+    /// or prefixes.
+    pub fn exact_attributes(&self, rtxn: &RoTxn) -> heed::Result<HashSet<String>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::EXACT_ATTRIBUTES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `exact_attributes`, but returns ids instead.
+    pub fn exact_attributes_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let fields = self.exact_attributes(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
+    }
+
+    /* hierarchical facets */
+
+    /// Writes the names of the faceted fields whose values are hierarchical paths.
+    pub(crate) fn put_hierarchical_facets(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &HashSet<String>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::HIERARCHICAL_FACETS_KEY, fields)
+    }
+
+    /// Deletes the hierarchical facets names in the database.
+    pub(crate) fn delete_hierarchical_facets(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::HIERARCHICAL_FACETS_KEY)
+    }
+
+    /// Returns the names of the faceted fields whose values are hierarchical paths.
+    pub fn hierarchical_facets(&self, rtxn: &RoTxn) -> heed::Result<HashSet<String>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::HIERARCHICAL_FACETS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `hierarchical_facets`, but returns ids instead.
+    pub fn hierarchical_facets_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let fields = self.hierarchical_facets(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
+    }
+
     /* faceted documents ids */
 
     /// Returns the faceted fields names.
@@ -674,100 +1224,354 @@ impl Index {
         self.main.delete::<_, Str>(wtxn, main_key::DISTINCT_FIELD_KEY)
     }
 
-    /* criteria */
+    /* document id validation */
 
-    pub(crate) fn put_criteria(
+    pub(crate) fn put_document_id_validation(
         &self,
         wtxn: &mut RwTxn,
-        criteria: &[Criterion],
+        validation: DocumentIdValidation,
     ) -> heed::Result<()> {
-        self.main.put::<_, Str, SerdeJson<&[Criterion]>>(wtxn, main_key::CRITERIA_KEY, &criteria)
+        self.main.put::<_, Str, SerdeJson<DocumentIdValidation>>(
+            wtxn,
+            main_key::DOCUMENT_ID_VALIDATION_KEY,
+            &validation,
+        )
     }
 
-    pub(crate) fn delete_criteria(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
-        self.main.delete::<_, Str>(wtxn, main_key::CRITERIA_KEY)
+    /// Returns the rule used to validate the external document ids, strict by default.
+    pub fn document_id_validation(&self, rtxn: &RoTxn) -> heed::Result<DocumentIdValidation> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<DocumentIdValidation>>(
+                rtxn,
+                main_key::DOCUMENT_ID_VALIDATION_KEY,
+            )?
+            .unwrap_or_default())
     }
 
-    pub fn criteria(&self, rtxn: &RoTxn) -> heed::Result<Vec<Criterion>> {
-        match self.main.get::<_, Str, SerdeJson<Vec<Criterion>>>(rtxn, main_key::CRITERIA_KEY)? {
-            Some(criteria) => Ok(criteria),
-            None => Ok(default_criteria()),
-        }
+    pub(crate) fn delete_document_id_validation(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::DOCUMENT_ID_VALIDATION_KEY)
     }
 
-    /* words fst */
+    /* terms dropping order */
 
-    /// Writes the FST which is the words dictionary of the engine.
-    pub(crate) fn put_words_fst<A: AsRef<[u8]>>(
+    pub(crate) fn put_terms_dropping_order(
         &self,
         wtxn: &mut RwTxn,
-        fst: &fst::Set<A>,
+        order: TermsDroppingOrder,
     ) -> heed::Result<()> {
-        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::WORDS_FST_KEY, fst.as_fst().as_bytes())
+        self.main.put::<_, Str, SerdeJson<TermsDroppingOrder>>(
+            wtxn,
+            main_key::TERMS_DROPPING_ORDER_KEY,
+            &order,
+        )
     }
 
-    /// Returns the FST which is the words dictionary of the engine.
-    pub fn words_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
-        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::WORDS_FST_KEY)? {
-            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
-            None => Ok(fst::Set::default().map_data(Cow::Owned)?),
-        }
+    /// Returns the order in which the words criterion drops the query terms,
+    /// from the end of the query by default.
+    pub fn terms_dropping_order(&self, rtxn: &RoTxn) -> heed::Result<TermsDroppingOrder> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<TermsDroppingOrder>>(rtxn, main_key::TERMS_DROPPING_ORDER_KEY)?
+            .unwrap_or_default())
     }
 
-    /* stop words */
+    pub(crate) fn delete_terms_dropping_order(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::TERMS_DROPPING_ORDER_KEY)
+    }
 
-    pub(crate) fn put_stop_words<A: AsRef<[u8]>>(
+    /* stemming language */
+
+    pub(crate) fn put_stemming_language(
         &self,
         wtxn: &mut RwTxn,
-        fst: &fst::Set<A>,
+        language: StemmingLanguage,
     ) -> heed::Result<()> {
-        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::STOP_WORDS_KEY, fst.as_fst().as_bytes())
+        self.main.put::<_, Str, SerdeJson<StemmingLanguage>>(
+            wtxn,
+            main_key::STEMMING_LANGUAGE_KEY,
+            &language,
+        )
     }
 
-    pub(crate) fn delete_stop_words(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
-        self.main.delete::<_, Str>(wtxn, main_key::STOP_WORDS_KEY)
+    /// Returns the language used to stem the indexed and the query words,
+    /// the words are not stemmed if there is none.
+    pub fn stemming_language(&self, rtxn: &RoTxn) -> heed::Result<Option<StemmingLanguage>> {
+        self.main.get::<_, Str, SerdeJson<StemmingLanguage>>(rtxn, main_key::STEMMING_LANGUAGE_KEY)
     }
 
-    pub fn stop_words<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
-        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::STOP_WORDS_KEY)? {
-            Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
-            None => Ok(None),
-        }
+    pub(crate) fn delete_stemming_language(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::STEMMING_LANGUAGE_KEY)
     }
 
-    /* synonyms */
+    /* diacritics sensitive */
 
-    pub(crate) fn put_synonyms(
+    pub(crate) fn put_diacritics_sensitive(
         &self,
         wtxn: &mut RwTxn,
-        synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
+        sensitive: bool,
     ) -> heed::Result<()> {
-        self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::SYNONYMS_KEY, synonyms)
-    }
-
-    pub(crate) fn delete_synonyms(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
-        self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)
+        self.main.put::<_, Str, SerdeJson<bool>>(
+            wtxn,
+            main_key::DIACRITICS_SENSITIVE_KEY,
+            &sensitive,
+        )
     }
 
-    pub fn synonyms(&self, rtxn: &RoTxn) -> heed::Result<HashMap<Vec<String>, Vec<Vec<String>>>> {
+    /// Returns whether the diacritics of the words are kept when indexing and searching,
+    /// they are removed by default.
+    pub fn diacritics_sensitive(&self, rtxn: &RoTxn) -> heed::Result<bool> {
         Ok(self
             .main
-            .get::<_, Str, SerdeBincode<_>>(rtxn, main_key::SYNONYMS_KEY)?
+            .get::<_, Str, SerdeJson<bool>>(rtxn, main_key::DIACRITICS_SENSITIVE_KEY)?
             .unwrap_or_default())
     }
 
-    pub fn words_synonyms<S: AsRef<str>>(
-        &self,
-        rtxn: &RoTxn,
-        words: &[S],
-    ) -> heed::Result<Option<Vec<Vec<String>>>> {
-        let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
-        Ok(self.synonyms(rtxn)?.remove(&words))
+    pub(crate) fn delete_diacritics_sensitive(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::DIACRITICS_SENSITIVE_KEY)
     }
 
-    /* words prefixes fst */
+    /* case sensitive */
 
-    /// Writes the FST which is the words prefixes dictionnary of the engine.
+    pub(crate) fn put_case_sensitive(&self, wtxn: &mut RwTxn, sensitive: bool) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<bool>>(wtxn, main_key::CASE_SENSITIVE_KEY, &sensitive)
+    }
+
+    /// Returns whether the words are also indexed and searched with their case,
+    /// they are only lowercased by default.
+    pub fn case_sensitive(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<bool>>(rtxn, main_key::CASE_SENSITIVE_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn delete_case_sensitive(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::CASE_SENSITIVE_KEY)
+    }
+
+    /* numbers normalization */
+
+    pub(crate) fn put_numbers_normalization(
+        &self,
+        wtxn: &mut RwTxn,
+        normalization: bool,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<bool>>(
+            wtxn,
+            main_key::NUMBERS_NORMALIZATION_KEY,
+            &normalization,
+        )
+    }
+
+    /// Returns whether the numeric words are indexed and searched in a canonical form,
+    /// without their leading zeros and thousands separators and written with ASCII digits,
+    /// they are kept as is by default.
+    pub fn numbers_normalization(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<bool>>(rtxn, main_key::NUMBERS_NORMALIZATION_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn delete_numbers_normalization(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::NUMBERS_NORMALIZATION_KEY)
+    }
+
+    /* typo tolerance */
+
+    pub(crate) fn put_typo_tolerance(
+        &self,
+        wtxn: &mut RwTxn,
+        typo_tolerance: TypoTolerance,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<TypoTolerance>>(
+            wtxn,
+            main_key::TYPO_TOLERANCE_KEY,
+            &typo_tolerance,
+        )
+    }
+
+    /// Returns the rules deciding which typos are accepted when deriving the query words.
+    pub fn typo_tolerance(&self, rtxn: &RoTxn) -> heed::Result<TypoTolerance> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<TypoTolerance>>(rtxn, main_key::TYPO_TOLERANCE_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn delete_typo_tolerance(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::TYPO_TOLERANCE_KEY)
+    }
+
+    /* query rules */
+
+    pub(crate) fn put_query_rules(
+        &self,
+        wtxn: &mut RwTxn,
+        rules: &[QueryRule],
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<&[QueryRule]>>(wtxn, main_key::QUERY_RULES_KEY, &rules)
+    }
+
+    /// Returns the rules applied, in order, to the searches whose query matches their pattern.
+    pub fn query_rules(&self, rtxn: &RoTxn) -> heed::Result<Vec<QueryRule>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<Vec<QueryRule>>>(rtxn, main_key::QUERY_RULES_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn delete_query_rules(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::QUERY_RULES_KEY)
+    }
+
+    /* criteria */
+
+    pub(crate) fn put_criteria(
+        &self,
+        wtxn: &mut RwTxn,
+        criteria: &[Criterion],
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<&[Criterion]>>(wtxn, main_key::CRITERIA_KEY, &criteria)
+    }
+
+    pub(crate) fn delete_criteria(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::CRITERIA_KEY)
+    }
+
+    pub fn criteria(&self, rtxn: &RoTxn) -> heed::Result<Vec<Criterion>> {
+        match self.main.get::<_, Str, SerdeJson<Vec<Criterion>>>(rtxn, main_key::CRITERIA_KEY)? {
+            Some(criteria) => Ok(criteria),
+            None => Ok(default_criteria()),
+        }
+    }
+
+    /* words fst */
+
+    /// Writes the FST which is the words dictionary of the engine.
+    pub(crate) fn put_words_fst<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::WORDS_FST_KEY, fst.as_fst().as_bytes())
+    }
+
+    /// Returns the FST which is the words dictionary of the engine.
+    pub fn words_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::WORDS_FST_KEY)? {
+            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
+            None => Ok(fst::Set::default().map_data(Cow::Owned)?),
+        }
+    }
+
+    /* stop words */
+
+    pub(crate) fn put_stop_words<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::STOP_WORDS_KEY, fst.as_fst().as_bytes())
+    }
+
+    pub(crate) fn delete_stop_words(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::STOP_WORDS_KEY)
+    }
+
+    pub fn stop_words<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::STOP_WORDS_KEY)? {
+            Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /* exact words */
+
+    pub(crate) fn put_exact_words<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::EXACT_WORDS_KEY, fst.as_fst().as_bytes())
+    }
+
+    pub(crate) fn delete_exact_words(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::EXACT_WORDS_KEY)
+    }
+
+    /// Returns the words of the queries that never match through typos or prefixes.
+    pub fn exact_words<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::EXACT_WORDS_KEY)? {
+            Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /* decompounding dictionary */
+
+    pub(crate) fn put_decompounding_dictionary<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(
+            wtxn,
+            main_key::DECOMPOUNDING_DICTIONARY_KEY,
+            fst.as_fst().as_bytes(),
+        )
+    }
+
+    pub(crate) fn delete_decompounding_dictionary(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::DECOMPOUNDING_DICTIONARY_KEY)
+    }
+
+    /// Returns the words used to split the compound words of the documents,
+    /// the compound words are not split if there is none.
+    pub fn decompounding_dictionary<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+    ) -> Result<Option<fst::Set<&'t [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::DECOMPOUNDING_DICTIONARY_KEY)? {
+            Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /* synonyms */
+
+    pub(crate) fn put_synonyms(
+        &self,
+        wtxn: &mut RwTxn,
+        synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::SYNONYMS_KEY, synonyms)
+    }
+
+    pub(crate) fn delete_synonyms(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)
+    }
+
+    pub fn synonyms(&self, rtxn: &RoTxn) -> heed::Result<HashMap<Vec<String>, Vec<Vec<String>>>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeBincode<_>>(rtxn, main_key::SYNONYMS_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub fn words_synonyms<S: AsRef<str>>(
+        &self,
+        rtxn: &RoTxn,
+        words: &[S],
+    ) -> heed::Result<Option<Vec<Vec<String>>>> {
+        let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
+        Ok(self.synonyms(rtxn)?.remove(&words))
+    }
+
+    /* words prefixes fst */
+
+    /// Writes the FST which is the words prefixes dictionnary of the engine.
     pub(crate) fn put_words_prefixes_fst<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
@@ -788,6 +1592,92 @@ impl Index {
         }
     }
 
+    /* phonetic matching */
+
+    #[cfg(feature = "phonetic")]
+    pub(crate) fn put_phonetic_matching(
+        &self,
+        wtxn: &mut RwTxn,
+        enabled: bool,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<bool>>(wtxn, main_key::PHONETIC_MATCHING_KEY, &enabled)
+    }
+
+    /// Returns whether the query words can match the words that sound alike, disabled by default.
+    #[cfg(feature = "phonetic")]
+    pub fn phonetic_matching(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<bool>>(rtxn, main_key::PHONETIC_MATCHING_KEY)?
+            .unwrap_or_default())
+    }
+
+    #[cfg(feature = "phonetic")]
+    pub(crate) fn delete_phonetic_matching(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::PHONETIC_MATCHING_KEY)
+    }
+
+    /* phonetic words fst */
+
+    /// Writes the FST which associates the phonetic keys to the words of the dictionnary.
+    #[cfg(feature = "phonetic")]
+    pub(crate) fn put_phonetic_words_fst<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(
+            wtxn,
+            main_key::PHONETIC_WORDS_FST_KEY,
+            fst.as_fst().as_bytes(),
+        )
+    }
+
+    /// Returns the FST which associates the phonetic keys to the words of the dictionnary,
+    /// each entry is the phonetic key followed by a null byte and the word.
+    #[cfg(feature = "phonetic")]
+    pub fn phonetic_words_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::PHONETIC_WORDS_FST_KEY)? {
+            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
+            None => Ok(fst::Set::default().map_data(Cow::Owned)?),
+        }
+    }
+
+    #[cfg(feature = "phonetic")]
+    pub(crate) fn delete_phonetic_words_fst(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::PHONETIC_WORDS_FST_KEY)
+    }
+
+    /// Returns the words of the dictionnary that sound like the given word,
+    /// if the phonetic matching is enabled.
+    #[cfg(feature = "phonetic")]
+    pub fn phonetic_words(&self, rtxn: &RoTxn, word: &str) -> Result<Vec<String>> {
+        use fst::{IntoStreamer, Streamer};
+
+        let key = match crate::phonetic::phonetic_key(word) {
+            Some(key) if self.phonetic_matching(rtxn)? => key,
+            _ => return Ok(Vec::new()),
+        };
+
+        // The entries are the phonetic key, the separator and the word, we retrieve all
+        // the entries between the separator and the next byte to get the words of this key.
+        let mut start = key.into_bytes();
+        start.push(crate::phonetic::PHONETIC_SEPARATOR);
+        let mut end = start.clone();
+        if let Some(last) = end.last_mut() {
+            *last += 1;
+        }
+
+        let fst = self.phonetic_words_fst(rtxn)?;
+        let mut stream = fst.range().ge(&start).lt(&end).into_stream();
+        let mut words = Vec::new();
+        while let Some(bytes) = stream.next() {
+            words.push(std::str::from_utf8(&bytes[start.len()..])?.to_string());
+        }
+
+        Ok(words)
+    }
+
     /* word documents count */
 
     /// Returns the number of documents ids associated with the given word,
@@ -796,47 +1686,378 @@ impl Index {
         self.word_docids.remap_data_type::<RoaringBitmapLenCodec>().get(rtxn, word)
     }
 
-    /* documents */
+    /// Returns the number of documents containing a word starting with the prefix,
+    /// `None` if the prefix is not in the words prefixes FST.
+    pub fn word_prefix_documents_count(
+        &self,
+        rtxn: &RoTxn,
+        prefix: &str,
+    ) -> heed::Result<Option<u64>> {
+        self.word_prefix_docids.remap_data_type::<RoaringBitmapLenCodec>().get(rtxn, prefix)
+    }
 
-    /// Returns a [`Vec`] of the requested documents. Returns an error if a document is missing.
-    pub fn documents<'t>(
+    /// Returns the number of documents in which the right word follows the left one at
+    /// the given proximity, from 1 to 7, the right word preceding the left one increases
+    /// the proximity by one.
+    pub fn word_pair_proximity_documents_count(
+        &self,
+        rtxn: &RoTxn,
+        left: &str,
+        right: &str,
+        proximity: u8,
+    ) -> heed::Result<Option<u64>> {
+        self.word_pair_proximity_docids
+            .remap_data_type::<CboRoaringBitmapLenCodec>()
+            .get(rtxn, &(left, right, proximity))
+    }
+
+    /// Returns the number of documents in which a word starting with the right prefix follows
+    /// the left word at the given proximity, see `Index::word_pair_proximity_documents_count`,
+    /// `None` if the prefix is not in the words prefixes FST.
+    pub fn word_prefix_pair_proximity_documents_count(
+        &self,
+        rtxn: &RoTxn,
+        left: &str,
+        right_prefix: &str,
+        proximity: u8,
+    ) -> heed::Result<Option<u64>> {
+        self.word_prefix_pair_proximity_docids
+            .remap_data_type::<CboRoaringBitmapLenCodec>()
+            .get(rtxn, &(left, right_prefix, proximity))
+    }
+
+    /// Returns the indexed words starting with the prefix, all of them if it is empty,
+    /// and in at least `min_frequency` documents, with their number of documents.
+    pub fn word_frequencies<'t>(
         &self,
         rtxn: &'t RoTxn,
-        ids: impl IntoIterator<Item = DocumentId>,
-    ) -> Result<Vec<(DocumentId, obkv::KvReaderU16<'t>)>> {
-        let mut documents = Vec::new();
+        prefix: &str,
+        min_frequency: u64,
+    ) -> Result<impl Iterator<Item = Result<(&'t str, u64)>> + 't> {
+        let iter = self
+            .word_docids
+            .remap_data_type::<RoaringBitmapLenCodec>()
+            .prefix_iter(rtxn, prefix)?;
+        Ok(iter.filter_map(move |result| match result {
+            Ok((_, count)) if count < min_frequency => None,
+            Ok(entry) => Some(Ok(entry)),
+            Err(error) => Some(Err(error.into())),
+        }))
+    }
+
+    /// Returns the statistics of the word as it is indexed, normalized,
+    /// `None` if it isn't in any document.
+    pub fn term_stats(&self, rtxn: &RoTxn, word: &str) -> Result<Option<TermStats>> {
+        let mut docids = self.word_docids.get(rtxn, word)?.unwrap_or_default();
+        docids |= self.exact_word_docids.get(rtxn, word)?.unwrap_or_default();
+        if docids.is_empty() {
+            return Ok(None);
+        }
+
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let mut fields_occurrences = HashMap::new();
+        for docid in docids.iter() {
+            if let Some(positions) = self.docid_word_positions.get(rtxn, &(docid, word))? {
+                for position in positions {
+                    let (field_id, _) = relative_from_absolute_position(position);
+                    *fields_occurrences.entry(field_id).or_insert(0) += 1;
+                }
+            }
+        }
 
-        for id in ids {
-            let kv = self
-                .documents
-                .get(rtxn, &BEU32::new(id))?
-                .ok_or_else(|| UserError::UnknownInternalDocumentId { document_id: id })?;
-            documents.push((id, kv));
+        let mut stats = TermStats { document_frequency: docids.len(), ..TermStats::default() };
+        for (field_id, count) in fields_occurrences {
+            let name = fields_ids_map
+                .name(field_id)
+                .ok_or(FieldIdMapMissingEntry::FieldId { field_id, process: "term_stats" })?;
+            stats.occurrences += count;
+            stats.fields_occurrences.insert(name.to_string(), count);
         }
 
-        Ok(documents)
+        Ok(Some(stats))
+    }
+
+    /// Returns the `limit` words contained in the most documents with their number of documents.
+    pub fn most_frequent_words(&self, rtxn: &RoTxn, limit: usize) -> Result<Vec<(String, u64)>> {
+        self.complete_prefix(rtxn, "", limit)
+    }
+
+    /// Returns the words present in more than the `threshold` ratio of the documents,
+    /// between 0 and 1, with their ratio, as candidates to the stop words setting.
+    ///
+    /// The words are sorted by decreasing ratio.
+    pub fn stop_words_candidates(
+        &self,
+        rtxn: &RoTxn,
+        threshold: f64,
+    ) -> Result<Vec<(String, f64)>> {
+        let number_of_documents = self.number_of_documents(rtxn)?;
+        if number_of_documents == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut candidates = Vec::new();
+        for result in self.word_frequencies(rtxn, "", 0)? {
+            let (word, count) = result?;
+            let ratio = count as f64 / number_of_documents as f64;
+            if ratio > threshold {
+                candidates.push((word.to_string(), ratio));
+            }
+        }
+
+        candidates.sort_by(|(a, ar), (b, br)| br.partial_cmp(ar).unwrap().then_with(|| a.cmp(b)));
+        Ok(candidates)
+    }
+
+    /* documents compression dictionary */
+
+    /// Writes the dictionary used to compress the documents and compresses the documents
+    /// already in the index with it. Once written, all the documents are stored compressed.
+    #[cfg(feature = "compression")]
+    pub(crate) fn put_documents_compression_dictionary(
+        &self,
+        wtxn: &mut RwTxn,
+        dictionary: &[u8],
+    ) -> Result<()> {
+        let documents = self.documents.remap_data_type::<ByteSlice>();
+
+        // The previous codec reads the documents, the new one writes them with the dictionary.
+        let previous_codec = self.documents_codec(wtxn)?;
+        self.main.put::<_, Str, ByteSlice>(
+            wtxn,
+            main_key::DOCUMENTS_COMPRESSION_DICTIONARY_KEY,
+            dictionary,
+        )?;
+        let codec = self.documents_codec(wtxn)?;
+
+        // The documents are re-encoded in place, one at a time, with a cursor.
+        let mut iter = documents.iter_mut(wtxn)?;
+        while let Some((id, bytes)) = iter.next().transpose()? {
+            let obkv = previous_codec.decode(bytes)?;
+            let bytes = codec.encode(&obkv)?.into_owned();
+            drop(obkv);
+            // safety: we don't keep references from inside the LMDB database.
+            unsafe { iter.put_current(&id, &bytes)? };
+        }
+
+        Ok(())
+    }
+
+    /// Returns the dictionary used to compress the documents, if any.
+    pub fn documents_compression_dictionary<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+    ) -> heed::Result<Option<&'t [u8]>> {
+        self.main.get::<_, Str, ByteSlice>(rtxn, main_key::DOCUMENTS_COMPRESSION_DICTIONARY_KEY)
+    }
+
+    /* warmup */
+
+    /// Reads the databases of the given level to pull them into the page cache, for the
+    /// first searches after opening a cold index not to wait for the disk.
+    pub fn warmup(&self, rtxn: &RoTxn, level: WarmupLevel) -> Result<WarmupStats> {
+        let mut stats = WarmupStats::default();
+
+        if level == WarmupLevel::Full {
+            for (_, db) in self.databases().iter() {
+                stats.touch_entries(db.iter::<_, ByteSlice, ByteSlice>(rtxn)?)?;
+            }
+            return Ok(stats);
+        }
+
+        for key in [main_key::WORDS_FST_KEY, main_key::WORDS_PREFIXES_FST_KEY].iter() {
+            if let Some(bytes) = self.main.get::<_, Str, ByteSlice>(rtxn, key)? {
+                stats.touch(key.as_bytes(), bytes);
+            }
+        }
+
+        if level == WarmupLevel::Search {
+            let word_dbs = [
+                self.word_docids.as_polymorph(),
+                self.exact_word_docids.as_polymorph(),
+                self.word_prefix_docids.as_polymorph(),
+            ];
+            for db in word_dbs.iter() {
+                stats.touch_entries(db.iter::<_, ByteSlice, ByteSlice>(rtxn)?)?;
+            }
+
+            let facet_dbs = [
+                self.facet_id_f64_docids.as_polymorph(),
+                self.facet_id_string_docids.as_polymorph(),
+            ];
+            for field_id in self.faceted_fields_ids(rtxn)? {
+                let [a, b] = field_id.to_be_bytes();
+                let level_zero_prefix = [a, b, 0];
+                for db in facet_dbs.iter() {
+                    let entries =
+                        db.prefix_iter::<_, ByteSlice, ByteSlice>(rtxn, &level_zero_prefix)?;
+                    stats.touch_entries(entries)?;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns all the databases of the index with their names.
+    pub(crate) fn databases(&self) -> [(&'static str, &PolyDatabase); NUMBER_OF_DBS as usize] {
+        use db_name::*;
+
+        [
+            (MAIN, &self.main),
+            (WORD_DOCIDS, self.word_docids.as_polymorph()),
+            (EXACT_WORD_DOCIDS, self.exact_word_docids.as_polymorph()),
+            (WORD_PREFIX_DOCIDS, self.word_prefix_docids.as_polymorph()),
+            (DOCID_WORD_POSITIONS, self.docid_word_positions.as_polymorph()),
+            (WORD_PAIR_PROXIMITY_DOCIDS, self.word_pair_proximity_docids.as_polymorph()),
+            (
+                WORD_PREFIX_PAIR_PROXIMITY_DOCIDS,
+                self.word_prefix_pair_proximity_docids.as_polymorph(),
+            ),
+            (WORD_POSITION_DOCIDS, self.word_position_docids.as_polymorph()),
+            (WORD_PREFIX_POSITION_DOCIDS, self.word_prefix_position_docids.as_polymorph()),
+            (FIELD_ID_WORD_COUNT_DOCIDS, self.field_id_word_count_docids.as_polymorph()),
+            (FACET_ID_F64_DOCIDS, self.facet_id_f64_docids.as_polymorph()),
+            (FACET_ID_I128_DOCIDS, self.facet_id_i128_docids.as_polymorph()),
+            (FACET_ID_STRING_DOCIDS, self.facet_id_string_docids.as_polymorph()),
+            (FIELD_ID_DOCID_FACET_F64S, self.field_id_docid_facet_f64s.as_polymorph()),
+            (FIELD_ID_DOCID_FACET_STRINGS, self.field_id_docid_facet_strings.as_polymorph()),
+            (DOCUMENTS, self.documents.as_polymorph()),
+        ]
+    }
+
+    /* documents */
+
+    /// Returns a [`Vec`] of the requested documents.
+    /// Returns an error if a document is missing.
+    ///
+    /// The obkvs are borrowed from the LMDB pages for the lifetime of the transaction,
+    /// they are only copied when the documents are compressed or encrypted. Use
+    /// [`crate::obkv_to_json_writer`] to write them as JSON without deserializing them.
+    pub fn documents<'t>(
+        &'t self,
+        rtxn: &'t RoTxn,
+        ids: impl IntoIterator<Item = DocumentId>,
+    ) -> Result<Vec<(DocumentId, StoredDocument<'t>)>> {
+        self.iter_documents(rtxn, ids).collect()
+    }
+
+    /// Returns an iterator over the stored documents with the given ids,
+    /// each document being fetched when the iterator advances.
+    pub fn iter_documents<'t, I>(
+        &'t self,
+        rtxn: &'t RoTxn,
+        ids: I,
+    ) -> DocumentsIter<'t, I::IntoIter>
+    where
+        I: IntoIterator<Item = DocumentId>,
+    {
+        DocumentsIter { index: self, rtxn, codec: None, ids: ids.into_iter() }
     }
 
     /// Returns an iterator over all the documents in the index.
     pub fn all_documents<'t>(
-        &self,
+        &'t self,
         rtxn: &'t RoTxn,
-    ) -> Result<impl Iterator<Item = heed::Result<(DocumentId, obkv::KvReaderU16<'t>)>>> {
-        Ok(self
-            .documents
-            .iter(rtxn)?
+    ) -> Result<impl Iterator<Item = Result<(DocumentId, StoredDocument<'t>)>>> {
+        let codec = self.documents_codec(rtxn)?;
+        Ok(self.documents.remap_data_type::<ByteSlice>().iter(rtxn)?.map(move |document| {
+            let (id, bytes) = document?;
             // we cast the BEU32 to a DocumentId
-            .map(|document| document.map(|(id, obkv)| (id.get(), obkv))))
+            Ok((id.get(), StoredDocument::new(codec.decode(bytes)?)))
+        }))
     }
 
     pub fn facets_distribution<'a>(&'a self, rtxn: &'a RoTxn) -> FacetDistribution<'a> {
         FacetDistribution::new(rtxn, self)
     }
 
+    /// Returns the distinct values of the filterable field with their number of documents,
+    /// independently of any search, in the given order and paginated by `offset` and `limit`.
+    pub fn facet_values(
+        &self,
+        rtxn: &RoTxn,
+        field: &str,
+        order: FacetOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(String, u64)>> {
+        let options = FacetOptions { order, ..Default::default() };
+        let mut distribution = self
+            .facets_distribution(rtxn)
+            .facets(Some(field))
+            .facet_options(field, options)
+            .execute_ordered()?;
+        let values = distribution.remove(field).unwrap_or_default();
+        Ok(values.into_iter().skip(offset).take(limit).collect())
+    }
+
+    pub fn facet_histogram<'a, A: AsRef<str>>(
+        &'a self,
+        rtxn: &'a RoTxn,
+        field: A,
+        buckets: HistogramBuckets,
+    ) -> FacetHistogram<'a> {
+        FacetHistogram::new(rtxn, self, field, buckets)
+    }
+
+    /// Returns the number of geo-indexed documents in the geohash cells of the given
+    /// precision, the number of characters of the geohashes, from 1 to 12.
+    pub fn geo_clusters<'a>(&'a self, rtxn: &'a RoTxn, precision: usize) -> GeoClusters<'a> {
+        GeoClusters::new(rtxn, self, precision)
+    }
+
     pub fn search<'a>(&'a self, rtxn: &'a RoTxn) -> Search<'a> {
         Search::new(rtxn, self)
     }
 
+    /// Returns the query with its misspelled words replaced by the closest and most frequent
+    /// indexed words, to propose a "did you mean" query when a query returns few results.
+    /// Returns `None` if there is nothing to correct.
+    pub fn suggest_correction(&self, rtxn: &RoTxn, query: &str) -> Result<Option<String>> {
+        crate::search::suggest_correction(self, rtxn, query)
+    }
+
+    /// Returns the `limit` most frequent indexed words starting with the prefix
+    /// with the number of documents containing them, to autocomplete a query.
+    pub fn complete_prefix(
+        &self,
+        rtxn: &RoTxn,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, u64)>> {
+        let prefix = prefix.to_lowercase();
+        let words_fst = self.words_fst(rtxn)?;
+        let automaton = StrAutomaton::new(&prefix).starts_with();
+        let mut stream = words_fst.search(automaton).into_stream();
+
+        // A min-heap only keeps the most frequent words, the first ones lexicographically.
+        let mut heap = BinaryHeap::with_capacity(limit + 1);
+        while let Some(word) = stream.next() {
+            let word = str::from_utf8(word)?;
+            let count = self.word_documents_count(rtxn, word)?.unwrap_or(0);
+            heap.push(Reverse((count, Reverse(word.to_string()))));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+
+        let mut words: Vec<_> =
+            heap.into_iter().map(|Reverse((count, Reverse(word)))| (word, count)).collect();
+        words.sort_by(|(a, ac), (b, bc)| bc.cmp(ac).then_with(|| a.cmp(b)));
+        Ok(words)
+    }
+
+    /// Executes the queries in the same transaction, the settings and the
+    /// dictionaries of the index are loaded once for all the queries.
+    pub fn search_many(&self, rtxn: &RoTxn, queries: &[SearchQuery]) -> Result<Vec<SearchResult>> {
+        let context = SearchContext::new(rtxn, self)?;
+        queries
+            .iter()
+            .map(|query| Search::from_query(rtxn, self, query)?.execute_in(&context))
+            .collect()
+    }
+
     /// Returns the index creation time.
     pub fn created_at(&self, rtxn: &RoTxn) -> Result<OffsetDateTime> {
         Ok(self
@@ -866,42 +2087,498 @@ impl Index {
     ) -> heed::Result<()> {
         self.main.put::<_, Str, SerdeJson<OffsetDateTime>>(wtxn, main_key::UPDATED_AT_KEY, &time)
     }
+
+    /// Returns the id of the last update of the update journal committed in the index.
+    pub fn last_journaled_update(&self, rtxn: &RoTxn) -> heed::Result<Option<UpdateId>> {
+        self.main.get::<_, Str, SerdeJson<UpdateId>>(rtxn, main_key::LAST_JOURNALED_UPDATE_KEY)
+    }
+
+    pub(crate) fn put_last_journaled_update(
+        &self,
+        wtxn: &mut RwTxn,
+        update_id: UpdateId,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<UpdateId>>(
+            wtxn,
+            main_key::LAST_JOURNALED_UPDATE_KEY,
+            &update_id,
+        )
+    }
+}
+
+/// An iterator over the stored documents with the given ids, see `Index::iter_documents`.
+pub struct DocumentsIter<'t, I> {
+    index: &'t Index,
+    rtxn: &'t RoTxn<'t>,
+    codec: Option<DocumentsCodec>,
+    ids: I,
+}
+
+impl<'t, I: Iterator<Item = DocumentId>> Iterator for DocumentsIter<'t, I> {
+    type Item = Result<(DocumentId, StoredDocument<'t>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.ids.next()?;
+        let codec = match self.codec {
+            Some(ref codec) => codec,
+            None => match self.index.documents_codec(self.rtxn) {
+                Ok(codec) => self.codec.insert(codec),
+                Err(error) => return Some(Err(error)),
+            },
+        };
+
+        let documents = self.index.documents.remap_data_type::<ByteSlice>();
+        let document = match documents.get(self.rtxn, &BEU32::new(id)) {
+            Ok(Some(bytes)) => codec.decode(bytes).map(|obkv| (id, StoredDocument::new(obkv))),
+            Ok(None) => Err(UserError::UnknownInternalDocumentId { document_id: id }.into()),
+            Err(error) => Err(error.into()),
+        };
+        Some(document)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ids.size_hint()
+    }
+}
+
+/// Re-encodes the positions stored as roaring bitmaps into delta-encoded positions.
+fn migrate_docid_word_positions(
+    wtxn: &mut RwTxn,
+    docid_word_positions: Database<BEU32StrCodec, DeltaPositionsCodec>,
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    let db = docid_word_positions.remap_types::<ByteSlice, ByteSlice>();
+    let mut iter = db.iter_mut(wtxn)?;
+    while let Some((key, bytes)) = iter.next().transpose()? {
+        let positions = BoRoaringBitmapCodec::bytes_decode(bytes)
+            .ok_or(SerializationError::Decoding { db_name: Some(db_name::DOCID_WORD_POSITIONS) })?;
+        let key = key.to_vec();
+        buffer.clear();
+        DeltaPositionsCodec::serialize_into(&positions, &mut buffer);
+        // safety: we don't keep references from inside the LMDB database.
+        unsafe { iter.put_current(&key, &buffer)? };
+    }
+    Ok(())
+}
+
+/// Re-encodes the `i64` keys of the integer facets into `i128` keys. The keys are written
+/// into a temporary file as they change size and can't be replaced in place.
+fn migrate_facet_id_i128_docids(
+    wtxn: &mut RwTxn,
+    facet_id_i128_docids: Database<FieldIdI128Codec, CboRoaringBitmapCodec>,
+) -> Result<()> {
+    let db = facet_id_i128_docids.remap_types::<ByteSlice, ByteSlice>();
+    let mut writer = create_writer(grenad::CompressionType::None, None, tempfile::tempfile()?);
+    let decoding_error =
+        || SerializationError::Decoding { db_name: Some(db_name::FACET_ID_I128_DOCIDS) };
+    for result in db.iter(wtxn)? {
+        let (key, bytes) = result?;
+        let (field_id, key) = try_split_array_at(key).ok_or_else(decoding_error)?;
+        let (integer, _) = try_split_array_at(key).ok_or_else(decoding_error)?;
+        let key = (u16::from_be_bytes(field_id), i128::from(i64_from_bytes(integer)));
+        let key = FieldIdI128Codec::bytes_encode(&key).unwrap();
+        // The keys are written in order as the i64 and i128 encodings are both ordered.
+        writer.insert(key, bytes)?;
+    }
+
+    db.clear(wtxn)?;
+    let mut cursor = writer_into_reader(writer)?.into_cursor()?;
+    while let Some((key, bytes)) = cursor.move_on_next()? {
+        db.put(wtxn, key, bytes)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::ops::Deref;
-
+    use big_s::S;
     use heed::EnvOpenOptions;
-    use maplit::btreemap;
-    use tempfile::TempDir;
+    use maplit::{btreemap, hashset};
+    use roaring::RoaringBitmap;
+
+    use heed::types::{ByteSlice, SerdeJson, Str};
 
-    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig};
-    use crate::Index;
+    use super::{main_key, LmdbOptions, TermStats, WarmupLevel, FORMAT_VERSION};
+    use crate::error::{Error, UserError};
+    use crate::facet::value_encoding::i64_into_bytes;
+    pub(crate) use crate::test_utils::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
+    use crate::{BoRoaringBitmapCodec, FacetOptions, FacetOrder, Index};
 
-    pub(crate) struct TempIndex {
-        inner: Index,
-        _tempdir: TempDir,
+    #[test]
+    fn lmdb_options() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let lmdb_options = LmdbOptions {
+            no_sync: true,
+            no_meta_sync: true,
+            write_map: true,
+            max_readers: Some(16),
+            max_dbs: Some(2),
+        };
+        let index = Index::with_lmdb_options(options, &path, lmdb_options).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 1);
     }
 
-    impl Deref for TempIndex {
-        type Target = Index;
+    #[test]
+    fn facet_values() {
+        let index = TempIndex::new();
+        let config = IndexerConfig::default();
 
-        fn deref(&self) -> &Self::Target {
-            &self.inner
-        }
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("genre") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 1, "genre": "Rock" },
+            { "id": 2, "genre": "Jazz" },
+            { "id": 3, "genre": "Blues" },
+            { "id": 4, "genre": "Jazz" },
+            { "id": 5, "genre": "Rock" },
+            { "id": 6, "genre": "Jazz" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let values = index.facet_values(&rtxn, "genre", FacetOrder::Lexicographic, 0, 10).unwrap();
+        assert_eq!(values, vec![(S("Blues"), 1), (S("Jazz"), 3), (S("Rock"), 2)]);
+
+        let values = index.facet_values(&rtxn, "genre", FacetOrder::Count, 1, 1).unwrap();
+        assert_eq!(values, vec![(S("Rock"), 2)]);
+
+        assert!(index.facet_values(&rtxn, "id", FacetOrder::Count, 0, 10).is_err());
     }
 
-    impl TempIndex {
-        /// Creates a temporary index, with a default `4096 * 100` size. This should be enough for
-        /// most tests.
-        pub fn new() -> Self {
-            let mut options = EnvOpenOptions::new();
-            options.map_size(100 * 4096);
-            let _tempdir = TempDir::new_in(".").unwrap();
-            let inner = Index::new(options, _tempdir.path()).unwrap();
-            Self { inner, _tempdir }
-        }
+    #[test]
+    fn facet_distribution_max_values() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("fruit") }))
+            .unwrap();
+        index
+            .add_documents(serde_json::json!([
+                { "id": 0, "fruit": "cherry" },
+                { "id": 1, "fruit": "Banana" },
+                { "id": 2, "fruit": "apple" },
+                { "id": 3, "fruit": "Banana" },
+                { "id": 4, "fruit": 12 },
+                { "id": 5, "fruit": "date" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let distribution = |candidates: Option<RoaringBitmap>, options: FacetOptions| {
+            let mut distribution = index.facets_distribution(&rtxn);
+            if let Some(candidates) = candidates {
+                distribution.candidates(candidates);
+            }
+            distribution.facet_options("fruit", options);
+            distribution.execute_ordered().unwrap().remove("fruit").unwrap()
+        };
+
+        // The values are sorted by their normalized keys, whatever their case.
+        let options = FacetOptions { max_values: Some(3), ..Default::default() };
+        let expected = vec![(S("12"), 1), (S("apple"), 1), (S("Banana"), 2)];
+        assert_eq!(distribution(None, options.clone()), expected);
+        let candidates = (0..6).collect();
+        assert_eq!(distribution(Some(candidates), options), expected);
+
+        let options = FacetOptions {
+            max_values: Some(1),
+            order: FacetOrder::Count,
+            include: vec![S("date")],
+            ..Default::default()
+        };
+        assert_eq!(distribution(None, options), vec![(S("Banana"), 2), (S("date"), 1)]);
+    }
+
+    #[test]
+    fn facet_stats() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("color") })
+            })
+            .unwrap();
+        index
+            .add_documents(serde_json::json!([
+                { "id": 0, "price": 12.5, "color": "red" },
+                { "id": 1, "price": [3, 40], "color": "blue" },
+                { "id": 2, "price": -7, "color": "red" },
+                { "id": 3, "price": "free", "color": "green" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let stats = index.facets_distribution(&rtxn).compute_stats().unwrap();
+        assert_eq!(stats, btreemap! { S("price") => (-7.0, 40.0) });
+
+        let stats = index
+            .facets_distribution(&rtxn)
+            .facets(Some("price"))
+            .candidates((0..1).chain(3..4).collect())
+            .compute_stats()
+            .unwrap();
+        assert_eq!(stats, btreemap! { S("price") => (12.5, 12.5) });
+
+        let stats =
+            index.facets_distribution(&rtxn).candidates((3..4).collect()).compute_stats().unwrap();
+        assert!(stats.is_empty());
+    }
+
+    #[test]
+    fn complete_prefix() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "hello world" },
+            { "id": 2, "title": "hello help" },
+            { "id": 3, "title": "helium hello" },
+            { "id": 4, "title": "help me" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let words = index.complete_prefix(&rtxn, "Hel", 2).unwrap();
+        assert_eq!(words, vec![(S("hello"), 3), (S("help"), 2)]);
+
+        let words = index.complete_prefix(&rtxn, "hel", 10).unwrap();
+        assert_eq!(words, vec![(S("hello"), 3), (S("help"), 2), (S("helium"), 1)]);
+
+        assert!(index.complete_prefix(&rtxn, "xyz", 10).unwrap().is_empty());
+        assert!(index.complete_prefix(&rtxn, "hel", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn term_stats() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "hello hello world", "body": "hello" },
+            { "id": 2, "title": "hello help", "body": "nothing" },
+            { "id": 3, "title": "help me", "body": "help" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let stats = index.term_stats(&rtxn, "hello").unwrap().unwrap();
+        assert_eq!(
+            stats,
+            TermStats {
+                document_frequency: 2,
+                occurrences: 4,
+                fields_occurrences: btreemap! { S("title") => 3, S("body") => 1 },
+            }
+        );
+        assert_eq!(index.term_stats(&rtxn, "unknown").unwrap(), None);
+
+        let words = index.most_frequent_words(&rtxn, 2).unwrap();
+        assert_eq!(words, vec![(S("hello"), 2), (S("help"), 2)]);
+    }
+
+    #[test]
+    fn prefix_cache() {
+        let mut index = TempIndex::new();
+        index.set_prefix_cache_capacity(10);
+        index
+            .add_documents(serde_json::json!([
+                { "id": 0, "title": "hello world" },
+                { "id": 1, "title": "help me" },
+                { "id": 2, "title": "goodbye" }
+            ]))
+            .unwrap();
+
+        assert_eq!(index.search_external_ids("hel").unwrap(), vec!["0", "1"]);
+        assert_eq!(index.prefix_cache.as_ref().unwrap().len(), 1);
+        // The second search is answered from the cache.
+        assert_eq!(index.search_external_ids("hel").unwrap(), vec!["0", "1"]);
+        // Longer prefixes are not cached.
+        assert_eq!(index.search_external_ids("hello").unwrap(), vec!["0"]);
+        assert_eq!(index.prefix_cache.as_ref().unwrap().len(), 1);
+
+        // The cached candidates are dropped when the index is updated.
+        index.add_documents(serde_json::json!([{ "id": 3, "title": "helicopter" }])).unwrap();
+        assert_eq!(index.search_external_ids("hel").unwrap(), vec!["0", "1", "3"]);
+    }
+
+    #[test]
+    fn stop_words_candidates() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "the cat and the dog" },
+            { "id": 2, "title": "the bird" },
+            { "id": 3, "title": "a cat and a bird" },
+            { "id": 4, "title": "the fish" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let candidates = index.stop_words_candidates(&rtxn, 0.5).unwrap();
+        assert_eq!(candidates, vec![(S("the"), 0.75)]);
+
+        let candidates = index.stop_words_candidates(&rtxn, 0.4).unwrap();
+        assert_eq!(
+            candidates,
+            vec![(S("the"), 0.75), (S("and"), 0.5), (S("bird"), 0.5), (S("cat"), 0.5)]
+        );
+    }
+
+    #[test]
+    fn word_frequencies() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "hello world" },
+            { "id": 2, "title": "hello help" },
+            { "id": 3, "title": "help me" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let words: Vec<_> =
+            index.word_frequencies(&rtxn, "", 0).unwrap().map(Result::unwrap).collect();
+        assert_eq!(
+            words,
+            vec![("1", 1), ("2", 1), ("3", 1), ("hello", 2), ("help", 2), ("me", 1), ("world", 1)]
+        );
+
+        let words: Vec<_> =
+            index.word_frequencies(&rtxn, "hel", 2).unwrap().map(Result::unwrap).collect();
+        assert_eq!(words, vec![("hello", 2), ("help", 2)]);
+    }
+
+    #[test]
+    fn warmup_levels() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("color") }))
+            .unwrap();
+        index
+            .add_documents(serde_json::json!([
+                { "id": 0, "title": "hello world", "color": "blue" },
+                { "id": 1, "title": "hello there", "color": "red" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fsts = index.warmup(&rtxn, WarmupLevel::Fsts).unwrap();
+        let search = index.warmup(&rtxn, WarmupLevel::Search).unwrap();
+        let full = index.warmup(&rtxn, WarmupLevel::Full).unwrap();
+
+        assert_eq!(fsts.entries, 2);
+        // The FSTs, the words, the prefixes and the level 0 of the two colors.
+        let words = index.word_docids.len(&rtxn).unwrap();
+        let prefixes = index.word_prefix_docids.len(&rtxn).unwrap();
+        assert_eq!(search.entries, fsts.entries + words + prefixes + 2);
+        assert!(full.entries > search.entries && full.bytes > search.bytes);
+    }
+
+    #[test]
+    fn word_statistics() {
+        let index = TempIndex::new();
+        let mut documents = vec![
+            serde_json::json!({ "id": 0, "title": "hello world" }),
+            serde_json::json!({ "id": 1, "title": "world hello" }),
+            serde_json::json!({ "id": 2, "title": "hello big world" }),
+        ];
+        // The prefixes are only indexed when they are shared by enough words.
+        let letters = (b'a'..=b'z').map(char::from);
+        let words =
+            letters.clone().flat_map(|a| letters.clone().map(move |b| format!("he{}{}", a, b)));
+        documents.extend(
+            words
+                .take(120)
+                .enumerate()
+                .map(|(i, word)| serde_json::json!({ "id": i + 3, "title": word })),
+        );
+        index.add_documents(serde_json::Value::Array(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.word_documents_count(&rtxn, "hello").unwrap(), Some(3));
+        assert_eq!(index.word_documents_count(&rtxn, "bye").unwrap(), None);
+        assert_eq!(index.word_prefix_documents_count(&rtxn, "he").unwrap(), Some(123));
+        assert_eq!(index.word_prefix_documents_count(&rtxn, "wo").unwrap(), None);
+
+        let count = |l, r, p| index.word_pair_proximity_documents_count(&rtxn, l, r, p).unwrap();
+        assert_eq!(count("hello", "world", 1), Some(1));
+        assert_eq!(count("hello", "world", 2), Some(2));
+        assert_eq!(count("world", "hello", 1), Some(1));
+        assert_eq!(count("hello", "big", 3), None);
+
+        let count =
+            |l, r, p| index.word_prefix_pair_proximity_documents_count(&rtxn, l, r, p).unwrap();
+        assert_eq!(count("world", "he", 1), Some(1));
+        assert_eq!(count("big", "he", 2), Some(1));
+    }
+
+    #[test]
+    fn iter_documents() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "bob", "age": 20 },
+            { "id": 3, "name": "alice" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut documents = index.iter_documents(&rtxn, vec![2, 0, 42]);
+        assert_eq!(documents.size_hint(), (3, Some(3)));
+        let (id, document) = documents.next().unwrap().unwrap();
+        assert_eq!(id, 2);
+        assert_eq!(document.get(1), Some(&b"\"alice\""[..]));
+        assert_eq!(documents.next().unwrap().unwrap().0, 0);
+        assert!(matches!(
+            documents.next(),
+            Some(Err(crate::Error::UserError(crate::UserError::UnknownInternalDocumentId {
+                document_id: 42
+            })))
+        ));
+        assert!(documents.next().is_none());
     }
 
     #[test]
@@ -989,4 +2666,71 @@ pub(crate) mod tests {
             }
         );
     }
+
+    #[test]
+    fn migrate_an_index_without_format_version() {
+        let index = TempIndex::new();
+
+        // We write the positions and the integer facets as they were stored before
+        // the format version, the positions as roaring bitmaps and the integers as i64.
+        let positions: RoaringBitmap = (0..10).map(|i| i * 3).collect();
+        let mut bytes = Vec::new();
+        BoRoaringBitmapCodec::serialize_into(&positions, &mut bytes);
+        let mut wtxn = index.write_txn().unwrap();
+        let db = index.docid_word_positions.remap_data_type::<ByteSlice>();
+        db.put(&mut wtxn, &(0, "hello"), &bytes).unwrap();
+
+        let docids: RoaringBitmap = (1..3).collect();
+        let db = index.facet_id_i128_docids.remap_key_type::<ByteSlice>();
+        for integer in [i64::MIN, -5, i64::MAX].iter().copied() {
+            let mut key = 3u16.to_be_bytes().to_vec();
+            key.extend_from_slice(&i64_into_bytes(integer));
+            db.put(&mut wtxn, &key, &docids).unwrap();
+        }
+        index.main.delete::<_, Str>(&mut wtxn, main_key::FORMAT_VERSION_KEY).unwrap();
+        wtxn.commit().unwrap();
+
+        Index::initialize_format_version(
+            &index.env,
+            index.main,
+            index.docid_word_positions,
+            index.facet_id_i128_docids,
+        )
+        .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.docid_word_positions.get(&rtxn, &(0, "hello")).unwrap(), Some(positions));
+        let integers: Vec<_> =
+            index.facet_id_i128_docids.iter(&rtxn).unwrap().map(|result| result.unwrap()).collect();
+        let expected = vec![
+            ((3, i64::MIN as i128), docids.clone()),
+            ((3, -5), docids.clone()),
+            ((3, i64::MAX as i128), docids),
+        ];
+        assert_eq!(integers, expected);
+        let version =
+            index.main.get::<_, Str, SerdeJson<u32>>(&rtxn, main_key::FORMAT_VERSION_KEY).unwrap();
+        assert_eq!(version, Some(FORMAT_VERSION));
+        drop(rtxn);
+
+        // An index written by a newer version of milli is refused.
+        let mut wtxn = index.write_txn().unwrap();
+        let newer = FORMAT_VERSION + 1;
+        index
+            .main
+            .put::<_, Str, SerdeJson<u32>>(&mut wtxn, main_key::FORMAT_VERSION_KEY, &newer)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let result = Index::initialize_format_version(
+            &index.env,
+            index.main,
+            index.docid_word_positions,
+            index.facet_id_i128_docids,
+        );
+        assert!(matches!(
+            result,
+            Err(Error::UserError(UserError::UnsupportedIndexFormat { version, .. })) if version == newer
+        ));
+    }
 }
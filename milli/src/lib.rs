@@ -2,29 +2,45 @@
 pub mod documents;
 
 mod asc_desc;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+mod cache;
 mod criterion;
+mod document;
+mod documents_codec;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 mod error;
 mod external_documents_ids;
 pub mod facet;
 mod fields_ids_map;
 pub mod heed_codec;
 pub mod index;
+pub mod metrics;
+mod normalization;
+#[cfg(feature = "phonetic")]
+pub mod phonetic;
 pub mod proximity;
 mod search;
+pub mod storage;
+pub mod test_utils;
 pub mod update;
 
 use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::hash::BuildHasherDefault;
+use std::io;
 
 pub use filter_parser::{Condition, FilterCondition};
 use fxhash::{FxHasher32, FxHasher64};
 pub use grenad::CompressionType;
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder};
 pub use meilisearch_tokenizer as tokenizer;
 use serde_json::{Map, Value};
 
-pub use self::asc_desc::{AscDesc, AscDescError, Member, SortError};
-pub use self::criterion::{default_criteria, Criterion, CriterionError};
+pub use self::asc_desc::{parse_sort_criteria, AscDesc, AscDescError, Member, SortError};
+pub use self::criterion::{default_criteria, Criterion, CriterionError, TermsDroppingOrder};
+pub use self::document::{Document, StoredDocument};
 pub use self::error::{
     Error, FieldIdMapMissingEntry, InternalError, SerializationError, UserError,
 };
@@ -32,11 +48,19 @@ pub use self::external_documents_ids::ExternalDocumentsIds;
 pub use self::fields_ids_map::FieldsIdsMap;
 pub use self::heed_codec::{
     BEU32StrCodec, BoRoaringBitmapCodec, BoRoaringBitmapLenCodec, CboRoaringBitmapCodec,
-    CboRoaringBitmapLenCodec, FieldIdWordCountCodec, ObkvCodec, RoaringBitmapCodec,
-    RoaringBitmapLenCodec, StrBEU32Codec, StrStrU8Codec,
+    CboRoaringBitmapLenCodec, DeltaPositionsCodec, DeltaPositionsLenCodec, FieldIdWordCountCodec,
+    ObkvCodec, RoaringBitmapCodec, RoaringBitmapLenCodec, StrBEU32Codec, StrStrU8Codec,
+};
+pub use self::index::{Index, WarmupLevel, WarmupStats};
+pub use self::metrics::MetricsSink;
+pub use self::normalization::{Normalizer, StemmingLanguage};
+pub use self::search::{
+    highlight_matches, CriterionExplanation, CustomCriterion, FacetDistribution, FacetHistogram,
+    FacetOptions, FacetOrder, Filter, FilterExplanation, GeoCell, GeoClusters, HistogramBucket,
+    HistogramBuckets, MatchBounds, MatchKind, MatchedTerm, Matcher, MatchingWords, QueryPattern,
+    QueryRule, Search, SearchContext, SearchExplanation, SearchIter, SearchQuery, SearchResult,
+    SlowQuery, SynonymsExplanation, TermExplanation, TypoTolerance,
 };
-pub use self::index::Index;
-pub use self::search::{FacetDistribution, Filter, MatchingWords, Search, SearchResult};
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
@@ -95,6 +119,34 @@ pub fn obkv_to_json(
         .collect()
 }
 
+/// Writes a raw obkv store as a JSON object, the values being the JSON bytes
+/// stored in the obkv, written as-is instead of being deserialized and serialized again.
+pub fn obkv_to_json_writer<W: io::Write>(
+    displayed_fields: &[FieldId],
+    fields_ids_map: &FieldsIdsMap,
+    obkv: obkv::KvReaderU16,
+    mut writer: W,
+) -> Result<()> {
+    writer.write_all(b"{")?;
+    let fields =
+        displayed_fields.iter().copied().flat_map(|id| obkv.get(id).map(|value| (id, value)));
+    for (i, (id, value)) in fields.enumerate() {
+        let name = fields_ids_map.name(id).ok_or(error::FieldIdMapMissingEntry::FieldId {
+            field_id: id,
+            process: "obkv_to_json_writer",
+        })?;
+        if i != 0 {
+            writer.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut writer, name).map_err(error::InternalError::SerdeJson)?;
+        writer.write_all(b":")?;
+        writer.write_all(value)?;
+    }
+    writer.write_all(b"}")?;
+
+    Ok(())
+}
+
 /// Transform a JSON value into a string that can be indexed.
 pub fn json_to_string(value: &Value) -> Option<String> {
     fn inner(value: &Value, output: &mut String) -> bool {
@@ -172,6 +224,24 @@ pub fn distance_between_two_points(a: &[f64; 2], b: &[f64; 2]) -> f64 {
     a.haversine_distance_to(&b).meters()
 }
 
+/// Returns the `[latitude, longitude]` point of a `_geo` object, `None` if one of the
+/// coordinates is missing or out of range.
+pub(crate) fn geo_point_from_value(point: &Value) -> Option<[f64; 2]> {
+    let lat = geo_coordinate(point, "lat").filter(|lat| (-90.0..=90.0).contains(lat))?;
+    let lng = geo_coordinate(point, "lng").filter(|lng| (-180.0..=180.0).contains(lng))?;
+    Some([lat, lng])
+}
+
+/// Returns the coordinate under the given key of a `_geo` object, a number
+/// or a string containing a number.
+fn geo_coordinate(point: &Value, key: &str) -> Option<f64> {
+    match &point[key] {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse().ok().filter(|n: &f64| n.is_finite()),
+        _ => None,
+    }
+}
+
 /// Convert a point expressed in terms of latitude and longitude to a point in the
 /// cartesian coordinate expressed in terms of x, y and z.
 pub fn lat_lng_to_xyz(coord: &[f64; 2]) -> [f64; 3] {
@@ -183,6 +253,23 @@ pub fn lat_lng_to_xyz(coord: &[f64; 2]) -> [f64; 3] {
     [x, y, z]
 }
 
+/// Returns the candidate which is the closest to the given name, ignoring the case,
+/// if it is at most two typos away from it.
+pub(crate) fn did_you_mean<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let dfa = LevenshteinAutomatonBuilder::new(2, true).build_dfa(&name.to_lowercase());
+    candidates
+        .into_iter()
+        .filter_map(|candidate| match dfa.eval(candidate.to_lowercase()) {
+            Distance::Exact(distance) => Some((distance, candidate)),
+            Distance::AtLeast(_) => None,
+        })
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -217,6 +304,39 @@ mod tests {
         assert_eq!(string, "name: John Doe. . 43. hello. I. am. fine. . ");
     }
 
+    #[test]
+    fn obkv_to_json_writer_raw_values() {
+        let mut fields_ids_map = FieldsIdsMap::new();
+        let id = fields_ids_map.insert("id").unwrap();
+        let name = fields_ids_map.insert("na\"me").unwrap();
+        let tags = fields_ids_map.insert("tags").unwrap();
+
+        let mut writer = obkv::KvWriterU16::memory();
+        writer.insert(id, br#"1"#).unwrap();
+        writer.insert(name, br#""kevin""#).unwrap();
+        writer.insert(tags, br#"["a","b"]"#).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        let obkv = obkv::KvReaderU16::new(&bytes);
+
+        let mut json = Vec::new();
+        obkv_to_json_writer(&[tags, id, name], &fields_ids_map, obkv, &mut json).unwrap();
+        let expected = obkv_to_json(&[tags, id, name], &fields_ids_map, obkv).unwrap();
+        assert_eq!(serde_json::from_slice::<Map<String, Value>>(&json).unwrap(), expected);
+
+        let mut json = Vec::new();
+        obkv_to_json_writer(&[tags], &fields_ids_map, obkv, &mut json).unwrap();
+        assert_eq!(json, br#"{"tags":["a","b"]}"#);
+    }
+
+    #[test]
+    fn did_you_mean_closest_candidate() {
+        let candidates = ["price", "title", "release_date", "prices"];
+        assert_eq!(did_you_mean("prise", candidates.iter().copied()), Some("price"));
+        assert_eq!(did_you_mean("PrIcE", candidates.iter().copied()), Some("price"));
+        assert_eq!(did_you_mean("releasedate", candidates.iter().copied()), Some("release_date"));
+        assert_eq!(did_you_mean("author", candidates.iter().copied()), None);
+    }
+
     #[test]
     fn test_relative_position_conversion() {
         assert_eq!((0x0000, 0x0000), relative_from_absolute_position(0x00000000));
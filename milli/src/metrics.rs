@@ -0,0 +1,37 @@
+//! Hooks to export the internal metrics of milli to a monitoring system.
+//!
+//! An embedder implements the [`MetricsSink`] trait, e.g. on top of a Prometheus registry,
+//! and registers it on the searches and on the [`IndexerConfig`](crate::update::IndexerConfig).
+
+use std::fmt;
+
+/// The duration of a search, in seconds.
+pub const SEARCH_DURATION_SECONDS: &str = "milli_search_duration_seconds";
+/// The number of documents that were candidates of a search.
+pub const SEARCH_CANDIDATES: &str = "milli_search_candidates";
+/// The number of searches that were executed.
+pub const SEARCHES_TOTAL: &str = "milli_searches_total";
+/// The duration of a documents addition, in seconds.
+pub const INDEXING_DURATION_SECONDS: &str = "milli_indexing_duration_seconds";
+/// The number of documents that were indexed.
+pub const INDEXED_DOCUMENTS_TOTAL: &str = "milli_indexed_documents_total";
+/// The size of the database file on disk, in bytes.
+pub const DATABASE_SIZE_BYTES: &str = "milli_database_size_bytes";
+
+/// Receives the metrics reported by milli, every method does nothing by default.
+pub trait MetricsSink: Send + Sync {
+    /// Increments the counter of the given name by `value`.
+    fn increment_counter(&self, _name: &'static str, _value: u64) {}
+
+    /// Records a new observation in the histogram of the given name.
+    fn record_histogram(&self, _name: &'static str, _value: f64) {}
+
+    /// Sets the current value of the gauge of the given name.
+    fn set_gauge(&self, _name: &'static str, _value: f64) {}
+}
+
+impl fmt::Debug for dyn MetricsSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("MetricsSink")
+    }
+}
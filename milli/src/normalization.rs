@@ -0,0 +1,359 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use meilisearch_tokenizer::Analyzer;
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+
+use crate::{FieldId, Index};
+
+/// The language used to reduce the indexed and the query words to their stem,
+/// so that "running" and "runs" both match "run".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StemmingLanguage {
+    Arabic,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Tamil,
+    Turkish,
+}
+
+impl From<StemmingLanguage> for Algorithm {
+    fn from(language: StemmingLanguage) -> Algorithm {
+        match language {
+            StemmingLanguage::Arabic => Algorithm::Arabic,
+            StemmingLanguage::Danish => Algorithm::Danish,
+            StemmingLanguage::Dutch => Algorithm::Dutch,
+            StemmingLanguage::English => Algorithm::English,
+            StemmingLanguage::Finnish => Algorithm::Finnish,
+            StemmingLanguage::French => Algorithm::French,
+            StemmingLanguage::German => Algorithm::German,
+            StemmingLanguage::Greek => Algorithm::Greek,
+            StemmingLanguage::Hungarian => Algorithm::Hungarian,
+            StemmingLanguage::Italian => Algorithm::Italian,
+            StemmingLanguage::Norwegian => Algorithm::Norwegian,
+            StemmingLanguage::Portuguese => Algorithm::Portuguese,
+            StemmingLanguage::Romanian => Algorithm::Romanian,
+            StemmingLanguage::Russian => Algorithm::Russian,
+            StemmingLanguage::Spanish => Algorithm::Spanish,
+            StemmingLanguage::Swedish => Algorithm::Swedish,
+            StemmingLanguage::Tamil => Algorithm::Tamil,
+            StemmingLanguage::Turkish => Algorithm::Turkish,
+        }
+    }
+}
+
+/// The normalization stage applied to the words produced by the tokenizer, both when
+/// indexing the documents and when building the query, before they are used as keys.
+#[derive(Default)]
+pub struct Normalizer {
+    stemmer: Option<Stemmer>,
+    field_stemmers: HashMap<FieldId, Stemmer>,
+    diacritics_sensitive: bool,
+    case_sensitive: bool,
+    numbers_normalization: bool,
+}
+
+impl Normalizer {
+    pub fn new(
+        stemming: Option<StemmingLanguage>,
+        diacritics_sensitive: bool,
+        case_sensitive: bool,
+        numbers_normalization: bool,
+    ) -> Normalizer {
+        Normalizer {
+            stemmer: stemming.map(|language| Stemmer::create(language.into())),
+            field_stemmers: HashMap::new(),
+            diacritics_sensitive,
+            case_sensitive,
+            numbers_normalization,
+        }
+    }
+
+    /// Creates the normalizer corresponding to the settings of the index.
+    pub fn from_index(index: &Index, rtxn: &heed::RoTxn) -> heed::Result<Normalizer> {
+        let stemming = index.stemming_language(rtxn)?;
+        let diacritics_sensitive = index.diacritics_sensitive(rtxn)?;
+        let case_sensitive = index.case_sensitive(rtxn)?;
+        let numbers_normalization = index.numbers_normalization(rtxn)?;
+        let normalizer =
+            Normalizer::new(stemming, diacritics_sensitive, case_sensitive, numbers_normalization);
+        Ok(normalizer.with_field_languages(index.field_languages_ids(rtxn)?))
+    }
+
+    /// Replaces the stemming language of the normalizer, the words of the fields
+    /// with a language are still stemmed with the language of their field.
+    pub fn with_stemming_language(mut self, language: StemmingLanguage) -> Normalizer {
+        self.stemmer = Some(Stemmer::create(language.into()));
+        self
+    }
+
+    /// Stems the words of the given fields with their language
+    /// instead of the stemming language of the normalizer.
+    pub fn with_field_languages(
+        mut self,
+        languages: HashMap<FieldId, StemmingLanguage>,
+    ) -> Normalizer {
+        self.field_stemmers = languages
+            .into_iter()
+            .map(|(field_id, language)| (field_id, Stemmer::create(language.into())))
+            .collect();
+        self
+    }
+
+    /// Returns the normalized version of the given token word, the numbers are
+    /// replaced by their canonical form if the normalizer normalizes them.
+    pub fn normalize<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        self.normalize_with(self.stemmer.as_ref(), word)
+    }
+
+    /// Returns the normalized version of a token word of the given field,
+    /// stemmed with the language of the field if it has one.
+    pub fn normalize_field_word<'a>(&self, field_id: FieldId, word: &'a str) -> Cow<'a, str> {
+        let stemmer = self.field_stemmers.get(&field_id).or_else(|| self.stemmer.as_ref());
+        self.normalize_with(stemmer, word)
+    }
+
+    fn normalize_with<'a>(&self, stemmer: Option<&Stemmer>, word: &'a str) -> Cow<'a, str> {
+        if self.numbers_normalization {
+            if let Some(number) = normalize_number(word) {
+                return Cow::Owned(number);
+            }
+        }
+
+        match stemmer {
+            Some(stemmer) => stemmer.stem(word),
+            None => Cow::Borrowed(word),
+        }
+    }
+
+    /// Returns the given token word before it is stemmed, `original` is the part of the
+    /// text the token comes from, its diacritics are kept if the normalizer is sensitive
+    /// to them, the tokenizer removing them from the token word.
+    pub fn token_word<'a>(&self, word: &'a str, original: &str) -> Cow<'a, str> {
+        if self.diacritics_sensitive {
+            Cow::Owned(original.trim().to_lowercase())
+        } else {
+            Cow::Borrowed(word)
+        }
+    }
+
+    /// Returns the token word with its original case if the normalizer is case sensitive
+    /// and the original text of the token contains uppercase letters, `word` is the token
+    /// word returned by [`Normalizer::token_word`].
+    ///
+    /// Only the words that only differ from their original text by their case are kept,
+    /// these words are not stemmed.
+    pub fn cased_word<'a>(&self, word: &str, original: &'a str) -> Option<&'a str> {
+        let original = original.trim();
+        if self.case_sensitive
+            && original.chars().any(char::is_uppercase)
+            && original.to_lowercase() == word
+        {
+            Some(original)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the normalized version of a word returned by [`Normalizer::cased_word`]
+    /// once lowercased, the version of the word that is always indexed.
+    pub fn case_fold(&self, cased_word: &str) -> String {
+        self.normalize(&cased_word.to_lowercase()).into_owned()
+    }
+
+    /// Returns the normalized version of the given token word,
+    /// see [`Normalizer::token_word`] for the meaning of `original`.
+    pub fn normalize_token<'a>(&self, word: &'a str, original: &str) -> Cow<'a, str> {
+        match self.token_word(word, original) {
+            Cow::Borrowed(word) => self.normalize(word),
+            Cow::Owned(word) => Cow::Owned(self.normalize(&word).into_owned()),
+        }
+    }
+
+    /// Returns the normalized words of the text, analyzed with the given analyzer.
+    fn normalize_text(&self, analyzer: &Analyzer<&[u8]>, text: &str) -> Vec<String> {
+        analyzer
+            .analyze(text)
+            .tokens()
+            .filter_map(|token| {
+                if token.is_word() {
+                    let original = &text[token.byte_start..token.byte_end];
+                    Some(self.normalize_token(token.text(), original).into_owned())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Normalizes both the words and their synonyms, merging the possible duplicate words,
+    /// the synonyms are matched against the normalized query words.
+    pub(crate) fn normalize_synonyms(
+        &self,
+        analyzer: &Analyzer<&[u8]>,
+        synonyms: &HashMap<String, Vec<String>>,
+    ) -> HashMap<Vec<String>, Vec<Vec<String>>> {
+        let mut normalized = HashMap::new();
+        for (word, synonyms) in synonyms {
+            let normalized_word = self.normalize_text(analyzer, word);
+            let normalized_synonyms =
+                synonyms.iter().map(|synonym| self.normalize_text(analyzer, synonym));
+            let entry = normalized.entry(normalized_word).or_insert_with(Vec::new);
+            entry.extend(normalized_synonyms);
+        }
+
+        // Make sure that we don't have duplicate synonyms.
+        normalized.iter_mut().for_each(|(_, synonyms)| {
+            synonyms.sort_unstable();
+            synonyms.dedup();
+        });
+
+        normalized
+    }
+}
+
+/// The zeros of the decimal digits of the supported scripts,
+/// the digits of a script being the ten characters following its zero.
+const DIGITS_ZEROS: &[char] = &[
+    '0', '\u{660}', '\u{6F0}', '\u{966}', '\u{9E6}', '\u{A66}', '\u{AE6}', '\u{B66}', '\u{BE6}',
+    '\u{C66}', '\u{CE6}', '\u{D66}', '\u{E50}', '\u{ED0}', '\u{F20}', '\u{1040}', '\u{17E0}',
+    '\u{1810}', '\u{FF10}',
+];
+
+/// The characters separating the groups of thousands of a number.
+const THOUSANDS_SEPARATORS: &[char] = &[',', '.', '\'', '_', ' ', '\u{A0}', '\u{202F}', '\u{2019}'];
+
+fn ascii_digit(c: char) -> Option<char> {
+    DIGITS_ZEROS.iter().find_map(|&zero| {
+        let offset = (c as u32).checked_sub(zero as u32)?;
+        char::from_digit(offset, 10)
+    })
+}
+
+/// Returns the canonical form of the word if it is an integer: written with ASCII digits,
+/// without its leading zeros nor its thousands separators, "00١,000" becomes "1000".
+///
+/// The thousands separators must all be the same and separate groups of three digits,
+/// the other numbers, like "3.14", are not integers and are kept as is.
+fn normalize_number(word: &str) -> Option<String> {
+    let mut digits = String::with_capacity(word.len());
+    let mut separator = None;
+    let mut groups = 0;
+    let mut group_len = 0;
+
+    for c in word.chars() {
+        if let Some(digit) = ascii_digit(c) {
+            digits.push(digit);
+            group_len += 1;
+        } else if THOUSANDS_SEPARATORS.contains(&c) {
+            let valid_group =
+                if groups == 0 { (1..=3).contains(&group_len) } else { group_len == 3 };
+            if !valid_group || separator.map_or(false, |separator| separator != c) {
+                return None;
+            }
+            separator = Some(c);
+            groups += 1;
+            group_len = 0;
+        } else {
+            return None;
+        }
+    }
+
+    if digits.is_empty() || (groups != 0 && group_len != 3) {
+        return None;
+    }
+
+    match digits.trim_start_matches('0') {
+        "" => Some(String::from("0")),
+        number => Some(number.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_words() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.normalize("running"), "running");
+
+        let normalizer = Normalizer::new(Some(StemmingLanguage::English), false, false, false);
+        assert_eq!(normalizer.normalize("running"), "run");
+        assert_eq!(normalizer.normalize("runs"), "run");
+        assert_eq!(normalizer.normalize("run"), "run");
+
+        let normalizer = Normalizer::new(Some(StemmingLanguage::French), false, false, false);
+        assert_eq!(normalizer.normalize("chanteuses"), normalizer.normalize("chanteuse"));
+    }
+
+    #[test]
+    fn normalize_tokens_diacritics() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.normalize_token("peche", "Péché"), "peche");
+
+        let normalizer = Normalizer::new(None, true, false, false);
+        assert_eq!(normalizer.normalize_token("peche", "Péché"), "péché");
+        assert_eq!(normalizer.normalize_token("peche", "PECHE"), "peche");
+    }
+
+    #[test]
+    fn cased_words() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.cased_word("us", "US"), None);
+
+        let normalizer = Normalizer::new(Some(StemmingLanguage::English), false, true, false);
+        assert_eq!(normalizer.cased_word("us", "US"), Some("US"));
+        assert_eq!(normalizer.cased_word("us", "us"), None);
+        // The word was also normalized by the tokenizer.
+        assert_eq!(normalizer.cased_word("eric", "Éric"), None);
+        assert_eq!(normalizer.case_fold("Runs"), "run");
+    }
+
+    #[test]
+    fn normalize_numbers() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.normalize("007"), "007");
+
+        let normalizer = Normalizer::new(Some(StemmingLanguage::English), false, false, true);
+        assert_eq!(normalizer.normalize("007"), "7");
+        assert_eq!(normalizer.normalize("000"), "0");
+        assert_eq!(normalizer.normalize("1,000"), "1000");
+        assert_eq!(normalizer.normalize("12.345.678"), "12345678");
+        assert_eq!(normalizer.normalize("\u{665}\u{660}"), "50");
+        assert_eq!(normalizer.normalize("\u{FF11},\u{FF10}\u{FF10}\u{FF10}"), "1000");
+        // These are not integers written with thousands separators.
+        assert_eq!(normalizer.normalize("3.14"), "3.14");
+        assert_eq!(normalizer.normalize("1,000.000"), "1,000.000");
+        assert_eq!(normalizer.normalize("1234,567"), "1234,567");
+        assert_eq!(normalizer.normalize("007a"), "007a");
+        assert_eq!(normalizer.normalize("runs"), "run");
+    }
+
+    #[test]
+    fn normalize_field_words() {
+        let languages = maplit::hashmap! { 1 => StemmingLanguage::French };
+        let normalizer = Normalizer::new(Some(StemmingLanguage::English), false, false, false)
+            .with_field_languages(languages);
+        assert_eq!(normalizer.normalize_field_word(0, "running"), "run");
+        assert_eq!(
+            normalizer.normalize_field_word(1, "chanteuses"),
+            normalizer.with_stemming_language(StemmingLanguage::French).normalize("chanteuse"),
+        );
+    }
+}
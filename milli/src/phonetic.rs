@@ -0,0 +1,178 @@
+//! A simplified [Metaphone](https://en.wikipedia.org/wiki/Metaphone) implementation used
+//! to match the words that sound alike, mostly useful for people and place names.
+
+/// The separator between the phonetic key and the word in the phonetic words FST.
+pub(crate) const PHONETIC_SEPARATOR: u8 = 0;
+
+/// Returns the phonetic key of the given lowercased word or `None` if the word is
+/// too short or contains other characters than the ASCII letters.
+pub fn phonetic_key(word: &str) -> Option<String> {
+    if word.len() < 3 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return None;
+    }
+
+    // Adjacent duplicate letters are merged, except the `c` which is handled below.
+    let mut letters: Vec<u8> = Vec::with_capacity(word.len());
+    for byte in word.bytes() {
+        if letters.last() != Some(&byte) || byte == b'c' {
+            letters.push(byte);
+        }
+    }
+
+    // Some initial letters are silent or pronounced differently.
+    match letters.as_slice() {
+        [b'a', b'e', ..]
+        | [b'g', b'n', ..]
+        | [b'k', b'n', ..]
+        | [b'p', b'n', ..]
+        | [b'w', b'r', ..] => {
+            letters.remove(0);
+        }
+        [b'x', ..] => letters[0] = b's',
+        [b'w', b'h', ..] => {
+            letters.remove(1);
+        }
+        _ => (),
+    }
+
+    let is_vowel = |b: Option<&u8>| matches!(b, Some(b'a' | b'e' | b'i' | b'o' | b'u'));
+    let is_front_vowel = |b: Option<&u8>| matches!(b, Some(b'e' | b'i' | b'y'));
+
+    let mut key = String::with_capacity(letters.len());
+    for (i, &letter) in letters.iter().enumerate() {
+        let prev = i.checked_sub(1).and_then(|i| letters.get(i));
+        let next = letters.get(i + 1);
+        let after_next = letters.get(i + 2);
+
+        match letter {
+            b'a' | b'e' | b'i' | b'o' | b'u' => {
+                if i == 0 {
+                    key.push(letter as char);
+                }
+            }
+            b'b' => {
+                // The `b` of a trailing `mb` is silent.
+                if !(prev == Some(&b'm') && next.is_none()) {
+                    key.push('b');
+                }
+            }
+            b'c' => {
+                if next == Some(&b'i') && after_next == Some(&b'a') {
+                    key.push('x');
+                } else if next == Some(&b'h') {
+                    key.push(if prev == Some(&b's') { 'k' } else { 'x' });
+                } else if is_front_vowel(next) {
+                    if prev != Some(&b's') {
+                        key.push('s');
+                    }
+                } else {
+                    key.push('k');
+                }
+            }
+            b'd' => {
+                if next == Some(&b'g') && is_front_vowel(after_next) {
+                    key.push('j');
+                } else {
+                    key.push('t');
+                }
+            }
+            b'g' => {
+                if next == Some(&b'h') && !is_vowel(after_next) {
+                    // The `g` of a `gh` that is not followed by a vowel is silent.
+                } else if next == Some(&b'n') && (after_next.is_none() || after_next == Some(&b'e'))
+                {
+                    // The `g` of a trailing `gn` or `gne` is silent.
+                } else if prev == Some(&b'd') && is_front_vowel(next) {
+                    // Already encoded by the `dg` above.
+                } else if is_front_vowel(next) {
+                    key.push('j');
+                } else {
+                    key.push('k');
+                }
+            }
+            b'h' => {
+                let after_digraph = matches!(prev, Some(b'c' | b's' | b'p' | b't' | b'g'));
+                let silent = is_vowel(prev) && !is_vowel(next);
+                if !after_digraph && !silent {
+                    key.push('h');
+                }
+            }
+            b'k' => {
+                if prev != Some(&b'c') {
+                    key.push('k');
+                }
+            }
+            b'p' => key.push(if next == Some(&b'h') { 'f' } else { 'p' }),
+            b'q' => key.push('k'),
+            b's' => {
+                if next == Some(&b'h')
+                    || (next == Some(&b'i') && matches!(after_next, Some(b'o' | b'a')))
+                {
+                    key.push('x');
+                } else {
+                    key.push('s');
+                }
+            }
+            b't' => {
+                if next == Some(&b'i') && matches!(after_next, Some(b'o' | b'a')) {
+                    key.push('x');
+                } else if next == Some(&b'h') {
+                    key.push('0');
+                } else if !(next == Some(&b'c') && after_next == Some(&b'h')) {
+                    key.push('t');
+                }
+            }
+            b'v' => key.push('f'),
+            b'w' | b'y' => {
+                if is_vowel(next) {
+                    key.push(letter as char);
+                }
+            }
+            b'x' => key.push_str("ks"),
+            b'z' => key.push('s'),
+            other => key.push(other as char),
+        }
+    }
+
+    if key.is_empty() {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_names_share_the_same_key() {
+        let same_sounds = [
+            ("smith", "smyth"),
+            ("catherine", "kathryn"),
+            ("stephen", "steven"),
+            ("philip", "filip"),
+            ("knight", "night"),
+            ("carl", "karl"),
+        ];
+
+        for (left, right) in same_sounds {
+            let left_key = phonetic_key(left);
+            assert!(left_key.is_some(), "{} has no phonetic key", left);
+            assert_eq!(left_key, phonetic_key(right), "{} and {} must sound alike", left, right);
+        }
+    }
+
+    #[test]
+    fn different_names_have_different_keys() {
+        assert_ne!(phonetic_key("smith"), phonetic_key("jones"));
+        assert_ne!(phonetic_key("paris"), phonetic_key("berlin"));
+    }
+
+    #[test]
+    fn unsupported_words() {
+        assert_eq!(phonetic_key("to"), None);
+        assert_eq!(phonetic_key("héloïse"), None);
+        assert_eq!(phonetic_key("r2d2"), None);
+    }
+}
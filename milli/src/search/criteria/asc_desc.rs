@@ -7,7 +7,7 @@ use roaring::RoaringBitmap;
 
 use super::{Criterion, CriterionParameters, CriterionResult};
 use crate::search::criteria::{resolve_query_tree, CriteriaBuilder};
-use crate::search::facet::{FacetNumberIter, FacetStringIter};
+use crate::search::facet::{exact_integer_groups, FacetNumberIter, FacetStringIter};
 use crate::search::query_tree::Operation;
 use crate::{FieldId, Index, Result};
 
@@ -192,7 +192,15 @@ fn facet_ordered<'t>(
             FacetNumberIter::new_reverse_reducing
         };
         let number_iter = facet_number_fn(rtxn, index, field_id, candidates.clone())?
-            .map(|res| res.map(|(_, docids)| docids));
+            .map(move |res| {
+                res.and_then(|(value, docids)| {
+                    split_unsafe_integers(index, rtxn, field_id, is_ascending, value, docids)
+                })
+            })
+            .flat_map(|res| match res {
+                Ok(groups) => groups.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            });
 
         let facet_string_fn = if is_ascending {
             FacetStringIter::new_reducing
@@ -237,15 +245,51 @@ fn iterative_facet_number_ordered_iter<'t>(
     // The itertools GroupBy iterator doesn't provide an owned version, we are therefore
     // required to collect the result into an owned collection (a Vec).
     // https://github.com/rust-itertools/itertools/issues/499
-    let vec: Vec<_> = iter
-        .group_by(|(_, v)| *v)
-        .into_iter()
-        .map(|(_, ids)| ids.map(|(id, _)| id).collect())
-        .collect();
+    let mut vec = Vec::new();
+    for (value, ids) in &iter.group_by(|(_, v)| *v) {
+        let docids = ids.map(|(id, _)| id).collect();
+        vec.extend(split_unsafe_integers(index, rtxn, field_id, is_ascending, value.0, docids)?);
+    }
 
     Ok(vec.into_iter())
 }
 
+/// Splits the documents ids associated to a facet number by the exact integers that
+/// were rounded to it, in the requested order. A document is only returned in the first
+/// group it appears in.
+fn split_unsafe_integers(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    field_id: FieldId,
+    is_ascending: bool,
+    value: f64,
+    docids: RoaringBitmap,
+) -> heed::Result<Vec<RoaringBitmap>> {
+    let groups = match exact_integer_groups(rtxn, index, field_id, value, &docids)? {
+        Some(groups) => groups,
+        None => return Ok(vec![docids]),
+    };
+
+    let iter = groups.into_iter().map(|(_, docids)| docids);
+    let iter = if is_ascending {
+        Box::new(iter) as Box<dyn Iterator<Item = _>>
+    } else {
+        Box::new(iter.rev())
+    };
+
+    let mut seen = RoaringBitmap::new();
+    let mut vec = Vec::new();
+    for docids in iter {
+        let docids = docids - &seen;
+        if !docids.is_empty() {
+            seen |= &docids;
+            vec.push(docids);
+        }
+    }
+
+    Ok(vec)
+}
+
 /// Fetch the whole list of candidates facet string values one by one and order them by it.
 ///
 /// This function is fast when the amount of candidates to rank is small.
@@ -9,7 +9,7 @@ use roaring::RoaringBitmap;
 use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
 use crate::search::criteria::Query;
 use crate::search::query_tree::{Operation, QueryKind};
-use crate::search::{build_dfa, word_derivations, WordDerivationsCache};
+use crate::search::{build_dfa, word_derivations, TypoTolerance, WordDerivationsCache};
 use crate::Result;
 
 /// To be able to divide integers by the number of words in the query
@@ -199,17 +199,28 @@ impl<'t> QueryPositionIterator<'t> {
                         let iter = ctx.word_position_iterator(word, in_prefix_cache)?;
                         inner.push(iter.peekable());
                     } else {
-                        for (word, _) in word_derivations(&word, true, 0, ctx.words_fst(), wdcache)?
-                        {
+                        for (word, _) in word_derivations(
+                            &word,
+                            true,
+                            0,
+                            ctx.words_fst(),
+                            ctx.typo_tolerance(),
+                            wdcache,
+                        )? {
                             let iter = ctx.word_position_iterator(&word, in_prefix_cache)?;
                             inner.push(iter.peekable());
                         }
                     }
                 }
                 QueryKind::Tolerant { typo, word } => {
-                    for (word, _) in
-                        word_derivations(&word, query.prefix, *typo, ctx.words_fst(), wdcache)?
-                    {
+                    for (word, _) in word_derivations(
+                        &word,
+                        query.prefix,
+                        *typo,
+                        ctx.words_fst(),
+                        ctx.typo_tolerance(),
+                        wdcache,
+                    )? {
                         let iter = ctx.word_position_iterator(&word, in_prefix_cache)?;
                         inner.push(iter.peekable());
                     }
@@ -457,6 +468,7 @@ fn initialize_linear_buckets(
 ) -> Result<BTreeMap<u64, RoaringBitmap>> {
     fn compute_candidate_rank(
         branches: &FlattenedQueryTree,
+        typo_tolerance: TypoTolerance,
         words_positions: HashMap<String, RoaringBitmap>,
     ) -> u64 {
         let mut min_rank = u64::max_value();
@@ -470,7 +482,7 @@ fn initialize_linear_buckets(
                     let current_position = match kind {
                         QueryKind::Exact { word, .. } => {
                             if *prefix {
-                                word_derivations(word, true, 0, &words_positions)
+                                word_derivations(word, true, 0, &words_positions, typo_tolerance)
                                     .flat_map(|positions| positions.iter().next())
                                     .min()
                             } else {
@@ -481,7 +493,7 @@ fn initialize_linear_buckets(
                             }
                         }
                         QueryKind::Tolerant { typo, word } => {
-                            word_derivations(word, *prefix, *typo, &words_positions)
+                            word_derivations(word, *prefix, *typo, &words_positions, typo_tolerance)
                                 .flat_map(|positions| positions.iter().next())
                                 .min()
                         }
@@ -524,13 +536,17 @@ fn initialize_linear_buckets(
         is_prefix: bool,
         max_typo: u8,
         words_positions: &'a HashMap<String, RoaringBitmap>,
+        typo_tolerance: TypoTolerance,
     ) -> impl Iterator<Item = &'a RoaringBitmap> {
-        let dfa = build_dfa(word, max_typo, is_prefix);
+        let dfa = build_dfa(word, max_typo, is_prefix, typo_tolerance);
+        let word = word.to_string();
         words_positions.iter().filter_map(move |(document_word, positions)| {
             use levenshtein_automata::Distance;
             match dfa.eval(document_word) {
-                Distance::Exact(_) => Some(positions),
-                Distance::AtLeast(_) => None,
+                Distance::Exact(_) if typo_tolerance.accepts(&word, document_word) => {
+                    Some(positions)
+                }
+                _ => None,
             }
         })
     }
@@ -538,7 +554,7 @@ fn initialize_linear_buckets(
     let mut candidates = BTreeMap::new();
     for docid in allowed_candidates {
         let words_positions = ctx.docid_words_positions(docid)?;
-        let rank = compute_candidate_rank(branches, words_positions);
+        let rank = compute_candidate_rank(branches, ctx.typo_tolerance(), words_positions);
         candidates.entry(rank).or_insert_with(RoaringBitmap::new).insert(docid);
     }
 
@@ -0,0 +1,137 @@
+use std::mem::take;
+use std::sync::Arc;
+
+use log::debug;
+use roaring::RoaringBitmap;
+
+use super::{Criterion, CriterionParameters, CriterionResult};
+use crate::search::criteria::{resolve_query_tree, CriteriaBuilder};
+use crate::search::query_tree::Operation;
+use crate::{Index, Result};
+
+/// A ranking stage that can be implemented outside of milli and inserted in the
+/// ranking rules of an index with the `name:custom` syntax.
+///
+/// The custom criterion must be registered under the same name on the `Search`,
+/// the search returns an error otherwise.
+pub trait CustomCriterion: Send + Sync {
+    /// Splits the candidates into buckets, the documents of the first bucket are
+    /// ranked before the documents of the second one and so on.
+    ///
+    /// The candidates that are not returned in any bucket are ranked after all
+    /// of them, in a last bucket.
+    fn buckets(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        candidates: &RoaringBitmap,
+    ) -> Result<Vec<RoaringBitmap>>;
+}
+
+pub struct Custom<'t> {
+    index: &'t Index,
+    rtxn: &'t heed::RoTxn<'t>,
+    name: String,
+    custom: Arc<dyn CustomCriterion>,
+    query_tree: Option<Operation>,
+    candidates: std::vec::IntoIter<RoaringBitmap>,
+    allowed_candidates: RoaringBitmap,
+    bucket_candidates: RoaringBitmap,
+    parent: Box<dyn Criterion + 't>,
+}
+
+impl<'t> Custom<'t> {
+    pub fn new(
+        index: &'t Index,
+        rtxn: &'t heed::RoTxn,
+        parent: Box<dyn Criterion + 't>,
+        name: String,
+        custom: Arc<dyn CustomCriterion>,
+    ) -> Self {
+        Custom {
+            index,
+            rtxn,
+            name,
+            custom,
+            query_tree: None,
+            candidates: Vec::new().into_iter(),
+            allowed_candidates: RoaringBitmap::new(),
+            bucket_candidates: RoaringBitmap::new(),
+            parent,
+        }
+    }
+}
+
+impl<'t> Criterion for Custom<'t> {
+    #[logging_timer::time("Custom::{}")]
+    fn next(&mut self, params: &mut CriterionParameters) -> Result<Option<CriterionResult>> {
+        // remove excluded candidates when next is called, instead of doing it in the loop.
+        self.allowed_candidates -= params.excluded_candidates;
+
+        loop {
+            debug!("Custom({}) iteration", self.name);
+
+            match self.candidates.next() {
+                None if !self.allowed_candidates.is_empty() => {
+                    return Ok(Some(CriterionResult {
+                        query_tree: self.query_tree.clone(),
+                        candidates: Some(take(&mut self.allowed_candidates)),
+                        filtered_candidates: None,
+                        bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                    }));
+                }
+                None => match self.parent.next(params)? {
+                    Some(CriterionResult {
+                        query_tree,
+                        candidates,
+                        filtered_candidates,
+                        bucket_candidates,
+                    }) => {
+                        self.query_tree = query_tree;
+                        let mut candidates = match (&self.query_tree, candidates) {
+                            (_, Some(candidates)) => candidates,
+                            (Some(qt), None) => {
+                                let context = CriteriaBuilder::new(&self.rtxn, &self.index)?;
+                                resolve_query_tree(&context, qt, params.wdcache)?
+                            }
+                            (None, None) => self.index.documents_ids(self.rtxn)?,
+                        };
+
+                        if let Some(filtered_candidates) = filtered_candidates {
+                            candidates &= filtered_candidates;
+                        }
+
+                        match bucket_candidates {
+                            Some(bucket_candidates) => self.bucket_candidates |= bucket_candidates,
+                            None => self.bucket_candidates |= &candidates,
+                        }
+
+                        if candidates.is_empty() {
+                            continue;
+                        }
+
+                        self.allowed_candidates = &candidates - params.excluded_candidates;
+                        let buckets = self.custom.buckets(self.rtxn, self.index, &candidates)?;
+                        self.candidates = buckets.into_iter();
+                    }
+                    None => return Ok(None),
+                },
+                Some(mut candidates) => {
+                    // The buckets are restricted to the candidates that were not returned yet.
+                    candidates &= &self.allowed_candidates;
+                    if candidates.is_empty() {
+                        continue;
+                    }
+
+                    self.allowed_candidates -= &candidates;
+                    return Ok(Some(CriterionResult {
+                        query_tree: self.query_tree.clone(),
+                        candidates: Some(candidates),
+                        filtered_candidates: None,
+                        bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                    }));
+                }
+            }
+        }
+    }
+}
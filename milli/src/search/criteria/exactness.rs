@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::mem::take;
 use std::ops::BitOr;
@@ -9,7 +10,7 @@ use roaring::RoaringBitmap;
 use crate::search::criteria::{
     resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult,
 };
-use crate::search::query_tree::{Operation, PrimitiveQueryPart};
+use crate::search::query_tree::{merge_synonyms, Operation, PrimitiveQueryPart};
 use crate::{absolute_from_relative_position, FieldId, Result};
 
 pub struct Exactness<'t> {
@@ -26,10 +27,11 @@ impl<'t> Exactness<'t> {
         ctx: &'t dyn Context<'t>,
         parent: Box<dyn Criterion + 't>,
         primitive_query: &[PrimitiveQueryPart],
+        synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
     ) -> heed::Result<Self> {
         let mut query: Vec<_> = Vec::with_capacity(primitive_query.len());
         for part in primitive_query {
-            query.push(ExactQueryPart::from_primitive_query_part(ctx, part)?);
+            query.push(ExactQueryPart::from_primitive_query_part(ctx, part, synonyms)?);
         }
 
         Ok(Exactness {
@@ -343,10 +345,12 @@ impl ExactQueryPart {
     fn from_primitive_query_part(
         ctx: &dyn Context,
         part: &PrimitiveQueryPart,
+        additional_synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
     ) -> heed::Result<Self> {
         let part = match part {
             PrimitiveQueryPart::Word(word, _) => {
-                match ctx.synonyms(word)? {
+                let additional = additional_synonyms.get(std::slice::from_ref(word));
+                match merge_synonyms(ctx.synonyms(word)?, additional) {
                     Some(synonyms) => {
                         let mut synonyms: Vec<_> = synonyms
                             .into_iter()
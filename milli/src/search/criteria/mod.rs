@@ -1,10 +1,18 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use rayon::prelude::*;
 use roaring::RoaringBitmap;
+use time::OffsetDateTime;
 
 use self::asc_desc::AscDesc;
 use self::attribute::Attribute;
+use self::custom::Custom;
+pub use self::custom::CustomCriterion;
 use self::exactness::Exactness;
 use self::initial::Initial;
 use self::proximity::Proximity;
@@ -12,12 +20,17 @@ use self::r#final::Final;
 use self::typo::Typo;
 use self::words::Words;
 use super::query_tree::{Operation, PrimitiveQueryPart, Query, QueryKind};
+use crate::cache::LruCache;
 use crate::search::criteria::geo::Geo;
-use crate::search::{word_derivations, WordDerivationsCache};
-use crate::{AscDesc as AscDescName, DocumentId, FieldId, Index, Member, Result};
+use crate::search::{word_derivations, TypoTolerance, WordDerivationsCache};
+use crate::{AscDesc as AscDescName, CriterionError, DocumentId, FieldId, Index, Member, Result};
+
+/// The maximum number of characters of the prefix queries whose candidates are cached.
+const PREFIX_CACHE_MAX_CHARS: usize = 3;
 
 mod asc_desc;
 mod attribute;
+mod custom;
 mod exactness;
 pub mod r#final;
 mod geo;
@@ -68,7 +81,15 @@ impl Default for Candidates {
 pub trait Context<'c> {
     fn documents_ids(&self) -> heed::Result<RoaringBitmap>;
     fn word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>>;
+    /// Returns the number of documents containing the word.
+    fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>> {
+        Ok(self.word_docids(word)?.map(|docids| docids.len()))
+    }
     fn word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>>;
+    /// Returns the documents containing the word in one of their exact attributes.
+    fn exact_word_docids(&self, _word: &str) -> heed::Result<Option<RoaringBitmap>> {
+        Ok(None)
+    }
     fn word_pair_proximity_docids(
         &self,
         left: &str,
@@ -100,6 +121,16 @@ pub trait Context<'c> {
         word_count: u8,
     ) -> heed::Result<Option<RoaringBitmap>>;
     fn word_position_docids(&self, word: &str, pos: u32) -> heed::Result<Option<RoaringBitmap>>;
+    fn typo_tolerance(&self) -> TypoTolerance;
+    /// Returns the cached candidates of the prefix query, if there is a prefix cache.
+    fn cached_prefix_docids(&self, _word: &str, _typo: u8) -> Option<RoaringBitmap> {
+        None
+    }
+    fn cache_prefix_docids(&self, _word: &str, _typo: u8, _docids: &RoaringBitmap) {}
+    /// Returns the candidates of the query resolved before the criteria were evaluated.
+    fn prefetched_query_docids(&self, _query: &Query) -> Option<RoaringBitmap> {
+        None
+    }
 }
 
 pub struct CriteriaBuilder<'t> {
@@ -107,6 +138,11 @@ pub struct CriteriaBuilder<'t> {
     index: &'t Index,
     words_fst: fst::Set<Cow<'t, [u8]>>,
     words_prefixes_fst: fst::Set<Cow<'t, [u8]>>,
+    typo_tolerance: TypoTolerance,
+    /// The prefix cache of the index and the version of the index seen by the transaction.
+    prefix_cache: Option<(Arc<LruCache<(String, u8), RoaringBitmap>>, OffsetDateTime)>,
+    /// The candidates of the queries of the last built query tree, resolved in parallel.
+    prefetched_query_docids: Mutex<HashMap<Query, RoaringBitmap>>,
 }
 
 impl<'c> Context<'c> for CriteriaBuilder<'c> {
@@ -118,10 +154,18 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         self.index.word_docids.get(self.rtxn, &word)
     }
 
+    fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>> {
+        self.index.word_documents_count(self.rtxn, word)
+    }
+
     fn word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
         self.index.word_prefix_docids.get(self.rtxn, &word)
     }
 
+    fn exact_word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
+        self.index.exact_word_docids.get(self.rtxn, &word)
+    }
+
     fn word_pair_proximity_docids(
         &self,
         left: &str,
@@ -207,13 +251,82 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         let key = (word, pos);
         self.index.word_position_docids.get(self.rtxn, &key)
     }
+    fn typo_tolerance(&self) -> TypoTolerance {
+        self.typo_tolerance
+    }
+
+    fn cached_prefix_docids(&self, word: &str, typo: u8) -> Option<RoaringBitmap> {
+        let (cache, version) = self.prefix_cache.as_ref()?;
+        cache.get(*version, &(word.to_string(), typo))
+    }
+
+    fn cache_prefix_docids(&self, word: &str, typo: u8, docids: &RoaringBitmap) {
+        if let Some((cache, version)) = &self.prefix_cache {
+            cache.insert(*version, (word.to_string(), typo), docids.clone());
+        }
+    }
+
+    fn prefetched_query_docids(&self, query: &Query) -> Option<RoaringBitmap> {
+        self.prefetched_query_docids.lock().unwrap().get(query).cloned()
+    }
 }
 
 impl<'t> CriteriaBuilder<'t> {
     pub fn new(rtxn: &'t heed::RoTxn<'t>, index: &'t Index) -> Result<Self> {
         let words_fst = index.words_fst(rtxn)?;
         let words_prefixes_fst = index.words_prefixes_fst(rtxn)?;
-        Ok(Self { rtxn, index, words_fst, words_prefixes_fst })
+        let typo_tolerance = index.typo_tolerance(rtxn)?;
+        let prefix_cache = match &index.prefix_cache {
+            Some(cache) => Some((cache.clone(), index.updated_at(rtxn)?)),
+            None => None,
+        };
+        Ok(Self {
+            rtxn,
+            index,
+            words_fst,
+            words_prefixes_fst,
+            typo_tolerance,
+            prefix_cache,
+            prefetched_query_docids: Mutex::default(),
+        })
+    }
+
+    /// Resolves the candidates of the distinct queries of the tree on the rayon thread pool,
+    /// the criteria then use them instead of reading the word databases one query at a time.
+    ///
+    /// The candidates of the previous query tree are dropped, a search that is still
+    /// running with them resolves its queries itself.
+    fn prefetch_query_docids(&self, query_tree: &Operation) -> Result<()> {
+        fn collect_queries<'o>(operation: &'o Operation, queries: &mut HashSet<&'o Query>) {
+            match operation {
+                Operation::And(ops) | Operation::Or(_, ops) => {
+                    ops.iter().for_each(|op| collect_queries(op, queries))
+                }
+                Operation::Query(query) => {
+                    queries.insert(query);
+                }
+                Operation::Phrase(_) => (),
+            }
+        }
+
+        let mut queries = HashSet::new();
+        collect_queries(query_tree, &mut queries);
+
+        // There is nothing to parallelize with a single query.
+        let prefetched = if queries.len() > 1 {
+            queries
+                .into_par_iter()
+                .map_init(WordDerivationsCache::new, |wdcache, query| {
+                    let docids = query_docids(self, query, wdcache)?;
+                    Ok((query.clone(), docids))
+                })
+                .collect::<Result<_>>()?
+        } else {
+            HashMap::new()
+        };
+
+        *self.prefetched_query_docids.lock().unwrap() = prefetched;
+        Ok(())
     }
 
     pub fn build(
@@ -222,14 +335,23 @@ impl<'t> CriteriaBuilder<'t> {
         primitive_query: Option<Vec<PrimitiveQueryPart>>,
         filtered_candidates: Option<RoaringBitmap>,
         sort_criteria: Option<Vec<AscDescName>>,
+        custom_criteria: &HashMap<String, Arc<dyn CustomCriterion>>,
+        synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
+        timings: Option<&CriteriaTimings>,
+        trace: Option<&CriteriaTrace>,
     ) -> Result<Final<'t>> {
         use crate::criterion::Criterion as Name;
 
         let primitive_query = primitive_query.unwrap_or_default();
+        if let Some(query_tree) = &query_tree {
+            self.prefetch_query_docids(query_tree)?;
+        }
 
         let mut criterion =
             Box::new(Initial::new(query_tree, filtered_candidates)) as Box<dyn Criterion>;
         for name in self.index.criteria(&self.rtxn)? {
+            let timed_name = timings.map(|_| name.to_string());
+            let traced_name = trace.map(|_| name.to_string());
             criterion = match name {
                 Name::Words => Box::new(Words::new(self, criterion)),
                 Name::Typo => Box::new(Typo::new(self, criterion)),
@@ -269,20 +391,124 @@ impl<'t> CriteriaBuilder<'t> {
                 },
                 Name::Proximity => Box::new(Proximity::new(self, criterion)),
                 Name::Attribute => Box::new(Attribute::new(self, criterion)),
-                Name::Exactness => Box::new(Exactness::new(self, criterion, &primitive_query)?),
+                Name::Exactness => {
+                    Box::new(Exactness::new(self, criterion, &primitive_query, synonyms)?)
+                }
                 Name::Asc(field) => {
                     Box::new(AscDesc::asc(&self.index, &self.rtxn, criterion, field)?)
                 }
                 Name::Desc(field) => {
                     Box::new(AscDesc::desc(&self.index, &self.rtxn, criterion, field)?)
                 }
+                Name::Custom(name) => match custom_criteria.get(&name) {
+                    Some(custom) => Box::new(Custom::new(
+                        &self.index,
+                        &self.rtxn,
+                        criterion,
+                        name,
+                        custom.clone(),
+                    )),
+                    None => return Err(CriterionError::UnknownCustomCriterion { name }.into()),
+                },
             };
+
+            if let Some((timings, name)) = timings.zip(timed_name) {
+                criterion = Box::new(Timed::new(timings.clone(), name, criterion));
+            }
+            if let Some((trace, name)) = trace.zip(traced_name) {
+                criterion = Box::new(Traced::new(trace.clone(), name, criterion));
+            }
         }
 
         Ok(Final::new(self, criterion))
     }
 }
 
+/// The time spent in each criterion of a search, shared with the criteria measuring it.
+#[derive(Debug, Default, Clone)]
+pub struct CriteriaTimings(Rc<RefCell<Vec<(String, Duration)>>>);
+
+impl CriteriaTimings {
+    /// Returns the time spent in each criterion, excluding the time spent in the previous ones.
+    pub fn exclusive_timings(&self) -> Vec<(String, Duration)> {
+        let timings = self.0.borrow();
+        let mut previous = Duration::default();
+        let mut exclusive = Vec::with_capacity(timings.len());
+        for (name, inclusive) in timings.iter() {
+            exclusive.push((name.clone(), inclusive.saturating_sub(previous)));
+            previous = *inclusive;
+        }
+        exclusive
+    }
+}
+
+/// Measures the time spent in a criterion, which includes the time spent in its parents.
+struct Timed<'t> {
+    timings: CriteriaTimings,
+    position: usize,
+    criterion: Box<dyn Criterion + 't>,
+}
+
+impl<'t> Timed<'t> {
+    fn new(timings: CriteriaTimings, name: String, criterion: Box<dyn Criterion + 't>) -> Self {
+        let position = {
+            let mut inner = timings.0.borrow_mut();
+            inner.push((name, Duration::default()));
+            inner.len() - 1
+        };
+        Timed { timings, position, criterion }
+    }
+}
+
+impl<'t> Criterion for Timed<'t> {
+    fn next(&mut self, params: &mut CriterionParameters) -> Result<Option<CriterionResult>> {
+        let before = Instant::now();
+        let result = self.criterion.next(params);
+        self.timings.0.borrow_mut()[self.position].1 += before.elapsed();
+        result
+    }
+}
+
+/// The number of candidates of the buckets returned by each criterion of a search,
+/// shared with the criteria recording them.
+#[derive(Debug, Default, Clone)]
+pub struct CriteriaTrace(Rc<RefCell<Vec<(String, Vec<u64>)>>>);
+
+impl CriteriaTrace {
+    /// Returns the criteria, in order, with the number of candidates of their buckets.
+    pub fn buckets(&self) -> Vec<(String, Vec<u64>)> {
+        self.0.borrow().clone()
+    }
+}
+
+/// Records the number of candidates of the buckets returned by a criterion.
+struct Traced<'t> {
+    trace: CriteriaTrace,
+    position: usize,
+    criterion: Box<dyn Criterion + 't>,
+}
+
+impl<'t> Traced<'t> {
+    fn new(trace: CriteriaTrace, name: String, criterion: Box<dyn Criterion + 't>) -> Self {
+        let position = {
+            let mut inner = trace.0.borrow_mut();
+            inner.push((name, Vec::new()));
+            inner.len() - 1
+        };
+        Traced { trace, position, criterion }
+    }
+}
+
+impl<'t> Criterion for Traced<'t> {
+    fn next(&mut self, params: &mut CriterionParameters) -> Result<Option<CriterionResult>> {
+        let result = self.criterion.next(params)?;
+        if let Some(CriterionResult { candidates: Some(candidates), .. }) = &result {
+            self.trace.0.borrow_mut()[self.position].1.push(candidates.len());
+        }
+        Ok(result)
+    }
+}
+
 pub fn resolve_query_tree<'t>(
     ctx: &'t dyn Context,
     query_tree: &Operation,
@@ -391,32 +617,79 @@ fn query_docids(
     query: &Query,
     wdcache: &mut WordDerivationsCache,
 ) -> Result<RoaringBitmap> {
-    match &query.kind {
+    if let Some(docids) = ctx.prefetched_query_docids(query) {
+        return Ok(docids);
+    }
+
+    // The short prefixes are the ones matching the most words.
+    let word = query.kind.word();
+    if !query.prefix || word.chars().count() > PREFIX_CACHE_MAX_CHARS {
+        return resolve_query_docids(ctx, query, wdcache);
+    }
+
+    let typo = if query.kind.is_exact() { 0 } else { query.kind.typo() };
+    match ctx.cached_prefix_docids(word, typo) {
+        Some(docids) => Ok(docids),
+        None => {
+            let docids = resolve_query_docids(ctx, query, wdcache)?;
+            ctx.cache_prefix_docids(word, typo, &docids);
+            Ok(docids)
+        }
+    }
+}
+
+fn resolve_query_docids(
+    ctx: &dyn Context,
+    query: &Query,
+    wdcache: &mut WordDerivationsCache,
+) -> Result<RoaringBitmap> {
+    let mut docids = match &query.kind {
         QueryKind::Exact { word, .. } => {
             if query.prefix && ctx.in_prefix_cache(&word) {
-                Ok(ctx.word_prefix_docids(&word)?.unwrap_or_default())
+                ctx.word_prefix_docids(&word)?.unwrap_or_default()
             } else if query.prefix {
-                let words = word_derivations(&word, true, 0, ctx.words_fst(), wdcache)?;
+                let words = word_derivations(
+                    &word,
+                    true,
+                    0,
+                    ctx.words_fst(),
+                    ctx.typo_tolerance(),
+                    wdcache,
+                )?;
                 let mut docids = RoaringBitmap::new();
                 for (word, _typo) in words {
                     let current_docids = ctx.word_docids(&word)?.unwrap_or_default();
                     docids |= current_docids;
                 }
-                Ok(docids)
+                docids
             } else {
-                Ok(ctx.word_docids(&word)?.unwrap_or_default())
+                ctx.word_docids(&word)?.unwrap_or_default()
             }
         }
         QueryKind::Tolerant { typo, word } => {
-            let words = word_derivations(&word, query.prefix, *typo, ctx.words_fst(), wdcache)?;
+            let words = word_derivations(
+                &word,
+                query.prefix,
+                *typo,
+                ctx.words_fst(),
+                ctx.typo_tolerance(),
+                wdcache,
+            )?;
             let mut docids = RoaringBitmap::new();
             for (word, _typo) in words {
                 let current_docids = ctx.word_docids(&word)?.unwrap_or_default();
                 docids |= current_docids;
             }
-            Ok(docids)
+            docids
         }
+    };
+
+    // The words of the exact attributes only match the same word of the query.
+    if let Some(exact_docids) = ctx.exact_word_docids(query.kind.word())? {
+        docids |= exact_docids;
     }
+
+    Ok(docids)
 }
 
 fn query_pair_proximity_docids(
@@ -444,7 +717,14 @@ fn query_pair_proximity_docids(
                 )? {
                     Some(docids) => Ok(docids),
                     None => {
-                        let r_words = word_derivations(&right, true, 0, ctx.words_fst(), wdcache)?;
+                        let r_words = word_derivations(
+                            &right,
+                            true,
+                            0,
+                            ctx.words_fst(),
+                            ctx.typo_tolerance(),
+                            wdcache,
+                        )?;
                         all_word_pair_proximity_docids(ctx, &[(left, 0)], &r_words, proximity)
                     }
                 }
@@ -455,8 +735,15 @@ fn query_pair_proximity_docids(
             }
         }
         (QueryKind::Tolerant { typo, word: left }, QueryKind::Exact { word: right, .. }) => {
-            let l_words =
-                word_derivations(&left, false, *typo, ctx.words_fst(), wdcache)?.to_owned();
+            let l_words = word_derivations(
+                &left,
+                false,
+                *typo,
+                ctx.words_fst(),
+                ctx.typo_tolerance(),
+                wdcache,
+            )?
+            .to_owned();
             if prefix {
                 let mut docids = RoaringBitmap::new();
                 for (left, _) in l_words {
@@ -467,8 +754,14 @@ fn query_pair_proximity_docids(
                     )? {
                         Some(docids) => Ok(docids),
                         None => {
-                            let r_words =
-                                word_derivations(&right, true, 0, ctx.words_fst(), wdcache)?;
+                            let r_words = word_derivations(
+                                &right,
+                                true,
+                                0,
+                                ctx.words_fst(),
+                                ctx.typo_tolerance(),
+                                wdcache,
+                            )?;
                             all_word_pair_proximity_docids(ctx, &[(left, 0)], &r_words, proximity)
                         }
                     }?;
@@ -480,16 +773,37 @@ fn query_pair_proximity_docids(
             }
         }
         (QueryKind::Exact { word: left, .. }, QueryKind::Tolerant { typo, word: right }) => {
-            let r_words = word_derivations(&right, prefix, *typo, ctx.words_fst(), wdcache)?;
+            let r_words = word_derivations(
+                &right,
+                prefix,
+                *typo,
+                ctx.words_fst(),
+                ctx.typo_tolerance(),
+                wdcache,
+            )?;
             all_word_pair_proximity_docids(ctx, &[(left, 0)], &r_words, proximity)
         }
         (
             QueryKind::Tolerant { typo: l_typo, word: left },
             QueryKind::Tolerant { typo: r_typo, word: right },
         ) => {
-            let l_words =
-                word_derivations(&left, false, *l_typo, ctx.words_fst(), wdcache)?.to_owned();
-            let r_words = word_derivations(&right, prefix, *r_typo, ctx.words_fst(), wdcache)?;
+            let l_words = word_derivations(
+                &left,
+                false,
+                *l_typo,
+                ctx.words_fst(),
+                ctx.typo_tolerance(),
+                wdcache,
+            )?
+            .to_owned();
+            let r_words = word_derivations(
+                &right,
+                prefix,
+                *r_typo,
+                ctx.words_fst(),
+                ctx.typo_tolerance(),
+                wdcache,
+            )?;
             all_word_pair_proximity_docids(ctx, &l_words, &r_words, proximity)
         }
     }
@@ -601,6 +915,10 @@ pub mod test {
             todo!()
         }
 
+        fn typo_tolerance(&self) -> TypoTolerance {
+            TypoTolerance::default()
+        }
+
         fn field_id_word_count_docids(
             &self,
             _field_id: FieldId,
@@ -717,4 +1035,40 @@ pub mod test {
             }
         }
     }
+
+    #[test]
+    fn prefetch_query_docids() {
+        let index = crate::test_utils::TempIndex::new();
+        index
+            .add_documents(serde_json::json!([
+                { "id": 0, "title": "hello world" },
+                { "id": 1, "title": "hello there" },
+                { "id": 2, "title": "world peace" }
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let builder = CriteriaBuilder::new(&rtxn, &index).unwrap();
+        let hello = Query { prefix: false, kind: QueryKind::exact(s("hello")) };
+        let world = Query { prefix: true, kind: QueryKind::tolerant(1, s("world")) };
+        let query_tree = Operation::And(vec![
+            Operation::Query(hello.clone()),
+            Operation::Or(
+                false,
+                vec![Operation::Query(world.clone()), Operation::Query(hello.clone())],
+            ),
+        ]);
+
+        builder.prefetch_query_docids(&query_tree).unwrap();
+        let mut wdcache = WordDerivationsCache::new();
+        for query in &[hello, world] {
+            let prefetched = builder.prefetched_query_docids(query).unwrap();
+            assert_eq!(prefetched, resolve_query_docids(&builder, query, &mut wdcache).unwrap());
+        }
+        assert_eq!(builder.prefetched_query_docids.lock().unwrap().len(), 2);
+        assert_eq!(
+            resolve_query_tree(&builder, &query_tree, &mut wdcache).unwrap(),
+            (0..2).collect()
+        );
+    }
 }
@@ -10,7 +10,7 @@ use super::{
     CriterionParameters, CriterionResult,
 };
 use crate::search::query_tree::{maximum_proximity, Operation, Query, QueryKind};
-use crate::search::{build_dfa, WordDerivationsCache};
+use crate::search::{build_dfa, TypoTolerance, WordDerivationsCache};
 use crate::{DocumentId, Position, Result};
 
 type Cache = HashMap<(Operation, u8), Vec<(Query, Query, RoaringBitmap)>>;
@@ -528,16 +528,28 @@ fn resolve_plane_sweep_candidates(
                 match kind {
                     QueryKind::Exact { word, .. } => {
                         if *prefix {
-                            let iter = word_derivations(word, true, 0, &words_positions)
-                                .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
+                            let iter = word_derivations(
+                                word,
+                                true,
+                                0,
+                                &words_positions,
+                                ctx.typo_tolerance(),
+                            )
+                            .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
                             result.extend(iter);
                         } else if let Some(positions) = words_positions.get(word) {
                             result.extend(positions.iter().map(|p| (p, 0, p)));
                         }
                     }
                     QueryKind::Tolerant { typo, word } => {
-                        let iter = word_derivations(word, *prefix, *typo, &words_positions)
-                            .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
+                        let iter = word_derivations(
+                            word,
+                            *prefix,
+                            *typo,
+                            &words_positions,
+                            ctx.typo_tolerance(),
+                        )
+                        .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
                         result.extend(iter);
                     }
                 }
@@ -556,13 +568,17 @@ fn resolve_plane_sweep_candidates(
         is_prefix: bool,
         max_typo: u8,
         words_positions: &'a HashMap<String, RoaringBitmap>,
+        typo_tolerance: TypoTolerance,
     ) -> impl Iterator<Item = &'a RoaringBitmap> {
-        let dfa = build_dfa(word, max_typo, is_prefix);
+        let dfa = build_dfa(word, max_typo, is_prefix, typo_tolerance);
+        let word = word.to_string();
         words_positions.iter().filter_map(move |(document_word, positions)| {
             use levenshtein_automata::Distance;
             match dfa.eval(document_word) {
-                Distance::Exact(_) => Some(positions),
-                Distance::AtLeast(_) => None,
+                Distance::Exact(_) if typo_tolerance.accepts(&word, document_word) => {
+                    Some(positions)
+                }
+                _ => None,
             }
         })
     }
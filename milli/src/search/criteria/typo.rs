@@ -1,5 +1,4 @@
-use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::mem::take;
 
 use log::debug;
@@ -16,6 +15,10 @@ use crate::Result;
 /// Maximum number of typo for a word of any length.
 const MAX_TYPOS_PER_WORD: u8 = 2;
 
+/// A derived word contained in this many times fewer documents than the
+/// most frequent derivation of the same query word is considered rare.
+const RARE_DERIVATION_RATIO: u64 = 100;
+
 pub struct Typo<'t> {
     ctx: &'t dyn Context<'t>,
     /// (max_typos, query_tree, candidates)
@@ -66,10 +69,9 @@ impl<'t> Criterion for Typo<'t> {
                     self.state = None; // reset state
                 }
                 Some((_, query_tree, candidates_authorization)) => {
-                    let fst = self.ctx.words_fst();
                     let new_query_tree = match self.typos {
                         typos if typos < MAX_TYPOS_PER_WORD => alterate_query_tree(
-                            &fst,
+                            self.ctx,
                             query_tree.clone(),
                             self.typos,
                             params.wdcache,
@@ -78,7 +80,7 @@ impl<'t> Criterion for Typo<'t> {
                             // When typos >= MAX_TYPOS_PER_WORD, no more alteration of the query tree is possible,
                             // we keep the altered query tree
                             *query_tree = alterate_query_tree(
-                                &fst,
+                                self.ctx,
                                 query_tree.clone(),
                                 self.typos,
                                 params.wdcache,
@@ -181,37 +183,70 @@ impl<'t> Criterion for Typo<'t> {
 /// containing all of the corresponding exact words in the words FST. Each tolerant
 /// query will only be replaced by exact query with up to `number_typos` maximum typos.
 fn alterate_query_tree(
-    words_fst: &fst::Set<Cow<[u8]>>,
+    ctx: &dyn Context,
     mut query_tree: Operation,
     number_typos: u8,
     wdcache: &mut WordDerivationsCache,
 ) -> Result<Operation> {
     fn recurse(
-        words_fst: &fst::Set<Cow<[u8]>>,
+        ctx: &dyn Context,
         operation: &mut Operation,
         number_typos: u8,
         wdcache: &mut WordDerivationsCache,
     ) -> Result<()> {
         use Operation::{And, Or, Phrase};
 
+        let words_fst = ctx.words_fst();
+        let typo_tolerance = ctx.typo_tolerance();
         match operation {
             And(ops) | Or(_, ops) => {
-                ops.iter_mut().try_for_each(|op| recurse(words_fst, op, number_typos, wdcache))
+                ops.iter_mut().try_for_each(|op| recurse(ctx, op, number_typos, wdcache))
             }
             // Because Phrases don't allow typos, no alteration can be done.
             Phrase(_words) => return Ok(()),
             Operation::Query(q) => {
                 if let QueryKind::Tolerant { typo, word } = &q.kind {
+                    if typo_tolerance.frequency_ranking && *typo > 0 {
+                        // The numbers of typos of the derivations depend on all of them, we
+                        // derive the word with every typo allowed and keep the ones in budget.
+                        let words = word_derivations(
+                            word,
+                            q.prefix,
+                            *typo,
+                            words_fst,
+                            typo_tolerance,
+                            wdcache,
+                        )?;
+                        let queries = frequency_ranked_derivations(ctx, words, *typo)?
+                            .into_iter()
+                            .filter(|(_, typo)| *typo <= number_typos)
+                            .map(|(word, typo)| {
+                                Operation::Query(Query {
+                                    prefix: false,
+                                    kind: QueryKind::Exact { original_typo: typo, word },
+                                })
+                            })
+                            .collect();
+
+                        *operation = Operation::or(false, queries);
+                    }
                     // if no typo is allowed we don't call word_derivations function,
                     // and directly create an Exact query
-                    if number_typos == 0 {
+                    else if number_typos == 0 {
                         *operation = Operation::Query(Query {
                             prefix: q.prefix,
                             kind: QueryKind::Exact { original_typo: 0, word: word.clone() },
                         });
                     } else {
                         let typo = *typo.min(&number_typos);
-                        let words = word_derivations(word, q.prefix, typo, words_fst, wdcache)?;
+                        let words = word_derivations(
+                            word,
+                            q.prefix,
+                            typo,
+                            words_fst,
+                            typo_tolerance,
+                            wdcache,
+                        )?;
                         let queries = words
                             .iter()
                             .map(|(word, typo)| {
@@ -234,10 +269,52 @@ fn alterate_query_tree(
         }
     }
 
-    recurse(words_fst, &mut query_tree, number_typos, wdcache)?;
+    recurse(ctx, &mut query_tree, number_typos, wdcache)?;
     Ok(query_tree)
 }
 
+/// Counts the rare derivations as having the maximum number of typos, the common
+/// derivations are moved to the numbers of typos that only rare derivations had.
+fn frequency_ranked_derivations(
+    ctx: &dyn Context,
+    derivations: &[(String, u8)],
+    max_typo: u8,
+) -> Result<Vec<(String, u8)>> {
+    let mut counts = Vec::with_capacity(derivations.len());
+    for (word, _) in derivations {
+        counts.push(ctx.word_documents_count(word)?.unwrap_or_default());
+    }
+
+    let max_count = counts.iter().copied().max().unwrap_or_default();
+    let is_rare = |count: u64| count.saturating_mul(RARE_DERIVATION_RATIO) < max_count;
+
+    let common_typos: BTreeSet<_> = derivations
+        .iter()
+        .zip(&counts)
+        .filter(|(_, count)| !is_rare(**count))
+        .map(|((_, typo), _)| *typo)
+        .collect();
+    let freed_typos: BTreeSet<_> = derivations
+        .iter()
+        .zip(&counts)
+        .filter(|((_, typo), count)| is_rare(**count) && !common_typos.contains(typo))
+        .map(|((_, typo), _)| *typo)
+        .collect();
+
+    Ok(derivations
+        .iter()
+        .zip(counts)
+        .map(|((word, typo), count)| {
+            let typo = if is_rare(count) {
+                max_typo
+            } else {
+                typo - freed_typos.range(..typo).count() as u8
+            };
+            (word.clone(), typo)
+        })
+        .collect())
+}
+
 fn resolve_candidates<'t>(
     ctx: &'t dyn Context,
     query_tree: &Operation,
@@ -587,4 +664,40 @@ mod test {
 
         assert_eq!(criteria.next(&mut criterion_parameters).unwrap(), Some(expected_2));
     }
+
+    #[test]
+    fn frequency_ranking() {
+        use serde_json::{json, Value};
+
+        use crate::index::tests::TempIndex;
+        use crate::TypoTolerance;
+
+        let index = TempIndex::new();
+        let mut documents = vec![json!({ "id": 0, "title": "wordl" })];
+        documents.extend((1..=150).map(|id| json!({ "id": id, "title": "world" })));
+        index.add_documents(Value::Array(documents)).unwrap();
+
+        // The exact match comes first even if the word is a noise token.
+        assert_eq!(index.search_external_ids("wordl ").unwrap()[0], "0");
+
+        index
+            .update_settings(|settings| {
+                settings.set_typo_tolerance(TypoTolerance {
+                    frequency_ranking: true,
+                    ..Default::default()
+                })
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("wordl ").limit(200).execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 151);
+        assert_eq!(result.documents_ids.last(), Some(&0));
+        drop(rtxn);
+
+        // The words that are not much rarer than the others keep their number of typos.
+        index.add_documents(json!([{ "id": 151, "title": "wordl" }])).unwrap();
+        index.add_documents(json!([{ "id": 152, "title": "wordl" }])).unwrap();
+        assert_eq!(index.search_external_ids("wordl ").unwrap()[0], "0");
+    }
 }
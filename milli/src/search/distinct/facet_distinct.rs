@@ -1,4 +1,6 @@
+use std::cell::RefCell;
 use std::mem::size_of;
+use std::rc::Rc;
 
 use concat_arrays::concat_arrays;
 use heed::types::{ByteSlice, Str, Unit};
@@ -21,21 +23,39 @@ const DOCID_SIZE: usize = size_of::<DocumentId>();
 /// care to keep the document we are currently on, and remove it from the excluded list. The next
 /// iterations will never contain any occurence of a document with the same distinct value as a
 /// document from previous iterations.
+///
+/// With a group size, the documents of a facet value are only excluded once that many documents
+/// with this value have been kept, the kept documents are shared by the iterators of every bucket.
 pub struct FacetDistinct<'a> {
     distinct: FieldId,
+    group_size: usize,
+    kept: Rc<RefCell<RoaringBitmap>>,
     index: &'a Index,
     txn: &'a heed::RoTxn<'a>,
 }
 
 impl<'a> FacetDistinct<'a> {
     pub fn new(distinct: FieldId, index: &'a Index, txn: &'a heed::RoTxn<'a>) -> Self {
-        Self { distinct, index, txn }
+        Self::with_group_size(distinct, 1, index, txn)
+    }
+
+    /// Keeps up to `group_size` documents for each value of the distinct field.
+    pub fn with_group_size(
+        distinct: FieldId,
+        group_size: usize,
+        index: &'a Index,
+        txn: &'a heed::RoTxn<'a>,
+    ) -> Self {
+        let kept = Rc::new(RefCell::new(RoaringBitmap::new()));
+        Self { distinct, group_size, kept, index, txn }
     }
 }
 
 pub struct FacetDistinctIter<'a> {
     candidates: RoaringBitmap,
     distinct: FieldId,
+    group_size: usize,
+    kept: Rc<RefCell<RoaringBitmap>>,
     excluded: RoaringBitmap,
     index: &'a Index,
     iter_offset: usize,
@@ -55,6 +75,15 @@ impl<'a> FacetDistinctIter<'a> {
         self.index.facet_id_f64_docids.get(self.txn, &(self.distinct, 0, key, key))
     }
 
+    /// Excludes the documents of the facet value that are not kept
+    /// if the group of this value is full.
+    fn exclude_full_group(&mut self, facet_docids: RoaringBitmap) {
+        let kept = self.kept.borrow();
+        if (&facet_docids & &*kept).len() >= self.group_size as u64 {
+            self.excluded |= facet_docids - &*kept;
+        }
+    }
+
     fn distinct_string(&mut self, id: DocumentId) -> Result<()> {
         let iter = facet_string_values(id, self.distinct, self.index, self.txn)?;
 
@@ -65,11 +94,9 @@ impl<'a> FacetDistinctIter<'a> {
                     db_name: db_name::FACET_ID_STRING_DOCIDS,
                     key: None,
                 })?;
-            self.excluded |= facet_docids;
+            self.exclude_full_group(facet_docids);
         }
 
-        self.excluded.remove(id);
-
         Ok(())
     }
 
@@ -83,11 +110,9 @@ impl<'a> FacetDistinctIter<'a> {
                     db_name: db_name::FACET_ID_F64_DOCIDS,
                     key: None,
                 })?;
-            self.excluded |= facet_docids;
+            self.exclude_full_group(facet_docids);
         }
 
-        self.excluded.remove(id);
-
         Ok(())
     }
 
@@ -101,7 +126,9 @@ impl<'a> FacetDistinctIter<'a> {
         let mut candidates_iter = self.candidates.iter().skip(self.iter_offset);
         match candidates_iter.next() {
             Some(id) => {
-                // We distinct the document id on its facet strings and facet numbers.
+                // We distinct the document id on its facet strings and facet numbers,
+                // the kept documents are never excluded.
+                self.kept.borrow_mut().insert(id);
                 self.distinct_string(id)?;
                 self.distinct_number(id)?;
 
@@ -178,6 +205,8 @@ impl<'a> Distinct for FacetDistinct<'a> {
         FacetDistinctIter {
             candidates,
             distinct: self.distinct,
+            group_size: self.group_size,
+            kept: self.kept.clone(),
             excluded,
             index: self.index,
             iter_offset: 0,
@@ -210,4 +239,31 @@ mod test {
     test_facet_distinct!(test_string, "txt");
     test_facet_distinct!(test_strings, "txts");
     test_facet_distinct!(test_number, "cat-int");
+
+    #[test]
+    fn test_group_size() {
+        let (index, fid, candidates) = generate_index("cat-int");
+        let txn = index.read_txn().unwrap();
+        let mut map_distinct = FacetDistinct::with_group_size(fid, 2, &index, &txn);
+
+        // The groups are filled across the buckets.
+        let (first, second): (RoaringBitmap, RoaringBitmap) =
+            candidates.iter().partition(|docid| docid % 2 == 0);
+        let mut iter = map_distinct.distinct(first, RoaringBitmap::new());
+        let mut kept: Vec<_> = iter.by_ref().map(Result::unwrap).collect();
+        let mut iter = map_distinct.distinct(second, iter.into_excluded());
+        kept.extend(iter.by_ref().map(Result::unwrap));
+        let excluded = iter.into_excluded();
+        assert_eq!(kept.len() as u64 + excluded.len(), candidates.len());
+
+        let mut groups = std::collections::HashMap::<_, Vec<_>>::new();
+        for docid in candidates {
+            let document = index.documents.get(&txn, &crate::BEU32::new(docid)).unwrap().unwrap();
+            groups.entry(document.get(fid).unwrap().to_vec()).or_default().push(docid);
+        }
+        for docids in groups.values() {
+            let count = docids.iter().filter(|docid| kept.contains(docid)).count();
+            assert_eq!(count, docids.len().min(2));
+        }
+    }
 }
@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::query_tree::{merge_synonyms, Operation, PrimitiveQueryPart, Query};
+use super::{word_derivations, WordDerivationsCache};
+use crate::{Index, Result};
+
+/// The maximum number of derivations reported for each term of the query tree.
+const MAX_EXPLAINED_DERIVATIONS: usize = 50;
+
+/// A trace of how a search found its candidates, returned when the search is explained.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchExplanation {
+    /// The query tree built from the query, in its debug representation.
+    pub query_tree: Option<String>,
+    /// The terms of the query tree with the words of the index they match.
+    pub terms: Vec<TermExplanation>,
+    /// The synonyms of the words of the query, from the index and the search.
+    pub synonyms: Vec<SynonymsExplanation>,
+    /// The filter of the search then the filters forced by the query rules.
+    pub filters: Vec<FilterExplanation>,
+    /// The ranking rules, in order, with the number of candidates of each of
+    /// the buckets they returned until the page was filled.
+    pub criteria: Vec<CriterionExplanation>,
+}
+
+/// A term of the query tree and the words of the index it is derived into.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermExplanation {
+    pub word: String,
+    pub prefix: bool,
+    pub max_typo: u8,
+    /// The words of the index matching the term and their number of typos,
+    /// only the first ones are reported.
+    pub derivations: Vec<(String, u8)>,
+    /// The number of words of the index matching the term.
+    pub derivations_count: usize,
+}
+
+/// The synonyms of a word of the query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SynonymsExplanation {
+    pub word: String,
+    pub synonyms: Vec<Vec<String>>,
+}
+
+/// A branch of a filter and the documents it matched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterExplanation {
+    pub expression: String,
+    /// The number of documents matching the branch.
+    pub candidates: u64,
+    /// The number of documents removed by the branch, the documents matching the
+    /// other side of an `AND` but not this branch, or the documents of the index
+    /// not matching the whole filter.
+    pub pruned: u64,
+    /// The sub-branches of an `AND` or an `OR`.
+    pub branches: Vec<FilterExplanation>,
+}
+
+/// A ranking rule and the number of candidates of each of the buckets it returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriterionExplanation {
+    pub name: String,
+    pub buckets: Vec<u64>,
+}
+
+/// Returns the terms of the query tree with their derivations and the synonyms of
+/// the words of the query.
+pub(crate) fn explain_query(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    query_tree: &Operation,
+    primitive_query: &[PrimitiveQueryPart],
+    synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
+) -> Result<(Vec<TermExplanation>, Vec<SynonymsExplanation>)> {
+    let words_fst = index.words_fst(rtxn)?;
+    let typo_tolerance = index.typo_tolerance(rtxn)?;
+    let mut wdcache = WordDerivationsCache::new();
+
+    let mut queries = Vec::new();
+    collect_queries(query_tree, &mut queries);
+
+    let mut seen = HashSet::new();
+    let mut terms = Vec::new();
+    for (word, prefix, max_typo) in queries {
+        if !seen.insert((word.clone(), prefix, max_typo)) {
+            continue;
+        }
+        let words =
+            word_derivations(&word, prefix, max_typo, &words_fst, typo_tolerance, &mut wdcache)?;
+        terms.push(TermExplanation {
+            derivations: words.iter().take(MAX_EXPLAINED_DERIVATIONS).cloned().collect(),
+            derivations_count: words.len(),
+            word,
+            prefix,
+            max_typo,
+        });
+    }
+
+    let mut words_synonyms = Vec::new();
+    for part in primitive_query {
+        if let PrimitiveQueryPart::Word(word, _) = part {
+            let additional = synonyms.get(std::slice::from_ref(word));
+            let word_synonyms = index.words_synonyms(rtxn, &[word])?;
+            if let Some(synonyms) = merge_synonyms(word_synonyms, additional) {
+                words_synonyms.push(SynonymsExplanation { word: word.clone(), synonyms });
+            }
+        }
+    }
+
+    Ok((terms, words_synonyms))
+}
+
+/// Collects the word, the prefix flag and the maximum number of typos of the
+/// queries of the tree, the words of a phrase are exact words.
+fn collect_queries(operation: &Operation, queries: &mut Vec<(String, bool, u8)>) {
+    match operation {
+        Operation::And(children) | Operation::Or(_, children) => {
+            children.iter().for_each(|child| collect_queries(child, queries))
+        }
+        Operation::Phrase(words) => {
+            queries.extend(words.iter().map(|word| (word.clone(), false, 0)))
+        }
+        Operation::Query(Query { prefix, kind }) => {
+            let max_typo = if kind.is_tolerant() { kind.typo() } else { 0 };
+            queries.push((kind.word().to_string(), *prefix, max_typo));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::{hashmap, hashset};
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::Filter;
+
+    #[test]
+    fn explain_search() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color"), S("size") })
+            })
+            .unwrap();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "quick brown fox", "color": "red", "size": 1 },
+                { "id": 1, "title": "quack brother", "color": "blue", "size": 2 },
+                { "id": 2, "title": "fast bro", "color": "red", "size": 3 },
+                { "id": 3, "title": "slow turtle", "color": "red", "size": 2 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.query("quick bro");
+        search.synonyms(hashmap! { S("quick") => vec![S("fast")] });
+        search.filter(Filter::from_str("color = red AND size > 1").unwrap().unwrap());
+        search.explain(true);
+        let explanation = search.execute().unwrap().explanation.unwrap();
+
+        assert!(explanation.query_tree.is_some());
+        let term = |word: &str| explanation.terms.iter().find(|t| t.word == word).unwrap();
+        let quick = term("quick");
+        assert_eq!((quick.prefix, quick.max_typo), (false, 1));
+        assert_eq!(quick.derivations, vec![(S("quack"), 1), (S("quick"), 0)]);
+        let bro = term("bro");
+        assert_eq!((bro.prefix, bro.derivations_count), (true, 3));
+        assert_eq!(term("fast").derivations, vec![(S("fast"), 0)]);
+
+        assert_eq!(
+            explanation.synonyms,
+            vec![SynonymsExplanation { word: S("quick"), synonyms: vec![vec![S("fast")]] }]
+        );
+
+        // The color removes the document 1 and the size the document 0.
+        let filter = &explanation.filters[0];
+        assert_eq!((filter.candidates, filter.pruned), (2, 2));
+        let branches: Vec<_> =
+            filter.branches.iter().map(|branch| (branch.candidates, branch.pruned)).collect();
+        assert_eq!(branches, vec![(3, 1), (3, 1)]);
+
+        let names: Vec<_> = explanation.criteria.iter().map(|c| c.name.clone()).collect();
+        let criteria: Vec<_> = index.criteria(&rtxn).unwrap();
+        assert_eq!(names, criteria.iter().map(ToString::to_string).collect::<Vec<_>>());
+        assert!(!explanation.criteria[0].buckets.is_empty());
+
+        let result = index.search(&rtxn).query("quick bro").execute().unwrap();
+        assert!(result.explanation.is_none());
+    }
+}
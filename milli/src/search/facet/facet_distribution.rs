@@ -1,12 +1,15 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Bound::Unbounded;
 use std::{fmt, mem};
 
 use heed::types::ByteSlice;
 use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 
 use crate::error::UserError;
-use crate::facet::FacetType;
+use crate::facet::{
+    date_bucket, hierarchy_depth_prefix, DateGranularity, FacetNormalization, FacetType,
+};
 use crate::heed_codec::facet::{
     FacetStringLevelZeroCodec, FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec,
 };
@@ -17,8 +20,47 @@ use crate::{FieldId, Index, Result};
 /// the system to choose between one algorithm or another.
 const CANDIDATES_THRESHOLD: u64 = 3000;
 
+/// The order of the values of a facet in the distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetOrder {
+    /// The values are sorted lexicographically, by the keys they are normalized into.
+    Lexicographic,
+    /// The values are sorted by decreasing number of documents, then lexicographically.
+    Count,
+}
+
+impl Default for FacetOrder {
+    fn default() -> FacetOrder {
+        FacetOrder::Lexicographic
+    }
+}
+
+/// The options of the distribution of a single facet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FacetOptions {
+    /// The maximum number of values returned, the included values are not counted.
+    ///
+    /// In the lexicographic order and without included values, the traversal of the
+    /// string values stops once enough of them are found.
+    pub max_values: Option<usize>,
+    /// The order in which the values are selected and returned.
+    pub order: FacetOrder,
+    /// The values that are always returned, even when they are not in the first
+    /// values or don't match any candidate, in which case their count is zero.
+    pub include: Vec<String>,
+    /// The depth of the values returned for a hierarchical facet, the roots
+    /// of the hierarchy have a depth of one, all the levels are returned by default.
+    pub depth: Option<usize>,
+    /// The size of the buckets the values of a date field are counted in,
+    /// the bucket names are returned instead of the values when defined.
+    pub date_granularity: Option<DateGranularity>,
+}
+
 pub struct FacetDistribution<'a> {
     facets: Option<HashSet<String>>,
+    options: HashMap<String, FacetOptions>,
     candidates: Option<RoaringBitmap>,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
@@ -26,7 +68,7 @@ pub struct FacetDistribution<'a> {
 
 impl<'a> FacetDistribution<'a> {
     pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index) -> FacetDistribution<'a> {
-        FacetDistribution { facets: None, candidates: None, rtxn, index }
+        FacetDistribution { facets: None, options: HashMap::new(), candidates: None, rtxn, index }
     }
 
     pub fn facets<I: IntoIterator<Item = A>, A: AsRef<str>>(&mut self, names: I) -> &mut Self {
@@ -34,6 +76,17 @@ impl<'a> FacetDistribution<'a> {
         self
     }
 
+    /// Sets the options of the distribution of the given facet, the facet is
+    /// added to the requested facets if only some of them were requested.
+    pub fn facet_options<A: AsRef<str>>(&mut self, name: A, options: FacetOptions) -> &mut Self {
+        let name = name.as_ref().to_string();
+        if let Some(facets) = self.facets.as_mut() {
+            facets.insert(name.clone());
+        }
+        self.options.insert(name, options);
+        self
+    }
+
     pub fn candidates(&mut self, candidates: RoaringBitmap) -> &mut Self {
         self.candidates = Some(candidates);
         self
@@ -45,10 +98,13 @@ impl<'a> FacetDistribution<'a> {
         &self,
         field_id: FieldId,
         facet_type: FacetType,
+        level_prefix: Option<&str>,
         candidates: &RoaringBitmap,
         distribution: &mut BTreeMap<String, u64>,
     ) -> heed::Result<()> {
         match facet_type {
+            // The hierarchical facets levels are only strings.
+            FacetType::Number if level_prefix.is_some() => (),
             FacetType::Number => {
                 let mut key_buffer: Vec<_> = field_id.to_be_bytes().iter().copied().collect();
 
@@ -75,6 +131,9 @@ impl<'a> FacetDistribution<'a> {
                 for docid in candidates.into_iter() {
                     key_buffer.truncate(mem::size_of::<FieldId>());
                     key_buffer.extend_from_slice(&docid.to_be_bytes());
+                    if let Some(prefix) = level_prefix {
+                        key_buffer.extend_from_slice(prefix.as_bytes());
+                    }
                     let iter = db
                         .remap_key_type::<ByteSlice>()
                         .prefix_iter(self.rtxn, &key_buffer)?
@@ -121,20 +180,32 @@ impl<'a> FacetDistribution<'a> {
         Ok(())
     }
 
+    /// The string values are iterated in lexicographic order, only the first `limit`
+    /// ones matching the candidates are returned.
     fn facet_strings_distribution_from_facet_levels(
         &self,
         field_id: FieldId,
+        level_prefix: Option<&str>,
         candidates: &RoaringBitmap,
+        limit: Option<usize>,
         distribution: &mut BTreeMap<String, u64>,
     ) -> heed::Result<()> {
         let iter =
             FacetStringIter::new_non_reducing(self.rtxn, self.index, field_id, candidates.clone())?;
 
+        let mut count = 0;
         for result in iter {
-            let (_normalized, original, mut docids) = result?;
+            if limit.map_or(false, |limit| count >= limit) {
+                break;
+            }
+            let (normalized, original, mut docids) = result?;
+            if level_prefix.map_or(false, |prefix| !normalized.starts_with(prefix)) {
+                continue;
+            }
             docids &= candidates;
             if !docids.is_empty() {
                 distribution.insert(original.to_string(), docids.len());
+                count += 1;
             }
         }
 
@@ -142,30 +213,41 @@ impl<'a> FacetDistribution<'a> {
     }
 
     /// Placeholder search, a.k.a. no candidates were specified. We iterate throught the
-    /// facet values one by one and iterate on the facet level 0 for numbers.
+    /// facet values one by one and iterate on the facet level 0 for numbers, only the
+    /// first `limit` string values are returned.
     fn facet_values_from_raw_facet_database(
         &self,
         field_id: FieldId,
+        level_prefix: Option<&str>,
+        limit: Option<usize>,
     ) -> heed::Result<BTreeMap<String, u64>> {
         let mut distribution = BTreeMap::new();
 
-        let db = self.index.facet_id_f64_docids;
-        let range = FacetNumberRange::new(self.rtxn, db, field_id, 0, Unbounded, Unbounded)?;
+        if level_prefix.is_none() {
+            let db = self.index.facet_id_f64_docids;
+            let range = FacetNumberRange::new(self.rtxn, db, field_id, 0, Unbounded, Unbounded)?;
 
-        for result in range {
-            let ((_, _, value, _), docids) = result?;
-            distribution.insert(value.to_string(), docids.len());
+            for result in range {
+                let ((_, _, value, _), docids) = result?;
+                distribution.insert(value.to_string(), docids.len());
+            }
         }
 
+        // The level-aware keys of a hierarchical facet level share the same prefix.
+        let mut prefix = field_id.to_be_bytes().to_vec();
+        prefix.extend_from_slice(level_prefix.unwrap_or_default().as_bytes());
         let iter = self
             .index
             .facet_id_string_docids
             .remap_key_type::<ByteSlice>()
-            .prefix_iter(self.rtxn, &field_id.to_be_bytes())?
+            .prefix_iter(self.rtxn, &prefix)?
             .remap_key_type::<FacetStringLevelZeroCodec>();
 
         let mut normalized_distribution = BTreeMap::new();
         for result in iter {
+            if limit.map_or(false, |limit| normalized_distribution.len() >= limit) {
+                break;
+            }
             let ((_, normalized_value), (original_value, docids)) = result?;
             normalized_distribution.insert(normalized_value, (original_value, docids.len()));
         }
@@ -178,7 +260,54 @@ impl<'a> FacetDistribution<'a> {
         Ok(distribution)
     }
 
-    fn facet_values(&self, field_id: FieldId) -> heed::Result<BTreeMap<String, u64>> {
+    /// Returns the distribution of the facet over the candidates, the distribution over all
+    /// the documents is cached if the index has a facet distribution cache.
+    ///
+    /// The distribution contains all the number values but only the first `limit` string
+    /// values in lexicographic order, the first values of the distribution are complete.
+    fn facet_values(
+        &self,
+        field_id: FieldId,
+        level_prefix: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<BTreeMap<String, u64>> {
+        let cache = match &self.index.facet_distribution_cache {
+            Some(cache) => cache,
+            None => return Ok(self.candidates_facet_values(field_id, level_prefix, limit)?),
+        };
+        // The candidates are a subset of the documents, they are all the documents
+        // if there are as many of them.
+        let all_documents = match &self.candidates {
+            Some(candidates) => candidates.len() == self.index.number_of_documents(self.rtxn)?,
+            None => true,
+        };
+        if !all_documents {
+            return Ok(self.candidates_facet_values(field_id, level_prefix, limit)?);
+        }
+
+        let version = self.index.updated_at(self.rtxn)?;
+        let key = (field_id, level_prefix.map(ToOwned::to_owned));
+        match cache.get(version, &key) {
+            Some(distribution) => Ok(distribution),
+            // Only the complete distributions are cached.
+            None if limit.is_some() => {
+                Ok(self.facet_values_from_raw_facet_database(field_id, level_prefix, limit)?)
+            }
+            None => {
+                let distribution =
+                    self.facet_values_from_raw_facet_database(field_id, level_prefix, None)?;
+                cache.insert(version, key, distribution.clone());
+                Ok(distribution)
+            }
+        }
+    }
+
+    fn candidates_facet_values(
+        &self,
+        field_id: FieldId,
+        level_prefix: Option<&str>,
+        limit: Option<usize>,
+    ) -> heed::Result<BTreeMap<String, u64>> {
         use FacetType::{Number, String};
 
         match self.candidates {
@@ -190,69 +319,235 @@ impl<'a> FacetDistribution<'a> {
                     self.facet_distribution_from_documents(
                         field_id,
                         Number,
+                        level_prefix,
                         candidates,
                         &mut distribution,
                     )?;
                     self.facet_distribution_from_documents(
                         field_id,
                         String,
+                        level_prefix,
                         candidates,
                         &mut distribution,
                     )?;
                 } else {
-                    self.facet_numbers_distribution_from_facet_levels(
-                        field_id,
-                        candidates,
-                        &mut distribution,
-                    )?;
+                    if level_prefix.is_none() {
+                        self.facet_numbers_distribution_from_facet_levels(
+                            field_id,
+                            candidates,
+                            &mut distribution,
+                        )?;
+                    }
                     self.facet_strings_distribution_from_facet_levels(
                         field_id,
+                        level_prefix,
                         candidates,
+                        limit,
                         &mut distribution,
                     )?;
                 }
                 Ok(distribution)
             }
-            None => self.facet_values_from_raw_facet_database(field_id),
+            None => self.facet_values_from_raw_facet_database(field_id, level_prefix, limit),
         }
     }
 
-    pub fn execute(&self) -> Result<BTreeMap<String, BTreeMap<String, u64>>> {
-        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
-        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
-        let fields = match self.facets {
-            Some(ref facets) => {
-                let invalid_fields: HashSet<_> = facets.difference(&filterable_fields).collect();
-                if !invalid_fields.is_empty() {
-                    return Err(UserError::InvalidFacetsDistribution {
-                        invalid_facets_name: invalid_fields.into_iter().cloned().collect(),
-                    }
-                    .into());
+    /// Counts the documents whose dates, indexed as timestamps, are in each bucket of
+    /// the given granularity, a document is counted once per bucket.
+    fn facet_date_values(
+        &self,
+        field_id: FieldId,
+        granularity: DateGranularity,
+    ) -> heed::Result<BTreeMap<String, u64>> {
+        let candidates = match self.candidates {
+            Some(ref candidates) => candidates.clone(),
+            None => self.index.number_faceted_documents_ids(self.rtxn, field_id)?,
+        };
+
+        let mut buckets: BTreeMap<_, RoaringBitmap> = BTreeMap::new();
+        let iter =
+            FacetNumberIter::new_non_reducing(self.rtxn, self.index, field_id, candidates.clone())?;
+        for result in iter {
+            let (timestamp, mut docids) = result?;
+            docids &= &candidates;
+            if let Some(bucket) = date_bucket(timestamp, granularity) {
+                *buckets.entry(bucket).or_default() |= docids;
+            }
+        }
+
+        Ok(buckets
+            .into_iter()
+            .filter(|(_, docids)| !docids.is_empty())
+            .map(|(bucket, docids)| (bucket, docids.len()))
+            .collect())
+    }
+
+    /// Returns the values of the facet in the order and limits defined by its options.
+    fn ordered_facet_values(
+        &self,
+        field_id: FieldId,
+        options: &FacetOptions,
+    ) -> Result<Vec<(String, u64)>> {
+        let FacetOptions { max_values, order, include, depth, date_granularity } = options;
+        let include: Vec<_> = include.iter().map(|value| value.to_lowercase()).collect();
+        let is_included = |value: &str| include.contains(&value.to_lowercase());
+
+        let normalization = self
+            .index
+            .facet_normalization_ids(self.rtxn)?
+            .get(&field_id)
+            .copied()
+            .unwrap_or_default();
+
+        let values = match date_granularity {
+            Some(granularity) => self.facet_date_values(field_id, *granularity)?,
+            None => {
+                // The string values are traversed in the lexicographic order of their keys,
+                // the traversal can stop at the last value returned unless some values
+                // must be included wherever they are.
+                let limit = match order {
+                    FacetOrder::Lexicographic if include.is_empty() => *max_values,
+                    _ => None,
+                };
+                let level_prefix = depth.map(hierarchy_depth_prefix);
+                self.facet_values(field_id, level_prefix.as_deref(), limit)?
+            }
+        };
+        let mut values: Vec<_> = values.into_iter().collect();
+        sort_facet_values(&mut values, *order, normalization);
+
+        if let Some(max_values) = max_values {
+            let mut count = 0;
+            values.retain(|(value, _)| {
+                if is_included(value) {
+                    true
                 } else {
-                    facets.clone()
+                    count += 1;
+                    count <= *max_values
                 }
+            });
+        }
+
+        let missing: Vec<_> = options
+            .include
+            .iter()
+            .filter(|included| {
+                let included = included.to_lowercase();
+                values.iter().all(|(value, _)| value.to_lowercase() != included)
+            })
+            .map(|included| (included.clone(), 0))
+            .collect();
+        if !missing.is_empty() {
+            values.extend(missing);
+            sort_facet_values(&mut values, *order, normalization);
+        }
+
+        Ok(values)
+    }
+
+    /// Returns the requested facets and their field ids, checking that they are filterable.
+    fn requested_fields(&self) -> Result<Vec<(String, FieldId)>> {
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
+        let invalid_fields: HashSet<_> = self
+            .facets
+            .iter()
+            .flatten()
+            .chain(self.options.keys())
+            .filter(|name| !filterable_fields.contains(*name))
+            .collect();
+        if !invalid_fields.is_empty() {
+            return Err(UserError::InvalidFacetsDistribution {
+                invalid_facets_name: invalid_fields.into_iter().cloned().collect(),
             }
+            .into());
+        }
+
+        let fields = match self.facets {
+            Some(ref facets) => facets.clone(),
             None => filterable_fields,
         };
 
+        Ok(fields
+            .into_iter()
+            .filter_map(|name| fields_ids_map.id(&name).map(|fid| (name, fid)))
+            .collect())
+    }
+
+    /// Returns the distribution of the requested facets, the values of each facet
+    /// are returned in the order defined by its options, lexicographically by default.
+    pub fn execute_ordered(&self) -> Result<BTreeMap<String, Vec<(String, u64)>>> {
+        let default_options = FacetOptions::default();
         let mut distribution = BTreeMap::new();
-        for name in fields {
-            if let Some(fid) = fields_ids_map.id(&name) {
-                let values = self.facet_values(fid)?;
-                distribution.insert(name, values);
-            }
+        for (name, fid) in self.requested_fields()? {
+            let options = self.options.get(&name).unwrap_or(&default_options);
+            let values = self.ordered_facet_values(fid, options)?;
+            distribution.insert(name, values);
         }
 
         Ok(distribution)
     }
+
+    /// Returns the minimum and the maximum number values of the requested facets over the
+    /// candidates, read from the facet levels, the facets without any number value are omitted.
+    pub fn compute_stats(&self) -> Result<BTreeMap<String, (f64, f64)>> {
+        let mut stats = BTreeMap::new();
+        for (name, fid) in self.requested_fields()? {
+            let mut candidates = self.index.number_faceted_documents_ids(self.rtxn, fid)?;
+            if let Some(ref documents_ids) = self.candidates {
+                candidates &= documents_ids;
+            }
+            if candidates.is_empty() {
+                continue;
+            }
+
+            let min =
+                FacetNumberIter::new_reducing(self.rtxn, self.index, fid, candidates.clone())?
+                    .next()
+                    .transpose()?;
+            let max =
+                FacetNumberIter::new_reverse_reducing(self.rtxn, self.index, fid, candidates)?
+                    .next()
+                    .transpose()?;
+            if let (Some((min, _)), Some((max, _))) = (min, max) {
+                stats.insert(name, (min, max));
+            }
+        }
+
+        Ok(stats)
+    }
+
+    pub fn execute(&self) -> Result<BTreeMap<String, BTreeMap<String, u64>>> {
+        let distribution = self.execute_ordered()?;
+        Ok(distribution
+            .into_iter()
+            .map(|(name, values)| (name, values.into_iter().collect()))
+            .collect())
+    }
+}
+
+/// Sorts the facet values and their number of documents in the given order, the
+/// lexicographic order is the one of the normalized keys the values are stored under.
+fn sort_facet_values(
+    values: &mut [(String, u64)],
+    order: FacetOrder,
+    normalization: FacetNormalization,
+) {
+    match order {
+        FacetOrder::Lexicographic => {
+            values.sort_by_cached_key(|(value, _)| (normalization.normalize(value), value.clone()))
+        }
+        FacetOrder::Count => values.sort_by(|(a, ac), (b, bc)| bc.cmp(ac).then_with(|| a.cmp(b))),
+    }
 }
 
 impl fmt::Debug for FacetDistribution<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let FacetDistribution { facets, candidates, rtxn: _, index: _ } = self;
+        let FacetDistribution { facets, options, candidates, rtxn: _, index: _ } = self;
 
         f.debug_struct("FacetDistribution")
             .field("facets", facets)
+            .field("options", options)
             .field("candidates", candidates)
             .finish()
     }
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::ops::Bound::{self, Excluded, Included};
+
+use roaring::RoaringBitmap;
+
+use crate::error::UserError;
+use crate::search::facet::Filter;
+use crate::{Index, Result};
+
+/// The way the values of a numeric facet are split into buckets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistogramBuckets {
+    /// `count` consecutive buckets of the same `width`, the first one starting at `start`.
+    FixedWidth { start: f64, width: f64, count: usize },
+    /// The buckets between each pair of consecutive bounds, sorted in increasing order.
+    Bounds(Vec<f64>),
+}
+
+impl HistogramBuckets {
+    /// Returns the bounds of the buckets, checking that they can be used.
+    fn bounds(&self) -> Result<Vec<f64>> {
+        let bounds: Vec<_> = match *self {
+            HistogramBuckets::FixedWidth { start, width, count } => {
+                if !width.is_finite() || width <= 0.0 {
+                    let reason =
+                        format!("the width of the buckets must be positive, not {}", width);
+                    return Err(UserError::InvalidHistogramBuckets(reason).into());
+                }
+                (0..=count).map(|i| start + width * i as f64).collect()
+            }
+            HistogramBuckets::Bounds(ref bounds) => bounds.clone(),
+        };
+
+        if bounds.iter().any(|bound| !bound.is_finite()) {
+            let reason = String::from("the bounds of the buckets must be finite numbers");
+            return Err(UserError::InvalidHistogramBuckets(reason).into());
+        }
+        if bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            let reason = String::from("the bounds of the buckets must be strictly increasing");
+            return Err(UserError::InvalidHistogramBuckets(reason).into());
+        }
+
+        Ok(bounds)
+    }
+}
+
+/// The number of documents whose values are in a bucket of the histogram.
+///
+/// The buckets include their lower bound and exclude their upper bound,
+/// except the last one which includes both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBucket {
+    pub from: f64,
+    pub to: f64,
+    pub count: u64,
+}
+
+pub struct FacetHistogram<'a> {
+    field: String,
+    buckets: HistogramBuckets,
+    candidates: Option<RoaringBitmap>,
+    rtxn: &'a heed::RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> FacetHistogram<'a> {
+    pub fn new<A: AsRef<str>>(
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+        field: A,
+        buckets: HistogramBuckets,
+    ) -> FacetHistogram<'a> {
+        let field = field.as_ref().to_string();
+        FacetHistogram { field, buckets, candidates: None, rtxn, index }
+    }
+
+    pub fn candidates(&mut self, candidates: RoaringBitmap) -> &mut Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    /// Returns the number of documents, among the candidates if any, whose numeric
+    /// values of the field are in each bucket, computed from the facet levels.
+    pub fn execute(&self) -> Result<Vec<HistogramBucket>> {
+        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
+        if !filterable_fields.contains(&self.field) {
+            let invalid_facets_name = Some(self.field.clone()).into_iter().collect();
+            return Err(UserError::InvalidFacetsDistribution { invalid_facets_name }.into());
+        }
+
+        let bounds = self.buckets.bounds()?;
+        let field_id = match self.index.fields_ids_map(self.rtxn)?.id(&self.field) {
+            Some(field_id) => field_id,
+            None => {
+                let buckets = bounds.windows(2);
+                return Ok(buckets
+                    .map(|b| HistogramBucket { from: b[0], to: b[1], count: 0 })
+                    .collect());
+            }
+        };
+
+        let numbers_db = self.index.facet_id_f64_docids;
+        let last = bounds.len().saturating_sub(2);
+        let mut histogram = Vec::with_capacity(bounds.len().saturating_sub(1));
+        for (i, pair) in bounds.windows(2).enumerate() {
+            let (from, to) = (pair[0], pair[1]);
+            let right: Bound<f64> = if i == last { Included(to) } else { Excluded(to) };
+            let mut docids = Filter::number_range_docids(
+                self.rtxn,
+                numbers_db,
+                field_id,
+                Included(from),
+                right,
+            )?;
+            if let Some(candidates) = &self.candidates {
+                docids &= candidates;
+            }
+            histogram.push(HistogramBucket { from, to, count: docids.len() });
+        }
+
+        Ok(histogram)
+    }
+}
+
+impl fmt::Debug for FacetHistogram<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let FacetHistogram { field, buckets, candidates, rtxn: _, index: _ } = self;
+
+        f.debug_struct("FacetHistogram")
+            .field("field", field)
+            .field("buckets", buckets)
+            .field("candidates", candidates)
+            .finish()
+    }
+}
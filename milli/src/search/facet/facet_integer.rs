@@ -0,0 +1,76 @@
+use roaring::RoaringBitmap;
+
+use crate::{FieldId, Index};
+
+/// The integers starting from this one can't all be represented exactly by an `f64`.
+pub const MAX_SAFE_INTEGER: i64 = 1 << 53;
+
+/// The maximum distance between an `i64` or an `u64` and the `f64` it is rounded to.
+const MAX_ROUNDING_DISTANCE: i128 = 1 << 10;
+
+/// Returns `true` if the given facet number can be the rounded value of different integers.
+pub fn is_unsafe_integer(value: f64) -> bool {
+    value.fract() == 0.0 && value.abs() >= MAX_SAFE_INTEGER as f64
+}
+
+/// Returns the documents ids of the level 0 facet number entry of the given value.
+pub fn facet_number_docids(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    field_id: FieldId,
+    value: f64,
+) -> heed::Result<RoaringBitmap> {
+    Ok(index.facet_id_f64_docids.get(rtxn, &(field_id, 0, value, value))?.unwrap_or_default())
+}
+
+/// Splits the documents ids associated to a facet number by the exact integers
+/// that were rounded to this facet number.
+///
+/// Returns `None` if the facet number cannot be the rounded value of an integer, the groups
+/// are returned in ascending order otherwise. A document having multiple integers rounded
+/// to the same number can appear in multiple groups. The documents for which no exact
+/// integer is known are grouped under the facet number itself.
+pub fn exact_integer_groups(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    field_id: FieldId,
+    value: f64,
+    docids: &RoaringBitmap,
+) -> heed::Result<Option<Vec<(i128, RoaringBitmap)>>> {
+    if !is_unsafe_integer(value) {
+        return Ok(None);
+    }
+
+    // The cast saturates the values that are out of the i128 bounds.
+    let integer = value as i128;
+    let left = (field_id, integer.saturating_sub(MAX_ROUNDING_DISTANCE));
+    let right = (field_id, integer.saturating_add(MAX_ROUNDING_DISTANCE));
+
+    let mut groups = Vec::new();
+    let mut remaining = docids.clone();
+    for result in index.facet_id_i128_docids.range(rtxn, &(left..=right))? {
+        let ((_, integer), integer_docids) = result?;
+        if integer as f64 == value {
+            let group_docids = integer_docids & docids;
+            if !group_docids.is_empty() {
+                remaining -= &group_docids;
+                groups.push((integer, group_docids));
+            }
+        }
+    }
+
+    if !remaining.is_empty() {
+        groups.push((value as i128, remaining));
+        groups.sort_by_key(|(integer, _)| *integer);
+        groups.dedup_by(|(integer, docids), (previous, previous_docids)| {
+            if integer == previous {
+                *previous_docids |= &*docids;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    Ok(Some(groups))
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 use std::ops::Bound::{self, Excluded, Included};
 use std::ops::Deref;
@@ -8,13 +10,17 @@ use heed::types::DecodeIgnore;
 use log::debug;
 use roaring::RoaringBitmap;
 
+use super::facet_integer::{exact_integer_groups, facet_number_docids, is_unsafe_integer};
 use super::FacetNumberRange;
 use crate::error::{Error, UserError};
+use crate::facet::hierarchy_level_key;
 use crate::heed_codec::facet::{
     FacetLevelValueF64Codec, FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec,
 };
+use crate::search::FilterExplanation;
 use crate::{
-    distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec, FieldId, Index, Result,
+    did_you_mean, distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec, FieldId,
+    Index, Result,
 };
 
 /// The maximum number of filters the filter AST can process.
@@ -27,10 +33,11 @@ pub struct Filter<'a> {
 
 #[derive(Debug)]
 enum FilterError<'a> {
-    AttributeNotFilterable { attribute: &'a str, filterable: String },
+    AttributeNotFilterable { attribute: &'a str, filterable: String, suggestion: Option<String> },
     BadGeo(&'a str),
     BadGeoLat(f64),
     BadGeoLng(f64),
+    GeoPolygonTooLarge,
     Reserved(&'a str),
     TooDeep,
 }
@@ -39,12 +46,18 @@ impl<'a> std::error::Error for FilterError<'a> {}
 impl<'a> Display for FilterError<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::AttributeNotFilterable { attribute, filterable } => write!(
-                f,
-                "Attribute `{}` is not filterable. Available filterable attributes are: `{}`.",
-                attribute,
-                filterable,
-            ),
+            Self::AttributeNotFilterable { attribute, filterable, suggestion } => {
+                write!(
+                    f,
+                    "Attribute `{}` is not filterable. Available filterable attributes are: `{}`.",
+                    attribute,
+                    filterable,
+                )?;
+                match suggestion {
+                    Some(suggestion) => write!(f, " Did you mean `{}`?", suggestion),
+                    None => Ok(()),
+                }
+            }
             Self::TooDeep => write!(f,
                 "Too many filter conditions, can't process more than {} filters.",
                 MAX_FILTER_DEPTH
@@ -57,16 +70,34 @@ impl<'a> Display for FilterError<'a> {
             Self::BadGeo(keyword) => write!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the _geoRadius(latitude, longitude, distance) built-in rule to filter on _geo field coordinates.", keyword),
             Self::BadGeoLat(lat) => write!(f, "Bad latitude `{}`. Latitude must be contained between -90 and 90 degrees. ", lat),
             Self::BadGeoLng(lng) => write!(f, "Bad longitude `{}`. Longitude must be contained between -180 and 180 degrees. ", lng),
+            Self::GeoPolygonTooLarge => write!(f, "The `_geoPolygon` vertices must fit in a hemisphere, they must all be less than 90 degrees away from their center."),
         }
     }
 }
 
+impl<'a> FilterError<'a> {
+    /// Lists the filterable fields in a stable order and suggests the closest one.
+    fn not_filterable(attribute: &'a str, filterable_fields: HashSet<String>) -> Self {
+        let mut filterable: Vec<_> = filterable_fields.into_iter().collect();
+        filterable.sort_unstable();
+        let suggestion = did_you_mean(attribute, filterable.iter().map(String::as_str));
+        let suggestion = suggestion.map(String::from);
+        Self::AttributeNotFilterable { attribute, filterable: filterable.join(" "), suggestion }
+    }
+}
+
 impl<'a> From<FPError<'a>> for Error {
     fn from(error: FPError<'a>) -> Self {
         Self::UserError(UserError::InvalidFilter(error.to_string()))
     }
 }
 
+impl<'a> Display for Filter<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.condition, f)
+    }
+}
+
 impl<'a> From<Filter<'a>> for FilterCondition<'a> {
     fn from(f: Filter<'a>) -> Self {
         f.condition
@@ -143,6 +174,24 @@ impl<'a> Filter<'a> {
 }
 
 impl<'a> Filter<'a> {
+    /// Returns the base point of the first `_geoRadius` rule of the filter, if any.
+    pub(crate) fn geo_point(&self) -> Option<[f64; 2]> {
+        fn geo_point(condition: &FilterCondition) -> Option<[f64; 2]> {
+            match condition {
+                FilterCondition::GeoLowerThan { point, .. }
+                | FilterCondition::GeoGreaterThan { point, .. } => {
+                    Some([point[0].parse().ok()?, point[1].parse().ok()?])
+                }
+                FilterCondition::Or(lhs, rhs) | FilterCondition::And(lhs, rhs) => {
+                    geo_point(lhs).or_else(|| geo_point(rhs))
+                }
+                _ => None,
+            }
+        }
+
+        geo_point(&self.condition)
+    }
+
     /// Aggregates the documents ids that are part of the specified range automatically
     /// going deeper through the levels.
     fn explore_facet_number_levels(
@@ -270,26 +319,24 @@ impl<'a> Filter<'a> {
             Condition::LowerThanOrEqual(val) => (Included(f64::MIN), Included(val.parse()?)),
             Condition::Between { from, to } => (Included(from.parse()?), Included(to.parse()?)),
             Condition::Equal(val) => {
+                // The values of the hierarchical facets are stored with level-aware keys.
+                let normalized = if index.hierarchical_facets_ids(rtxn)?.contains(&field_id) {
+                    hierarchy_level_key(val).unwrap_or_default()
+                } else {
+                    let normalization = index.facet_normalization_ids(rtxn)?;
+                    normalization.get(&field_id).copied().unwrap_or_default().normalize(val)
+                };
                 let (_original_value, string_docids) =
-                    strings_db.get(rtxn, &(field_id, &val.to_lowercase()))?.unwrap_or_default();
+                    strings_db.get(rtxn, &(field_id, &normalized))?.unwrap_or_default();
                 let number = val.parse::<f64>().ok();
+                // The level 0 keys are bounded by the same value on both sides, we can
+                // directly fetch the entry of the number instead of exploring a range.
                 let number_docids = match number {
-                    Some(n) => {
-                        let n = Included(n);
-                        let mut output = RoaringBitmap::new();
-                        Self::explore_facet_number_levels(
-                            rtxn,
-                            numbers_db,
-                            field_id,
-                            0,
-                            n,
-                            n,
-                            &mut output,
-                        )?;
-                        output
-                    }
+                    Some(n) => numbers_db.get(rtxn, &(field_id, 0, n, n))?.unwrap_or_default(),
                     None => RoaringBitmap::new(),
                 };
+                let number_docids =
+                    Self::refine_unsafe_integers(rtxn, index, field_id, operator, number_docids)?;
                 return Ok(string_docids | number_docids);
             }
             Condition::NotEqual(val) => {
@@ -308,6 +355,19 @@ impl<'a> Filter<'a> {
             }
         };
 
+        let output = Self::number_range_docids(rtxn, numbers_db, field_id, left, right)?;
+        Self::refine_unsafe_integers(rtxn, index, field_id, operator, output)
+    }
+
+    /// Returns the documents ids of the numbers of the field in the given range,
+    /// exploring the facet levels from the biggest one of the field.
+    pub(crate) fn number_range_docids(
+        rtxn: &heed::RoTxn,
+        numbers_db: heed::Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+        field_id: FieldId,
+        left: Bound<f64>,
+        right: Bound<f64>,
+    ) -> Result<RoaringBitmap> {
         // Ask for the biggest value that can exist for this specific field, if it exists
         // that's fine if it don't, the value just before will be returned instead.
         let biggest_level = numbers_db
@@ -315,25 +375,127 @@ impl<'a> Filter<'a> {
             .get_lower_than_or_equal_to(rtxn, &(field_id, u8::MAX, f64::MAX, f64::MAX))?
             .and_then(|((id, level, _, _), _)| if id == field_id { Some(level) } else { None });
 
-        match biggest_level {
-            Some(level) => {
-                let mut output = RoaringBitmap::new();
-                Self::explore_facet_number_levels(
-                    rtxn,
-                    numbers_db,
-                    field_id,
-                    level,
-                    left,
-                    right,
-                    &mut output,
-                )?;
-                Ok(output)
+        let mut output = RoaringBitmap::new();
+        if let Some(level) = biggest_level {
+            Self::explore_facet_number_levels(
+                rtxn,
+                numbers_db,
+                field_id,
+                level,
+                left,
+                right,
+                &mut output,
+            )?;
+        }
+
+        Ok(output)
+    }
+
+    /// Compares exactly the integers that were rounded to the same facet number
+    /// as one of the values of the operator, and fixes the documents ids it matched.
+    fn refine_unsafe_integers(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field_id: FieldId,
+        operator: &Condition<'a>,
+        mut output: RoaringBitmap,
+    ) -> Result<RoaringBitmap> {
+        let values = match operator {
+            Condition::GreaterThan(val)
+            | Condition::GreaterThanOrEqual(val)
+            | Condition::Equal(val)
+            | Condition::LowerThan(val)
+            | Condition::LowerThanOrEqual(val) => vec![val],
+            Condition::Between { from, to } => vec![from, to],
+            Condition::NotEqual(_) => return Ok(output),
+        };
+
+        for val in values {
+            let value = match val.parse::<f64>() {
+                Ok(value) if is_unsafe_integer(value) => value,
+                _ => continue,
+            };
+            let docids = facet_number_docids(rtxn, index, field_id, value)?;
+            if let Some(groups) = exact_integer_groups(rtxn, index, field_id, value, &docids)? {
+                output -= docids;
+                for (integer, docids) in groups {
+                    if integer_matches_condition(integer, operator) {
+                        output |= docids;
+                    }
+                }
             }
-            None => Ok(RoaringBitmap::new()),
         }
+
+        Ok(output)
     }
 
+    /// Returns the documents matching the filter, the results of the filter and of its
+    /// sub-filters are cached by their normalized expression if the index has a filter cache.
     pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        let cache = match &index.filter_cache {
+            Some(cache) => cache,
+            None => return self.evaluate_uncached(rtxn, index),
+        };
+
+        let version = index.updated_at(rtxn)?;
+        let expression = self.to_string();
+        match cache.get(version, &expression) {
+            Some(docids) => Ok(docids),
+            None => {
+                let docids = self.evaluate_uncached(rtxn, index)?;
+                cache.insert(version, expression, docids.clone());
+                Ok(docids)
+            }
+        }
+    }
+
+    /// Returns the documents matching the filter and the documents matched and
+    /// pruned by each of its branches.
+    pub fn explain(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, FilterExplanation)> {
+        let (docids, mut explanation) = self.explain_branches(rtxn, index)?;
+        explanation.pruned = (index.documents_ids(rtxn)? - &docids).len();
+        Ok((docids, explanation))
+    }
+
+    fn explain_branches(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<(RoaringBitmap, FilterExplanation)> {
+        let (docids, branches) = match &self.condition {
+            FilterCondition::Or(lhs, rhs) => {
+                let (lhs, lhs_explanation) =
+                    Self::explain_branches(&(lhs.as_ref().clone()).into(), rtxn, index)?;
+                let (rhs, rhs_explanation) =
+                    Self::explain_branches(&(rhs.as_ref().clone()).into(), rtxn, index)?;
+                (lhs | rhs, vec![lhs_explanation, rhs_explanation])
+            }
+            FilterCondition::And(lhs, rhs) => {
+                let (lhs, mut lhs_explanation) =
+                    Self::explain_branches(&(lhs.as_ref().clone()).into(), rtxn, index)?;
+                let (rhs, mut rhs_explanation) =
+                    Self::explain_branches(&(rhs.as_ref().clone()).into(), rtxn, index)?;
+                lhs_explanation.pruned = (&rhs - &lhs).len();
+                rhs_explanation.pruned = (&lhs - &rhs).len();
+                (lhs & rhs, vec![lhs_explanation, rhs_explanation])
+            }
+            _ => (self.evaluate(rtxn, index)?, Vec::new()),
+        };
+
+        let explanation = FilterExplanation {
+            expression: self.to_string(),
+            candidates: docids.len(),
+            pruned: 0,
+            branches,
+        };
+        Ok((docids, explanation))
+    }
+
+    fn evaluate_uncached(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
         let numbers_db = index.facet_id_f64_docids;
         let strings_db = index.facet_id_string_docids;
 
@@ -359,15 +521,10 @@ impl<'a> Filter<'a> {
                             return Err(fid.as_external_error(FilterError::Reserved(attribute)))?;
                         }
                         attribute => {
-                            return Err(fid.as_external_error(
-                                FilterError::AttributeNotFilterable {
-                                    attribute,
-                                    filterable: filterable_fields
-                                        .into_iter()
-                                        .collect::<Vec<_>>()
-                                        .join(" "),
-                                },
-                            ))?;
+                            return Err(fid.as_external_error(FilterError::not_filterable(
+                                attribute,
+                                filterable_fields,
+                            )))?;
                         }
                     }
                 }
@@ -414,10 +571,10 @@ impl<'a> Filter<'a> {
 
                     Ok(result)
                 } else {
-                    return Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: "_geo",
-                        filterable: filterable_fields.into_iter().collect::<Vec<_>>().join(" "),
-                    }))?;
+                    return Err(point[0].as_external_error(FilterError::not_filterable(
+                        "_geo",
+                        filterable_fields,
+                    )))?;
                 }
             }
             FilterCondition::GeoGreaterThan { point, radius } => {
@@ -430,16 +587,143 @@ impl<'a> Filter<'a> {
                 let geo_faceted_doc_ids = index.geo_faceted_documents_ids(rtxn)?;
                 Ok(geo_faceted_doc_ids - result)
             }
+            FilterCondition::GeoInsidePolygon { points } => {
+                let filterable_fields = index.filterable_fields(rtxn)?;
+                if filterable_fields.contains("_geo") {
+                    let mut polygon = Vec::with_capacity(points.len());
+                    for [lat, lng] in points {
+                        let point: [f64; 2] = [lat.parse()?, lng.parse()?];
+                        if !(-90.0..=90.0).contains(&point[0]) {
+                            return Err(lat.as_external_error(FilterError::BadGeoLat(point[0])))?;
+                        }
+                        if !(-180.0..=180.0).contains(&point[1]) {
+                            return Err(lng.as_external_error(FilterError::BadGeoLng(point[1])))?;
+                        }
+                        polygon.push(point);
+                    }
+
+                    // The edges of the polygon are great-circle arcs, when its vertices fit in
+                    // a spherical cap smaller than a hemisphere the whole polygon is inside the
+                    // cap, we only test the points of the cap centered on the vertices centroid.
+                    let vertices: Vec<_> = polygon.iter().map(lat_lng_to_xyz).collect();
+                    let sum = vertices
+                        .iter()
+                        .fold([0.0; 3], |[x, y, z], [vx, vy, vz]| [x + vx, y + vy, z + vz]);
+                    let norm = sum.iter().map(|c| c * c).sum::<f64>().sqrt();
+                    let centroid = sum.map(|c| c / norm);
+                    let fits_in_hemisphere = norm > f64::EPSILON
+                        && vertices.iter().all(|vertex| dot(&centroid, vertex) > 0.0);
+                    if !fits_in_hemisphere {
+                        return Err(
+                            points[0][0].as_external_error(FilterError::GeoPolygonTooLarge)
+                        )?;
+                    }
+
+                    let rtree = match index.geo_rtree(rtxn)? {
+                        Some(rtree) => rtree,
+                        None => return Ok(RoaringBitmap::new()),
+                    };
+
+                    let center = xyz_to_lat_lng(&centroid);
+                    let radius = polygon
+                        .iter()
+                        .map(|point| distance_between_two_points(&center, point))
+                        .fold(0.0, f64::max);
+
+                    let result = rtree
+                        .nearest_neighbor_iter(&lat_lng_to_xyz(&center))
+                        .take_while(|point| {
+                            distance_between_two_points(&center, &point.data.1) <= radius
+                        })
+                        .filter(|point| point_in_polygon(&point.data.1, &vertices))
+                        .map(|point| point.data.0)
+                        .collect();
+
+                    Ok(result)
+                } else {
+                    return Err(points[0][0].as_external_error(FilterError::not_filterable(
+                        "_geo",
+                        filterable_fields,
+                    )))?;
+                }
+            }
+            FilterCondition::GeoOutsidePolygon { points } => {
+                let result = Self::evaluate(
+                    &FilterCondition::GeoInsidePolygon { points: points.clone() }.into(),
+                    rtxn,
+                    index,
+                )?;
+                let geo_faceted_doc_ids = index.geo_faceted_documents_ids(rtxn)?;
+                Ok(geo_faceted_doc_ids - result)
+            }
         }
     }
 }
 
+/// Returns whether the `[lat, lng]` point is inside the polygon whose vertices are given as
+/// unit vectors and whose edges are great-circle arcs, the polygon must fit in a hemisphere.
+///
+/// Sums the signed angles under which the edges are seen from the point, the polygon
+/// winds once around the point when it is inside and not at all when it is outside.
+fn point_in_polygon(point: &[f64; 2], vertices: &[[f64; 3]]) -> bool {
+    let p = lat_lng_to_xyz(point);
+    let mut winding = 0.0;
+    let mut previous = &vertices[vertices.len() - 1];
+    for vertex in vertices {
+        let (a, b) = (previous, vertex);
+        let sin = dot(&p, &cross(a, b));
+        let cos = dot(a, b) - dot(&p, a) * dot(&p, b);
+        winding += sin.atan2(cos);
+        previous = vertex;
+    }
+    winding.abs() > std::f64::consts::PI
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Converts a unit vector back into a `[lat, lng]` point, see `lat_lng_to_xyz`.
+fn xyz_to_lat_lng(&[x, y, z]: &[f64; 3]) -> [f64; 2] {
+    [z.asin().to_degrees(), y.atan2(x).to_degrees()]
+}
+
 impl<'a> From<FilterCondition<'a>> for Filter<'a> {
     fn from(fc: FilterCondition<'a>) -> Self {
         Self { condition: fc }
     }
 }
 
+/// Compares an integer with the value of a filter without rounding it to a float if possible.
+fn compare_integer(integer: i128, value: &str) -> Option<Ordering> {
+    match value.parse::<i128>() {
+        Ok(value) => Some(integer.cmp(&value)),
+        Err(_) => (integer as f64).partial_cmp(&value.parse::<f64>().ok()?),
+    }
+}
+
+fn integer_matches_condition(integer: i128, condition: &Condition) -> bool {
+    use Ordering::{Equal, Greater, Less};
+
+    let compare = |value: &Token| compare_integer(integer, value);
+    match condition {
+        Condition::GreaterThan(val) => compare(val) == Some(Greater),
+        Condition::GreaterThanOrEqual(val) => matches!(compare(val), Some(Greater | Equal)),
+        Condition::Equal(val) => compare(val) == Some(Equal),
+        Condition::NotEqual(val) => compare(val) != Some(Equal),
+        Condition::LowerThan(val) => compare(val) == Some(Less),
+        Condition::LowerThanOrEqual(val) => matches!(compare(val), Some(Less | Equal)),
+        Condition::Between { from, to } => {
+            matches!(compare(from), Some(Greater | Equal))
+                && matches!(compare(to), Some(Less | Equal))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fmt::Write;
@@ -450,7 +734,8 @@ mod tests {
     use maplit::hashset;
 
     use super::*;
-    use crate::update::{IndexerConfig, Settings};
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
     use crate::Index;
 
     #[test]
@@ -480,6 +765,45 @@ mod tests {
         assert!(bitmap.is_empty());
     }
 
+    #[test]
+    fn filter_cache() {
+        let mut index = TempIndex::new();
+        index.set_filter_cache_capacity(10);
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("tenant"), S("color") });
+            })
+            .unwrap();
+        index
+            .add_documents(serde_json::json!([
+                { "id": 0, "tenant": "a", "color": "red" },
+                { "id": 1, "tenant": "a", "color": "blue" },
+                { "id": 2, "tenant": "b", "color": "red" }
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("tenant = a AND color = red").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap().iter().collect::<Vec<_>>(), vec![0]);
+        // The filter and its two sub-filters are cached.
+        assert_eq!(index.filter_cache.as_ref().unwrap().len(), 3);
+
+        // The expressions are normalized before being cached.
+        let filter = Filter::from_str("tenant=\"a\"").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap().iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(index.filter_cache.as_ref().unwrap().len(), 3);
+        drop(rtxn);
+
+        // The cached documents are dropped when the index is updated.
+        index
+            .add_documents(serde_json::json!([{ "id": 3, "tenant": "a", "color": "red" }]))
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(bitmap.iter().collect::<Vec<_>>(), vec![0, 1, 3]);
+        assert_eq!(index.filter_cache.as_ref().unwrap().len(), 1);
+    }
+
     #[test]
     fn from_array() {
         // Simple array with Left
@@ -586,6 +910,13 @@ mod tests {
         assert!(error.to_string().starts_with(
             "Attribute `name` is not filterable. Available filterable attributes are: `title`."
         ));
+
+        let filter = Filter::from_str("Titel = 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Attribute `Titel` is not filterable. Available filterable attributes are: `title`. \
+Did you mean `title`?"
+        ));
     }
 
     #[test]
@@ -643,6 +974,113 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn geo_polygon() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("_geo") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 48.8566, "lng": 2.3522 } },   // Paris
+            { "id": 1, "_geo": { "lat": 48.8049, "lng": 2.1204 } },   // Versailles
+            { "id": 2, "_geo": { "lat": 45.7640, "lng": 4.8357 } },   // Lyon
+            { "id": 3, "_geo": { "lat": 50.6292, "lng": 3.0573 } },   // Lille
+            { "id": 4 },
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // a triangle around Paris and Versailles
+        let filter = Filter::from_str("_geoPolygon([[48.7, 2.0], [49.2, 2.4], [48.7, 2.8]])")
+            .unwrap()
+            .unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result.iter().collect::<Vec<_>>(), vec![0, 1]);
+
+        let filter = Filter::from_str("NOT _geoPolygon([[48.7, 2.0], [49.2, 2.4], [48.7, 2.8]])")
+            .unwrap()
+            .unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result.iter().collect::<Vec<_>>(), vec![2, 3]);
+
+        let filter = Filter::from_str("_geoPolygon([[48.7, 2.0], [95, 2.4], [48.7, 2.8]])")
+            .unwrap()
+            .unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Bad latitude `95`. Latitude must be contained between -90 and 90 degrees."
+        ));
+    }
+
+    #[test]
+    fn geo_polygon_on_the_sphere() {
+        let index = TempIndex::new();
+
+        let config = IndexerConfig::default();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("_geo") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 0.0, "lng": 179.5 } },
+            { "id": 1, "_geo": { "lat": 0.0, "lng": -179.5 } },
+            { "id": 2, "_geo": { "lat": 5.0, "lng": 175.0 } },
+            { "id": 3, "_geo": { "lat": 0.0, "lng": 0.0 } },
+            { "id": 4, "_geo": { "lat": 89.0, "lng": 30.0 } },
+            { "id": 5, "_geo": { "lat": 85.0, "lng": -135.0 } },
+            { "id": 6, "_geo": { "lat": 81.0, "lng": 45.0 } },
+            { "id": 7, "_geo": { "lat": 75.0, "lng": 0.0 } },
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the polygon crosses the antimeridian, its edges are the shortest arcs.
+        let filter =
+            Filter::from_str("_geoPolygon([[10, 170], [10, -170], [-10, -170], [-10, 170]])")
+                .unwrap()
+                .unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result.iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        // the polygon surrounds the north pole, its edges between the vertices at
+        // 80 degrees of latitude bulge towards the pole up to 82.9 degrees.
+        let filter = Filter::from_str("_geoPolygon([[80, 0], [80, 90], [80, 180], [80, -90]])")
+            .unwrap()
+            .unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result.iter().collect::<Vec<_>>(), vec![4, 5]);
+
+        // the vertices don't fit in a hemisphere.
+        let filter =
+            Filter::from_str("_geoPolygon([[0, 0], [0, 120], [0, -120]])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The `_geoPolygon` vertices must fit in a hemisphere"));
+    }
+
     #[test]
     fn filter_depth() {
         // generates a big (2 MiB) filter with too much of ORs.
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use roaring::RoaringBitmap;
+
+use crate::error::UserError;
+use crate::{Index, Result};
+
+/// The maximum precision of the geohashes, the cells are then about 3.7cm wide.
+pub const MAX_GEOHASH_PRECISION: usize = 12;
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// The documents whose `_geo` point is in a geohash cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoCell {
+    pub count: u64,
+    /// The average latitude and longitude of the points in the cell.
+    pub centroid: [f64; 2],
+}
+
+pub struct GeoClusters<'a> {
+    precision: usize,
+    candidates: Option<RoaringBitmap>,
+    rtxn: &'a heed::RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> GeoClusters<'a> {
+    pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index, precision: usize) -> GeoClusters<'a> {
+        GeoClusters { precision, candidates: None, rtxn, index }
+    }
+
+    pub fn candidates(&mut self, candidates: RoaringBitmap) -> &mut Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    /// Returns the documents, among the candidates if any, in each geohash
+    /// cell of the precision, keyed by the geohash of the cell.
+    pub fn execute(&self) -> Result<BTreeMap<String, GeoCell>> {
+        if self.precision == 0 || self.precision > MAX_GEOHASH_PRECISION {
+            return Err(UserError::InvalidGeohashPrecision(self.precision).into());
+        }
+
+        let rtree = match self.index.geo_rtree(self.rtxn)? {
+            Some(rtree) => rtree,
+            None => return Ok(BTreeMap::new()),
+        };
+
+        // We sum the coordinates of the points and divide them once the cells are complete.
+        let mut cells: BTreeMap<String, (u64, [f64; 2])> = BTreeMap::new();
+        for point in rtree.iter() {
+            let (docid, [lat, lng]) = point.data;
+            if self.candidates.as_ref().map_or(false, |candidates| !candidates.contains(docid)) {
+                continue;
+            }
+            let (count, sum) = cells.entry(geohash(lat, lng, self.precision)).or_default();
+            *count += 1;
+            sum[0] += lat;
+            sum[1] += lng;
+        }
+
+        Ok(cells
+            .into_iter()
+            .map(|(hash, (count, [lat, lng]))| {
+                let centroid = [lat / count as f64, lng / count as f64];
+                (hash, GeoCell { count, centroid })
+            })
+            .collect())
+    }
+}
+
+impl fmt::Debug for GeoClusters<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let GeoClusters { precision, candidates, rtxn: _, index: _ } = self;
+
+        f.debug_struct("GeoClusters")
+            .field("precision", precision)
+            .field("candidates", candidates)
+            .finish()
+    }
+}
+
+/// Encodes the point into the geohash of the given number of characters, the bits
+/// alternatively halve the longitude and latitude ranges, starting with the longitude.
+fn geohash(lat: f64, lng: f64, precision: usize) -> String {
+    let mut lat_range = (-90.0, 90.0);
+    let mut lng_range = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+
+    for _ in 0..precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            let (value, range) = if even { (lng, &mut lng_range) } else { (lat, &mut lat_range) };
+            let middle = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= middle {
+                index |= 1;
+                range.0 = middle;
+            } else {
+                range.1 = middle;
+            }
+            even = !even;
+        }
+        hash.push(GEOHASH_ALPHABET[index] as char);
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn encode_geohash() {
+        assert_eq!(geohash(57.64911, 10.40744, 11), "u4pruydqqvj");
+        assert_eq!(geohash(48.8566, 2.3522, 5), "u09tv");
+        assert_eq!(geohash(-33.8688, 151.2093, 4), "r3gx");
+        assert_eq!(geohash(0.0, 0.0, 1), "s");
+    }
+
+    #[test]
+    fn geo_cells() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("_geo") }))
+            .unwrap();
+        index
+            .add_documents(json!([
+                { "id": 0, "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+                { "id": 1, "_geo": { "lat": 48.8606, "lng": 2.3376 } },
+                { "id": 2, "_geo": { "lat": -33.8688, "lng": 151.2093 } },
+                { "id": 3, "name": "nowhere" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let cells = GeoClusters::new(&rtxn, &index, 3).execute().unwrap();
+        assert_eq!(cells.keys().collect::<Vec<_>>(), vec!["r3g", "u09"]);
+        assert_eq!(cells["u09"].count, 2);
+        assert!((cells["u09"].centroid[0] - 48.8586).abs() < 1e-9);
+        assert!((cells["u09"].centroid[1] - 2.3449).abs() < 1e-9);
+        assert_eq!(cells["r3g"], GeoCell { count: 1, centroid: [-33.8688, 151.2093] });
+
+        let cells = GeoClusters::new(&rtxn, &index, 3).candidates((1..4).collect()).execute();
+        let cells = cells.unwrap();
+        assert_eq!(cells["u09"], GeoCell { count: 1, centroid: [48.8606, 2.3376] });
+        assert_eq!(cells.len(), 2);
+
+        assert!(GeoClusters::new(&rtxn, &index, 0).execute().is_err());
+        assert!(GeoClusters::new(&rtxn, &index, 13).execute().is_err());
+    }
+}
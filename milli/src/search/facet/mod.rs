@@ -1,9 +1,15 @@
-pub use self::facet_distribution::FacetDistribution;
+pub use self::facet_distribution::{FacetDistribution, FacetOptions, FacetOrder};
+pub use self::facet_histogram::{FacetHistogram, HistogramBucket, HistogramBuckets};
+pub use self::facet_integer::exact_integer_groups;
 pub use self::facet_number::{FacetNumberIter, FacetNumberRange, FacetNumberRevRange};
 pub use self::facet_string::FacetStringIter;
 pub use self::filter::Filter;
+pub use self::geo_clusters::{GeoCell, GeoClusters, MAX_GEOHASH_PRECISION};
 
 mod facet_distribution;
+mod facet_histogram;
+mod facet_integer;
 mod facet_number;
 mod facet_string;
 mod filter;
+mod geo_clusters;
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+use super::query_tree::{merge_synonyms, typos, PrimitiveQueryPart};
+use super::{word_derivations, WordDerivationsCache};
+use crate::{DocumentId, Index, Result};
+
+/// How a query term matched a document, the first possible kind is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchKind {
+    /// The document contains the term itself.
+    Exact,
+    /// The document contains a word starting with the last term of the query.
+    Prefix,
+    /// The document contains a word at an accepted number of typos from the term.
+    Typo,
+    /// The document contains a synonym of the term.
+    Synonym,
+}
+
+/// A term of the query that matched a document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MatchedTerm {
+    /// The term as it was normalized, the words of a phrase are separated by spaces.
+    pub term: String,
+    pub kind: MatchKind,
+}
+
+/// Returns the terms of the query matched by each of the documents.
+pub(crate) fn matched_terms(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    primitive_query: &[PrimitiveQueryPart],
+    synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
+    authorize_typos: bool,
+    documents_ids: &[DocumentId],
+) -> Result<HashMap<DocumentId, Vec<MatchedTerm>>> {
+    let words_fst = index.words_fst(rtxn)?;
+    let typo_tolerance = index.typo_tolerance(rtxn)?;
+    let mut wdcache = WordDerivationsCache::new();
+
+    let words_docids = |words: &[(String, u8)]| -> Result<RoaringBitmap> {
+        let mut docids = RoaringBitmap::new();
+        for (word, _typo) in words {
+            docids |= index.word_docids.get(rtxn, word)?.unwrap_or_default();
+        }
+        Ok(docids)
+    };
+    let all_words_docids = |words: &[String]| -> Result<RoaringBitmap> {
+        let mut docids: Option<RoaringBitmap> = None;
+        for word in words {
+            let mut word_docids = index.word_docids.get(rtxn, word)?.unwrap_or_default();
+            word_docids |= index.exact_word_docids.get(rtxn, word)?.unwrap_or_default();
+            docids = Some(match docids {
+                Some(docids) => docids & word_docids,
+                None => word_docids,
+            });
+        }
+        Ok(docids.unwrap_or_default())
+    };
+
+    let mut matched_terms: HashMap<_, Vec<_>> = HashMap::new();
+    for part in primitive_query {
+        // The documents matching the term for each kind, in the order they are reported.
+        let (term, kinds) = match part {
+            PrimitiveQueryPart::Word(word, is_prefix) => {
+                let mut exact = index.word_docids.get(rtxn, word)?.unwrap_or_default();
+                // The words of the exact attributes only match the same word of the query.
+                exact |= index.exact_word_docids.get(rtxn, word)?.unwrap_or_default();
+
+                let prefix = if *is_prefix {
+                    let words =
+                        word_derivations(word, true, 0, &words_fst, typo_tolerance, &mut wdcache)?;
+                    words_docids(words)?
+                } else {
+                    RoaringBitmap::new()
+                };
+
+                let max_typo = typos(word.clone(), authorize_typos).typo();
+                let typo = if max_typo > 0 {
+                    let words = word_derivations(
+                        word,
+                        *is_prefix,
+                        max_typo,
+                        &words_fst,
+                        typo_tolerance,
+                        &mut wdcache,
+                    )?;
+                    words_docids(words)?
+                } else {
+                    RoaringBitmap::new()
+                };
+
+                let mut synonym = RoaringBitmap::new();
+                let additional = synonyms.get(std::slice::from_ref(word));
+                let word_synonyms = index.words_synonyms(rtxn, &[word])?;
+                for words in merge_synonyms(word_synonyms, additional).unwrap_or_default() {
+                    synonym |= all_words_docids(&words)?;
+                }
+
+                let kinds = vec![
+                    (MatchKind::Exact, exact),
+                    (MatchKind::Prefix, prefix),
+                    (MatchKind::Typo, typo),
+                    (MatchKind::Synonym, synonym),
+                ];
+                (word.clone(), kinds)
+            }
+            PrimitiveQueryPart::Phrase(words) => {
+                (words.join(" "), vec![(MatchKind::Exact, all_words_docids(words)?)])
+            }
+        };
+
+        for docid in documents_ids {
+            if let Some((kind, _)) = kinds.iter().find(|(_, docids)| docids.contains(*docid)) {
+                let matched_term = MatchedTerm { term: term.clone(), kind: *kind };
+                matched_terms.entry(*docid).or_default().push(matched_term);
+            }
+        }
+    }
+
+    Ok(matched_terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashmap;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig};
+
+    #[test]
+    fn matched_terms_kinds() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "exact", "title": "quick brown fox" },
+            { "id": "typo", "title": "quack brother" },
+            { "id": "synonym", "title": "fast bro" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.query("quick bro");
+        search.synonyms(hashmap! { S("quick") => vec![S("fast")] });
+        search.show_matched_terms(true);
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 3);
+
+        let matched_terms = result.matched_terms.unwrap();
+        let external_ids = index.external_documents_ids(&rtxn).unwrap();
+        let matched = |id: &str| -> Vec<(String, MatchKind)> {
+            let docid = external_ids.get(id).unwrap();
+            matched_terms[&docid].iter().map(|m| (m.term.clone(), m.kind)).collect()
+        };
+        assert_eq!(
+            matched("exact"),
+            vec![(S("quick"), MatchKind::Exact), (S("bro"), MatchKind::Prefix)]
+        );
+        assert_eq!(
+            matched("typo"),
+            vec![(S("quick"), MatchKind::Typo), (S("bro"), MatchKind::Prefix)]
+        );
+        assert_eq!(
+            matched("synonym"),
+            vec![(S("quick"), MatchKind::Synonym), (S("bro"), MatchKind::Exact)]
+        );
+
+        let result = index.search(&rtxn).query("quick bro").execute().unwrap();
+        assert!(result.matched_terms.is_none());
+    }
+}
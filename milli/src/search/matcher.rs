@@ -0,0 +1,137 @@
+use std::cmp::Reverse;
+
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
+
+use super::MatchingWords;
+
+/// The marker written where the text is cropped by default.
+pub const DEFAULT_CROP_MARKER: &str = "…";
+
+/// Crops the values of the documents around the words matching the query.
+pub struct Matcher<'a> {
+    matching_words: &'a MatchingWords,
+    crop_marker: String,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(matching_words: &'a MatchingWords) -> Matcher<'a> {
+        Matcher { matching_words, crop_marker: DEFAULT_CROP_MARKER.to_string() }
+    }
+
+    /// Sets the marker written at the start and at the end of a cropped text,
+    /// where words were removed.
+    pub fn crop_marker<S: Into<String>>(&mut self, marker: S) -> &mut Self {
+        self.crop_marker = marker.into();
+        self
+    }
+
+    /// Returns a window of `crop_length` words of the text, around the part of the text
+    /// where the most words match the query, the text is returned as is if it is shorter.
+    ///
+    /// The window is centered on the matches it contains, the words are the ones of the
+    /// tokenizer and the separators between them are kept as they are in the text.
+    pub fn crop(&self, text: &str, crop_length: usize) -> String {
+        let analyzer = Analyzer::new(AnalyzerConfig::<&[u8]>::default());
+        let analyzed = analyzer.analyze(text);
+        // The bounds of the words in the text and whether they match.
+        let words: Vec<_> = analyzed
+            .tokens()
+            .filter(|token| token.is_word())
+            .map(|token| {
+                let matches = self.matching_words.matching_bytes(&token).is_some();
+                (token.byte_start, token.byte_end, matches)
+            })
+            .collect();
+        if words.len() <= crop_length {
+            return text.to_string();
+        }
+        if crop_length == 0 {
+            return self.crop_marker.clone();
+        }
+
+        let (start, end) = best_window(&words, crop_length);
+        let mut cropped = String::new();
+        if start > 0 {
+            cropped.push_str(&self.crop_marker);
+        }
+        let (byte_start, _, _) = words[start];
+        let (_, byte_end, _) = words[end - 1];
+        cropped.push_str(&text[byte_start..byte_end]);
+        if end < words.len() {
+            cropped.push_str(&self.crop_marker);
+        }
+        cropped
+    }
+}
+
+/// Returns the range of the words of the window with the most matches, the densest one
+/// when several windows have as many, centered on its matches.
+fn best_window(words: &[(usize, usize, bool)], crop_length: usize) -> (usize, usize) {
+    let matches: Vec<_> =
+        words.iter().enumerate().filter(|(_, (_, _, matches))| *matches).map(|(i, _)| i).collect();
+    if matches.is_empty() {
+        return (0, crop_length);
+    }
+
+    // The window starting at each match, the best one holds the most matches
+    // and the ones it holds are the closest to each other.
+    let mut best = None;
+    for (first, &start) in matches.iter().enumerate() {
+        let count = matches[first..].iter().take_while(|&&i| i < start + crop_length).count();
+        let span = matches[first + count - 1] - start + 1;
+        let score = (count, Reverse(span));
+        if best.as_ref().map_or(true, |(best_score, _)| score > *best_score) {
+            best = Some((score, (start, span)));
+        }
+    }
+
+    let (_, (first_match, span)) = best.unwrap();
+    let start = first_match.saturating_sub((crop_length - span) / 2);
+    let start = start.min(words.len() - crop_length);
+    (start, start + crop_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::query_tree::{Operation, Query, QueryKind};
+    use crate::{Normalizer, TypoTolerance};
+
+    fn matching_words(words: &[&str]) -> MatchingWords {
+        let queries = words
+            .iter()
+            .map(|word| {
+                Operation::Query(Query { prefix: false, kind: QueryKind::exact(word.to_string()) })
+            })
+            .collect();
+        let query_tree = Operation::Or(false, vec![Operation::And(queries)]);
+        MatchingWords::from_query_tree(&query_tree, TypoTolerance::default(), Normalizer::default())
+    }
+
+    #[test]
+    fn crop_around_matches() {
+        let matching_words = matching_words(&["split", "world"]);
+        let matcher = Matcher::new(&matching_words);
+
+        let text =
+            "The quick brown fox, who was tired of the world, split the world in two halves.";
+        assert_eq!(matcher.crop(text, 5), "…world, split the world in…");
+        assert_eq!(matcher.crop(text, 3), "…world, split the…");
+        assert_eq!(matcher.crop(text, 100), text);
+
+        // Without any match the start of the text is returned.
+        assert_eq!(matcher.crop("a b c d e", 2), "a b…");
+    }
+
+    #[test]
+    fn crop_densest_matches() {
+        let matching_words = matching_words(&["split", "world"]);
+        let mut matcher = Matcher::new(&matching_words);
+        matcher.crop_marker("[...]");
+
+        // Both windows hold two matches but the second ones are closer.
+        let text = "world one two split three four five world split six seven eight";
+        assert_eq!(matcher.crop(text, 4), "[...]five world split six[...]");
+        assert_eq!(matcher.crop("split the world", 2), "split the[...]");
+    }
+}
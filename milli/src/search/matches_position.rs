@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap};
+
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::MatchingWords;
+use crate::error::{FieldIdMapMissingEntry, InternalError};
+use crate::{DocumentId, Index, Result};
+
+/// The position of a match in a string value of a document, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MatchBounds {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Returns the positions of the matches in the searchable fields of each of the documents.
+///
+/// The fields are keyed by their flattened name, the keys of the nested objects and the
+/// indexes of the arrays are joined with dots, e.g. `comments.3.text`, and the positions
+/// are relative to the string they are in.
+pub(crate) fn matches_position(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    stop_words: Option<&fst::Set<&[u8]>>,
+    matching_words: &MatchingWords,
+    documents_ids: &[DocumentId],
+) -> Result<HashMap<DocumentId, BTreeMap<String, Vec<MatchBounds>>>> {
+    let mut config = AnalyzerConfig::<&[u8]>::default();
+    if let Some(stop_words) = stop_words {
+        config.stop_words(stop_words);
+    }
+    let analyzer = Analyzer::new(config);
+    let fields_ids_map = index.fields_ids_map(rtxn)?;
+    let searchable_fields = index.searchable_fields_ids(rtxn)?;
+
+    let mut matches_position = HashMap::new();
+    for (docid, document) in index.documents(rtxn, documents_ids.iter().copied())? {
+        let mut fields = BTreeMap::new();
+        for (field_id, value) in document.iter() {
+            if searchable_fields.as_ref().map_or(false, |fields| !fields.contains(&field_id)) {
+                continue;
+            }
+            let name = fields_ids_map
+                .name(field_id)
+                .ok_or(FieldIdMapMissingEntry::FieldId { field_id, process: "matches_position" })?;
+            let value: Value = serde_json::from_slice(value).map_err(InternalError::SerdeJson)?;
+            value_matches(&analyzer, matching_words, name.to_string(), &value, &mut fields);
+        }
+        matches_position.insert(docid, fields);
+    }
+
+    Ok(matches_position)
+}
+
+fn value_matches(
+    analyzer: &Analyzer<&[u8]>,
+    matching_words: &MatchingWords,
+    key: String,
+    value: &Value,
+    fields: &mut BTreeMap<String, Vec<MatchBounds>>,
+) {
+    match value {
+        Value::String(string) => {
+            let analyzed = analyzer.analyze(string);
+            let mut start = 0;
+            let mut bounds = Vec::new();
+            for (word, token) in analyzed.reconstruct() {
+                if token.is_word() {
+                    if let Some(length) = matching_words.matching_bytes(&token) {
+                        bounds.push(MatchBounds { start, length });
+                    }
+                }
+                start += word.chars().count();
+            }
+            if !bounds.is_empty() {
+                fields.entry(key).or_default().extend(bounds);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                let key = format!("{}.{}", key, i);
+                value_matches(analyzer, matching_words, key, value, fields);
+            }
+        }
+        Value::Object(object) => {
+            for (name, value) in object {
+                let key = format!("{}.{}", key, name);
+                value_matches(analyzer, matching_words, key, value, fields);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => (),
+    }
+}
+
+/// Wraps the matches of the document with the tags, in place, the matches are
+/// keyed by the flattened names of the strings as returned by the search.
+pub fn highlight_matches(
+    document: &mut Map<String, Value>,
+    matches: &BTreeMap<String, Vec<MatchBounds>>,
+    pre_tag: &str,
+    post_tag: &str,
+) {
+    fn highlight_value(
+        key: String,
+        value: &mut Value,
+        matches: &BTreeMap<String, Vec<MatchBounds>>,
+        tags: (&str, &str),
+    ) {
+        match value {
+            Value::String(string) => {
+                if let Some(bounds) = matches.get(&key) {
+                    *string = highlight_string(string, bounds, tags);
+                }
+            }
+            Value::Array(values) => {
+                for (i, value) in values.iter_mut().enumerate() {
+                    highlight_value(format!("{}.{}", key, i), value, matches, tags);
+                }
+            }
+            Value::Object(object) => {
+                for (name, value) in object {
+                    highlight_value(format!("{}.{}", key, name), value, matches, tags);
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => (),
+        }
+    }
+
+    for (name, value) in document {
+        highlight_value(name.clone(), value, matches, (pre_tag, post_tag));
+    }
+}
+
+fn highlight_string(
+    string: &str,
+    bounds: &[MatchBounds],
+    (pre_tag, post_tag): (&str, &str),
+) -> String {
+    let mut bounds = bounds.to_vec();
+    bounds.sort_unstable_by_key(|bounds| bounds.start);
+
+    let mut output = String::with_capacity(string.len());
+    let mut chars = string.chars();
+    let mut position = 0;
+    for MatchBounds { start, length } in bounds {
+        // The overlapping matches are ignored.
+        if start < position {
+            continue;
+        }
+        output.extend(chars.by_ref().take(start - position));
+        output.push_str(pre_tag);
+        output.extend(chars.by_ref().take(length));
+        output.push_str(post_tag);
+        position = start + length;
+    }
+    output.extend(chars);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
+
+    #[test]
+    fn matches_position_per_field() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let config = IndexerConfig::default();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_searchable_fields(vec![S("title"), S("tags"), S("author")]);
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            {
+                "id": 0,
+                "title": "the quick fox and the quicker dog",
+                "tags": ["fast", "quick"],
+                "author": { "name": "Quickly" },
+                "description": "quick"
+            },
+            { "id": 1, "title": "a lazy dog" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.query("quick");
+        search.show_matches_position(true);
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+
+        let matches_position = result.matches_position.unwrap();
+        let fields = &matches_position[&0];
+        let bounds = |start, length| MatchBounds { start, length };
+        assert_eq!(fields["title"], vec![bounds(4, 5), bounds(22, 5)]);
+        assert_eq!(fields["tags.1"], vec![bounds(0, 5)]);
+        assert_eq!(fields["author.name"], vec![bounds(0, 5)]);
+        // the description is not searchable.
+        assert!(!fields.contains_key("description"));
+
+        let result = index.search(&rtxn).query("quick").execute().unwrap();
+        assert!(result.matches_position.is_none());
+    }
+
+    #[test]
+    fn highlight_nested_matches() {
+        use serde_json::json;
+
+        let index = TempIndex::new();
+        index
+            .add_documents(json!([{
+                "id": 0,
+                "title": "Hello world",
+                "comments": [
+                    { "author": "kevin", "text": "first comment" },
+                    { "author": "hello", "text": "the world is big, hello world" }
+                ]
+            }]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.query("hello world");
+        search.show_matches_position(true);
+        let result = search.execute().unwrap();
+        let fields = &result.matches_position.unwrap()[&0];
+        let bounds = |start, length| MatchBounds { start, length };
+        assert_eq!(fields["comments.1.author"], vec![bounds(0, 5)]);
+        assert_eq!(fields["comments.1.text"], vec![bounds(4, 5), bounds(18, 5), bounds(24, 5)]);
+        assert!(!fields.contains_key("comments.0.text"));
+
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let displayed: Vec<_> = fields_ids_map.ids().collect();
+        let (_, stored) = index.documents(&rtxn, Some(0)).unwrap().remove(0);
+        let mut document = crate::obkv_to_json(&displayed, &fields_ids_map, stored.obkv()).unwrap();
+        highlight_matches(&mut document, fields, "<em>", "</em>");
+        assert_eq!(
+            Value::Object(document),
+            json!({
+                "id": 0,
+                "title": "<em>Hello</em> <em>world</em>",
+                "comments": [
+                    { "author": "kevin", "text": "first comment" },
+                    { "author": "<em>hello</em>", "text": "the <em>world</em> is big, <em>hello</em> <em>world</em>" }
+                ]
+            })
+        );
+    }
+}
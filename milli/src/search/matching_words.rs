@@ -3,10 +3,11 @@ use std::collections::{BTreeMap, HashSet};
 use std::ops::{Index, IndexMut};
 
 use levenshtein_automata::{Distance, DFA};
-use meilisearch_tokenizer::Token;
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig, Token};
 
-use super::build_dfa;
+use super::{build_dfa, TypoTolerance};
 use crate::search::query_tree::{Operation, Query};
+use crate::Normalizer;
 
 type IsPrefix = bool;
 
@@ -15,40 +16,69 @@ type IsPrefix = bool;
 #[derive(Default)]
 pub struct MatchingWords {
     dfas: Vec<(DFA, String, u8, IsPrefix)>,
+    typo_tolerance: TypoTolerance,
+    normalizer: Normalizer,
 }
 
 impl MatchingWords {
-    pub fn from_query_tree(tree: &Operation) -> Self {
+    pub fn from_query_tree(
+        tree: &Operation,
+        typo_tolerance: TypoTolerance,
+        normalizer: Normalizer,
+    ) -> Self {
         // fetch matchable words from the query tree
         let mut dfas: Vec<_> = fetch_queries(tree)
             .into_iter()
             // create DFAs for each word
-            .map(|(w, t, p)| (build_dfa(w, t, p), w.to_string(), t, p))
+            .map(|(w, t, p)| (build_dfa(w, t, p, typo_tolerance), w.to_string(), t, p))
             .collect();
         // Sort word by len in DESC order prioritizing the longuest word,
         // in order to highlight the longuest part of the matched word.
         dfas.sort_unstable_by_key(|(_dfa, query_word, _typo, _is_prefix)| {
             Reverse(query_word.len())
         });
-        Self { dfas }
+        Self { dfas, typo_tolerance, normalizer }
     }
 
     /// Returns the number of matching bytes if the word matches one of the query words.
     pub fn matching_bytes(&self, word_to_highlight: &Token) -> Option<usize> {
+        // The query words are stemmed, the word is matched in its stemmed form
+        // but the whole word is highlighted when it differs from its stem.
+        let word = word_to_highlight.text();
+        let normalized = self.normalizer.normalize(word);
         self.dfas.iter().find_map(|(dfa, query_word, typo, is_prefix)| {
-            match dfa.eval(word_to_highlight.text()) {
-                Distance::Exact(t) if t <= *typo => {
-                    if *is_prefix {
-                        let len = bytes_to_highlight(word_to_highlight.text(), query_word);
+            match dfa.eval(normalized.as_ref()) {
+                Distance::Exact(t)
+                    if t <= *typo && self.typo_tolerance.accepts(query_word, &normalized) =>
+                {
+                    if *is_prefix && normalized == word {
+                        let len = bytes_to_highlight(word, query_word);
                         Some(word_to_highlight.num_chars_from_bytes(len))
                     } else {
-                        Some(word_to_highlight.num_chars_from_bytes(word_to_highlight.text().len()))
+                        Some(word_to_highlight.num_chars_from_bytes(word.len()))
                     }
                 }
                 _otherwise => None,
             }
         })
     }
+
+    /// Returns the byte offset and the length in bytes of the matches in the original text,
+    /// the text is tokenized without stop words.
+    pub fn match_positions(&self, text: &str) -> Vec<(usize, usize)> {
+        let analyzer = Analyzer::new(AnalyzerConfig::<&[u8]>::default());
+        let analyzed = analyzer.analyze(text);
+        analyzed
+            .tokens()
+            .filter(|token| token.is_word())
+            .filter_map(|token| {
+                let chars = self.matching_bytes(&token)?;
+                let original = &text[token.byte_start..token.byte_end];
+                let length = original.char_indices().nth(chars).map_or(original.len(), |(i, _)| i);
+                Some((token.byte_start, length))
+            })
+            .collect()
+    }
 }
 
 /// Lists all words which can be considered as a match for the query tree.
@@ -271,7 +301,11 @@ mod tests {
             ])],
         );
 
-        let matching_words = MatchingWords::from_query_tree(&query_tree);
+        let matching_words = MatchingWords::from_query_tree(
+            &query_tree,
+            TypoTolerance::default(),
+            Normalizer::default(),
+        );
 
         assert_eq!(
             matching_words.matching_bytes(&Token {
@@ -351,4 +385,35 @@ mod tests {
             Some(4)
         );
     }
+
+    #[test]
+    fn match_positions() {
+        let query_tree = Operation::Or(
+            false,
+            vec![Operation::And(vec![
+                Operation::Query(Query {
+                    prefix: true,
+                    kind: QueryKind::exact("split".to_string()),
+                }),
+                Operation::Query(Query {
+                    prefix: true,
+                    kind: QueryKind::tolerant(1, "world".to_string()),
+                }),
+            ])],
+        );
+        let matching_words = MatchingWords::from_query_tree(
+            &query_tree,
+            TypoTolerance::default(),
+            Normalizer::default(),
+        );
+
+        let text = "Ça World, splitted words";
+        let positions = matching_words.match_positions(text);
+        assert_eq!(positions, vec![(4, 5), (11, 5), (20, 4)]);
+        let matches: Vec<_> =
+            positions.iter().map(|&(start, len)| &text[start..start + len]).collect();
+        assert_eq!(matches, vec!["World", "split", "word"]);
+
+        assert!(matching_words.match_positions("nothing to see here").is_empty());
+    }
 }
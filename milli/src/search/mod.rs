@@ -1,10 +1,12 @@
 use std::borrow::Cow;
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
 use std::mem::take;
 use std::result::Result as StdResult;
 use std::str::Utf8Error;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use distinct::{Distinct, DocIter, FacetDistinct, NoopDistinct};
 use fst::{IntoStreamer, Streamer};
@@ -13,24 +15,51 @@ use log::debug;
 use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
 use once_cell::sync::Lazy;
 use roaring::bitmap::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 
-pub use self::facet::{FacetDistribution, FacetNumberIter, Filter};
+pub use self::criteria::CustomCriterion;
+pub use self::explain::{
+    CriterionExplanation, FilterExplanation, SearchExplanation, SynonymsExplanation,
+    TermExplanation,
+};
+pub use self::facet::{
+    FacetDistribution, FacetHistogram, FacetNumberIter, FacetOptions, FacetOrder, Filter, GeoCell,
+    GeoClusters, HistogramBucket, HistogramBuckets, MAX_GEOHASH_PRECISION,
+};
+pub use self::matched_terms::{MatchKind, MatchedTerm};
+pub use self::matcher::{Matcher, DEFAULT_CROP_MARKER};
+pub use self::matches_position::{highlight_matches, MatchBounds};
 pub use self::matching_words::MatchingWords;
+use self::query_rules::{matching_rules_actions, RulesActions};
+pub use self::query_rules::{QueryPattern, QueryRule};
 use self::query_tree::QueryTreeBuilder;
-use crate::error::UserError;
+pub(crate) use self::suggestion::suggest_correction;
+use crate::error::{FieldIdMapMissingEntry, InternalError, UserError};
+use crate::index::db_name;
 use crate::search::criteria::r#final::{Final, FinalResult};
-use crate::{AscDesc, Criterion, DocumentId, Index, Member, Result};
+use crate::{
+    did_you_mean, distance_between_two_points, geo_point_from_value, metrics, AscDesc, Criterion,
+    DocumentId, FieldId, Index, Member, MetricsSink, Normalizer, Result, StemmingLanguage,
+};
 
 // Building these factories is not free.
 static LEVDIST0: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(0, true));
 static LEVDIST1: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(1, true));
 static LEVDIST2: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(2, true));
+static LEVDIST1_NO_TRANSPOSITION: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(1, false));
+static LEVDIST2_NO_TRANSPOSITION: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(2, false));
 
 mod criteria;
 mod distinct;
+mod explain;
 mod facet;
+mod matched_terms;
+mod matcher;
+mod matches_position;
 mod matching_words;
+mod query_rules;
 mod query_tree;
+mod suggestion;
 
 pub struct Search<'a> {
     query: Option<String>,
@@ -42,11 +71,75 @@ pub struct Search<'a> {
     optional_words: bool,
     authorize_typos: bool,
     words_limit: usize,
+    synonyms: HashMap<String, Vec<String>>,
+    query_language: Option<StemmingLanguage>,
+    show_matched_terms: bool,
+    show_matches_position: bool,
+    show_external_ids: bool,
+    skip_documents_fetching: bool,
+    explain: bool,
+    pinned_documents: Vec<String>,
+    boosted_documents: Vec<String>,
+    group_by: Option<(String, usize)>,
+    custom_criteria: HashMap<String, Arc<dyn CustomCriterion>>,
+    facets_distribution: Option<Option<HashSet<String>>>,
+    facet_options: HashMap<String, FacetOptions>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    slow_query_log: Option<(Duration, Arc<dyn Fn(&SlowQuery) + Send + Sync>)>,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
 }
 
 impl<'a> Search<'a> {
+    /// Creates a search with the parameters of the query, parsing its filter.
+    pub fn from_query(
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+        query: &'a SearchQuery,
+    ) -> Result<Search<'a>> {
+        let mut search = Search::new(rtxn, index);
+        search
+            .offset(query.offset)
+            .limit(query.limit)
+            .optional_words(query.optional_words)
+            .authorize_typos(query.authorize_typos)
+            .words_limit(query.words_limit)
+            .synonyms(query.synonyms.clone())
+            .query_language(query.query_language)
+            .pin_documents(&query.pinned_documents)
+            .boost_documents(&query.boosted_documents)
+            .show_external_ids(query.show_external_ids)
+            .skip_documents_fetching(query.skip_documents_fetching)
+            .explain(query.explain);
+
+        if let Some(text) = &query.query {
+            search.query(text.clone());
+        }
+        if let Some(filter) = query.filter.as_deref().map(Filter::from_str).transpose()?.flatten() {
+            search.filter(filter);
+        }
+        if let Some(sort_criteria) = &query.sort_criteria {
+            search.sort_criteria(sort_criteria.clone());
+        }
+        if let Some((field, group_size)) = &query.group_by {
+            search.group_by(field.clone(), *group_size);
+        }
+        match &query.facets_distribution {
+            Some(facets) if facets.iter().any(|facet| facet == "*") => {
+                search.facets_distribution(None);
+            }
+            Some(facets) => {
+                search.facets_distribution(Some(facets.iter().cloned().collect()));
+            }
+            None => (),
+        }
+        for (name, options) in &query.facet_options {
+            search.facet_options(name, options.clone());
+        }
+
+        Ok(search)
+    }
+
     pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index) -> Search<'a> {
         Search {
             query: None,
@@ -57,6 +150,21 @@ impl<'a> Search<'a> {
             optional_words: true,
             authorize_typos: true,
             words_limit: 10,
+            synonyms: HashMap::new(),
+            query_language: None,
+            show_matched_terms: false,
+            show_matches_position: false,
+            show_external_ids: false,
+            skip_documents_fetching: false,
+            explain: false,
+            pinned_documents: Vec::new(),
+            boosted_documents: Vec::new(),
+            group_by: None,
+            custom_criteria: HashMap::new(),
+            facets_distribution: None,
+            facet_options: HashMap::new(),
+            metrics: None,
+            slow_query_log: None,
             rtxn,
             index,
         }
@@ -97,31 +205,256 @@ impl<'a> Search<'a> {
         self
     }
 
+    /// Adds synonyms to the synonyms of the index for this search only,
+    /// they are normalized like the synonyms setting.
+    pub fn synonyms(&mut self, synonyms: HashMap<String, Vec<String>>) -> &mut Search<'a> {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Returns, for each of the returned documents, the query terms they matched and how.
+    /// Stems the words of the query with this language instead of the stemming language
+    /// of the index, for the queries targeting the fields in this language.
+    pub fn query_language(&mut self, language: Option<StemmingLanguage>) -> &mut Search<'a> {
+        self.query_language = language;
+        self
+    }
+
+    pub fn show_matched_terms(&mut self, value: bool) -> &mut Search<'a> {
+        self.show_matched_terms = value;
+        self
+    }
+
+    /// Returns, for each of the returned documents, the positions of the matches in its fields.
+    pub fn show_matches_position(&mut self, value: bool) -> &mut Search<'a> {
+        self.show_matches_position = value;
+        self
+    }
+
+    /// Returns the external ids of the returned documents, in the same order.
+    pub fn show_external_ids(&mut self, value: bool) -> &mut Search<'a> {
+        self.show_external_ids = value;
+        self
+    }
+
+    /// Never reads the whole stored documents, for the callers that only need the ids of the
+    /// returned documents. The geo distances are then not computed and asking for the matches
+    /// positions is an error, the external ids are still read from the primary keys.
+    pub fn skip_documents_fetching(&mut self, value: bool) -> &mut Search<'a> {
+        self.skip_documents_fetching = value;
+        self
+    }
+
+    /// Returns a trace of the search, the query tree and the derivations of its terms,
+    /// the documents matched by each branch of the filters and the size of the buckets
+    /// of each ranking rule, to understand why a document is returned or not.
+    pub fn explain(&mut self, value: bool) -> &mut Search<'a> {
+        self.explain = value;
+        self
+    }
+
+    /// Returns the documents with the given external ids first, in this order, whether they
+    /// match the query or not, the ones that don't match the filter or don't exist are ignored.
+    pub fn pin_documents<S: AsRef<str>>(&mut self, external_ids: &[S]) -> &mut Search<'a> {
+        self.pinned_documents = external_ids.iter().map(|id| id.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Returns the documents with the given external ids that match the search before the
+    /// other ones, in this order and after the pinned documents.
+    pub fn boost_documents<S: AsRef<str>>(&mut self, external_ids: &[S]) -> &mut Search<'a> {
+        self.boosted_documents = external_ids.iter().map(|id| id.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Returns up to `group_size` documents for each value of the field, e.g. the top 3 products
+    /// of each brand, instead of one document for each value of the distinct field of the index.
+    ///
+    /// The field must be faceted and a group size of zero is considered as one.
+    pub fn group_by(&mut self, field: impl Into<String>, group_size: usize) -> &mut Search<'a> {
+        self.group_by = Some((field.into(), group_size.max(1)));
+        self
+    }
+
     pub fn filter(&mut self, condition: Filter<'a>) -> &mut Search<'a> {
         self.filter = Some(condition);
         self
     }
 
+    /// Registers the implementation of the `name:custom` ranking rule.
+    pub fn custom_criterion(
+        &mut self,
+        name: impl Into<String>,
+        criterion: Arc<dyn CustomCriterion>,
+    ) -> &mut Search<'a> {
+        self.custom_criteria.insert(name.into(), criterion);
+        self
+    }
+
+    /// Computes the distribution of the given facets, or of all the filterable fields
+    /// if `None`, over the candidates of the search, in the same read transaction.
+    pub fn facets_distribution(&mut self, facets: Option<HashSet<String>>) -> &mut Search<'a> {
+        self.facets_distribution = Some(facets);
+        self
+    }
+
+    /// Sets the options of the distribution of the given facet, see `facets_distribution`.
+    pub fn facet_options(
+        &mut self,
+        name: impl Into<String>,
+        options: FacetOptions,
+    ) -> &mut Search<'a> {
+        self.facet_options.insert(name.into(), options);
+        self
+    }
+
+    /// Reports the latency and the number of candidates of the search to the given sink.
+    pub fn metrics(&mut self, sink: Arc<dyn MetricsSink>) -> &mut Search<'a> {
+        self.metrics = Some(sink);
+        self
+    }
+
+    /// Calls the callback with a report of the search when it takes longer than the threshold.
+    pub fn slow_query_log(
+        &mut self,
+        threshold: Duration,
+        callback: impl Fn(&SlowQuery) + Send + Sync + 'static,
+    ) -> &mut Search<'a> {
+        self.slow_query_log = Some((threshold, Arc::new(callback)));
+        self
+    }
+
     pub fn execute(&self) -> Result<SearchResult> {
+        let context = SearchContext::new(self.rtxn, self.index)?;
+        self.execute_in(&context)
+    }
+
+    /// Executes the search reusing the data loaded by the context.
+    pub fn execute_in(&self, context: &SearchContext) -> Result<SearchResult> {
+        let before_search = Instant::now();
+
+        // We validate the sort parameters before doing any work.
+        self.check_sort_criteria()?;
+        if self.show_matches_position && self.skip_documents_fetching {
+            return Err(UserError::MatchesPositionWithoutDocuments.into());
+        }
+
+        let PreparedSearch {
+            matching_words,
+            criteria,
+            promoted,
+            filter_duration,
+            filtered_candidates_count,
+            timings,
+            explanation,
+            trace,
+            matched_terms_query,
+        } = self.prepare(context)?;
+
+        let mut result = match self.distinct_field(context)? {
+            None => self.perform_sort(NoopDistinct, matching_words, criteria, promoted)?,
+            Some(Some((fid, group_size))) => {
+                let distinct =
+                    FacetDistinct::with_group_size(fid, group_size, self.index, self.rtxn);
+                self.perform_sort(distinct, matching_words, criteria, promoted)?
+            }
+            Some(None) => SearchResult::default(),
+        };
+
+        if let Some(mut explanation) = explanation {
+            let buckets = trace.map_or_else(Vec::new, |trace| trace.buckets());
+            explanation.criteria = buckets
+                .into_iter()
+                .map(|(name, buckets)| CriterionExplanation { name, buckets })
+                .collect();
+            result.explanation = Some(explanation);
+        }
+
+        if let Some((primitive_query, synonyms)) = matched_terms_query {
+            result.matched_terms = Some(matched_terms::matched_terms(
+                self.index,
+                self.rtxn,
+                &primitive_query,
+                &synonyms,
+                self.authorize_typos,
+                &result.documents_ids,
+            )?);
+        }
+
+        if self.show_external_ids {
+            result.external_ids = Some(self.external_ids(&result.documents_ids)?);
+        }
+
+        if self.show_matches_position {
+            result.matches_position = Some(matches_position::matches_position(
+                self.index,
+                self.rtxn,
+                context.stop_words.as_ref(),
+                &result.matching_words,
+                &result.documents_ids,
+            )?);
+        }
+
+        if let Some(sink) = &self.metrics {
+            let duration = before_search.elapsed().as_secs_f64();
+            sink.increment_counter(metrics::SEARCHES_TOTAL, 1);
+            sink.record_histogram(metrics::SEARCH_DURATION_SECONDS, duration);
+            sink.record_histogram(metrics::SEARCH_CANDIDATES, result.candidates.len() as f64);
+        }
+
+        if let Some((threshold, callback)) = &self.slow_query_log {
+            let duration = before_search.elapsed();
+            if duration >= *threshold {
+                callback(&SlowQuery {
+                    query: self.query.clone(),
+                    filter: self.filter.as_ref().map(ToString::to_string),
+                    duration,
+                    filter_duration,
+                    filtered_candidates: filtered_candidates_count,
+                    candidates: result.candidates.len(),
+                    criteria_timings: timings.map_or_else(Vec::new, |t| t.exclusive_timings()),
+                });
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Applies the query rules of the index, builds the query tree, evaluates
+    /// the filter and builds the ranking rules of the search.
+    fn prepare<'c>(&self, context: &'c SearchContext) -> Result<PreparedSearch<'c>> {
+        let rules_actions = match self.query.as_deref() {
+            Some(query) => matching_rules_actions(&context.query_rules, query),
+            None => RulesActions::default(),
+        };
+        let query = rules_actions.query.as_deref().or_else(|| self.query.as_deref());
+
         // We create the query tree by spliting the query into tokens.
         let before = Instant::now();
-        let (query_tree, primitive_query) = match self.query.as_ref() {
+        // We make sure that the analyzer is aware of the stop words
+        // this ensures that the query builder is able to properly remove them.
+        let mut config = AnalyzerConfig::default();
+        if let Some(ref stop_words) = context.stop_words {
+            config.stop_words(stop_words);
+        }
+        let analyzer = Analyzer::new(config);
+        let mut normalizer = Normalizer::from_index(self.index, self.rtxn)?;
+        if let Some(language) = self.query_language {
+            normalizer = normalizer.with_stemming_language(language);
+        }
+        let synonyms = normalizer.normalize_synonyms(&analyzer, &self.synonyms);
+
+        let (query_tree, primitive_query) = match query {
             Some(query) => {
                 let mut builder = QueryTreeBuilder::new(self.rtxn, self.index);
                 builder.optional_words(self.optional_words);
                 builder.authorize_typos(self.authorize_typos);
                 builder.words_limit(self.words_limit);
-                // We make sure that the analyzer is aware of the stop words
-                // this ensures that the query builder is able to properly remove them.
-                let mut config = AnalyzerConfig::default();
-                let stop_words = self.index.stop_words(self.rtxn)?;
-                if let Some(ref stop_words) = stop_words {
-                    config.stop_words(stop_words);
-                }
-                let analyzer = Analyzer::new(config);
+                builder.query_synonyms(synonyms.clone());
+                builder.query_language(self.query_language);
                 let result = analyzer.analyze(query);
                 let tokens = result.tokens();
-                builder.build(tokens)?.map_or((None, None), |(qt, pq)| (Some(qt), Some(pq)))
+                builder.build(query, tokens)?.map_or((None, None), |(qt, pq)| (Some(qt), Some(pq)))
             }
             None => (None, None),
         };
@@ -130,70 +463,261 @@ impl<'a> Search<'a> {
 
         // We create the original candidates with the facet conditions results.
         let before = Instant::now();
-        let filtered_candidates = match &self.filter {
-            Some(condition) => Some(condition.evaluate(self.rtxn, self.index)?),
+        let mut filters_explanations = Vec::new();
+        let mut evaluate = |condition: &Filter| -> Result<RoaringBitmap> {
+            if self.explain {
+                let (docids, explanation) = condition.explain(self.rtxn, self.index)?;
+                filters_explanations.push(explanation);
+                Ok(docids)
+            } else {
+                condition.evaluate(self.rtxn, self.index)
+            }
+        };
+        let mut filtered_candidates = match &self.filter {
+            Some(condition) => Some(evaluate(condition)?),
             None => None,
         };
 
-        debug!("facet candidates: {:?} took {:.02?}", filtered_candidates, before.elapsed());
+        // The filters forced by the rules restrict the candidates the same way.
+        for expression in &rules_actions.filters {
+            if let Some(condition) = Filter::from_str(expression)? {
+                let docids = evaluate(&condition)?;
+                filtered_candidates = Some(match filtered_candidates {
+                    Some(candidates) => candidates & docids,
+                    None => docids,
+                });
+            }
+        }
+
+        if !rules_actions.hide.is_empty() {
+            let external_documents_ids = self.index.external_documents_ids(self.rtxn)?;
+            let hidden: RoaringBitmap =
+                rules_actions.hide.iter().filter_map(|id| external_documents_ids.get(id)).collect();
+            let candidates = match filtered_candidates {
+                Some(candidates) => candidates,
+                None => self.index.documents_ids(self.rtxn)?,
+            };
+            filtered_candidates = Some(candidates - hidden);
+        }
+
+        let filter_duration = before.elapsed();
+        debug!("facet candidates: {:?} took {:.02?}", filtered_candidates, filter_duration);
+        let filtered_candidates_count = filtered_candidates.as_ref().map(RoaringBitmap::len);
 
         let matching_words = match query_tree.as_ref() {
-            Some(query_tree) => MatchingWords::from_query_tree(&query_tree),
+            Some(query_tree) => {
+                MatchingWords::from_query_tree(&query_tree, context.typo_tolerance, normalizer)
+            }
             None => MatchingWords::default(),
         };
 
-        // We check that we are allowed to use the sort criteria, we check
-        // that they are declared in the sortable fields.
-        if let Some(sort_criteria) = &self.sort_criteria {
-            let sortable_fields = self.index.sortable_fields(self.rtxn)?;
-            for asc_desc in sort_criteria {
-                match asc_desc.member() {
-                    Member::Field(ref field) if !sortable_fields.contains(field) => {
-                        return Err(UserError::InvalidSortableAttribute {
-                            field: field.to_string(),
-                            valid_fields: sortable_fields.into_iter().collect(),
-                        })?
-                    }
-                    Member::Geo(_) if !sortable_fields.contains("_geo") => {
-                        return Err(UserError::InvalidSortableAttribute {
-                            field: "_geo".to_string(),
-                            valid_fields: sortable_fields.into_iter().collect(),
-                        })?
-                    }
-                    _ => (),
-                }
+        // The query terms are kept to find the ones matched by the returned documents.
+        let matched_terms_query = match &primitive_query {
+            Some(primitive_query) if self.show_matched_terms => {
+                Some((primitive_query.clone(), synonyms.clone()))
             }
-        }
+            _ => None,
+        };
 
-        // We check that the sort ranking rule exists and throw an
-        // error if we try to use it and that it doesn't.
-        let sort_ranking_rule_missing = !self.index.criteria(self.rtxn)?.contains(&Criterion::Sort);
-        let empty_sort_criteria = self.sort_criteria.as_ref().map_or(true, |s| s.is_empty());
-        if sort_ranking_rule_missing && !empty_sort_criteria {
-            return Err(UserError::SortRankingRuleMissing.into());
-        }
+        let promoted = self.promoted_documents(
+            context,
+            &rules_actions.pin,
+            query_tree.as_ref(),
+            filtered_candidates.as_ref(),
+        )?;
+
+        let explanation = if self.explain {
+            let mut explanation =
+                SearchExplanation { filters: filters_explanations, ..Default::default() };
+            if let (Some(query_tree), Some(primitive_query)) = (&query_tree, &primitive_query) {
+                let (terms, words_synonyms) = explain::explain_query(
+                    self.index,
+                    self.rtxn,
+                    query_tree,
+                    primitive_query,
+                    &synonyms,
+                )?;
+                explanation.query_tree = Some(format!("{:?}", query_tree));
+                explanation.terms = terms;
+                explanation.synonyms = words_synonyms;
+            }
+            Some(explanation)
+        } else {
+            None
+        };
 
-        let criteria_builder = criteria::CriteriaBuilder::new(self.rtxn, self.index)?;
-        let criteria = criteria_builder.build(
+        // The time spent in the criteria is only measured for the slow query log.
+        let timings = self.slow_query_log.as_ref().map(|_| criteria::CriteriaTimings::default());
+        let trace = explanation.as_ref().map(|_| criteria::CriteriaTrace::default());
+        let criteria = context.criteria_builder.build(
             query_tree,
             primitive_query,
             filtered_candidates,
             self.sort_criteria.clone(),
+            &self.custom_criteria,
+            &synonyms,
+            timings.as_ref(),
+            trace.as_ref(),
         )?;
 
-        match self.index.distinct_field(self.rtxn)? {
-            None => self.perform_sort(NoopDistinct, matching_words, criteria),
-            Some(name) => {
-                let field_ids_map = self.index.fields_ids_map(self.rtxn)?;
-                match field_ids_map.id(name) {
-                    Some(fid) => {
-                        let distinct = FacetDistinct::new(fid, self.index, self.rtxn);
-                        self.perform_sort(distinct, matching_words, criteria)
+        Ok(PreparedSearch {
+            matching_words,
+            criteria,
+            promoted,
+            filter_duration,
+            filtered_candidates_count,
+            timings,
+            explanation,
+            trace,
+            matched_terms_query,
+        })
+    }
+
+    /// Returns the pinned documents, then the ones pinned by the query rules, followed by
+    /// the boosted ones matching the query, ignoring the documents that don't exist or
+    /// don't match the filter.
+    fn promoted_documents(
+        &self,
+        context: &SearchContext,
+        rules_pinned_documents: &[String],
+        query_tree: Option<&query_tree::Operation>,
+        filtered_candidates: Option<&RoaringBitmap>,
+    ) -> Result<Vec<DocumentId>> {
+        if self.pinned_documents.is_empty()
+            && rules_pinned_documents.is_empty()
+            && self.boosted_documents.is_empty()
+        {
+            return Ok(Vec::new());
+        }
+
+        let external_documents_ids = self.index.external_documents_ids(self.rtxn)?;
+        let matching_query = match query_tree {
+            Some(query_tree) if !self.boosted_documents.is_empty() => {
+                let mut wdcache = WordDerivationsCache::new();
+                Some(criteria::resolve_query_tree(
+                    &context.criteria_builder,
+                    query_tree,
+                    &mut wdcache,
+                )?)
+            }
+            _ => None,
+        };
+
+        let mut promoted = Vec::new();
+        let mut seen = RoaringBitmap::new();
+        let pinned =
+            self.pinned_documents.iter().chain(rules_pinned_documents).map(|id| (id, true));
+        let boosted = self.boosted_documents.iter().map(|id| (id, false));
+        for (external_id, pinned) in pinned.chain(boosted) {
+            let docid = match external_documents_ids.get(external_id) {
+                Some(docid) => docid,
+                None => continue,
+            };
+            let matches_filter = filtered_candidates.map_or(true, |f| f.contains(docid));
+            let matches_query =
+                pinned || matching_query.as_ref().map_or(true, |m| m.contains(docid));
+            if matches_filter && matches_query && seen.insert(docid) {
+                promoted.push(docid);
+            }
+        }
+
+        Ok(promoted)
+    }
+
+    /// Returns the ranked documents ids lazily, starting at the offset and ignoring the limit.
+    ///
+    /// The buckets of the ranking rules are only computed when the documents of the
+    /// previous ones have been consumed, the caller stops whenever it has enough documents.
+    pub fn execute_iter<'c>(&'c self, context: &'c SearchContext) -> Result<SearchIter<'c>> {
+        self.check_sort_criteria()?;
+        let PreparedSearch { matching_words, criteria, promoted, .. } = self.prepare(context)?;
+
+        let (distinct, done) = match self.distinct_field(context)? {
+            None => (None, false),
+            Some(Some((fid, group_size))) => {
+                let distinct =
+                    FacetDistinct::with_group_size(fid, group_size, self.index, self.rtxn);
+                (Some(distinct), false)
+            }
+            Some(None) => (None, true),
+        };
+
+        // The promoted documents are the first bucket and are never returned by the criteria.
+        let excluded = promoted.iter().copied().collect();
+        let bucket: Vec<_> = promoted.iter().copied().skip(self.offset).collect();
+        let offset = self.offset.saturating_sub(promoted.len());
+
+        Ok(SearchIter {
+            matching_words,
+            criteria,
+            distinct,
+            excluded,
+            bucket: bucket.into_iter(),
+            offset,
+            done,
+        })
+    }
+
+    /// Returns the field to group the documents by and the size of the groups,
+    /// `Some(None)` when the field is not in the fields ids map.
+    fn distinct_field(&self, context: &SearchContext) -> Result<Option<Option<(FieldId, usize)>>> {
+        let (field, group_size) = match &self.group_by {
+            Some((field, group_size)) => (field, *group_size),
+            None => return Ok(context.distinct_field.map(|fid| fid.map(|fid| (fid, 1)))),
+        };
+
+        let faceted_fields = self.index.faceted_fields(self.rtxn)?;
+        if !faceted_fields.contains(field) {
+            return Err(UserError::InvalidGroupByField {
+                field: field.clone(),
+                valid_fields: faceted_fields.into_iter().collect(),
+            }
+            .into());
+        }
+
+        let fid = self.index.fields_ids_map(self.rtxn)?.id(field);
+        Ok(Some(fid.map(|fid| (fid, group_size))))
+    }
+
+    /// Checks that the sort criteria only use sortable fields and that the
+    /// sort ranking rule is part of the criteria of the index.
+    fn check_sort_criteria(&self) -> Result<()> {
+        let sort_criteria = match &self.sort_criteria {
+            Some(sort_criteria) if !sort_criteria.is_empty() => sort_criteria,
+            _ => return Ok(()),
+        };
+
+        let sortable_fields = self.index.sortable_fields(self.rtxn)?;
+        for asc_desc in sort_criteria {
+            let field = match asc_desc.member() {
+                Member::Field(field) => field.as_str(),
+                Member::Geo(_) => "_geo",
+            };
+
+            if !sortable_fields.contains(field) {
+                let valid_fields: BTreeSet<_> = sortable_fields.into_iter().collect();
+                let suggestion = match asc_desc.member() {
+                    Member::Field(_) => {
+                        did_you_mean(field, valid_fields.iter().map(String::as_str))
                     }
-                    None => Ok(SearchResult::default()),
-                }
+                    Member::Geo(_) => None,
+                };
+                return Err(UserError::InvalidSortableAttribute {
+                    field: field.to_string(),
+                    expression: asc_desc.to_string(),
+                    suggestion: suggestion.map(String::from),
+                    valid_fields,
+                })?;
             }
         }
+
+        // We check that the sort ranking rule exists and throw an
+        // error if we try to use it and that it doesn't.
+        if !self.index.criteria(self.rtxn)?.contains(&Criterion::Sort) {
+            return Err(UserError::SortRankingRuleMissing.into());
+        }
+
+        Ok(())
     }
 
     fn perform_sort<D: Distinct>(
@@ -201,11 +725,15 @@ impl<'a> Search<'a> {
         mut distinct: D,
         matching_words: MatchingWords,
         mut criteria: Final,
+        promoted: Vec<DocumentId>,
     ) -> Result<SearchResult> {
-        let mut offset = self.offset;
-        let mut initial_candidates = RoaringBitmap::new();
-        let mut excluded_candidates = RoaringBitmap::new();
-        let mut documents_ids = Vec::new();
+        // The pinned and boosted documents come before the ones returned by the criteria.
+        let promoted_ids: RoaringBitmap = promoted.iter().copied().collect();
+        let mut documents_ids: Vec<_> =
+            promoted.iter().copied().skip(self.offset).take(self.limit).collect();
+        let mut offset = self.offset.saturating_sub(promoted.len());
+        let mut initial_candidates = promoted_ids.clone();
+        let mut excluded_candidates = promoted_ids.clone();
 
         while let Some(FinalResult { candidates, bucket_candidates, .. }) =
             criteria.next(&excluded_candidates)?
@@ -214,6 +742,7 @@ impl<'a> Search<'a> {
 
             let excluded = take(&mut excluded_candidates);
 
+            let candidates = candidates - &promoted_ids;
             let mut candidates = distinct.distinct(candidates, excluded);
 
             initial_candidates |= bucket_candidates;
@@ -232,7 +761,113 @@ impl<'a> Search<'a> {
             excluded_candidates = candidates.into_excluded();
         }
 
-        Ok(SearchResult { matching_words, candidates: initial_candidates, documents_ids })
+        // The distribution is computed on the final candidates, reusing the transaction.
+        let (facets_distribution, facet_stats) = match &self.facets_distribution {
+            Some(facets) => {
+                let mut distribution = FacetDistribution::new(self.rtxn, self.index);
+                if let Some(facets) = facets {
+                    distribution.facets(facets);
+                }
+                for (name, options) in &self.facet_options {
+                    distribution.facet_options(name, options.clone());
+                }
+                distribution.candidates(initial_candidates.clone());
+                (Some(distribution.execute_ordered()?), Some(distribution.compute_stats()?))
+            }
+            None => (None, None),
+        };
+
+        let geo_distances = match self.geo_base_point() {
+            Some(base_point) if !self.skip_documents_fetching => {
+                Some(self.geo_distances(&base_point, &documents_ids)?)
+            }
+            _ => None,
+        };
+
+        Ok(SearchResult {
+            matching_words,
+            candidates: initial_candidates,
+            documents_ids,
+            facets_distribution,
+            facet_stats,
+            geo_distances,
+            external_ids: None,
+            matched_terms: None,
+            matches_position: None,
+            explanation: None,
+        })
+    }
+
+    /// Returns the external ids of the documents, read from the primary key of each of them,
+    /// only the returned documents are read whatever the size of the index.
+    fn external_ids(&self, documents_ids: &[DocumentId]) -> Result<Vec<String>> {
+        let primary_key = match self.index.primary_key(self.rtxn)? {
+            Some(primary_key) => primary_key,
+            None => return Ok(Vec::new()),
+        };
+        let primary_key_id =
+            self.index.fields_ids_map(self.rtxn)?.id(primary_key).ok_or_else(|| {
+                FieldIdMapMissingEntry::FieldName {
+                    field_name: primary_key.to_string(),
+                    process: "Search::external_ids",
+                }
+            })?;
+
+        let mut external_ids = Vec::with_capacity(documents_ids.len());
+        for result in self.index.iter_documents(self.rtxn, documents_ids.iter().copied()) {
+            let (_, document) = result?;
+            let value =
+                document.get(primary_key_id).ok_or(InternalError::DatabaseMissingEntry {
+                    db_name: db_name::DOCUMENTS,
+                    key: None,
+                })?;
+            // The external ids are the trimmed strings and the numbers written as strings.
+            let external_id =
+                match serde_json::from_slice(value).map_err(InternalError::SerdeJson)? {
+                    serde_json::Value::String(id) => id.trim().to_string(),
+                    value => value.to_string(),
+                };
+            external_ids.push(external_id);
+        }
+
+        Ok(external_ids)
+    }
+
+    /// Returns the point of the first geo sort criterion or, if there is none,
+    /// the point of the first `_geoRadius` rule of the filter.
+    fn geo_base_point(&self) -> Option<[f64; 2]> {
+        let sort_point = self
+            .sort_criteria
+            .iter()
+            .flatten()
+            .find_map(|asc_desc| asc_desc.member().geo_point().copied());
+        sort_point.or_else(|| self.filter.as_ref().and_then(Filter::geo_point))
+    }
+
+    /// Computes the distance in meters between the base point and each of the documents,
+    /// the documents without valid `_geo` coordinates are ignored.
+    fn geo_distances(
+        &self,
+        base_point: &[f64; 2],
+        documents_ids: &[DocumentId],
+    ) -> Result<HashMap<DocumentId, f64>> {
+        let mut distances = HashMap::new();
+        let geo_field_id = match self.index.fields_ids_map(self.rtxn)?.id("_geo") {
+            Some(fid) => fid,
+            None => return Ok(distances),
+        };
+
+        for (docid, document) in self.index.documents(self.rtxn, documents_ids.iter().copied())? {
+            let point: Option<serde_json::Value> = match document.get(geo_field_id) {
+                Some(value) => serde_json::from_slice(value).ok(),
+                None => None,
+            };
+            if let Some(point) = point.as_ref().and_then(geo_point_from_value) {
+                distances.insert(docid, distance_between_two_points(base_point, &point));
+            }
+        }
+
+        Ok(distances)
     }
 }
 
@@ -247,6 +882,21 @@ impl fmt::Debug for Search<'_> {
             optional_words,
             authorize_typos,
             words_limit,
+            synonyms,
+            query_language,
+            show_matched_terms,
+            show_matches_position,
+            show_external_ids,
+            skip_documents_fetching,
+            explain,
+            pinned_documents,
+            boosted_documents,
+            group_by,
+            custom_criteria,
+            facets_distribution,
+            facet_options,
+            metrics,
+            slow_query_log,
             rtxn: _,
             index: _,
         } = self;
@@ -259,16 +909,286 @@ impl fmt::Debug for Search<'_> {
             .field("optional_words", optional_words)
             .field("authorize_typos", authorize_typos)
             .field("words_limit", words_limit)
+            .field("synonyms", synonyms)
+            .field("query_language", query_language)
+            .field("show_matched_terms", show_matched_terms)
+            .field("show_matches_position", show_matches_position)
+            .field("show_external_ids", show_external_ids)
+            .field("skip_documents_fetching", skip_documents_fetching)
+            .field("explain", explain)
+            .field("pinned_documents", pinned_documents)
+            .field("boosted_documents", boosted_documents)
+            .field("group_by", group_by)
+            .field("custom_criteria", &custom_criteria.keys().collect::<Vec<_>>())
+            .field("facets_distribution", facets_distribution)
+            .field("facet_options", facet_options)
+            .field("metrics", metrics)
+            .field("slow_query_log", &slow_query_log.as_ref().map(|(threshold, _)| threshold))
             .finish()
     }
 }
 
 #[derive(Default)]
 pub struct SearchResult {
+    /// The words of the index matching the query, the matches in the documents can be
+    /// found with them without building the query tree again.
     pub matching_words: MatchingWords,
     pub candidates: RoaringBitmap,
     // TODO those documents ids should be associated with their criteria scores.
     pub documents_ids: Vec<DocumentId>,
+    /// The distribution of the requested facets over the candidates, if requested.
+    pub facets_distribution: Option<BTreeMap<String, Vec<(String, u64)>>>,
+    /// The minimum and the maximum number values of the requested facets over
+    /// the candidates, computed with the distribution.
+    pub facet_stats: Option<BTreeMap<String, (f64, f64)>>,
+    /// The distance in meters between the documents and the point of the geo sort,
+    /// or of the `_geoRadius` filter, when one of them is used.
+    pub geo_distances: Option<HashMap<DocumentId, f64>>,
+    /// The external ids of the returned documents, in the same order, when requested.
+    pub external_ids: Option<Vec<String>>,
+    /// The query terms matched by each of the returned documents, when requested.
+    pub matched_terms: Option<HashMap<DocumentId, Vec<MatchedTerm>>>,
+    /// The positions of the matches in the fields of each of the returned documents,
+    /// when requested.
+    pub matches_position: Option<HashMap<DocumentId, BTreeMap<String, Vec<MatchBounds>>>>,
+    /// The trace of the search, when explained.
+    pub explanation: Option<SearchExplanation>,
+}
+
+/// The query tree, the filter and the ranking rules of a search, ready to be executed.
+struct PreparedSearch<'c> {
+    matching_words: MatchingWords,
+    criteria: Final<'c>,
+    /// The pinned and boosted documents, returned before the ranked ones.
+    promoted: Vec<DocumentId>,
+    filter_duration: Duration,
+    filtered_candidates_count: Option<u64>,
+    timings: Option<criteria::CriteriaTimings>,
+    /// The trace of the query and of the filters, completed with the criteria buckets.
+    explanation: Option<SearchExplanation>,
+    trace: Option<criteria::CriteriaTrace>,
+    /// The primitive query and the normalized query synonyms, if the matched terms are shown.
+    matched_terms_query:
+        Option<(Vec<query_tree::PrimitiveQueryPart>, HashMap<Vec<String>, Vec<Vec<String>>>)>,
+}
+
+/// The ranked documents ids of a search, computed bucket by bucket, see `Search::execute_iter`.
+pub struct SearchIter<'c> {
+    matching_words: MatchingWords,
+    criteria: Final<'c>,
+    distinct: Option<FacetDistinct<'c>>,
+    excluded: RoaringBitmap,
+    bucket: std::vec::IntoIter<DocumentId>,
+    offset: usize,
+    done: bool,
+}
+
+impl<'c> SearchIter<'c> {
+    pub fn matching_words(&self) -> &MatchingWords {
+        &self.matching_words
+    }
+
+    /// Fills the current bucket with the documents of the next bucket of the ranking rules,
+    /// returns `false` if there is no more bucket.
+    fn next_bucket(&mut self) -> Result<bool> {
+        let candidates = match self.criteria.next(&self.excluded)? {
+            // The excluded documents contain the promoted ones, already returned.
+            Some(FinalResult { candidates, .. }) => candidates - &self.excluded,
+            None => return Ok(false),
+        };
+
+        let excluded = take(&mut self.excluded);
+        let (mut documents_ids, excluded) = match &mut self.distinct {
+            Some(distinct) => distinct_bucket(distinct, candidates, excluded)?,
+            None => distinct_bucket(&mut NoopDistinct, candidates, excluded)?,
+        };
+        self.excluded = excluded;
+
+        let discarded = self.offset.min(documents_ids.len());
+        documents_ids.drain(..discarded);
+        self.offset -= discarded;
+        self.bucket = documents_ids.into_iter();
+
+        Ok(true)
+    }
+}
+
+impl Iterator for SearchIter<'_> {
+    type Item = Result<DocumentId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(docid) = self.bucket.next() {
+                return Some(Ok(docid));
+            }
+            if self.done {
+                return None;
+            }
+            match self.next_bucket() {
+                Ok(true) => continue,
+                Ok(false) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the distinct documents of the bucket and the updated excluded documents.
+fn distinct_bucket<D: Distinct>(
+    distinct: &mut D,
+    candidates: RoaringBitmap,
+    excluded: RoaringBitmap,
+) -> Result<(Vec<DocumentId>, RoaringBitmap)> {
+    let mut documents_ids = distinct.distinct(candidates, excluded);
+    let bucket = documents_ids.by_ref().collect::<Result<Vec<_>>>()?;
+    Ok((bucket, documents_ids.into_excluded()))
+}
+
+/// The data of an index that is loaded once and shared by the searches of a transaction.
+pub struct SearchContext<'t> {
+    criteria_builder: criteria::CriteriaBuilder<'t>,
+    stop_words: Option<fst::Set<&'t [u8]>>,
+    typo_tolerance: TypoTolerance,
+    query_rules: Vec<QueryRule>,
+    /// The distinct field id, `Some(None)` when the distinct field is not in the fields ids map.
+    distinct_field: Option<Option<FieldId>>,
+}
+
+impl<'t> SearchContext<'t> {
+    pub fn new(rtxn: &'t heed::RoTxn, index: &'t Index) -> Result<SearchContext<'t>> {
+        let distinct_field = match index.distinct_field(rtxn)? {
+            Some(name) => Some(index.fields_ids_map(rtxn)?.id(name)),
+            None => None,
+        };
+
+        Ok(SearchContext {
+            criteria_builder: criteria::CriteriaBuilder::new(rtxn, index)?,
+            stop_words: index.stop_words(rtxn)?,
+            typo_tolerance: index.typo_tolerance(rtxn)?,
+            query_rules: index.query_rules(rtxn)?,
+            distinct_field,
+        })
+    }
+}
+
+/// The parameters of a search as plain data, that can be built on a thread,
+/// logged or cached, and executed on another one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub query: Option<String>,
+    /// The filter expression, parsed when the query is executed.
+    pub filter: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+    pub sort_criteria: Option<Vec<AscDesc>>,
+    pub optional_words: bool,
+    pub authorize_typos: bool,
+    pub words_limit: usize,
+    /// The synonyms added to the synonyms of the index for this query only.
+    pub synonyms: HashMap<String, Vec<String>>,
+    /// The language the query words are stemmed with, see `Search::query_language`.
+    pub query_language: Option<StemmingLanguage>,
+    /// The facets to compute the distribution of, `*` meaning all the filterable fields.
+    pub facets_distribution: Option<Vec<String>>,
+    pub facet_options: HashMap<String, FacetOptions>,
+    /// The external ids of the documents returned first, see `Search::pin_documents`.
+    pub pinned_documents: Vec<String>,
+    /// The external ids of the documents promoted, see `Search::boost_documents`.
+    pub boosted_documents: Vec<String>,
+    /// The field to group the documents by and the size of the groups, see `Search::group_by`.
+    pub group_by: Option<(String, usize)>,
+    /// Whether to return the external ids of the documents, see `Search::show_external_ids`.
+    pub show_external_ids: bool,
+    /// Whether to never read the whole stored documents, see `Search::skip_documents_fetching`,
+    /// the geo distances are not returned and it can't be used with `show_matches_position`.
+    pub skip_documents_fetching: bool,
+    /// Whether to return a trace of the search, see `Search::explain`.
+    pub explain: bool,
+}
+
+impl Default for SearchQuery {
+    fn default() -> SearchQuery {
+        SearchQuery {
+            query: None,
+            filter: None,
+            offset: 0,
+            limit: 20,
+            sort_criteria: None,
+            optional_words: true,
+            authorize_typos: true,
+            words_limit: 10,
+            synonyms: HashMap::new(),
+            query_language: None,
+            facets_distribution: None,
+            facet_options: HashMap::new(),
+            pinned_documents: Vec::new(),
+            boosted_documents: Vec::new(),
+            group_by: None,
+            show_external_ids: false,
+            skip_documents_fetching: false,
+            explain: false,
+        }
+    }
+}
+
+impl SearchQuery {
+    /// Executes the query in the given transaction.
+    pub fn execute(&self, index: &Index, rtxn: &heed::RoTxn) -> Result<SearchResult> {
+        Search::from_query(rtxn, index, self)?.execute()
+    }
+}
+
+/// The report of a search that took longer than the threshold of the slow query log.
+#[derive(Debug, Clone)]
+pub struct SlowQuery {
+    pub query: Option<String>,
+    /// The filter of the search written back as an expression.
+    pub filter: Option<String>,
+    /// The total duration of the search.
+    pub duration: Duration,
+    /// The time spent evaluating the filter.
+    pub filter_duration: Duration,
+    /// The number of documents matching the filter.
+    pub filtered_candidates: Option<u64>,
+    /// The number of documents matching the search.
+    pub candidates: u64,
+    /// The time spent in each ranking rule, excluding the time spent in the previous ones.
+    pub criteria_timings: Vec<(String, Duration)>,
+}
+
+/// The rules deciding which typos are accepted when deriving the query words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TypoTolerance {
+    /// Whether a typo is accepted on the first character of a word, e.g. `fmail` for `gmail`.
+    /// Disabling it reduces the number of derivations and therefore the number of irrelevant
+    /// documents, at the cost of missing the documents matching such typos.
+    pub first_char_typo: bool,
+    /// Whether swapping two adjacent characters counts as a single typo, e.g. `teh` for `the`.
+    /// It counts as two typos otherwise, like with the plain levenshtein distance.
+    pub transpositions: bool,
+    /// Whether the typo criterion also considers the number of documents containing the
+    /// derived words, a word much rarer than the other derivations of the query word, often
+    /// a noise token, then ranks after the common ones even when it has fewer typos.
+    pub frequency_ranking: bool,
+}
+
+impl Default for TypoTolerance {
+    fn default() -> Self {
+        Self { first_char_typo: true, transpositions: true, frequency_ranking: false }
+    }
+}
+
+impl TypoTolerance {
+    /// Returns `true` if the word derived from the query word respects
+    /// the rules that are not expressed by the levenshtein automaton.
+    pub(crate) fn accepts(&self, word: &str, derived_word: &str) -> bool {
+        self.first_char_typo || word.chars().next().map_or(true, |c| derived_word.starts_with(c))
+    }
 }
 
 pub type WordDerivationsCache = HashMap<(String, bool, u8), Vec<(String, u8)>>;
@@ -278,19 +1198,22 @@ pub fn word_derivations<'c>(
     is_prefix: bool,
     max_typo: u8,
     fst: &fst::Set<Cow<[u8]>>,
+    typo_tolerance: TypoTolerance,
     cache: &'c mut WordDerivationsCache,
 ) -> StdResult<&'c [(String, u8)], Utf8Error> {
     match cache.entry((word.to_string(), is_prefix, max_typo)) {
         Entry::Occupied(entry) => Ok(entry.into_mut()),
         Entry::Vacant(entry) => {
             let mut derived_words = Vec::new();
-            let dfa = build_dfa(word, max_typo, is_prefix);
+            let dfa = build_dfa(word, max_typo, is_prefix, typo_tolerance);
             let mut stream = fst.search_with_state(&dfa).into_stream();
 
-            while let Some((word, state)) = stream.next() {
-                let word = std::str::from_utf8(word)?;
-                let distance = dfa.distance(state);
-                derived_words.push((word.to_string(), distance.to_u8()));
+            while let Some((derived_word, state)) = stream.next() {
+                let derived_word = std::str::from_utf8(derived_word)?;
+                if typo_tolerance.accepts(word, derived_word) {
+                    let distance = dfa.distance(state);
+                    derived_words.push((derived_word.to_string(), distance.to_u8()));
+                }
             }
 
             Ok(entry.insert(derived_words))
@@ -298,11 +1221,13 @@ pub fn word_derivations<'c>(
     }
 }
 
-pub fn build_dfa(word: &str, typos: u8, is_prefix: bool) -> DFA {
-    let lev = match typos {
-        0 => &LEVDIST0,
-        1 => &LEVDIST1,
-        _ => &LEVDIST2,
+pub fn build_dfa(word: &str, typos: u8, is_prefix: bool, typo_tolerance: TypoTolerance) -> DFA {
+    let lev = match (typos, typo_tolerance.transpositions) {
+        (0, _) => &LEVDIST0,
+        (1, true) => &LEVDIST1,
+        (1, false) => &LEVDIST1_NO_TRANSPOSITION,
+        (_, true) => &LEVDIST2,
+        (_, false) => &LEVDIST2_NO_TRANSPOSITION,
     };
 
     if is_prefix {
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// The queries a rule applies to, compared word by word and ignoring the case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryPattern {
+    /// The query is exactly these words.
+    Is(String),
+    /// The query contains these words, one after the other.
+    Contains(String),
+}
+
+impl QueryPattern {
+    fn matches(&self, query_words: &[String]) -> bool {
+        match self {
+            QueryPattern::Is(pattern) => normalized_words(pattern) == query_words,
+            QueryPattern::Contains(pattern) => {
+                let pattern = normalized_words(pattern);
+                !pattern.is_empty() && query_words.windows(pattern.len()).any(|w| w == pattern)
+            }
+        }
+    }
+}
+
+/// A rule of the index changing the searches whose query matches its pattern,
+/// it is applied before the search is executed, see `Settings::set_query_rules`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct QueryRule {
+    pub pattern: QueryPattern,
+    /// The external ids of the documents returned first, see `Search::pin_documents`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pin: Vec<String>,
+    /// The external ids of the documents that are never returned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hide: Vec<String>,
+    /// A filter expression the documents must also match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// The query searched instead of the one of the search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace_query: Option<String>,
+}
+
+/// The actions of the rules matching the query of a search, the rules are applied in order,
+/// the first replacement of the query is used and the other actions are combined.
+#[derive(Debug, Default)]
+pub(crate) struct RulesActions {
+    pub query: Option<String>,
+    pub pin: Vec<String>,
+    pub hide: Vec<String>,
+    pub filters: Vec<String>,
+}
+
+pub(crate) fn matching_rules_actions(rules: &[QueryRule], query: &str) -> RulesActions {
+    let query_words = normalized_words(query);
+    let mut actions = RulesActions::default();
+    if query_words.is_empty() {
+        return actions;
+    }
+
+    for rule in rules.iter().filter(|rule| rule.pattern.matches(&query_words)) {
+        if actions.query.is_none() {
+            actions.query = rule.replace_query.clone();
+        }
+        actions.pin.extend(rule.pin.iter().cloned());
+        actions.hide.extend(rule.hide.iter().cloned());
+        actions.filters.extend(rule.filter.iter().cloned());
+    }
+
+    actions
+}
+
+fn normalized_words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_lowercase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+
+    fn rule(pattern: QueryPattern) -> QueryRule {
+        QueryRule { pattern, pin: Vec::new(), hide: Vec::new(), filter: None, replace_query: None }
+    }
+
+    #[test]
+    fn rules_matching_the_query() {
+        let rules = vec![
+            QueryRule { pin: vec![S("1")], ..rule(QueryPattern::Is(S("Red  Shoes"))) },
+            QueryRule {
+                hide: vec![S("2")],
+                replace_query: Some(S("sneakers")),
+                ..rule(QueryPattern::Contains(S("shoes")))
+            },
+            QueryRule {
+                filter: Some(S("color = red")),
+                replace_query: Some(S("boots")),
+                ..rule(QueryPattern::Contains(S("red shoes")))
+            },
+        ];
+
+        let actions = matching_rules_actions(&rules, "red shoes");
+        assert_eq!(actions.query, Some(S("sneakers")));
+        assert_eq!(actions.pin, vec![S("1")]);
+        assert_eq!(actions.hide, vec![S("2")]);
+        assert_eq!(actions.filters, vec![S("color = red")]);
+
+        let actions = matching_rules_actions(&rules, "cheap shoes red");
+        assert_eq!(actions.query, Some(S("sneakers")));
+        assert!(actions.pin.is_empty() && actions.filters.is_empty());
+
+        let actions = matching_rules_actions(&rules, "shoelaces");
+        assert!(actions.query.is_none() && actions.hide.is_empty());
+        assert!(matching_rules_actions(&rules, " ").hide.is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::{cmp, fmt, mem};
 
 use fst::Set;
@@ -7,7 +9,7 @@ use meilisearch_tokenizer::TokenKind;
 use roaring::RoaringBitmap;
 use slice_group_by::GroupBy;
 
-use crate::{Index, Result};
+use crate::{Index, Normalizer, Result, StemmingLanguage, TermsDroppingOrder};
 
 type IsOptionalWord = bool;
 type IsPrefix = bool;
@@ -155,6 +157,19 @@ trait Context {
             None => Ok(None),
         }
     }
+    /// Returns the indexed words that sound like the given word.
+    fn phonetic_words(&self, _word: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+    /// Returns the lowercased version of a query word that kept its case.
+    fn case_folded_word(&self, word: &str) -> Result<String> {
+        Ok(word.to_lowercase())
+    }
+    /// Returns whether the word is one of the exact words of the index,
+    /// that only match without typos nor as a prefix.
+    fn is_exact_word(&self, _word: &str) -> Result<bool> {
+        Ok(false)
+    }
 }
 
 /// The query tree builder is the interface to build a query tree.
@@ -164,6 +179,25 @@ pub struct QueryTreeBuilder<'a> {
     optional_words: bool,
     authorize_typos: bool,
     words_limit: Option<usize>,
+    synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    query_language: Option<StemmingLanguage>,
+}
+
+/// Returns the synonyms of the index merged with the additional synonyms of the words.
+pub(crate) fn merge_synonyms(
+    synonyms: Option<Vec<Vec<String>>>,
+    additional: Option<&Vec<Vec<String>>>,
+) -> Option<Vec<Vec<String>>> {
+    match (synonyms, additional) {
+        (synonyms, None) => synonyms,
+        (synonyms, Some(additional)) => {
+            let mut synonyms = synonyms.unwrap_or_default();
+            synonyms.extend(additional.iter().cloned());
+            synonyms.sort_unstable();
+            synonyms.dedup();
+            Some(synonyms)
+        }
+    }
 }
 
 impl<'a> Context for QueryTreeBuilder<'a> {
@@ -172,19 +206,45 @@ impl<'a> Context for QueryTreeBuilder<'a> {
     }
 
     fn synonyms<S: AsRef<str>>(&self, words: &[S]) -> heed::Result<Option<Vec<Vec<String>>>> {
-        self.index.words_synonyms(self.rtxn, words)
+        let synonyms = self.index.words_synonyms(self.rtxn, words)?;
+        let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
+        Ok(merge_synonyms(synonyms, self.synonyms.get(&words)))
     }
 
     fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>> {
         self.index.word_documents_count(self.rtxn, word)
     }
+
+    #[cfg(feature = "phonetic")]
+    fn phonetic_words(&self, word: &str) -> Result<Vec<String>> {
+        self.index.phonetic_words(self.rtxn, word)
+    }
+
+    fn case_folded_word(&self, word: &str) -> Result<String> {
+        Ok(self.normalizer()?.case_fold(word))
+    }
+
+    fn is_exact_word(&self, word: &str) -> Result<bool> {
+        match self.index.exact_words(self.rtxn)? {
+            Some(exact_words) => Ok(exact_words.contains(word)),
+            None => Ok(false),
+        }
+    }
 }
 
 impl<'a> QueryTreeBuilder<'a> {
     /// Create a `QueryTreeBuilder` from a heed ReadOnly transaction `rtxn`
     /// and an Index `index`.
     pub fn new(rtxn: &'a heed::RoTxn<'a>, index: &'a Index) -> Self {
-        Self { rtxn, index, optional_words: true, authorize_typos: true, words_limit: None }
+        Self {
+            rtxn,
+            index,
+            optional_words: true,
+            authorize_typos: true,
+            words_limit: None,
+            synonyms: HashMap::new(),
+            query_language: None,
+        }
     }
 
     /// if `optional_words` is set to `false` the query tree will be
@@ -214,6 +274,29 @@ impl<'a> QueryTreeBuilder<'a> {
         self
     }
 
+    /// Adds normalized synonyms to the synonyms of the index, for this query tree only.
+    pub fn query_synonyms(
+        &mut self,
+        synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    ) -> &mut Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Stems the query words with this language instead of the stemming language of the index.
+    pub fn query_language(&mut self, language: Option<StemmingLanguage>) -> &mut Self {
+        self.query_language = language;
+        self
+    }
+
+    fn normalizer(&self) -> heed::Result<Normalizer> {
+        let normalizer = Normalizer::from_index(self.index, self.rtxn)?;
+        Ok(match self.query_language {
+            Some(language) => normalizer.with_stemming_language(language),
+            None => normalizer,
+        })
+    }
+
     /// Build the query tree:
     /// - if `optional_words` is set to `false` the query tree will be
     ///   generated forcing all query words to be present in each matching documents
@@ -221,13 +304,23 @@ impl<'a> QueryTreeBuilder<'a> {
     /// - if `authorize_typos` is set to `false` the query tree will be generated
     ///   forcing all query words to match documents without any typo
     ///   (the criterion `typo` will be ignored)
-    pub fn build(&self, query: TokenStream) -> Result<Option<(Operation, PrimitiveQuery)>> {
+    ///
+    /// The `query` tokens must come from the analysis of the `text`.
+    pub fn build(
+        &self,
+        text: &str,
+        query: TokenStream,
+    ) -> Result<Option<(Operation, PrimitiveQuery)>> {
         let stop_words = self.index.stop_words(self.rtxn)?;
-        let primitive_query = create_primitive_query(query, stop_words, self.words_limit);
+        let terms_dropping_order = self.index.terms_dropping_order(self.rtxn)?;
+        let normalizer = self.normalizer()?;
+        let primitive_query =
+            create_primitive_query(query, text, stop_words, &normalizer, self.words_limit);
         if !primitive_query.is_empty() {
             let qt = create_query_tree(
                 self,
                 self.optional_words,
+                terms_dropping_order,
                 self.authorize_typos,
                 &primitive_query,
             )?;
@@ -260,7 +353,27 @@ fn split_best_frequency(ctx: &impl Context, word: &str) -> heed::Result<Option<O
 
 /// Return the `QueryKind` of a word depending on `authorize_typos`
 /// and the provided word length.
-fn typos(word: String, authorize_typos: bool) -> QueryKind {
+/// The maximum number of words that sound alike added as alternatives of a query word.
+const MAX_PHONETIC_ALTERNATIVES: usize = 10;
+
+/// Return the words that sound like the given one, other than itself, as operations ranked
+/// like words containing one typo.
+fn phonetic_alternatives(ctx: &impl Context, word: &str) -> Result<Vec<Operation>> {
+    Ok(ctx
+        .phonetic_words(word)?
+        .into_iter()
+        .filter(|phonetic_word| phonetic_word != word)
+        .take(MAX_PHONETIC_ALTERNATIVES)
+        .map(|phonetic_word| {
+            Operation::Query(Query {
+                prefix: false,
+                kind: QueryKind::exact_with_typo(1, phonetic_word),
+            })
+        })
+        .collect())
+}
+
+pub(crate) fn typos(word: String, authorize_typos: bool) -> QueryKind {
     if authorize_typos {
         match word.chars().count() {
             0..=4 => QueryKind::exact(word),
@@ -297,6 +410,7 @@ fn synonyms(ctx: &impl Context, word: &[&str]) -> heed::Result<Option<Vec<Operat
 fn create_query_tree(
     ctx: &impl Context,
     optional_words: bool,
+    terms_dropping_order: TermsDroppingOrder,
     authorize_typos: bool,
     query: &[PrimitiveQueryPart],
 ) -> Result<Operation> {
@@ -309,13 +423,39 @@ fn create_query_tree(
         match part {
             // 1. try to split word in 2
             // 2. try to fetch synonyms
-            // 3. create an operation containing the word
-            // 4. wrap all in an OR operation
+            // 3. fetch the words that sound alike
+            // 4. create an operation containing the word
+            // 5. wrap all in an OR operation
             PrimitiveQueryPart::Word(word, prefix) => {
-                let mut children = synonyms(ctx, &[&word])?.unwrap_or_default();
+                // Only the words that kept their case contain uppercase letters,
+                // their lowercased version is a fallback ranked like a typo.
+                let folded_word = if word.chars().any(char::is_uppercase) {
+                    Some(ctx.case_folded_word(&word)?)
+                } else {
+                    None
+                };
+                let synonyms_word = folded_word.as_deref().unwrap_or(&word);
+                let mut children = synonyms(ctx, &[synonyms_word])?.unwrap_or_default();
+
+                // The exact words are neither split, nor derived with typos or as a prefix.
+                if ctx.is_exact_word(synonyms_word)? {
+                    if let Some(folded_word) = folded_word {
+                        let kind = QueryKind::exact_with_typo(1, folded_word);
+                        children.push(Operation::Query(Query { prefix: false, kind }));
+                    }
+                    let kind = QueryKind::exact(word);
+                    children.push(Operation::Query(Query { prefix: false, kind }));
+                    return Ok(Operation::or(false, children));
+                }
+
                 if let Some(child) = split_best_frequency(ctx, &word)? {
                     children.push(child);
                 }
+                children.extend(phonetic_alternatives(ctx, &word)?);
+                if let Some(folded_word) = folded_word {
+                    let kind = QueryKind::exact_with_typo(1, folded_word);
+                    children.push(Operation::Query(Query { prefix, kind }));
+                }
                 children
                     .push(Operation::Query(Query { prefix, kind: typos(word, authorize_typos) }));
                 Ok(Operation::or(false, children))
@@ -383,31 +523,48 @@ fn create_query_tree(
         Ok(Operation::and(op_children))
     }
 
-    /// Create a new branch removing the last non-phrase query parts.
+    /// Create a new branch removing the non-phrase query parts in the terms dropping order.
     fn optional_word(
         ctx: &impl Context,
         authorize_typos: bool,
+        terms_dropping_order: TermsDroppingOrder,
         query: PrimitiveQuery,
     ) -> Result<Operation> {
         let number_phrases = query.iter().filter(|p| p.is_phrase()).count();
         let mut operation_children = Vec::new();
 
+        // The positions of the non-phrase query parts in the order they must be removed.
+        let mut dropping_order: Vec<_> =
+            query.iter().enumerate().filter(|(_, p)| !p.is_phrase()).map(|(i, _)| i).collect();
+        match terms_dropping_order {
+            TermsDroppingOrder::Last => dropping_order.reverse(),
+            TermsDroppingOrder::First => (),
+            TermsDroppingOrder::Frequency => {
+                let mut words_counts = Vec::with_capacity(dropping_order.len());
+                for i in dropping_order {
+                    let count = match &query[i] {
+                        PrimitiveQueryPart::Word(word, _) => {
+                            ctx.word_documents_count(word)?.unwrap_or(0)
+                        }
+                        PrimitiveQueryPart::Phrase(_) => 0,
+                    };
+                    words_counts.push((i, count));
+                }
+                // The most frequent words are removed first, the last ones on equality.
+                words_counts.sort_by_key(|&(i, count)| (Reverse(count), Reverse(i)));
+                dropping_order = words_counts.into_iter().map(|(i, _)| i).collect();
+            }
+        }
+
         let start = number_phrases + (number_phrases == 0) as usize;
         for len in start..=query.len() {
-            let mut word_count = len - number_phrases;
+            let word_count = len - number_phrases;
+            let removed = &dropping_order[..dropping_order.len() - word_count];
             let query: Vec<_> = query
                 .iter()
-                .filter(|p| {
-                    if p.is_phrase() {
-                        true
-                    } else if word_count != 0 {
-                        word_count -= 1;
-                        true
-                    } else {
-                        false
-                    }
-                })
-                .cloned()
+                .enumerate()
+                .filter(|(i, p)| p.is_phrase() || !removed.contains(i))
+                .map(|(_, p)| p.clone())
                 .collect();
 
             let ngrams = ngrams(ctx, authorize_typos, &query)?;
@@ -418,7 +575,7 @@ fn create_query_tree(
     }
 
     if optional_words {
-        optional_word(ctx, authorize_typos, query.to_vec())
+        optional_word(ctx, authorize_typos, terms_dropping_order, query.to_vec())
     } else {
         ngrams(ctx, authorize_typos, query)
     }
@@ -446,7 +603,9 @@ impl PrimitiveQueryPart {
 /// the primitive query is an intermediate state to build the query tree.
 fn create_primitive_query(
     query: TokenStream,
+    text: &str,
     stop_words: Option<Set<&[u8]>>,
+    normalizer: &Normalizer,
     words_limit: Option<usize>,
 ) -> PrimitiveQuery {
     let mut primitive_query = Vec::new();
@@ -467,18 +626,23 @@ fn create_primitive_query(
                 // 1. if the word is quoted we push it in a phrase-buffer waiting for the ending quote,
                 // 2. if the word is not the last token of the query and is not a stop_word we push it as a non-prefix word,
                 // 3. if the word is the last token of the query we push it as a prefix word.
+                let original = text.get(token.byte_start..token.byte_end).unwrap_or_default();
+                let token_word = normalizer.token_word(token.word.as_ref(), original);
+                let word = match normalizer.cased_word(&token_word, original) {
+                    Some(cased_word) => cased_word.to_string(),
+                    None => normalizer.normalize(&token_word).into_owned(),
+                };
                 if quoted {
-                    phrase.push(token.word.to_string());
+                    phrase.push(word);
                 } else if peekable.peek().is_some() {
                     if !stop_words
                         .as_ref()
                         .map_or(false, |swords| swords.contains(token.word.as_ref()))
                     {
-                        primitive_query
-                            .push(PrimitiveQueryPart::Word(token.word.to_string(), false));
+                        primitive_query.push(PrimitiveQueryPart::Word(word, false));
                     }
                 } else {
-                    primitive_query.push(PrimitiveQueryPart::Word(token.word.to_string(), true));
+                    primitive_query.push(PrimitiveQueryPart::Word(word, true));
                 }
             }
             TokenKind::Separator(separator_kind) => {
@@ -544,6 +708,7 @@ mod test {
     struct TestContext {
         synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
         postings: HashMap<String, RoaringBitmap>,
+        terms_dropping_order: TermsDroppingOrder,
     }
 
     impl TestContext {
@@ -554,10 +719,17 @@ mod test {
             words_limit: Option<usize>,
             query: TokenStream,
         ) -> Result<Option<(Operation, PrimitiveQuery)>> {
-            let primitive_query = create_primitive_query(query, None, words_limit);
+            // The default normalizer doesn't need the original text of the tokens.
+            let primitive_query =
+                create_primitive_query(query, "", None, &Normalizer::default(), words_limit);
             if !primitive_query.is_empty() {
-                let qt =
-                    create_query_tree(self, optional_words, authorize_typos, &primitive_query)?;
+                let qt = create_query_tree(
+                    self,
+                    optional_words,
+                    self.terms_dropping_order,
+                    authorize_typos,
+                    &primitive_query,
+                )?;
                 Ok(Some((qt, primitive_query)))
             } else {
                 Ok(None)
@@ -630,6 +802,7 @@ mod test {
                     String::from("good")       => random_postings(rng,   1250),
                     String::from("morning")    => random_postings(rng,    125),
                 },
+                terms_dropping_order: TermsDroppingOrder::default(),
             }
         }
     }
@@ -1058,6 +1231,35 @@ mod test {
         assert_eq!(expected, query_tree);
     }
 
+    #[test]
+    fn optional_word_dropping_order() {
+        let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+        let build = |context: &TestContext, optional_words, query| {
+            let result = analyzer.analyze(query);
+            let (query_tree, _) =
+                context.build(optional_words, true, None, result.tokens()).unwrap().unwrap();
+            query_tree
+        };
+
+        // The branch with the least words is the first one.
+        let context =
+            TestContext { terms_dropping_order: TermsDroppingOrder::First, ..Default::default() };
+        let query_tree = build(&context, true, "hey my friend ");
+        let expected = build(&context, false, "friend ");
+        assert!(matches!(query_tree, Operation::Or(true, children) if children[0] == expected));
+
+        // "is" is the most frequent word and "hello" the least frequent one.
+        let context = TestContext {
+            terms_dropping_order: TermsDroppingOrder::Frequency,
+            ..Default::default()
+        };
+        let query_tree = build(&context, true, "world is hello ");
+        let expected_first = build(&context, false, "hello ");
+        let expected_second = build(&context, false, "world hello ");
+        assert!(matches!(query_tree, Operation::Or(true, children)
+            if children[0] == expected_first && children[1] == expected_second));
+    }
+
     #[test]
     fn optional_word_phrase() {
         let query = "\"hey my\"";
@@ -0,0 +1,114 @@
+use std::cmp::Reverse;
+
+use fst::{IntoStreamer, Streamer};
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig, TokenKind};
+
+use super::build_dfa;
+use crate::{Index, Normalizer, Result};
+
+/// Returns the number of typos a query word can be corrected with.
+fn correction_typos(word: &str) -> u8 {
+    match word.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Returns the query where the words that are not in the index are replaced by the closest
+/// indexed words, the most frequent one among the words with the same number of typos.
+///
+/// Returns `None` if all the words are indexed or no indexed word is close enough.
+pub(crate) fn suggest_correction(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    query: &str,
+) -> Result<Option<String>> {
+    let words_fst = index.words_fst(rtxn)?;
+    let typo_tolerance = index.typo_tolerance(rtxn)?;
+    let normalizer = Normalizer::from_index(index, rtxn)?;
+
+    let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+    let analyzed = analyzer.analyze(query);
+
+    let mut suggestion = String::with_capacity(query.len());
+    let mut corrected = false;
+    let mut last_end = 0;
+    for token in analyzed.tokens() {
+        if token.kind != TokenKind::Word {
+            continue;
+        }
+
+        let original = &query[token.byte_start..token.byte_end];
+        let word = normalizer.normalize_token(token.word.as_ref(), original);
+        let typos = correction_typos(&word);
+        if typos == 0 || words_fst.contains(word.as_bytes()) {
+            continue;
+        }
+
+        // We keep the closest words and, among them, the one in the most documents.
+        let mut best: Option<(u8, u64, String)> = None;
+        let dfa = build_dfa(&word, typos, false, typo_tolerance);
+        let mut stream = words_fst.search_with_state(&dfa).into_stream();
+        while let Some((derived_word, state)) = stream.next() {
+            let derived_word = std::str::from_utf8(derived_word)?;
+            if !typo_tolerance.accepts(&word, derived_word) {
+                continue;
+            }
+            let distance = dfa.distance(state).to_u8();
+            let count = index.word_documents_count(rtxn, derived_word)?.unwrap_or(0);
+            let is_better = best.as_ref().map_or(true, |(best_distance, best_count, _)| {
+                (distance, Reverse(count)) < (*best_distance, Reverse(*best_count))
+            });
+            if is_better {
+                best = Some((distance, count, derived_word.to_string()));
+            }
+        }
+
+        if let Some((_, _, correction)) = best {
+            suggestion.push_str(&query[last_end..token.byte_start]);
+            suggestion.push_str(&correction);
+            last_end = token.byte_end;
+            corrected = true;
+        }
+    }
+
+    if corrected {
+        suggestion.push_str(&query[last_end..]);
+        Ok(Some(suggestion))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig};
+
+    #[test]
+    fn did_you_mean() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "title": "the quick brown fox" },
+            { "id": 1, "title": "the quick brown fox jumps" },
+            { "id": 2, "title": "the lazy dog" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        // `fox` and `dog` are both one typo away from `dox`, `fox` is in more documents.
+        let suggestion = suggest_correction(&index, &rtxn, "quikc  bruwn dox").unwrap();
+        assert_eq!(suggestion.as_deref(), Some("quick  brown fox"));
+
+        assert_eq!(suggest_correction(&index, &rtxn, "quick brown").unwrap(), None);
+        assert_eq!(suggest_correction(&index, &rtxn, "zzzzzz").unwrap(), None);
+    }
+}
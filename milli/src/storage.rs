@@ -0,0 +1,333 @@
+//! A key-value adapter over the raw databases of an index, get, put and range operations
+//! on named databases accessed through transactions.
+//!
+//! The index, the search and the updates don't go through it, they still use heed directly.
+//! It is a standalone base to develop and test other backends against the raw databases:
+//! the [`LmdbStorage`] exposes the databases of an [`Index`] and the [`MemoryStorage`]
+//! keeps everything in memory, other backends implement the [`Storage`], [`ReadTxn`]
+//! and [`WriteTxn`] traits.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+use heed::types::ByteSlice;
+use heed::PolyDatabase;
+
+use crate::error::InternalError;
+use crate::{Index, Result};
+
+/// An iterator over the entries of a database, ordered by key.
+pub type KvIter<'t> = Box<dyn Iterator<Item = Result<(Cow<'t, [u8]>, Cow<'t, [u8]>)>> + 't>;
+
+/// A key-value store made of named databases, accessed through transactions.
+pub trait Storage: Send + Sync {
+    /// Creates a read transaction seeing a consistent snapshot of the databases.
+    fn read_txn(&self) -> Result<Box<dyn ReadTxn + '_>>;
+
+    /// Creates a write transaction, there can only be one at a time.
+    fn write_txn(&self) -> Result<Box<dyn WriteTxn + '_>>;
+}
+
+pub trait ReadTxn {
+    /// Returns the value associated with the key in the database.
+    fn get(&self, database: &str, key: &[u8]) -> Result<Option<Cow<[u8]>>>;
+
+    /// Returns the entries of the database whose keys are in the given bounds.
+    fn range(&self, database: &str, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Result<KvIter>;
+}
+
+pub trait WriteTxn: ReadTxn {
+    /// Inserts the value with the key in the database, replacing the previous value.
+    fn put(&mut self, database: &str, key: &[u8], value: &[u8]) -> Result<()>;
+
+    /// Removes the key from the database, returns `true` if the key existed.
+    fn delete(&mut self, database: &str, key: &[u8]) -> Result<bool>;
+
+    /// Makes the changes of the transaction visible to the new transactions.
+    fn commit(self: Box<Self>) -> Result<()>;
+}
+
+/// The storage backed by the LMDB environment of an index.
+pub struct LmdbStorage {
+    env: heed::Env,
+    databases: HashMap<&'static str, PolyDatabase>,
+}
+
+impl LmdbStorage {
+    pub fn new(index: &Index) -> Result<LmdbStorage> {
+        let databases = index.databases().iter().map(|(name, database)| (*name, **database));
+        Ok(LmdbStorage { env: index.env.clone(), databases: databases.collect() })
+    }
+
+    fn database(&self, name: &str) -> Result<PolyDatabase> {
+        match self.databases.get(name) {
+            Some(database) => Ok(*database),
+            None => Err(InternalError::UnknownDatabase { db_name: name.to_string() }.into()),
+        }
+    }
+}
+
+impl Storage for LmdbStorage {
+    fn read_txn(&self) -> Result<Box<dyn ReadTxn + '_>> {
+        let txn = self.env.read_txn()?;
+        Ok(Box::new(LmdbReadTxn { storage: self, txn }))
+    }
+
+    fn write_txn(&self) -> Result<Box<dyn WriteTxn + '_>> {
+        let txn = self.env.write_txn()?;
+        Ok(Box::new(LmdbWriteTxn { storage: self, txn }))
+    }
+}
+
+struct LmdbReadTxn<'e> {
+    storage: &'e LmdbStorage,
+    txn: heed::RoTxn<'e>,
+}
+
+impl ReadTxn for LmdbReadTxn<'_> {
+    fn get(&self, database: &str, key: &[u8]) -> Result<Option<Cow<[u8]>>> {
+        lmdb_get(self.storage, &self.txn, database, key)
+    }
+
+    fn range(&self, database: &str, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Result<KvIter> {
+        lmdb_range(self.storage, &self.txn, database, start, end)
+    }
+}
+
+struct LmdbWriteTxn<'e> {
+    storage: &'e LmdbStorage,
+    txn: heed::RwTxn<'e, 'e>,
+}
+
+impl ReadTxn for LmdbWriteTxn<'_> {
+    fn get(&self, database: &str, key: &[u8]) -> Result<Option<Cow<[u8]>>> {
+        lmdb_get(self.storage, &self.txn, database, key)
+    }
+
+    fn range(&self, database: &str, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Result<KvIter> {
+        lmdb_range(self.storage, &self.txn, database, start, end)
+    }
+}
+
+impl WriteTxn for LmdbWriteTxn<'_> {
+    fn put(&mut self, database: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        let database = self.storage.database(database)?;
+        database.put::<_, ByteSlice, ByteSlice>(&mut self.txn, key, value)?;
+        Ok(())
+    }
+
+    fn delete(&mut self, database: &str, key: &[u8]) -> Result<bool> {
+        let database = self.storage.database(database)?;
+        Ok(database.delete::<_, ByteSlice>(&mut self.txn, key)?)
+    }
+
+    fn commit(self: Box<Self>) -> Result<()> {
+        self.txn.commit()?;
+        Ok(())
+    }
+}
+
+fn lmdb_get<'t>(
+    storage: &LmdbStorage,
+    txn: &'t heed::RoTxn,
+    database: &str,
+    key: &[u8],
+) -> Result<Option<Cow<'t, [u8]>>> {
+    let database = storage.database(database)?;
+    Ok(database.get::<_, ByteSlice, ByteSlice>(txn, key)?.map(Cow::Borrowed))
+}
+
+fn lmdb_range<'t>(
+    storage: &LmdbStorage,
+    txn: &'t heed::RoTxn,
+    database: &str,
+    start: Bound<&[u8]>,
+    end: Bound<&[u8]>,
+) -> Result<KvIter<'t>> {
+    let database = storage.database(database)?;
+    let iter = database.range::<_, ByteSlice, ByteSlice, _>(txn, &(start, end))?;
+    Ok(Box::new(iter.map(|result| match result {
+        Ok((key, value)) => Ok((Cow::Borrowed(key), Cow::Borrowed(value))),
+        Err(error) => Err(error.into()),
+    })))
+}
+
+type Databases = BTreeMap<String, BTreeMap<Vec<u8>, Vec<u8>>>;
+
+/// A storage keeping the databases in memory, the databases are created on the first write.
+///
+/// A write transaction works on a copy of the databases that replaces
+/// the current ones on commit, it is only meant to be used for the tests.
+#[derive(Default)]
+pub struct MemoryStorage {
+    databases: RwLock<Arc<Databases>>,
+    writer: Mutex<()>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+
+    fn snapshot(&self) -> Arc<Databases> {
+        self.databases.read().unwrap().clone()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read_txn(&self) -> Result<Box<dyn ReadTxn + '_>> {
+        Ok(Box::new(MemoryReadTxn { databases: self.snapshot() }))
+    }
+
+    fn write_txn(&self) -> Result<Box<dyn WriteTxn + '_>> {
+        let guard = self.writer.lock().unwrap();
+        let databases = Databases::clone(&self.snapshot());
+        Ok(Box::new(MemoryWriteTxn { storage: self, _guard: guard, databases }))
+    }
+}
+
+struct MemoryReadTxn {
+    databases: Arc<Databases>,
+}
+
+impl ReadTxn for MemoryReadTxn {
+    fn get(&self, database: &str, key: &[u8]) -> Result<Option<Cow<[u8]>>> {
+        Ok(memory_get(&self.databases, database, key))
+    }
+
+    fn range(&self, database: &str, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Result<KvIter> {
+        Ok(memory_range(&self.databases, database, start, end))
+    }
+}
+
+struct MemoryWriteTxn<'s> {
+    storage: &'s MemoryStorage,
+    _guard: MutexGuard<'s, ()>,
+    databases: Databases,
+}
+
+impl ReadTxn for MemoryWriteTxn<'_> {
+    fn get(&self, database: &str, key: &[u8]) -> Result<Option<Cow<[u8]>>> {
+        Ok(memory_get(&self.databases, database, key))
+    }
+
+    fn range(&self, database: &str, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Result<KvIter> {
+        Ok(memory_range(&self.databases, database, start, end))
+    }
+}
+
+impl WriteTxn for MemoryWriteTxn<'_> {
+    fn put(&mut self, database: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        let database = self.databases.entry(database.to_string()).or_default();
+        database.insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, database: &str, key: &[u8]) -> Result<bool> {
+        match self.databases.get_mut(database) {
+            Some(database) => Ok(database.remove(key).is_some()),
+            None => Ok(false),
+        }
+    }
+
+    fn commit(self: Box<Self>) -> Result<()> {
+        let MemoryWriteTxn { storage, _guard, databases } = *self;
+        *storage.databases.write().unwrap() = Arc::new(databases);
+        Ok(())
+    }
+}
+
+fn memory_get<'t>(databases: &'t Databases, database: &str, key: &[u8]) -> Option<Cow<'t, [u8]>> {
+    databases.get(database).and_then(|db| db.get(key)).map(|value| Cow::Borrowed(&value[..]))
+}
+
+fn memory_range<'t>(
+    databases: &'t Databases,
+    database: &str,
+    start: Bound<&[u8]>,
+    end: Bound<&[u8]>,
+) -> KvIter<'t> {
+    // A BTreeMap panics on the ranges that LMDB considers empty.
+    let is_empty = match (start, end) {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s), Bound::Excluded(e))
+        | (Bound::Excluded(s), Bound::Included(e))
+        | (Bound::Excluded(s), Bound::Excluded(e)) => s >= e,
+        _ => false,
+    };
+
+    match databases.get(database) {
+        Some(database) if !is_empty => Box::new(
+            database
+                .range::<[u8], _>((start, end))
+                .map(|(key, value)| Ok((Cow::Borrowed(&key[..]), Cow::Borrowed(&value[..])))),
+        ),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heed::EnvOpenOptions;
+
+    use super::*;
+    use crate::index::db_name;
+
+    fn check_storage(storage: &dyn Storage, database: &str) {
+        let mut wtxn = storage.write_txn().unwrap();
+        wtxn.put(database, b"hello", b"world").unwrap();
+        wtxn.put(database, b"hi", b"there").unwrap();
+        wtxn.put(database, b"zebra", b"stripes").unwrap();
+
+        // the changes are only visible to the new transactions once committed.
+        let rtxn = storage.read_txn().unwrap();
+        assert_eq!(rtxn.get(database, b"hello").unwrap(), None);
+        drop(rtxn);
+
+        assert_eq!(wtxn.get(database, b"hello").unwrap().as_deref(), Some(&b"world"[..]));
+        assert!(wtxn.delete(database, b"zebra").unwrap());
+        assert!(!wtxn.delete(database, b"zebra").unwrap());
+        wtxn.commit().unwrap();
+
+        let rtxn = storage.read_txn().unwrap();
+        assert_eq!(rtxn.get(database, b"hi").unwrap().as_deref(), Some(&b"there"[..]));
+        assert_eq!(rtxn.get(database, b"zebra").unwrap(), None);
+
+        let keys: Vec<_> = rtxn
+            .range(database, Bound::Included(&b"h"[..]), Bound::Excluded(&b"hj"[..]))
+            .unwrap()
+            .map(|result| result.unwrap().0.into_owned())
+            .collect();
+        assert_eq!(keys, vec![b"hello".to_vec(), b"hi".to_vec()]);
+
+        let count = rtxn
+            .range(database, Bound::Included(&b"z"[..]), Bound::Included(&b"a"[..]))
+            .unwrap()
+            .count();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn memory_storage() {
+        let storage = MemoryStorage::new();
+        check_storage(&storage, "main");
+    }
+
+    #[test]
+    fn lmdb_storage() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let storage = LmdbStorage::new(&index).unwrap();
+        check_storage(&storage, db_name::DOCUMENTS);
+
+        let rtxn = storage.read_txn().unwrap();
+        assert!(rtxn.get("unknown", b"hello").is_err());
+        for (name, _) in index.databases().iter() {
+            assert!(rtxn.get(name, b"hello").is_ok(), "{} is not accessible", name);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Utilities to write tests against a temporary index.
+//!
+//! ```
+//! use milli::test_utils::TempIndex;
+//! use serde_json::json;
+//!
+//! let index = TempIndex::new();
+//! index.add_documents(json!([{ "id": 1, "title": "hello" }])).unwrap();
+//! assert_eq!(index.search_external_ids("hello").unwrap(), vec!["1"]);
+//! ```
+
+use std::io::Cursor;
+use std::ops::{Deref, DerefMut};
+
+use heed::EnvOpenOptions;
+use serde_json::Value;
+use tempfile::TempDir;
+
+use crate::documents::{DocumentBatchBuilder, DocumentBatchReader};
+use crate::update::{
+    DocumentAdditionResult, IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings,
+};
+use crate::{Index, Result};
+
+/// An index stored in a temporary directory that is removed when the index is dropped.
+pub struct TempIndex {
+    inner: Index,
+    _tempdir: TempDir,
+}
+
+impl Deref for TempIndex {
+    type Target = Index;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for TempIndex {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl TempIndex {
+    /// Creates a temporary index with a map size of 10 MB.
+    pub fn new() -> TempIndex {
+        TempIndex::with_map_size(10 * 1024 * 1024)
+    }
+
+    /// Creates a temporary index with the given map size, a multiple of the OS page size.
+    pub fn with_map_size(map_size: usize) -> TempIndex {
+        let mut options = EnvOpenOptions::new();
+        options.map_size(map_size);
+        let _tempdir = tempfile::tempdir().unwrap();
+        let inner = Index::new(options, _tempdir.path()).unwrap();
+        TempIndex { inner, _tempdir }
+    }
+
+    /// Adds the documents of a JSON object or array of objects, built
+    /// with `serde_json::json!`, with the default indexing configuration.
+    ///
+    /// Panics if the value is not a document or an array of documents.
+    pub fn add_documents(&self, documents: Value) -> Result<DocumentAdditionResult> {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        let bytes = serde_json::to_vec(&documents).unwrap();
+        builder.extend_from_json(Cursor::new(bytes)).expect("invalid documents");
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let reader = DocumentBatchReader::from_reader(cursor).unwrap();
+
+        let mut wtxn = self.write_txn()?;
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, self, &config, indexing_config, |_| ());
+        builder.add_documents(reader)?;
+        let result = builder.execute()?;
+        wtxn.commit()?;
+
+        Ok(result)
+    }
+
+    /// Updates the settings of the index with the default indexer configuration.
+    pub fn update_settings(&self, update: impl FnOnce(&mut Settings)) -> Result<()> {
+        let mut wtxn = self.write_txn()?;
+        let config = IndexerConfig::default();
+        let mut builder = Settings::new(&mut wtxn, self, &config);
+        update(&mut builder);
+        builder.execute(|_| ())?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the primary key values of the documents matching the query, in ranking order,
+    /// the numbers are returned in their JSON representation.
+    pub fn search_external_ids(&self, query: &str) -> Result<Vec<String>> {
+        let rtxn = self.read_txn()?;
+        let result = self.search(&rtxn).query(query).execute()?;
+        let primary_key = match self.primary_key(&rtxn)? {
+            Some(primary_key) => primary_key,
+            None => return Ok(Vec::new()),
+        };
+        let primary_key_id = self.fields_ids_map(&rtxn)?.id(primary_key).unwrap();
+
+        let mut external_ids = Vec::with_capacity(result.documents_ids.len());
+        for (_, document) in self.documents(&rtxn, result.documents_ids)? {
+            let value = document.get(primary_key_id).unwrap();
+            match serde_json::from_slice(value).unwrap() {
+                Value::String(id) => external_ids.push(id),
+                value => external_ids.push(value.to_string()),
+            }
+        }
+
+        Ok(external_ids)
+    }
+}
+
+impl Default for TempIndex {
+    fn default() -> TempIndex {
+        TempIndex::new()
+    }
+}
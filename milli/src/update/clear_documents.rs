@@ -1,8 +1,21 @@
+use heed::types::ByteSlice;
+use heed::PolyDatabase;
 use roaring::RoaringBitmap;
 use time::OffsetDateTime;
 
 use crate::{ExternalDocumentsIds, FieldDistribution, Index, Result};
 
+/// What was removed from the index by a `ClearDocuments` update.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClearDocumentsResult {
+    pub deleted_documents: u64,
+    /// The number of databases of the documents, the words and the facets that were emptied.
+    pub cleared_databases: usize,
+    /// The size of the keys and values removed from those databases, the pages they used
+    /// are reused by the next updates but the size of the index file doesn't change.
+    pub reclaimed_bytes: u64,
+}
+
 pub struct ClearDocuments<'t, 'u, 'i> {
     wtxn: &'t mut heed::RwTxn<'i, 'u>,
     index: &'i Index,
@@ -13,12 +26,13 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         ClearDocuments { wtxn, index }
     }
 
-    pub fn execute(self) -> Result<u64> {
+    pub fn execute(self) -> Result<ClearDocumentsResult> {
         self.index.set_updated_at(self.wtxn, &OffsetDateTime::now_utc())?;
         let Index {
             env: _env,
             main: _main,
             word_docids,
+            exact_word_docids,
             word_prefix_docids,
             docid_word_positions,
             word_pair_proximity_docids,
@@ -27,10 +41,13 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
             field_id_word_count_docids,
             word_prefix_position_docids,
             facet_id_f64_docids,
+            facet_id_i128_docids,
             facet_id_string_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             documents,
+            // The other fields of the index are not databases.
+            ..
         } = self.index;
 
         // We retrieve the number of documents ids that we are deleting.
@@ -40,6 +57,8 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         // We clean some of the main engine datastructures.
         self.index.put_words_fst(self.wtxn, &fst::Set::default())?;
         self.index.put_words_prefixes_fst(self.wtxn, &fst::Set::default())?;
+        #[cfg(feature = "phonetic")]
+        self.index.delete_phonetic_words_fst(self.wtxn)?;
         self.index.put_external_documents_ids(self.wtxn, &ExternalDocumentsIds::default())?;
         self.index.put_documents_ids(self.wtxn, &RoaringBitmap::default())?;
         self.index.put_field_distribution(self.wtxn, &FieldDistribution::default())?;
@@ -54,22 +73,45 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         }
 
         // Clear the other databases.
-        word_docids.clear(self.wtxn)?;
-        word_prefix_docids.clear(self.wtxn)?;
-        docid_word_positions.clear(self.wtxn)?;
-        word_pair_proximity_docids.clear(self.wtxn)?;
-        word_prefix_pair_proximity_docids.clear(self.wtxn)?;
-        word_position_docids.clear(self.wtxn)?;
-        field_id_word_count_docids.clear(self.wtxn)?;
-        word_prefix_position_docids.clear(self.wtxn)?;
-        facet_id_f64_docids.clear(self.wtxn)?;
-        facet_id_string_docids.clear(self.wtxn)?;
-        field_id_docid_facet_f64s.clear(self.wtxn)?;
-        field_id_docid_facet_strings.clear(self.wtxn)?;
-        documents.clear(self.wtxn)?;
-
-        Ok(number_of_documents)
+        let databases = [
+            word_docids.as_polymorph(),
+            exact_word_docids.as_polymorph(),
+            word_prefix_docids.as_polymorph(),
+            docid_word_positions.as_polymorph(),
+            word_pair_proximity_docids.as_polymorph(),
+            word_prefix_pair_proximity_docids.as_polymorph(),
+            word_position_docids.as_polymorph(),
+            field_id_word_count_docids.as_polymorph(),
+            word_prefix_position_docids.as_polymorph(),
+            facet_id_f64_docids.as_polymorph(),
+            facet_id_i128_docids.as_polymorph(),
+            facet_id_string_docids.as_polymorph(),
+            field_id_docid_facet_f64s.as_polymorph(),
+            field_id_docid_facet_strings.as_polymorph(),
+            documents.as_polymorph(),
+        ];
+        let mut reclaimed_bytes = 0;
+        for database in &databases {
+            reclaimed_bytes += clear_database(self.wtxn, database)?;
+        }
+
+        Ok(ClearDocumentsResult {
+            deleted_documents: number_of_documents,
+            cleared_databases: databases.len(),
+            reclaimed_bytes,
+        })
+    }
+}
+
+/// Clears the database and returns the size of the keys and values it contained.
+fn clear_database(wtxn: &mut heed::RwTxn, database: &PolyDatabase) -> Result<u64> {
+    let mut bytes = 0;
+    for result in database.iter::<_, ByteSlice, ByteSlice>(wtxn)? {
+        let (key, value) = result?;
+        bytes += (key.len() + value.len()) as u64;
     }
+    database.clear(wtxn)?;
+    Ok(bytes)
 }
 
 #[cfg(test)]
@@ -100,7 +142,10 @@ mod tests {
 
         // Clear all documents from the database.
         let builder = ClearDocuments::new(&mut wtxn, &index);
-        assert_eq!(builder.execute().unwrap(), 3);
+        let result = builder.execute().unwrap();
+        assert_eq!(result.deleted_documents, 3);
+        assert_eq!(result.cleared_databases, 15);
+        assert!(result.reclaimed_bytes > 0);
 
         wtxn.commit().unwrap();
 
@@ -117,12 +162,14 @@ mod tests {
         assert!(index.geo_faceted_documents_ids(&rtxn).unwrap().is_empty());
 
         assert!(index.word_docids.is_empty(&rtxn).unwrap());
+        assert!(index.exact_word_docids.is_empty(&rtxn).unwrap());
         assert!(index.word_prefix_docids.is_empty(&rtxn).unwrap());
         assert!(index.docid_word_positions.is_empty(&rtxn).unwrap());
         assert!(index.word_pair_proximity_docids.is_empty(&rtxn).unwrap());
         assert!(index.field_id_word_count_docids.is_empty(&rtxn).unwrap());
         assert!(index.word_prefix_pair_proximity_docids.is_empty(&rtxn).unwrap());
         assert!(index.facet_id_f64_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_i128_docids.is_empty(&rtxn).unwrap());
         assert!(index.facet_id_string_docids.is_empty(&rtxn).unwrap());
         assert!(index.field_id_docid_facet_f64s.is_empty(&rtxn).unwrap());
         assert!(index.field_id_docid_facet_strings.is_empty(&rtxn).unwrap());
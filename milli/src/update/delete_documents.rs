@@ -10,6 +10,8 @@ use serde_json::Value;
 use time::OffsetDateTime;
 
 use super::ClearDocuments;
+#[cfg(feature = "phonetic")]
+use super::PhoneticWordsFst;
 use crate::error::{InternalError, SerializationError, UserError};
 use crate::heed_codec::facet::{
     FacetLevelValueU32Codec, FacetStringLevelZeroValueCodec, FacetStringZeroBoundsValueCodec,
@@ -23,12 +25,17 @@ pub struct DeleteDocuments<'t, 'u, 'i> {
     index: &'i Index,
     external_documents_ids: ExternalDocumentsIds<'static>,
     documents_ids: RoaringBitmap,
+    not_found_external_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DocumentDeletionResult {
     pub deleted_documents: u64,
     pub remaining_documents: u64,
+    /// The external ids of the deleted documents, sorted.
+    pub deleted_external_ids: Vec<String>,
+    /// The external ids given to `delete_external_id` that are not in the index.
+    pub not_found_external_ids: Vec<String>,
 }
 
 impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
@@ -43,6 +50,7 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             index,
             external_documents_ids,
             documents_ids: RoaringBitmap::new(),
+            not_found_external_ids: Vec::new(),
         })
     }
 
@@ -55,9 +63,24 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
     }
 
     pub fn delete_external_id(&mut self, external_id: &str) -> Option<u32> {
-        let docid = self.external_documents_ids.get(external_id)?;
-        self.delete_document(docid);
-        Some(docid)
+        match self.external_documents_ids.get(external_id) {
+            Some(docid) => {
+                self.delete_document(docid);
+                Some(docid)
+            }
+            None => {
+                self.not_found_external_ids.push(external_id.to_string());
+                None
+            }
+        }
+    }
+
+    /// Deletes all the documents whose external id starts with the prefix,
+    /// returns the number of documents that will be deleted.
+    pub fn delete_external_id_prefix(&mut self, prefix: &str) -> u64 {
+        let docids = self.external_documents_ids.prefix_ids(prefix);
+        self.delete_documents(&docids);
+        docids.len()
     }
 
     pub fn execute(self) -> Result<DocumentDeletionResult> {
@@ -71,6 +94,8 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             return Ok(DocumentDeletionResult {
                 deleted_documents: 0,
                 remaining_documents: current_documents_ids_len,
+                deleted_external_ids: Vec::new(),
+                not_found_external_ids: self.not_found_external_ids,
             });
         }
 
@@ -82,10 +107,15 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         // We can execute a ClearDocuments operation when the number of documents
         // to delete is exactly the number of documents in the database.
         if current_documents_ids_len == self.documents_ids.len() {
-            let remaining_documents = ClearDocuments::new(self.wtxn, self.index).execute()?;
+            let mut deleted_external_ids: Vec<_> =
+                self.external_documents_ids.to_hash_map().into_iter().map(|(id, _)| id).collect();
+            deleted_external_ids.sort_unstable();
+            ClearDocuments::new(self.wtxn, self.index).execute()?;
             return Ok(DocumentDeletionResult {
                 deleted_documents: current_documents_ids_len,
-                remaining_documents,
+                remaining_documents: 0,
+                deleted_external_ids,
+                not_found_external_ids: self.not_found_external_ids,
             });
         }
 
@@ -108,6 +138,7 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             env: _env,
             main: _main,
             word_docids,
+            exact_word_docids,
             word_prefix_docids,
             docid_word_positions,
             word_pair_proximity_docids,
@@ -116,12 +147,19 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             word_position_docids,
             word_prefix_position_docids,
             facet_id_f64_docids,
+            facet_id_i128_docids,
             facet_id_string_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             documents,
+            // The other fields of the index are not databases.
+            ..
         } = self.index;
 
+        // The stored documents may be compressed or encrypted.
+        let codec = self.index.documents_codec(self.wtxn)?;
+        let documents = documents.remap_data_type::<ByteSlice>();
+
         // Number of fields for each document that has been deleted.
         let mut fields_ids_distribution_diff = HashMap::new();
 
@@ -134,7 +172,9 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             // as we avoid traversing the LMDB B-Tree two times but only once.
             let key = BEU32::new(docid);
             let mut iter = documents.range_mut(self.wtxn, &(key..=key))?;
-            if let Some((_key, obkv)) = iter.next().transpose()? {
+            if let Some((_key, bytes)) = iter.next().transpose()? {
+                let obkv = codec.decode(bytes)?;
+                let obkv = obkv::KvReaderU16::new(&obkv);
                 for (field_id, _) in obkv.iter() {
                     *fields_ids_distribution_diff.entry(field_id).or_default() += 1;
                 }
@@ -203,7 +243,7 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
 
         // We iterate over the words and delete the documents ids
         // from the word docids database.
-        for (word, must_remove) in &mut words {
+        for (word, _must_remove) in &words {
             // We create an iterator to be able to get the content and delete the word docids.
             // It's faster to acquire a cursor to get and delete or put, as we avoid traversing
             // the LMDB B-Tree two times but only once.
@@ -215,7 +255,6 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
                     if docids.is_empty() {
                         // safety: we don't keep references from inside the LMDB database.
                         unsafe { iter.del_current()? };
-                        *must_remove = true;
                     } else if docids.len() != previous_len {
                         let key = key.to_owned();
                         // safety: we don't keep references from inside the LMDB database.
@@ -225,6 +264,32 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             }
         }
 
+        // We do the same for the exact word docids database, the words FST contains the words
+        // of both databases, a word is removed from it when it is no more in any of them.
+        for (word, must_remove) in &mut words {
+            let mut iter = exact_word_docids.prefix_iter_mut(self.wtxn, &word)?;
+            let mut is_exact_word = false;
+            if let Some((key, mut docids)) = iter.next().transpose()? {
+                if key == word.as_ref() {
+                    let previous_len = docids.len();
+                    docids -= &self.documents_ids;
+                    if docids.is_empty() {
+                        // safety: we don't keep references from inside the LMDB database.
+                        unsafe { iter.del_current()? };
+                    } else {
+                        is_exact_word = true;
+                        if docids.len() != previous_len {
+                            let key = key.to_owned();
+                            // safety: we don't keep references from inside the LMDB database.
+                            unsafe { iter.put_current(&key, &docids)? };
+                        }
+                    }
+                }
+            }
+            drop(iter);
+            *must_remove = !is_exact_word && word_docids.get(self.wtxn, &word)?.is_none();
+        }
+
         // We construct an FST set that contains the words to delete from the words FST.
         let words_to_delete =
             words.iter().filter_map(
@@ -254,6 +319,12 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         // We write the new words FST into the main database.
         self.index.put_words_fst(self.wtxn, &new_words_fst)?;
 
+        // We rebuild the phonetic words FST from the new words FST.
+        #[cfg(feature = "phonetic")]
+        if self.index.phonetic_matching(self.wtxn)? {
+            PhoneticWordsFst::new(self.wtxn, self.index).execute()?;
+        }
+
         // We iterate over the word prefix docids database and remove the deleted documents ids
         // from every docids lists. We register the empty prefixes in an fst Set for futur deletion.
         let mut prefixes_to_delete = fst::SetBuilder::memory();
@@ -415,6 +486,12 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             &self.documents_ids,
         )?;
 
+        remove_docids_from_facet_field_id_number_docids(
+            self.wtxn,
+            facet_id_i128_docids,
+            &self.documents_ids,
+        )?;
+
         remove_docids_from_facet_field_id_string_docids(
             self.wtxn,
             facet_id_string_docids,
@@ -453,6 +530,8 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         Ok(DocumentDeletionResult {
             deleted_documents: self.documents_ids.len(),
             remaining_documents: documents_ids.len(),
+            deleted_external_ids: external_ids.iter().map(ToString::to_string).collect(),
+            not_found_external_ids: self.not_found_external_ids,
         })
     }
 }
@@ -624,13 +703,17 @@ mod tests {
         let index = Index::new(options, &path).unwrap();
 
         let mut wtxn = index.write_txn().unwrap();
+        let config = IndexerConfig::default();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_primary_key(S("mysuperid"));
+        builder.execute(|_| ()).unwrap();
+
         let content = documents!([
             { "mysuperid": 0, "name": "kevin" },
             { "mysuperid": 1, "name": "kevina" },
             { "mysuperid": 2, "name": "benoit" }
         ]);
 
-        let config = IndexerConfig::default();
         let indexing_config = IndexDocumentsConfig::default();
         let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
         builder.add_documents(content).unwrap();
@@ -700,6 +783,115 @@ mod tests {
         wtxn.commit().unwrap();
     }
 
+    #[test]
+    fn deleted_and_not_found_external_ids() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "a", "name": "kevin" },
+            { "id": "b", "name": "kevina" },
+            { "id": "c", "name": "benoit" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        builder.delete_external_id("c");
+        builder.delete_external_id("z");
+        builder.delete_external_id("a");
+        let result = builder.execute().unwrap();
+        assert_eq!(result.deleted_documents, 2);
+        assert_eq!(result.remaining_documents, 1);
+        assert_eq!(result.deleted_external_ids, vec![S("a"), S("c")]);
+        assert_eq!(result.not_found_external_ids, vec![S("z")]);
+
+        // The last document is deleted by clearing the index.
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        builder.delete_external_id("b");
+        let result = builder.execute().unwrap();
+        assert_eq!(result.deleted_external_ids, vec![S("b")]);
+        assert!(result.not_found_external_ids.is_empty());
+
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn delete_documents_by_external_id_prefix() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "tenant4:1", "name": "kevin" },
+            { "id": "tenant42:1", "name": "kevina" },
+            { "id": "tenant42:2", "name": "benoit" },
+            { "id": "tenant43:1", "name": "bernard" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        assert_eq!(builder.delete_external_id_prefix("tenant42:"), 2);
+        let result = builder.execute().unwrap();
+        assert_eq!(result.deleted_external_ids, vec![S("tenant42:1"), S("tenant42:2")]);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        let mut ids: Vec<_> =
+            external_documents_ids.to_hash_map().into_iter().map(|(id, _)| id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![S("tenant4:1"), S("tenant43:1")]);
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 2);
+    }
+
+    #[cfg(feature = "phonetic")]
+    #[test]
+    fn delete_documents_updates_the_phonetic_words() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let config = IndexerConfig::default();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_phonetic_matching(true);
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 1, "name": "kathryn smyth" },
+            { "id": 2, "name": "steven doe" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        assert_eq!(index.phonetic_words(&wtxn, "catherine").unwrap(), vec![S("kathryn")]);
+
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        builder.delete_external_id("1");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // The words of the deleted document are no more in the phonetic words.
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.phonetic_words(&rtxn, "catherine").unwrap().is_empty());
+        assert_eq!(index.phonetic_words(&rtxn, "stephen").unwrap(), vec![S("steven")]);
+    }
+
     #[test]
     fn delete_documents_with_geo_points() {
         let path = tempfile::tempdir().unwrap();
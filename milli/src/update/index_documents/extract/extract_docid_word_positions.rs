@@ -1,7 +1,8 @@
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::fs::File;
-use std::{io, mem, str};
+use std::{io, iter, mem, str};
 
 use meilisearch_tokenizer::token::SeparatorKind;
 use meilisearch_tokenizer::{Analyzer, AnalyzerConfig, Token, TokenKind};
@@ -10,7 +11,9 @@ use serde_json::Value;
 
 use super::helpers::{concat_u32s_array, create_sorter, sorter_into_reader, GrenadParameters};
 use crate::error::{InternalError, SerializationError};
-use crate::{absolute_from_relative_position, FieldId, Result, MAX_POSITION_PER_ATTRIBUTE};
+use crate::{
+    absolute_from_relative_position, FieldId, Normalizer, Result, MAX_POSITION_PER_ATTRIBUTE,
+};
 
 /// Extracts the word and positions where this word appear and
 /// prefixes it by the document id.
@@ -23,6 +26,8 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
     indexer: GrenadParameters,
     searchable_fields: &Option<HashSet<FieldId>>,
     stop_words: Option<&fst::Set<&[u8]>>,
+    decompounding_dictionary: Option<&fst::Set<&[u8]>>,
+    normalizer: &Normalizer,
     max_positions_per_attributes: Option<u32>,
 ) -> Result<(RoaringBitmap, grenad::Reader<File>)> {
     let max_positions_per_attributes = max_positions_per_attributes
@@ -69,17 +74,33 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
                         .take_while(|(p, _)| (*p as u32) < max_positions_per_attributes);
 
                     for (index, token) in tokens {
-                        let token = token.text().trim();
+                        let original = &field[token.byte_start..token.byte_end];
+                        let token = normalizer.token_word(token.text().trim(), original);
                         if !token.is_empty() {
-                            key_buffer.truncate(mem::size_of::<u32>());
-                            key_buffer.extend_from_slice(token.as_bytes());
-
                             let position: u16 = index
                                 .try_into()
                                 .map_err(|_| SerializationError::InvalidNumberSerialization)?;
                             let position = absolute_from_relative_position(field_id, position);
-                            docid_word_positions_sorter
-                                .insert(&key_buffer, &position.to_ne_bytes())?;
+
+                            // The parts of a compound word are indexed at the same position.
+                            let parts = decompounding_dictionary
+                                .and_then(|dictionary| decompound(&token, dictionary))
+                                .unwrap_or_default();
+
+                            // The words keeping their case are indexed along with their
+                            // lowercased version, which is used as a fallback at search time.
+                            let cased = normalizer.cased_word(&token, original).map(Cow::Borrowed);
+
+                            let words = iter::once(token.as_ref()).chain(parts);
+                            for word in words
+                                .map(|word| normalizer.normalize_field_word(field_id, word))
+                                .chain(cased)
+                            {
+                                key_buffer.truncate(mem::size_of::<u32>());
+                                key_buffer.extend_from_slice(word.as_bytes());
+                                docid_word_positions_sorter
+                                    .insert(&key_buffer, &position.to_ne_bytes())?;
+                            }
                         }
                     }
                 }
@@ -90,6 +111,50 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
     sorter_into_reader(docid_word_positions_sorter, indexer).map(|reader| (documents_ids, reader))
 }
 
+/// The minimum number of characters of the parts of a compound word.
+const MIN_COMPOUND_PART_LEN: usize = 3;
+
+/// Splits the word into the smallest number of dictionary words, returns `None`
+/// if the word can't be entirely covered by at least two dictionary words.
+fn decompound<'a>(word: &'a str, dictionary: &fst::Set<&[u8]>) -> Option<Vec<&'a str>> {
+    let mut boundaries: Vec<_> = word.char_indices().map(|(i, _)| i).collect();
+    boundaries.push(word.len());
+
+    // For each boundary, the minimum number of parts needed to cover the word
+    // until this boundary and the boundary at which the last part starts.
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; boundaries.len()];
+    best[0] = Some((0, 0));
+    for end in 1..boundaries.len() {
+        for start in 0..(end + 1).saturating_sub(MIN_COMPOUND_PART_LEN) {
+            let parts = match best[start] {
+                Some((parts, _)) => parts + 1,
+                None => continue,
+            };
+            let part = &word[boundaries[start]..boundaries[end]];
+            if best[end].map_or(true, |(best_parts, _)| parts < best_parts)
+                && dictionary.contains(part)
+            {
+                best[end] = Some((parts, start));
+            }
+        }
+    }
+
+    match best[boundaries.len() - 1] {
+        Some((parts, _)) if parts >= 2 => {
+            let mut output = Vec::with_capacity(parts);
+            let mut end = boundaries.len() - 1;
+            while end != 0 {
+                let (_, start) = best[end]?;
+                output.push(&word[boundaries[start]..boundaries[end]]);
+                end = start;
+            }
+            output.reverse();
+            Some(output)
+        }
+        _otherwise => None,
+    }
+}
+
 /// Transform a JSON value into a string that can be indexed.
 fn json_to_string<'a>(value: &'a Value, buffer: &'a mut String) -> Option<&'a str> {
     fn inner(value: &Value, output: &mut String) -> bool {
@@ -171,3 +236,21 @@ fn process_tokens<'a>(
         })
         .filter(|(_, t)| t.is_word())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompound_words() {
+        let dictionary = fst::Set::from_iter(["futter", "haus", "hunde", "hut", "tür"]).unwrap();
+        let dictionary = fst::Set::new(dictionary.as_fst().as_bytes()).unwrap();
+
+        assert_eq!(decompound("hundefutter", &dictionary), Some(vec!["hunde", "futter"]));
+        assert_eq!(decompound("haustür", &dictionary), Some(vec!["haus", "tür"]));
+        assert_eq!(decompound("hundehaustür", &dictionary), Some(vec!["hunde", "haus", "tür"]));
+        // A word that is in the dictionary or that can't be entirely split is kept as is.
+        assert_eq!(decompound("hunde", &dictionary), None);
+        assert_eq!(decompound("hundekuchen", &dictionary), None);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io;
+
+use heed::BytesEncode;
+use serde_json::Value;
+
+use super::helpers::{
+    create_sorter, merge_cbo_roaring_bitmaps, sorter_into_reader, GrenadParameters,
+};
+use crate::error::InternalError;
+use crate::facet::{select_facet_path_values, FacetPath, FacetPaths};
+use crate::heed_codec::facet::FieldIdI128Codec;
+use crate::{DocumentId, FieldId, Result};
+
+/// Extracts the exact integer values of each faceted field of each document.
+///
+/// Returns a grenad reader with the list of extracted facet integers and
+/// documents ids from the given chunk of documents.
+#[logging_timer::time]
+pub fn extract_facet_integer_docids<R: io::Read + io::Seek>(
+    obkv_documents: grenad::Reader<R>,
+    indexer: GrenadParameters,
+    faceted_fields: &HashSet<FieldId>,
+    facet_paths: &FacetPaths,
+) -> Result<grenad::Reader<File>> {
+    let max_memory = indexer.max_memory_by_thread();
+
+    let mut facet_integer_docids_sorter = create_sorter(
+        merge_cbo_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory,
+    );
+
+    let mut cursor = obkv_documents.into_cursor()?;
+    while let Some((docid_bytes, value)) = cursor.move_on_next()? {
+        let document_id = docid_bytes.try_into().map(DocumentId::from_be_bytes).unwrap();
+        let obkv = obkv::KvReader::new(value);
+
+        for (field_id, field_bytes) in obkv.iter() {
+            // We only parse the values that can contain an integer.
+            let maybe_integer = matches!(field_bytes.first(), Some(b'-' | b'0'..=b'9' | b'['));
+            if faceted_fields.contains(&field_id) && maybe_integer {
+                let value =
+                    serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
+                for integer in extract_facet_integers(&value) {
+                    let key = (field_id, integer);
+                    let key_bytes = FieldIdI128Codec::bytes_encode(&key).unwrap();
+                    facet_integer_docids_sorter.insert(key_bytes, document_id.to_ne_bytes())?;
+                }
+            }
+        }
+
+        // The faceted paths values are selected from the nested objects of their root field.
+        for (field_id, FacetPath { root, keys }) in facet_paths {
+            if let Some(field_bytes) = obkv.get(*root) {
+                let value: Value =
+                    serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
+                let value = select_facet_path_values(&value, keys);
+                for integer in extract_facet_integers(&value) {
+                    let key = (*field_id, integer);
+                    let key_bytes = FieldIdI128Codec::bytes_encode(&key).unwrap();
+                    facet_integer_docids_sorter.insert(key_bytes, document_id.to_ne_bytes())?;
+                }
+            }
+        }
+    }
+
+    sorter_into_reader(facet_integer_docids_sorter, indexer)
+}
+
+/// Returns the integers of a value like the facet numbers are extracted, the numbers with
+/// a fractional part or that don't fit in an `i64` or an `u64` are ignored.
+fn extract_facet_integers(value: &Value) -> Vec<i128> {
+    match value {
+        Value::Number(_) => as_integer(value).into_iter().collect(),
+        Value::Array(values) => values.iter().filter_map(as_integer).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn as_integer(value: &Value) -> Option<i128> {
+    value.as_i64().map(i128::from).or_else(|| value.as_u64().map(i128::from))
+}
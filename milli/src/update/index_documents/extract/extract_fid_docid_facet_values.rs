@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io;
 use std::mem::size_of;
@@ -9,6 +9,10 @@ use serde_json::Value;
 use super::helpers::{create_sorter, keep_first, sorter_into_reader, GrenadParameters};
 use crate::error::InternalError;
 use crate::facet::value_encoding::f64_into_bytes;
+use crate::facet::{
+    hierarchy_levels, parse_date, select_facet_path_values, FacetNormalization, FacetPath,
+    FacetPaths,
+};
 use crate::{DocumentId, FieldId, Result};
 
 /// Extracts the facet values of each faceted field of each document.
@@ -20,6 +24,10 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
     obkv_documents: grenad::Reader<R>,
     indexer: GrenadParameters,
     faceted_fields: &HashSet<FieldId>,
+    facet_paths: &FacetPaths,
+    hierarchical_fields: &HashSet<FieldId>,
+    date_fields: &HashSet<FieldId>,
+    facet_normalization: &HashMap<FieldId, FacetNormalization>,
 ) -> Result<(grenad::Reader<File>, grenad::Reader<File>)> {
     let max_memory = indexer.max_memory_by_thread();
 
@@ -44,35 +52,58 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
     while let Some((docid_bytes, value)) = cursor.move_on_next()? {
         let obkv = obkv::KvReader::new(value);
 
+        let mut faceted_values = Vec::new();
         for (field_id, field_bytes) in obkv.iter() {
             if faceted_fields.contains(&field_id) {
                 let value =
                     serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
-                let (numbers, strings) = extract_facet_values(&value);
+                faceted_values.push((field_id, value));
+            }
+        }
+        // The faceted paths values are selected from the nested objects of their root field.
+        for (field_id, FacetPath { root, keys }) in facet_paths {
+            if let Some(field_bytes) = obkv.get(*root) {
+                let value: Value =
+                    serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
+                faceted_values.push((*field_id, select_facet_path_values(&value, keys)));
+            }
+        }
+
+        for (field_id, value) in faceted_values {
+            let normalization = facet_normalization.get(&field_id).copied().unwrap_or_default();
+            let (mut numbers, mut strings) = extract_facet_values(&value, normalization);
+            if date_fields.contains(&field_id) {
+                // The dates are also indexed as their unix timestamps.
+                numbers.extend(strings.iter().filter_map(|(_, original)| parse_date(original)));
+            }
+            if hierarchical_fields.contains(&field_id) {
+                // Every level of the paths is indexed with a level-aware key.
+                strings =
+                    strings.iter().flat_map(|(_, original)| hierarchy_levels(original)).collect();
+            }
 
-                key_buffer.clear();
+            key_buffer.clear();
 
-                // prefix key with the field_id and the document_id
-                key_buffer.extend_from_slice(&field_id.to_be_bytes());
-                key_buffer.extend_from_slice(&docid_bytes);
+            // prefix key with the field_id and the document_id
+            key_buffer.extend_from_slice(&field_id.to_be_bytes());
+            key_buffer.extend_from_slice(&docid_bytes);
 
-                // insert facet numbers in sorter
-                for number in numbers {
-                    key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
-                    if let Some(value_bytes) = f64_into_bytes(number) {
-                        key_buffer.extend_from_slice(&value_bytes);
-                        key_buffer.extend_from_slice(&number.to_be_bytes());
+            // insert facet numbers in sorter
+            for number in numbers {
+                key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
+                if let Some(value_bytes) = f64_into_bytes(number) {
+                    key_buffer.extend_from_slice(&value_bytes);
+                    key_buffer.extend_from_slice(&number.to_be_bytes());
 
-                        fid_docid_facet_numbers_sorter.insert(&key_buffer, ().as_bytes())?;
-                    }
+                    fid_docid_facet_numbers_sorter.insert(&key_buffer, ().as_bytes())?;
                 }
+            }
 
-                // insert  normalized and original facet string in sorter
-                for (normalized, original) in strings.into_iter().filter(|(n, _)| !n.is_empty()) {
-                    key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
-                    key_buffer.extend_from_slice(normalized.as_bytes());
-                    fid_docid_facet_strings_sorter.insert(&key_buffer, original.as_bytes())?;
-                }
+            // insert  normalized and original facet string in sorter
+            for (normalized, original) in strings.into_iter().filter(|(n, _)| !n.is_empty()) {
+                key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
+                key_buffer.extend_from_slice(normalized.as_bytes());
+                fid_docid_facet_strings_sorter.insert(&key_buffer, original.as_bytes())?;
             }
         }
     }
@@ -83,9 +114,13 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
     ))
 }
 
-fn extract_facet_values(value: &Value) -> (Vec<f64>, Vec<(String, String)>) {
+fn extract_facet_values(
+    value: &Value,
+    normalization: FacetNormalization,
+) -> (Vec<f64>, Vec<(String, String)>) {
     fn inner_extract_facet_values(
         value: &Value,
+        normalization: FacetNormalization,
         can_recurse: bool,
         output_numbers: &mut Vec<f64>,
         output_strings: &mut Vec<(String, String)>,
@@ -99,13 +134,19 @@ fn extract_facet_values(value: &Value) -> (Vec<f64>, Vec<(String, String)>) {
                 }
             }
             Value::String(original) => {
-                let normalized = original.trim().to_lowercase();
+                let normalized = normalization.normalize(original);
                 output_strings.push((normalized, original.clone()));
             }
             Value::Array(values) => {
                 if can_recurse {
                     for value in values {
-                        inner_extract_facet_values(value, false, output_numbers, output_strings);
+                        inner_extract_facet_values(
+                            value,
+                            normalization,
+                            false,
+                            output_numbers,
+                            output_strings,
+                        );
                     }
                 }
             }
@@ -115,7 +156,13 @@ fn extract_facet_values(value: &Value) -> (Vec<f64>, Vec<(String, String)>) {
 
     let mut facet_number_values = Vec::new();
     let mut facet_string_values = Vec::new();
-    inner_extract_facet_values(value, true, &mut facet_number_values, &mut facet_string_values);
+    inner_extract_facet_values(
+        value,
+        normalization,
+        true,
+        &mut facet_number_values,
+        &mut facet_string_values,
+    );
 
     (facet_number_values, facet_string_values)
 }
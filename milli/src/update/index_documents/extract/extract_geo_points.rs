@@ -5,7 +5,7 @@ use concat_arrays::concat_arrays;
 use serde_json::Value;
 
 use super::helpers::{create_writer, writer_into_reader, GrenadParameters};
-use crate::{FieldId, InternalError, Result, UserError};
+use crate::{geo_point_from_value, FieldId, InternalError, Result, UserError};
 
 /// Extracts the geographical coordinates contained in each document under the `_geo` field.
 ///
@@ -30,7 +30,7 @@ pub fn extract_geo_points<R: io::Read + io::Seek>(
             None => continue,
         };
 
-        if let Some((lat, lng)) = point["lat"].as_f64().zip(point["lng"].as_f64()) {
+        if let Some([lat, lng]) = geo_point_from_value(&point) {
             // this will create an array of 16 bytes (two 8 bytes floats)
             let bytes: [u8; 16] = concat_arrays![lat.to_ne_bytes(), lng.to_ne_bytes()];
             writer.insert(docid_bytes, bytes)?;
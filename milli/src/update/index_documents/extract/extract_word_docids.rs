@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io;
 use std::iter::FromIterator;
@@ -5,22 +6,24 @@ use std::iter::FromIterator;
 use roaring::RoaringBitmap;
 
 use super::helpers::{
-    create_sorter, merge_roaring_bitmaps, serialize_roaring_bitmap, sorter_into_reader,
-    try_split_array_at, GrenadParameters,
+    create_sorter, merge_roaring_bitmaps, read_u32_ne_bytes, serialize_roaring_bitmap,
+    sorter_into_reader, try_split_array_at, GrenadParameters,
 };
 use crate::error::SerializationError;
 use crate::index::db_name::DOCID_WORD_POSITIONS;
-use crate::Result;
+use crate::{relative_from_absolute_position, FieldId, Result};
 
 /// Extracts the word and the documents ids where this word appear.
 ///
 /// Returns a grenad reader with the list of extracted words and
-/// documents ids from the given chunk of docid word positions.
+/// documents ids from the given chunk of docid word positions, and another
+/// one with the words that appear in the exact attributes of the documents.
 #[logging_timer::time]
 pub fn extract_word_docids<R: io::Read + io::Seek>(
     docid_word_positions: grenad::Reader<R>,
     indexer: GrenadParameters,
-) -> Result<grenad::Reader<File>> {
+    exact_attributes: &HashSet<FieldId>,
+) -> Result<(grenad::Reader<File>, grenad::Reader<File>)> {
     let max_memory = indexer.max_memory_by_thread();
 
     let mut word_docids_sorter = create_sorter(
@@ -28,20 +31,49 @@ pub fn extract_word_docids<R: io::Read + io::Seek>(
         indexer.chunk_compression_type,
         indexer.chunk_compression_level,
         indexer.max_nb_chunks,
-        max_memory,
+        max_memory.map(|m| m / 2),
+    );
+
+    let mut exact_word_docids_sorter = create_sorter(
+        merge_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory.map(|m| m / 2),
     );
 
     let mut value_buffer = Vec::new();
     let mut cursor = docid_word_positions.into_cursor()?;
-    while let Some((key, _value)) = cursor.move_on_next()? {
+    while let Some((key, positions)) = cursor.move_on_next()? {
         let (document_id_bytes, word_bytes) = try_split_array_at(key)
             .ok_or_else(|| SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
         let document_id = u32::from_be_bytes(document_id_bytes);
 
         let bitmap = RoaringBitmap::from_iter(Some(document_id));
         serialize_roaring_bitmap(&bitmap, &mut value_buffer)?;
-        word_docids_sorter.insert(word_bytes, &value_buffer)?;
+
+        // A word only matches through typos and prefixes if it appears
+        // in at least one attribute that is not exact.
+        let (mut exact, mut tolerant) = (false, false);
+        for position in read_u32_ne_bytes(positions) {
+            let (field_id, _) = relative_from_absolute_position(position);
+            if exact_attributes.contains(&field_id) {
+                exact = true;
+            } else {
+                tolerant = true;
+            }
+        }
+
+        if exact {
+            exact_word_docids_sorter.insert(word_bytes, &value_buffer)?;
+        }
+        if tolerant {
+            word_docids_sorter.insert(word_bytes, &value_buffer)?;
+        }
     }
 
-    sorter_into_reader(word_docids_sorter, indexer)
+    Ok((
+        sorter_into_reader(word_docids_sorter, indexer)?,
+        sorter_into_reader(exact_word_docids_sorter, indexer)?,
+    ))
 }
@@ -1,4 +1,5 @@
 mod extract_docid_word_positions;
+mod extract_facet_integer_docids;
 mod extract_facet_number_docids;
 mod extract_facet_string_docids;
 mod extract_fid_docid_facet_values;
@@ -8,7 +9,7 @@ mod extract_word_docids;
 mod extract_word_pair_proximity_docids;
 mod extract_word_position_docids;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 
 use crossbeam_channel::Sender;
@@ -16,6 +17,7 @@ use log::debug;
 use rayon::prelude::*;
 
 use self::extract_docid_word_positions::extract_docid_word_positions;
+use self::extract_facet_integer_docids::extract_facet_integer_docids;
 use self::extract_facet_number_docids::extract_facet_number_docids;
 use self::extract_facet_string_docids::extract_facet_string_docids;
 use self::extract_fid_docid_facet_values::extract_fid_docid_facet_values;
@@ -29,7 +31,8 @@ use super::helpers::{
     merge_readers, merge_roaring_bitmaps, CursorClonableMmap, GrenadParameters, MergeFn,
 };
 use super::{helpers, TypedChunk};
-use crate::{FieldId, Result};
+use crate::facet::{FacetNormalization, FacetPaths};
+use crate::{FieldId, Normalizer, Result};
 
 /// Extract data for each databases from obkv documents in parallel.
 /// Send data in grenad file over provided Sender.
@@ -39,10 +42,17 @@ pub(crate) fn data_from_obkv_documents(
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     searchable_fields: Option<HashSet<FieldId>>,
     faceted_fields: HashSet<FieldId>,
+    facet_paths: FacetPaths,
+    hierarchical_fields: HashSet<FieldId>,
+    date_fields: HashSet<FieldId>,
+    facet_normalization: HashMap<FieldId, FacetNormalization>,
     primary_key_id: FieldId,
     geo_field_id: Option<FieldId>,
     stop_words: Option<fst::Set<&[u8]>>,
+    decompounding_dictionary: Option<fst::Set<&[u8]>>,
+    normalizer: &Normalizer,
     max_positions_per_attributes: Option<u32>,
+    exact_attributes: HashSet<FieldId>,
 ) -> Result<()> {
     let result: Result<(Vec<_>, (Vec<_>, Vec<_>))> = obkv_chunks
         .par_bridge()
@@ -53,9 +63,15 @@ pub(crate) fn data_from_obkv_documents(
                 lmdb_writer_sx.clone(),
                 &searchable_fields,
                 &faceted_fields,
+                &facet_paths,
+                &hierarchical_fields,
+                &date_fields,
+                &facet_normalization,
                 primary_key_id,
                 geo_field_id,
                 &stop_words,
+                &decompounding_dictionary,
+                normalizer,
                 max_positions_per_attributes,
             )
         })
@@ -86,14 +102,11 @@ pub(crate) fn data_from_obkv_documents(
         "field-id-wordcount-docids",
     );
 
-    spawn_extraction_task(
+    spawn_word_docids_extraction_task(
         docid_word_positions_chunks.clone(),
         indexer.clone(),
         lmdb_writer_sx.clone(),
-        extract_word_docids,
-        merge_roaring_bitmaps,
-        TypedChunk::WordDocids,
-        "word-docids",
+        exact_attributes,
     );
 
     spawn_extraction_task(
@@ -164,21 +177,59 @@ fn spawn_extraction_task<FE, FS>(
     });
 }
 
+/// Spawn a new task to extract the word docids and the exact word docids,
+/// both are extracted from the same chunks in a single pass.
+fn spawn_word_docids_extraction_task(
+    chunks: Vec<grenad::Reader<CursorClonableMmap>>,
+    indexer: GrenadParameters,
+    lmdb_writer_sx: Sender<Result<TypedChunk>>,
+    exact_attributes: HashSet<FieldId>,
+) {
+    rayon::spawn(move || {
+        let chunks: Result<Vec<_>> = chunks
+            .into_par_iter()
+            .map(|chunk| extract_word_docids(chunk, indexer.clone(), &exact_attributes))
+            .collect();
+        rayon::spawn(move || match chunks {
+            Ok(chunks) => {
+                debug!("merge word-docids and exact-word-docids databases");
+                let (word_docids_chunks, exact_word_docids_chunks): (Vec<_>, Vec<_>) =
+                    chunks.into_iter().unzip();
+                let reader = merge_readers(word_docids_chunks, merge_roaring_bitmaps, indexer);
+                let _ = lmdb_writer_sx.send(reader.map(TypedChunk::WordDocids));
+                let reader =
+                    merge_readers(exact_word_docids_chunks, merge_roaring_bitmaps, indexer);
+                let _ = lmdb_writer_sx.send(reader.map(TypedChunk::ExactWordDocids));
+            }
+            Err(e) => {
+                let _ = lmdb_writer_sx.send(Err(e));
+            }
+        })
+    });
+}
+
 /// Extract chuncked data and send it into lmdb_writer_sx sender:
 /// - documents
 /// - documents_ids
 /// - docid_word_positions
 /// - docid_fid_facet_numbers
 /// - docid_fid_facet_strings
+/// - facet_id_integer_docids
 fn extract_documents_data(
     documents_chunk: Result<grenad::Reader<File>>,
     indexer: GrenadParameters,
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     searchable_fields: &Option<HashSet<FieldId>>,
     faceted_fields: &HashSet<FieldId>,
+    facet_paths: &FacetPaths,
+    hierarchical_fields: &HashSet<FieldId>,
+    date_fields: &HashSet<FieldId>,
+    facet_normalization: &HashMap<FieldId, FacetNormalization>,
     primary_key_id: FieldId,
     geo_field_id: Option<FieldId>,
     stop_words: &Option<fst::Set<&[u8]>>,
+    decompounding_dictionary: &Option<fst::Set<&[u8]>>,
+    normalizer: &Normalizer,
     max_positions_per_attributes: Option<u32>,
 ) -> Result<(
     grenad::Reader<CursorClonableMmap>,
@@ -201,6 +252,26 @@ fn extract_documents_data(
         });
     }
 
+    if !faceted_fields.is_empty() {
+        let documents_chunk_cloned = documents_chunk.clone();
+        let lmdb_writer_sx_cloned = lmdb_writer_sx.clone();
+        let faceted_fields = faceted_fields.clone();
+        let facet_paths = facet_paths.clone();
+        rayon::spawn(move || {
+            let result = extract_facet_integer_docids(
+                documents_chunk_cloned,
+                indexer,
+                &faceted_fields,
+                &facet_paths,
+            );
+            let _ = match result {
+                Ok(facet_integer_docids) => lmdb_writer_sx_cloned
+                    .send(Ok(TypedChunk::FieldIdFacetIntegerDocids(facet_integer_docids))),
+                Err(error) => lmdb_writer_sx_cloned.send(Err(error)),
+            };
+        });
+    }
+
     let (docid_word_positions_chunk, docid_fid_facet_values_chunks): (Result<_>, Result<_>) =
         rayon::join(
             || {
@@ -209,6 +280,8 @@ fn extract_documents_data(
                     indexer.clone(),
                     searchable_fields,
                     stop_words.as_ref(),
+                    decompounding_dictionary.as_ref(),
+                    normalizer,
                     max_positions_per_attributes,
                 )?;
 
@@ -229,6 +302,10 @@ fn extract_documents_data(
                         documents_chunk.clone(),
                         indexer.clone(),
                         faceted_fields,
+                        facet_paths,
+                        hierarchical_fields,
+                        date_fields,
+                        facet_normalization,
                     )?;
 
                 // send docid_fid_facet_numbers_chunk to DB writer
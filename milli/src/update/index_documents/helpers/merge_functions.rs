@@ -1,13 +1,17 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io;
 use std::result::Result as StdResult;
 
 use roaring::RoaringBitmap;
+use serde_json::Value;
 
 use super::read_u32_ne_bytes;
+use crate::error::InternalError;
 use crate::heed_codec::facet::{decode_prefix_string, encode_prefix_string};
 use crate::heed_codec::CboRoaringBitmapCodec;
-use crate::Result;
+use crate::update::ArrayMergePolicy;
+use crate::{FieldId, Result};
 
 pub type MergeFn = for<'a> fn(&[u8], &[Cow<'a, [u8]>]) -> Result<Cow<'a, [u8]>>;
 
@@ -116,6 +120,70 @@ pub fn merge_two_obkvs(base: obkv::KvReaderU16, update: obkv::KvReaderU16, buffe
     writer.finish().unwrap();
 }
 
+/// Merges the update into the base like `merge_two_obkvs` but the arrays of the fields
+/// that have a merge policy are merged with the previous arrays instead of replacing them.
+pub fn merge_two_obkvs_with_policies(
+    base: obkv::KvReaderU16,
+    update: obkv::KvReaderU16,
+    policies: &HashMap<FieldId, ArrayMergePolicy>,
+    buffer: &mut Vec<u8>,
+) -> Result<()> {
+    use itertools::merge_join_by;
+    use itertools::EitherOrBoth::{Both, Left, Right};
+
+    buffer.clear();
+
+    let mut writer = obkv::KvWriter::new(buffer);
+    for eob in merge_join_by(base.iter(), update.iter(), |(b, _), (u, _)| b.cmp(u)) {
+        match eob {
+            Both((k, base), (_, update)) => match policies.get(&k) {
+                Some(&policy) => match merge_json_arrays(base, update, policy)? {
+                    Some(merged) => writer.insert(k, merged)?,
+                    None => writer.insert(k, update)?,
+                },
+                None => writer.insert(k, update)?,
+            },
+            Left((k, v)) | Right((k, v)) => writer.insert(k, v)?,
+        }
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+/// Merges two JSON arrays according to the policy, returns `None` if the policy is to
+/// replace or if any of the values is not an array, the update then replaces the base value.
+fn merge_json_arrays(
+    base: &[u8],
+    update: &[u8],
+    policy: ArrayMergePolicy,
+) -> Result<Option<Vec<u8>>> {
+    if policy == ArrayMergePolicy::Replace {
+        return Ok(None);
+    }
+
+    let base: Value = serde_json::from_slice(base).map_err(InternalError::SerdeJson)?;
+    let update: Value = serde_json::from_slice(update).map_err(InternalError::SerdeJson)?;
+    let (mut base, update) = match (base, update) {
+        (Value::Array(base), Value::Array(update)) => (base, update),
+        _ => return Ok(None),
+    };
+
+    match policy {
+        ArrayMergePolicy::Replace => unreachable!(),
+        ArrayMergePolicy::Append => base.extend(update),
+        ArrayMergePolicy::Union => {
+            for value in update {
+                if !base.contains(&value) {
+                    base.push(value);
+                }
+            }
+        }
+    }
+
+    Ok(Some(serde_json::to_vec(&base).map_err(InternalError::SerdeJson)?))
+}
+
 pub fn merge_cbo_roaring_bitmaps<'a>(
     _key: &[u8],
     values: &[Cow<'a, [u8]>],
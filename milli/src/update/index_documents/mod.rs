@@ -4,9 +4,13 @@ mod transform;
 mod typed_chunk;
 
 use std::collections::HashSet;
+#[cfg(feature = "compression")]
+use std::fs::File;
 use std::io::{Read, Seek};
 use std::iter::FromIterator;
+use std::mem;
 use std::num::{NonZeroU32, NonZeroUsize};
+use std::time::Instant;
 
 use crossbeam_channel::{Receiver, Sender};
 use log::debug;
@@ -23,14 +27,17 @@ pub use self::helpers::{
 use self::helpers::{grenad_obkv_into_chunks, GrenadParameters};
 pub use self::transform::{Transform, TransformOutput};
 use crate::documents::DocumentBatchReader;
+#[cfg(feature = "compression")]
+use crate::documents_codec;
+use crate::error::UserError;
 pub use crate::update::index_documents::helpers::CursorClonableMmap;
 use crate::update::{
     self, Facets, IndexerConfig, UpdateIndexingStep, WordPrefixDocids,
     WordPrefixPairProximityDocids, WordPrefixPositionDocids, WordsPrefixesFst,
 };
-use crate::{Index, Result};
+use crate::{facet, metrics, Index, Normalizer, Result};
 
-static MERGED_DATABASE_COUNT: usize = 7;
+static MERGED_DATABASE_COUNT: usize = 8;
 static PREFIX_DATABASE_COUNT: usize = 5;
 static TOTAL_POSTING_DATABASE_COUNT: usize = MERGED_DATABASE_COUNT + PREFIX_DATABASE_COUNT;
 
@@ -40,6 +47,27 @@ pub struct DocumentAdditionResult {
     pub indexed_documents: u64,
     /// The total number of documents in the index after the update
     pub number_of_documents: u64,
+    /// The number of invalid documents skipped when continuing on errors
+    #[serde(default)]
+    pub skipped_documents: u64,
+    /// The errors of the first skipped documents
+    #[serde(default)]
+    pub skipped_documents_errors: Vec<SkippedDocument>,
+    /// The number of documents sharing their id with a previous document of the update
+    #[serde(default)]
+    pub duplicate_documents: u64,
+    /// A sample of the duplicated documents ids
+    #[serde(default)]
+    pub duplicate_documents_ids: Vec<String>,
+}
+
+/// A document that was not indexed because it was invalid.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedDocument {
+    /// The position of the document among all the documents added to the builder
+    pub position: usize,
+    /// The reason why the document was skipped
+    pub error: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -60,6 +88,46 @@ impl Default for IndexDocumentsMethod {
     }
 }
 
+/// Defines which strings are accepted as external document ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocumentIdValidation {
+    /// Only alphanumeric characters (a-z A-Z 0-9), hyphens (-) and underscores (_) are accepted.
+    Strict,
+
+    /// Any non-empty string of at most `max_bytes` bytes is accepted,
+    /// e.g. emails, unicode ids or ids containing dots.
+    #[serde(rename_all = "camelCase")]
+    Relaxed { max_bytes: usize },
+}
+
+impl Default for DocumentIdValidation {
+    fn default() -> Self {
+        Self::Strict
+    }
+}
+
+/// How the values of an array field are merged when a document is updated
+/// with the `UpdateDocuments` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArrayMergePolicy {
+    /// The new array replaces the previous one.
+    Replace,
+
+    /// The values of the new array are added after the previous ones.
+    Append,
+
+    /// Only the values of the new array that are not already in the previous one are added.
+    Union,
+}
+
+impl Default for ArrayMergePolicy {
+    fn default() -> Self {
+        Self::Replace
+    }
+}
+
 pub struct IndexDocuments<'t, 'u, 'i, 'a, F> {
     wtxn: &'t mut heed::RwTxn<'i, 'u>,
     index: &'i Index,
@@ -80,6 +148,19 @@ pub struct IndexDocumentsConfig {
     pub words_positions_min_level_size: Option<NonZeroU32>,
     pub update_method: IndexDocumentsMethod,
     pub autogenerate_docids: bool,
+    /// The maximum number of invalid documents reported when a batch is rejected,
+    /// or when they are skipped.
+    pub max_reported_errors: Option<NonZeroUsize>,
+    /// The maximum nesting depth of the arrays and objects of a document field.
+    pub max_nesting_depth: Option<NonZeroUsize>,
+    /// The maximum number of fields of a document, counting the keys of its nested objects.
+    pub max_fields_count: Option<NonZeroUsize>,
+    /// Skips the invalid documents and reports them in the `DocumentAdditionResult`
+    /// instead of rejecting the whole batch.
+    pub continue_on_error: bool,
+    /// Counts the documents sharing the same id in the update and reports a sample of those ids
+    /// in the `DocumentAdditionResult`, this requires keeping all the ids in memory.
+    pub report_duplicates: bool,
 }
 
 impl<'t, 'u, 'i, 'a, F> IndexDocuments<'t, 'u, 'i, 'a, F>
@@ -93,12 +174,24 @@ where
         config: IndexDocumentsConfig,
         progress: F,
     ) -> IndexDocuments<'t, 'u, 'i, 'a, F> {
-        let transform = Some(Transform::new(
+        let mut transform = Transform::new(
             &index,
             indexer_config,
             config.update_method,
             config.autogenerate_docids,
-        ));
+        );
+        if let Some(max_reported_errors) = config.max_reported_errors {
+            transform.max_reported_errors = max_reported_errors.get();
+        }
+        if let Some(max_nesting_depth) = config.max_nesting_depth {
+            transform.max_nesting_depth = max_nesting_depth.get();
+        }
+        if let Some(max_fields_count) = config.max_fields_count {
+            transform.max_fields_count = max_fields_count.get();
+        }
+        transform.continue_on_error = config.continue_on_error;
+        transform.report_duplicates = config.report_duplicates;
+        let transform = Some(transform);
 
         IndexDocuments {
             transform,
@@ -138,21 +231,61 @@ where
         Ok(indexed_documents)
     }
 
+    /// Validates a batch of documents without adding it to the builder.
+    ///
+    /// The primary key is resolved and every document id is checked like it would be by
+    /// `add_documents`, but all the errors found are returned instead of the first one.
+    pub fn validate_documents<R>(&self, reader: DocumentBatchReader<R>) -> Result<Vec<UserError>>
+    where
+        R: Read + Seek,
+    {
+        self.transform
+            .as_ref()
+            .expect("Invalid document addition state")
+            .validate_documents(reader, &self.wtxn)
+    }
+
     #[logging_timer::time("IndexDocuments::{}")]
     pub fn execute(mut self) -> Result<DocumentAdditionResult> {
+        let mut transform = self.transform.take().expect("Invalid document addition state");
+        let skipped_documents = transform.skipped_documents;
+        let skipped_documents_errors = mem::take(&mut transform.skipped_documents_errors);
+        let duplicate_documents = transform.duplicate_documents;
+        let duplicate_documents_ids = mem::take(&mut transform.duplicate_documents_ids);
+
         if self.added_documents == 0 {
             let number_of_documents = self.index.number_of_documents(self.wtxn)?;
-            return Ok(DocumentAdditionResult { indexed_documents: 0, number_of_documents });
+            return Ok(DocumentAdditionResult {
+                indexed_documents: 0,
+                number_of_documents,
+                skipped_documents,
+                skipped_documents_errors,
+                duplicate_documents,
+                duplicate_documents_ids,
+            });
         }
-        let output = self
-            .transform
-            .take()
-            .expect("Invalid document addition state")
-            .output_from_sorter(self.wtxn, &self.progress)?;
+        let before_indexing = Instant::now();
+        let index = self.index;
+        let metrics = self.indexer_config.metrics.clone();
+        let output = transform.output_from_sorter(self.wtxn, &self.progress)?;
         let indexed_documents = output.documents_count as u64;
         let number_of_documents = self.execute_raw(output)?;
 
-        Ok(DocumentAdditionResult { indexed_documents, number_of_documents })
+        if let Some(sink) = metrics {
+            let duration = before_indexing.elapsed().as_secs_f64();
+            sink.record_histogram(metrics::INDEXING_DURATION_SECONDS, duration);
+            sink.increment_counter(metrics::INDEXED_DOCUMENTS_TOTAL, indexed_documents);
+            sink.set_gauge(metrics::DATABASE_SIZE_BYTES, index.on_disk_size()? as f64);
+        }
+
+        Ok(DocumentAdditionResult {
+            indexed_documents,
+            number_of_documents,
+            skipped_documents,
+            skipped_documents_errors,
+            duplicate_documents,
+            duplicate_documents_ids,
+        })
     }
 
     /// Returns the total number of documents in the index after the update.
@@ -163,7 +296,7 @@ where
     {
         let TransformOutput {
             primary_key,
-            fields_ids_map,
+            mut fields_ids_map,
             field_distribution,
             external_documents_ids,
             new_documents_ids,
@@ -172,6 +305,16 @@ where
             documents_file,
         } = output;
 
+        // The faceted fields that are paths into the nested objects of the documents are
+        // not document fields, they must be given an id to store their facet values.
+        let faceted_field_names = self.index.faceted_fields(self.wtxn)?;
+        for name in &faceted_field_names {
+            if facet::is_facet_path(&fields_ids_map, name) {
+                fields_ids_map.insert(name).ok_or(UserError::AttributeLimitReached)?;
+            }
+        }
+        let facet_paths = facet::facet_paths(&fields_ids_map, &faceted_field_names);
+
         // The fields_ids_map is put back to the store now so the rest of the transaction sees an
         // up to date field map.
         self.index.put_fields_ids_map(self.wtxn, &fields_ids_map)?;
@@ -195,6 +338,17 @@ where
             }
         };
 
+        // The compression dictionary is trained on the first documents added to the index.
+        #[cfg(feature = "compression")]
+        if self.indexer_config.compress_documents
+            && self.index.documents_compression_dictionary(self.wtxn)?.is_none()
+        {
+            let samples = sample_documents(&documents_file)?;
+            if let Some(dictionary) = documents_codec::train_dictionary(&samples) {
+                self.index.put_documents_compression_dictionary(self.wtxn, &dictionary)?;
+            }
+        }
+
         let documents_file = grenad::Reader::new(documents_file)?;
 
         // create LMDB writer channel
@@ -211,6 +365,10 @@ where
             self.index.searchable_fields_ids(self.wtxn)?.map(HashSet::from_iter);
         // get filterable fields for facet databases
         let faceted_fields = self.index.faceted_fields_ids(self.wtxn)?;
+        let hierarchical_fields = self.index.hierarchical_facets_ids(self.wtxn)?;
+        let date_fields = self.index.date_fields_ids(self.wtxn)?;
+        let facet_normalization = self.index.facet_normalization_ids(self.wtxn)?;
+        let exact_attributes = self.index.exact_attributes_ids(self.wtxn)?;
         // get the fid of the `_geo` field.
         let geo_field_id = match self.index.fields_ids_map(self.wtxn)?.id("_geo") {
             Some(gfid) => {
@@ -226,6 +384,8 @@ where
         };
 
         let stop_words = self.index.stop_words(self.wtxn)?;
+        let decompounding_dictionary = self.index.decompounding_dictionary(self.wtxn)?;
+        let normalizer = Normalizer::from_index(self.index, self.wtxn)?;
 
         // Run extraction pipeline in parallel.
         pool.install(|| {
@@ -251,10 +411,17 @@ where
                     lmdb_writer_sx.clone(),
                     searchable_fields,
                     faceted_fields,
+                    facet_paths,
+                    hierarchical_fields,
+                    date_fields,
+                    facet_normalization,
                     primary_key_id,
                     geo_field_id,
                     stop_words,
+                    decompounding_dictionary,
+                    &normalizer,
                     self.indexer_config.max_positions_per_attributes,
+                    exact_attributes,
                 )
             });
 
@@ -384,6 +551,12 @@ where
             total_databases: TOTAL_POSTING_DATABASE_COUNT,
         });
 
+        // Run the phonetic words update operation.
+        #[cfg(feature = "phonetic")]
+        if self.index.phonetic_matching(self.wtxn)? {
+            update::PhoneticWordsFst::new(self.wtxn, self.index).execute()?;
+        }
+
         let previous_words_prefixes_fst =
             self.index.words_prefixes_fst(self.wtxn)?.map_data(|cow| cow.into_owned())?;
 
@@ -497,16 +670,40 @@ where
     }
 }
 
+/// Returns the first documents of the file, to train the compression dictionary on,
+/// and rewinds the file for the documents to be indexed.
+#[cfg(feature = "compression")]
+fn sample_documents(documents_file: &File) -> Result<Vec<Vec<u8>>> {
+    use std::io::SeekFrom;
+
+    let mut cursor = grenad::Reader::new(documents_file.try_clone()?)?.into_cursor()?;
+    let mut samples = Vec::new();
+    let mut samples_size = 0;
+    while let Some((_, obkv)) = cursor.move_on_next()? {
+        if samples_size >= documents_codec::SAMPLES_MAX_SIZE {
+            break;
+        }
+        samples_size += obkv.len();
+        samples.push(obkv.to_vec());
+    }
+    // The cloned file shares its offset with the documents file.
+    let mut documents_file = documents_file;
+    documents_file.seek(SeekFrom::Start(0))?;
+    Ok(samples)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
     use big_s::S;
     use heed::EnvOpenOptions;
+    use maplit::{btreemap, hashmap, hashset};
 
     use super::*;
     use crate::documents::DocumentBatchBuilder;
-    use crate::update::DeleteDocuments;
+    use crate::error::Error;
+    use crate::update::{DeleteDocuments, Settings};
     use crate::HashMap;
 
     #[test]
@@ -605,8 +802,8 @@ mod tests {
         // Check that we get only one document from the database.
         let docs = index.documents(&rtxn, Some(0)).unwrap();
         assert_eq!(docs.len(), 1);
-        let (id, doc) = docs[0];
-        assert_eq!(id, 0);
+        let (id, doc) = &docs[0];
+        assert_eq!(*id, 0);
 
         // Check that this document is equal to the last one sent.
         let mut doc_iter = doc.iter();
@@ -631,8 +828,8 @@ mod tests {
         // Check that we get only one document from the database.
         let docs = index.documents(&rtxn, Some(0)).unwrap();
         assert_eq!(docs.len(), 1);
-        let (id, doc) = docs[0];
-        assert_eq!(id, 0);
+        let (id, doc) = &docs[0];
+        assert_eq!(*id, 0);
 
         // Check that this document is equal to the last one sent.
         let mut doc_iter = doc.iter();
@@ -699,7 +896,11 @@ mod tests {
         assert_eq!(count, 3);
 
         let docs = index.documents(&rtxn, vec![0, 1, 2]).unwrap();
-        let (_id, obkv) = docs.iter().find(|(_id, kv)| kv.get(0) == Some(br#""kevin""#)).unwrap();
+        let obkv = docs
+            .iter()
+            .map(|(_id, document)| document.obkv())
+            .find(|kv| kv.get(0) == Some(br#""kevin""#))
+            .unwrap();
         let kevin_uuid: String = serde_json::from_slice(&obkv.get(1).unwrap()).unwrap();
         drop(rtxn);
 
@@ -717,6 +918,7 @@ mod tests {
         assert_eq!(count, 3);
 
         let docs = index.documents(&rtxn, vec![0, 1, 2]).unwrap();
+        let docs: Vec<_> = docs.iter().map(|(id, document)| (*id, document.obkv())).collect();
         let (kevin_id, _) =
             docs.iter().find(|(_, d)| d.get(0).unwrap() == br#""updated kevin""#).unwrap();
         let (id, doc) = docs[*kevin_id as usize];
@@ -832,6 +1034,269 @@ mod tests {
         drop(rtxn);
     }
 
+    #[test]
+    fn validate_documents_reports_every_error() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "brume bleue", "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+            { "name": "benoit" },
+            { "id": true, "name": "bernard" }
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config = IndexDocumentsConfig::default();
+        let builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        let errors = builder.validate_documents(content).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(errors[0], UserError::InvalidDocumentId { .. }));
+        assert!(matches!(errors[1], UserError::MissingDocumentId { .. }));
+        assert!(matches!(errors[2], UserError::InvalidDocumentId { .. }));
+        wtxn.commit().unwrap();
+
+        // Nothing must have been written, not even the primary key.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 0);
+        assert_eq!(index.primary_key(&rtxn).unwrap(), None);
+    }
+
+    #[test]
+    fn relaxed_document_id_validation() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        // The strict validation is used by default and rejects emails.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": "John.Doe@example.com", "name": "john" }]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        assert!(builder.add_documents(content).is_err());
+        drop(builder);
+        wtxn.abort().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_document_id_validation(DocumentIdValidation::Relaxed { max_bytes: 32 });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": "John.Doe@example.com", "name": "john" },
+            { "id": "3F2504E0-4F89-11D3-9A0C-0305E82C3301.v2", "name": "uuid" },
+            { "id": "ドキュメント", "name": "unicode" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        let errors = builder.validate_documents(content).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], UserError::InvalidRelaxedDocumentId { max_bytes: 32, .. }));
+
+        let content = documents!([
+            { "id": "John.Doe@example.com", "name": "john" },
+            { "id": "ドキュメント", "name": "unicode" }
+        ]);
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 2);
+        assert!(external_documents_ids.get("John.Doe@example.com").is_some());
+        assert!(external_documents_ids.get("ドキュメント").is_some());
+    }
+
+    #[test]
+    fn invalid_documents_are_reported_together() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "brume bleue", "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+            { "name": "benoit" },
+            { "id": true, "name": "bernard" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        match builder.add_documents(content) {
+            Err(Error::UserError(UserError::InvalidDocuments { errors, omitted_errors })) => {
+                assert_eq!(errors.len(), 3);
+                assert_eq!(omitted_errors, 0);
+                assert!(matches!(errors[0], UserError::InvalidDocumentId { .. }));
+                assert!(matches!(errors[1], UserError::MissingDocumentId { .. }));
+                assert!(matches!(errors[2], UserError::InvalidDocumentId { .. }));
+            }
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        drop(builder);
+
+        // Only the first errors are reported when the limit is reached.
+        let content = documents!([
+            { "id": "brume bleue", "name": "kevin" },
+            { "name": "benoit" },
+            { "id": true, "name": "bernard" }
+        ]);
+        let indexing_config = IndexDocumentsConfig {
+            max_reported_errors: NonZeroUsize::new(1),
+            ..Default::default()
+        };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        match builder.add_documents(content) {
+            Err(Error::UserError(UserError::InvalidDocuments { errors, omitted_errors })) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(omitted_errors, 2);
+            }
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+        drop(builder);
+
+        // A single invalid document is reported as is.
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": "brume bleue", "name": "kevina" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        assert!(matches!(
+            builder.add_documents(content),
+            Err(Error::UserError(UserError::InvalidDocumentId { .. }))
+        ));
+    }
+
+    #[test]
+    fn continue_on_error_skips_invalid_documents() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let indexing_config =
+            IndexDocumentsConfig { continue_on_error: true, ..Default::default() };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": "brume bleue", "name": "kevina" },
+            { "id": 3, "name": "benoit" }
+        ]);
+        assert_eq!(builder.add_documents(content).unwrap(), 2);
+        let content = documents!([
+            { "name": "bernard" },
+            { "id": 5, "name": "bertrand" }
+        ]);
+        assert_eq!(builder.add_documents(content).unwrap(), 1);
+        let result = builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(result.indexed_documents, 3);
+        assert_eq!(result.number_of_documents, 3);
+        assert_eq!(result.skipped_documents, 2);
+        let positions: Vec<_> =
+            result.skipped_documents_errors.iter().map(|s| s.position).collect();
+        assert_eq!(positions, vec![1, 3]);
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        assert!(external_documents_ids.get("1").is_some());
+        assert!(external_documents_ids.get("3").is_some());
+        assert!(external_documents_ids.get("5").is_some());
+    }
+
+    #[test]
+    fn report_duplicate_documents() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let indexing_config =
+            IndexDocumentsConfig { report_duplicates: true, ..Default::default() };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+            { "id": 1, "name": "benoit" }
+        ]);
+        builder.add_documents(content).unwrap();
+        let content = documents!([
+            { "id": 1, "name": "bernard" },
+            { "id": 2, "name": "bertrand" },
+            { "id": 3, "name": "bruno" }
+        ]);
+        builder.add_documents(content).unwrap();
+        let result = builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(result.indexed_documents, 3);
+        assert_eq!(result.duplicate_documents, 3);
+        assert_eq!(result.duplicate_documents_ids, vec![S("1"), S("2")]);
+    }
+
+    #[test]
+    fn reject_too_deep_documents() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let indexing_config =
+            IndexDocumentsConfig { max_nesting_depth: NonZeroUsize::new(2), ..Default::default() };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        let content = documents!([
+            { "id": 0, "name": "kevin", "object": { "key1": ["value1"] } },
+            { "id": 1, "name": "kevina", "object": { "key1": [{ "key2": "value2" }] } }
+        ]);
+        match builder.add_documents(content) {
+            Err(Error::UserError(UserError::DocumentTooDeep { document_id, max_depth })) => {
+                assert_eq!(document_id, "1");
+                assert_eq!(max_depth, 2);
+            }
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn reject_documents_with_too_many_fields() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let indexing_config =
+            IndexDocumentsConfig { max_fields_count: NonZeroUsize::new(4), ..Default::default() };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        // The keys of the nested objects are counted as fields, not the ones inside strings.
+        let content = documents!([
+            { "id": 0, "name": "kevin", "object": { "key1": "a: b" } },
+            { "id": 1, "name": "kevina", "object": { "key1": [{ "key2": "value2" }] } }
+        ]);
+        match builder.add_documents(content) {
+            Err(Error::UserError(UserError::DocumentTooManyFields { document_id, max_fields })) => {
+                assert_eq!(document_id, "1");
+                assert_eq!(max_fields, 4);
+            }
+            otherwise => panic!("unexpected result {:?}", otherwise),
+        }
+    }
+
     #[test]
     fn complex_documents() {
         let path = tempfile::tempdir().unwrap();
@@ -1203,4 +1668,389 @@ mod tests {
         let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
         assert_eq!(documents_ids.len(), 1);
     }
+
+    #[test]
+    fn big_integer_facets_are_exact() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("big") });
+        builder.set_sortable_fields(hashset! { S("big") });
+        builder.execute(|_| ()).unwrap();
+
+        // The first two values are rounded to the same float.
+        let content = documents!([
+            { "id": 0, "big": 9007199254740993i64 },
+            { "id": 1, "big": 9007199254740992i64 },
+            { "id": 2, "big": 9007199254740994i64 },
+            { "id": 3, "big": 1 }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter_ids = |expression: &'static str| {
+            let mut search = crate::Search::new(&rtxn, &index);
+            search.filter(crate::Filter::from_str(expression).unwrap().unwrap());
+            let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+            documents_ids
+        };
+        assert_eq!(filter_ids("big = 9007199254740993"), vec![0]);
+        assert_eq!(filter_ids("big != 9007199254740993"), vec![1, 2, 3]);
+        assert_eq!(filter_ids("big > 9007199254740992"), vec![0, 2]);
+        assert_eq!(filter_ids("big 9007199254740992 TO 9007199254740992"), vec![1]);
+
+        for (ascending, expected) in [(true, vec![3, 1, 0, 2]), (false, vec![2, 0, 1, 3])] {
+            let member = crate::Member::Field(S("big"));
+            let asc_desc =
+                if ascending { crate::AscDesc::Asc(member) } else { crate::AscDesc::Desc(member) };
+            let mut search = crate::Search::new(&rtxn, &index);
+            search.sort_criteria(vec![asc_desc]);
+            let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+            assert_eq!(documents_ids, expected);
+        }
+    }
+
+    #[test]
+    fn integer_facets_above_i64_max_are_exact() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("big") });
+        builder.set_sortable_fields(hashset! { S("big") });
+        builder.execute(|_| ()).unwrap();
+
+        // The first two values are above i64::MAX and rounded to the same float.
+        let content = documents!([
+            { "id": 0, "big": 18446744073709551615u64 },
+            { "id": 1, "big": 18446744073709551614u64 },
+            { "id": 2, "big": 9223372036854775808u64 },
+            { "id": 3, "big": -1 }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter_ids = |expression: &'static str| {
+            let mut search = crate::Search::new(&rtxn, &index);
+            search.filter(crate::Filter::from_str(expression).unwrap().unwrap());
+            let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+            documents_ids
+        };
+        assert_eq!(filter_ids("big = 18446744073709551615"), vec![0]);
+        assert_eq!(filter_ids("big != 18446744073709551615"), vec![1, 2, 3]);
+        assert_eq!(filter_ids("big > 18446744073709551614"), vec![0]);
+        assert_eq!(filter_ids("big >= 9223372036854775808"), vec![0, 1, 2]);
+
+        for (ascending, expected) in [(true, vec![3, 2, 1, 0]), (false, vec![0, 1, 2, 3])] {
+            let member = crate::Member::Field(S("big"));
+            let asc_desc =
+                if ascending { crate::AscDesc::Asc(member) } else { crate::AscDesc::Desc(member) };
+            let mut search = crate::Search::new(&rtxn, &index);
+            search.sort_criteria(vec![asc_desc]);
+            let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+            assert_eq!(documents_ids, expected);
+        }
+    }
+
+    #[test]
+    fn facets_on_nested_paths() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("genres.name"), S("genres.year") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "genres": [{ "name": "Drama", "year": 1999 }, { "name": "Drama" }, { "name": "Comedy" }] },
+            { "id": 1, "genres": [{ "name": "Drama", "year": 2004 }] },
+            { "id": 2, "genres": { "name": "Horror" } },
+            { "id": 3, "title": "no genres" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let distribution =
+            index.facets_distribution(&rtxn).facets(Some("genres.name")).execute().unwrap();
+        let expected = btreemap! {
+            S("Comedy") => 1,
+            S("Drama") => 2,
+            S("Horror") => 1,
+        };
+        assert_eq!(distribution["genres.name"], expected);
+
+        let filter_ids = |expression: &'static str| {
+            let mut search = crate::Search::new(&rtxn, &index);
+            search.filter(crate::Filter::from_str(expression).unwrap().unwrap());
+            let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+            documents_ids
+        };
+        assert_eq!(filter_ids("genres.name = Drama"), vec![0, 1]);
+        assert_eq!(filter_ids("genres.name = Horror"), vec![2]);
+        assert_eq!(filter_ids("genres.year > 2000"), vec![1]);
+    }
+
+    #[test]
+    fn validate_geo_points() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("_geo") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let invalid_points = [
+            serde_json::json!({ "lat": 91, "lng": 2 }),
+            serde_json::json!({ "lat": 45, "lng": -180.5 }),
+            serde_json::json!({ "lat": "north", "lng": 2 }),
+            serde_json::json!({ "lat": 45 }),
+        ];
+        for point in invalid_points {
+            let mut wtxn = index.write_txn().unwrap();
+            let content = documents!([{ "id": 0, "_geo": point }]);
+            let indexing_config = IndexDocumentsConfig::default();
+            let mut builder =
+                IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+            builder.add_documents(content).unwrap();
+            let result = builder.execute();
+            assert!(matches!(result, Err(Error::UserError(UserError::InvalidGeoField { .. }))));
+        }
+
+        // The coordinates can be given as strings.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": "50.63", "lng": " 3.06 " } },
+            { "id": 1, "_geo": { "lat": -90, "lng": 180 } }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let rtree = index.geo_rtree(&rtxn).unwrap().unwrap();
+        let mut points: Vec<_> = rtree.iter().map(|point| point.data).collect();
+        points.sort_by_key(|(docid, _)| *docid);
+        assert_eq!(points, vec![(0, [50.63, 3.06]), (1, [-90.0, 180.0])]);
+        assert_eq!(index.geo_faceted_documents_ids(&rtxn).unwrap().len(), 2);
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        counters: std::sync::Mutex<HashMap<&'static str, u64>>,
+        observations: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    impl crate::MetricsSink for RecordingSink {
+        fn increment_counter(&self, name: &'static str, value: u64) {
+            *self.counters.lock().unwrap().entry(name).or_default() += value;
+        }
+
+        fn record_histogram(&self, name: &'static str, _value: f64) {
+            self.observations.lock().unwrap().push(name);
+        }
+
+        fn set_gauge(&self, name: &'static str, value: f64) {
+            assert!(value > 0.0);
+            self.observations.lock().unwrap().push(name);
+        }
+    }
+
+    #[test]
+    fn report_metrics() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let sink = std::sync::Arc::new(RecordingSink::default());
+        let config = IndexerConfig { metrics: Some(sink.clone()), ..Default::default() };
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin" },
+            { "id": 1, "name": "kevina" },
+            { "id": 2, "name": "benoit" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.query("kevin").metrics(sink.clone());
+        search.execute().unwrap();
+
+        let counters = sink.counters.lock().unwrap();
+        assert_eq!(counters[crate::metrics::INDEXED_DOCUMENTS_TOTAL], 3);
+        assert_eq!(counters[crate::metrics::SEARCHES_TOTAL], 1);
+        let observations = sink.observations.lock().unwrap();
+        assert_eq!(
+            *observations,
+            vec![
+                crate::metrics::INDEXING_DURATION_SECONDS,
+                crate::metrics::DATABASE_SIZE_BYTES,
+                crate::metrics::SEARCH_DURATION_SECONDS,
+                crate::metrics::SEARCH_CANDIDATES,
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compressed_documents() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(100 * 1024 * 1024); // 100 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig { compress_documents: true, ..Default::default() };
+
+        let words = ["the", "quick", "brown", "fox", "jumps", "over", "the", "lazy", "dog"];
+        let documents: Vec<_> = (0..2000)
+            .map(|i| {
+                let description: Vec<_> =
+                    (0..20).map(|j| words[(i * 7 + j * 3) % words.len()]).collect();
+                serde_json::json!({
+                    "id": i,
+                    "title": format!("document number {}", i),
+                    "description": description.join(" "),
+                })
+            })
+            .collect();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!(documents);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.documents_compression_dictionary(&rtxn).unwrap().is_some());
+        let stored_size: usize = index
+            .documents
+            .remap_data_type::<heed::types::ByteSlice>()
+            .iter(&rtxn)
+            .unwrap()
+            .map(|result| result.unwrap().1.len())
+            .sum();
+        let obkvs_size: usize = index
+            .all_documents(&rtxn)
+            .unwrap()
+            .map(|result| result.unwrap().1.as_bytes().len())
+            .sum();
+        assert!(stored_size < obkvs_size);
+        let docid = index.external_documents_ids(&rtxn).unwrap().get("42").unwrap();
+        drop(rtxn);
+
+        // The updated document is merged with the decompressed stored one.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 42, "title": "updated" }]);
+        let indexing_config = IndexDocumentsConfig {
+            update_method: IndexDocumentsMethod::UpdateDocuments,
+            ..Default::default()
+        };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let (_, stored) = index.documents(&rtxn, Some(docid)).unwrap().remove(0);
+        let document = crate::Document::new(&fields_ids_map, stored.obkv());
+        assert_eq!(document.get_str("title").as_deref(), Some("updated"));
+        assert!(document.get_str("description").is_some());
+        drop(rtxn);
+
+        // The external id of a deleted document is read from the decompressed document.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        builder.delete_external_id("43").unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.external_documents_ids(&rtxn).unwrap().get("43"), None);
+        assert_eq!(index.field_distribution(&rtxn).unwrap().get("title"), Some(&1999));
+    }
+
+    #[test]
+    fn array_merge_policies() {
+        let index = crate::index::tests::TempIndex::new();
+        index
+            .add_documents(serde_json::json!([
+                { "id": 1, "tags": ["red", "blue"], "colors": ["red"], "sizes": ["s"], "title": "a" },
+            ]))
+            .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_array_merge_policies(hashmap! {
+                    S("tags") => ArrayMergePolicy::Union,
+                    S("colors") => ArrayMergePolicy::Append,
+                    S("title") => ArrayMergePolicy::Append,
+                })
+            })
+            .unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let config = IndexerConfig::default();
+        let content = documents!([
+            { "id": 1, "tags": ["blue", "green"], "colors": ["red"], "sizes": ["m"], "title": "b" },
+        ]);
+        let indexing_config = IndexDocumentsConfig {
+            update_method: IndexDocumentsMethod::UpdateDocuments,
+            ..Default::default()
+        };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let (_, document) = index.documents(&rtxn, Some(0)).unwrap().remove(0);
+        let value = |name| {
+            let bytes = document.get(fields_ids_map.id(name).unwrap()).unwrap();
+            serde_json::from_slice::<serde_json::Value>(bytes).unwrap()
+        };
+        // The sets are merged, the lists appended, the other fields replaced.
+        assert_eq!(value("tags"), serde_json::json!(["red", "blue", "green"]));
+        assert_eq!(value("colors"), serde_json::json!(["red", "red"]));
+        assert_eq!(value("sizes"), serde_json::json!(["m"]));
+        assert_eq!(value("title"), serde_json::json!("b"));
+    }
 }
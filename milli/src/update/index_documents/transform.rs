@@ -1,27 +1,42 @@
 use std::borrow::Cow;
 use std::collections::btree_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
+use std::result::Result as StdResult;
 use std::time::Instant;
 
+use heed::types::ByteSlice;
 use itertools::Itertools;
 use log::info;
 use roaring::RoaringBitmap;
 use serde_json::{Map, Value};
 
 use super::helpers::{
-    create_sorter, create_writer, keep_latest_obkv, merge_obkvs, merge_two_obkvs, MergeFn,
+    create_sorter, create_writer, keep_latest_obkv, merge_obkvs, merge_two_obkvs_with_policies,
+    MergeFn,
 };
-use super::{IndexDocumentsMethod, IndexerConfig};
+use super::{DocumentIdValidation, IndexDocumentsMethod, IndexerConfig, SkippedDocument};
 use crate::documents::{DocumentBatchReader, DocumentsBatchIndex};
-use crate::error::{Error, InternalError, UserError};
+use crate::error::{Error, InternalError, Object, UserError};
 use crate::index::db_name;
 use crate::update::{AvailableDocumentsIds, UpdateIndexingStep};
 use crate::{ExternalDocumentsIds, FieldDistribution, FieldId, FieldsIdsMap, Index, Result, BEU32};
 
 const DEFAULT_PRIMARY_KEY_NAME: &str = "id";
 
+/// The number of invalid documents reported by default when a batch is rejected.
+const DEFAULT_MAX_REPORTED_ERRORS: usize = 20;
+
+/// The maximum nesting depth of the arrays and objects of a field by default.
+const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+
+/// The maximum number of fields of a document by default, the number of field ids.
+const DEFAULT_MAX_FIELDS_COUNT: usize = u16::MAX as usize;
+
+/// The number of duplicated document ids kept as a sample when reporting duplicates.
+const DUPLICATE_IDS_SAMPLE_SIZE: usize = 10;
+
 pub struct TransformOutput {
     pub primary_key: String,
     pub fields_ids_map: FieldsIdsMap,
@@ -44,9 +59,20 @@ pub struct Transform<'a, 'i> {
     indexer_settings: &'a IndexerConfig,
     pub autogenerate_docids: bool,
     pub index_documents_method: IndexDocumentsMethod,
+    pub max_reported_errors: usize,
+    pub max_nesting_depth: usize,
+    pub max_fields_count: usize,
+    pub continue_on_error: bool,
+    pub skipped_documents: u64,
+    pub skipped_documents_errors: Vec<SkippedDocument>,
+    pub report_duplicates: bool,
+    pub duplicate_documents: u64,
+    pub duplicate_documents_ids: Vec<String>,
 
     sorter: grenad::Sorter<MergeFn>,
     documents_count: usize,
+    documents_seen: usize,
+    seen_external_ids: HashSet<String>,
 }
 
 /// Create a mapping between the field ids found in the document batch and the one that were
@@ -72,13 +98,45 @@ fn create_fields_mapping(
         .collect()
 }
 
-fn find_primary_key(index: &DocumentsBatchIndex) -> Option<&str> {
-    index
+/// Returns whether a field name looks like a document identifier, i.e. it ends with `id` right
+/// after a separator (`user_id`, `user-id`, `user.id`) or a camel case boundary (`userId`).
+/// Fields like `paid` or `video` are not considered.
+fn is_primary_key_candidate(name: &str) -> bool {
+    if let Some(head) = name.strip_suffix("Id").or_else(|| name.strip_suffix("ID")) {
+        if head.ends_with(char::is_lowercase) {
+            return true;
+        }
+    }
+
+    match name.to_lowercase().strip_suffix(DEFAULT_PRIMARY_KEY_NAME) {
+        Some(head) => head.ends_with(|c| matches!(c, '_' | '-' | '.')),
+        None => false,
+    }
+}
+
+/// Infers the primary key from the fields of a batch.
+///
+/// A field named `id` or `_id` is always preferred, then the fields ending with an `id` word are
+/// considered. If several fields are candidates an error listing them is returned.
+fn find_primary_key(index: &DocumentsBatchIndex) -> StdResult<Option<&str>, UserError> {
+    let fields: Vec<_> = index.iter().sorted_by_key(|(k, _)| *k).map(|(_, v)| v.as_str()).collect();
+
+    let exact_candidates = fields
         .iter()
-        .sorted_by_key(|(k, _)| *k)
-        .map(|(_, v)| v)
-        .find(|v| v.to_lowercase().contains(DEFAULT_PRIMARY_KEY_NAME))
-        .map(String::as_str)
+        .copied()
+        .filter(|name| matches!(name.to_lowercase().as_str(), DEFAULT_PRIMARY_KEY_NAME | "_id"));
+    let mut candidates: Vec<_> = exact_candidates.collect();
+    if candidates.is_empty() {
+        candidates = fields.into_iter().filter(|name| is_primary_key_candidate(name)).collect();
+    }
+
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [primary_key] => Ok(Some(*primary_key)),
+        candidates => Err(UserError::MultiplePrimaryKeyCandidatesFound {
+            candidates: candidates.iter().map(|name| name.to_string()).collect(),
+        }),
+    }
 }
 
 impl<'a, 'i> Transform<'a, 'i> {
@@ -110,7 +168,18 @@ impl<'a, 'i> Transform<'a, 'i> {
             autogenerate_docids,
             sorter,
             documents_count: 0,
+            documents_seen: 0,
             index_documents_method,
+            max_reported_errors: DEFAULT_MAX_REPORTED_ERRORS,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_fields_count: DEFAULT_MAX_FIELDS_COUNT,
+            continue_on_error: false,
+            skipped_documents: 0,
+            skipped_documents_errors: Vec::new(),
+            report_duplicates: false,
+            duplicate_documents: 0,
+            duplicate_documents_ids: Vec::new(),
+            seen_external_ids: HashSet::new(),
         }
     }
 
@@ -128,11 +197,10 @@ impl<'a, 'i> Transform<'a, 'i> {
         let mut fields_ids_map = self.index.fields_ids_map(wtxn)?;
         let mapping = create_fields_mapping(&mut fields_ids_map, fields_index)?;
 
-        let alternative_name = self
-            .index
-            .primary_key(wtxn)?
-            .or_else(|| find_primary_key(fields_index))
-            .map(String::from);
+        let alternative_name = match self.index.primary_key(wtxn)? {
+            Some(primary_key) => Some(primary_key.to_string()),
+            None => find_primary_key(fields_index)?.map(String::from),
+        };
 
         let (primary_key_id, primary_key_name) = compute_primary_key_pair(
             self.index.primary_key(wtxn)?,
@@ -140,12 +208,17 @@ impl<'a, 'i> Transform<'a, 'i> {
             alternative_name,
             self.autogenerate_docids,
         )?;
+        let id_validation = self.index.document_id_validation(wtxn)?;
 
         let mut obkv_buffer = Vec::new();
         let mut documents_count = 0;
         let mut external_id_buffer = Vec::new();
         let mut field_buffer: Vec<(u16, &[u8])> = Vec::new();
+        let mut invalid_documents = Vec::new();
+        let mut omitted_errors = 0;
         while let Some((addition_index, document)) = reader.next_document_with_index()? {
+            let position = self.documents_seen;
+            self.documents_seen += 1;
             let mut field_buffer_cache = drop_and_reuse(field_buffer);
             if self.indexer_settings.log_every_n.map_or(false, |len| documents_count % len == 0) {
                 progress_callback(UpdateIndexingStep::RemapDocumentAddition {
@@ -167,55 +240,88 @@ impl<'a, 'i> Transform<'a, 'i> {
             let external_id =
                 match field_buffer_cache.iter_mut().find(|(id, _)| *id == primary_key_id) {
                     Some((_, bytes)) => {
-                        let value = match serde_json::from_slice(bytes).unwrap() {
-                            Value::String(string) => match validate_document_id(&string) {
-                                Some(s) if s.len() == string.len() => string,
-                                Some(s) => s.to_string(),
-                                None => {
-                                    return Err(UserError::InvalidDocumentId {
-                                        document_id: Value::String(string),
-                                    }
-                                    .into())
-                                }
-                            },
-                            Value::Number(number) => number.to_string(),
-                            content => {
-                                return Err(UserError::InvalidDocumentId {
-                                    document_id: content.clone(),
-                                }
-                                .into())
-                            }
-                        };
-                        serde_json::to_writer(&mut external_id_buffer, &value).unwrap();
-                        Cow::Owned(value)
+                        let value = serde_json::from_slice(bytes).unwrap();
+                        external_id_from_json(value, id_validation).map(|value| {
+                            serde_json::to_writer(&mut external_id_buffer, &value).unwrap();
+                            Cow::Owned(value)
+                        })
                     }
-                    None => {
-                        if !self.autogenerate_docids {
-                            let mut json = Map::new();
-                            for (key, value) in document.iter() {
-                                let key = addition_index.name(key).cloned();
-                                let value = serde_json::from_slice::<Value>(&value).ok();
-
-                                if let Some((k, v)) = key.zip(value) {
-                                    json.insert(k, v);
-                                }
-                            }
-
-                            return Err(UserError::MissingDocumentId {
-                                primary_key: primary_key_name,
-                                document: json,
-                            }
-                            .into());
-                        }
-
+                    None if self.autogenerate_docids => {
                         let uuid =
                             uuid::Uuid::new_v4().to_hyphenated().encode_lower(&mut uuid_buffer);
                         serde_json::to_writer(&mut external_id_buffer, &uuid).unwrap();
                         field_buffer_cache.push((primary_key_id, &external_id_buffer));
-                        Cow::Borrowed(&*uuid)
+                        Ok(Cow::Borrowed(&*uuid))
                     }
+                    None => Err(UserError::MissingDocumentId {
+                        primary_key: primary_key_name.clone(),
+                        document: document_to_json(addition_index, document),
+                    }),
                 };
 
+            // We reject the documents that are too deeply nested or that have too many fields,
+            // counting the fields of their nested objects, before they reach the extractors.
+            let max_nesting_depth = self.max_nesting_depth;
+            let max_fields_count = self.max_fields_count;
+            let external_id = external_id.and_then(|external_id| {
+                let too_deep = field_buffer_cache
+                    .iter()
+                    .any(|(_, value)| json_nesting_depth(value) > max_nesting_depth);
+                let fields_count = field_buffer_cache.len()
+                    + field_buffer_cache
+                        .iter()
+                        .map(|(_, value)| json_object_keys_count(value))
+                        .sum::<usize>();
+                if too_deep {
+                    Err(UserError::DocumentTooDeep {
+                        document_id: external_id.into_owned(),
+                        max_depth: max_nesting_depth,
+                    })
+                } else if fields_count > max_fields_count {
+                    Err(UserError::DocumentTooManyFields {
+                        document_id: external_id.into_owned(),
+                        max_fields: max_fields_count,
+                    })
+                } else {
+                    Ok(external_id)
+                }
+            });
+
+            // An invalid document doesn't abort the transform right away, we keep reading
+            // the batch to report as many invalid documents as possible at once, or we skip
+            // it when we were told to continue on errors.
+            let external_id = match external_id {
+                Ok(external_id) => external_id,
+                Err(error) => {
+                    if self.continue_on_error {
+                        self.skipped_documents += 1;
+                        if self.skipped_documents_errors.len() < self.max_reported_errors {
+                            let error = error.to_string();
+                            self.skipped_documents_errors.push(SkippedDocument { position, error });
+                        }
+                    } else if invalid_documents.len() < self.max_reported_errors {
+                        invalid_documents.push(error);
+                    } else {
+                        omitted_errors += 1;
+                    }
+                    field_buffer = drop_and_reuse(field_buffer_cache);
+                    external_id_buffer.clear();
+                    continue;
+                }
+            };
+
+            // The sorter silently merges the documents sharing the same external id, we keep
+            // track of the ids we have already seen to report those duplicates.
+            if self.report_duplicates && !self.seen_external_ids.insert(external_id.to_string()) {
+                self.duplicate_documents += 1;
+                let external_id = external_id.as_ref();
+                if self.duplicate_documents_ids.len() < DUPLICATE_IDS_SAMPLE_SIZE
+                    && !self.duplicate_documents_ids.iter().any(|id| id == external_id)
+                {
+                    self.duplicate_documents_ids.push(external_id.to_string());
+                }
+            }
+
             // Insertion in a obkv need to be done with keys ordered. For now they are ordered
             // according to the document addition key order, so we sort it according to the
             // fieldids map keys order.
@@ -244,6 +350,18 @@ impl<'a, 'i> Transform<'a, 'i> {
             documents_seen: documents_count,
         });
 
+        match invalid_documents.len() {
+            0 => (),
+            1 if omitted_errors == 0 => return Err(invalid_documents.remove(0).into()),
+            _ => {
+                return Err(UserError::InvalidDocuments {
+                    errors: invalid_documents,
+                    omitted_errors,
+                }
+                .into())
+            }
+        }
+
         self.index.put_fields_ids_map(wtxn, &fields_ids_map)?;
         self.index.put_primary_key(wtxn, &primary_key_name)?;
         self.documents_count += documents_count;
@@ -252,6 +370,48 @@ impl<'a, 'i> Transform<'a, 'i> {
         Ok(documents_count)
     }
 
+    /// Runs the primary key resolution and the documents ids validation of `read_documents`
+    /// on the given batch, without writing anything, and returns all the errors found.
+    pub fn validate_documents<R>(
+        &self,
+        mut reader: DocumentBatchReader<R>,
+        rtxn: &heed::RoTxn,
+    ) -> Result<Vec<UserError>>
+    where
+        R: Read + Seek,
+    {
+        let primary_key_name = match self.index.primary_key(rtxn)? {
+            Some(primary_key) => primary_key.to_string(),
+            None => match find_primary_key(reader.index()) {
+                Ok(Some(primary_key)) => primary_key.to_string(),
+                Ok(None) if self.autogenerate_docids => DEFAULT_PRIMARY_KEY_NAME.to_string(),
+                Ok(None) => return Ok(vec![UserError::MissingPrimaryKey]),
+                Err(error) => return Ok(vec![error]),
+            },
+        };
+        let primary_key_id = reader.index().id(&primary_key_name);
+        let id_validation = self.index.document_id_validation(rtxn)?;
+
+        let mut errors = Vec::new();
+        while let Some((addition_index, document)) = reader.next_document_with_index()? {
+            match primary_key_id.and_then(|id| document.get(id)) {
+                Some(bytes) => {
+                    let value = serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)?;
+                    if let Err(error) = external_id_from_json(value, id_validation) {
+                        errors.push(error);
+                    }
+                }
+                None if self.autogenerate_docids => (),
+                None => errors.push(UserError::MissingDocumentId {
+                    primary_key: primary_key_name.clone(),
+                    document: document_to_json(addition_index, document),
+                }),
+            }
+        }
+
+        Ok(errors)
+    }
+
     /// Generate the `TransformOutput` based on the given sorter that can be generated from any
     /// format like CSV, JSON or JSON stream. This sorter must contain a key that is the document
     /// id for the user side and the value must be an obkv where keys are valid fields ids.
@@ -275,6 +435,8 @@ impl<'a, 'i> Transform<'a, 'i> {
         let documents_ids = self.index.documents_ids(wtxn)?;
         let mut field_distribution = self.index.field_distribution(wtxn)?;
         let mut available_documents_ids = AvailableDocumentsIds::from_documents_ids(&documents_ids);
+        let codec = self.index.documents_codec(wtxn)?;
+        let array_merge_policies = self.index.array_merge_policies_ids(wtxn)?;
 
         // consume sorter, in order to free the internal allocation, before creating a new one.
         let mut iter = self.sorter.into_stream_merger_iter()?;
@@ -315,12 +477,17 @@ impl<'a, 'i> Transform<'a, 'i> {
                     replaced_documents_ids.insert(docid);
 
                     let key = BEU32::new(docid);
-                    let base_obkv = self.index.documents.get(wtxn, &key)?.ok_or(
-                        InternalError::DatabaseMissingEntry {
+                    let base_bytes = self
+                        .index
+                        .documents
+                        .remap_data_type::<ByteSlice>()
+                        .get(wtxn, &key)?
+                        .ok_or(InternalError::DatabaseMissingEntry {
                             db_name: db_name::DOCUMENTS,
                             key: None,
-                        },
-                    )?;
+                        })?;
+                    let base_bytes = codec.decode(base_bytes)?;
+                    let base_obkv = obkv::KvReaderU16::new(&base_bytes);
 
                     // we remove all the fields that were already counted
                     for (field_id, _) in base_obkv.iter() {
@@ -341,7 +508,12 @@ impl<'a, 'i> Transform<'a, 'i> {
                         IndexDocumentsMethod::ReplaceDocuments => (docid, update_obkv),
                         IndexDocumentsMethod::UpdateDocuments => {
                             let update_obkv = obkv::KvReader::new(update_obkv);
-                            merge_two_obkvs(base_obkv, update_obkv, &mut obkv_buffer);
+                            merge_two_obkvs_with_policies(
+                                base_obkv,
+                                update_obkv,
+                                &array_merge_policies,
+                                &mut obkv_buffer,
+                            )?;
                             (docid, obkv_buffer.as_slice())
                         }
                     }
@@ -429,10 +601,13 @@ impl<'a, 'i> Transform<'a, 'i> {
             tempfile::tempfile()?,
         );
 
+        let codec = self.index.documents_codec(wtxn)?;
         let mut obkv_buffer = Vec::new();
-        for result in self.index.documents.iter(wtxn)? {
-            let (docid, obkv) = result?;
+        for result in self.index.documents.remap_data_type::<ByteSlice>().iter(wtxn)? {
+            let (docid, bytes) = result?;
             let docid = docid.get();
+            let bytes = codec.decode(bytes)?;
+            let obkv = obkv::KvReaderU16::new(&bytes);
 
             obkv_buffer.clear();
             let mut obkv_writer = obkv::KvWriter::<_, FieldId>::new(&mut obkv_buffer);
@@ -502,14 +677,105 @@ fn compute_primary_key_pair(
     }
 }
 
-fn validate_document_id(document_id: &str) -> Option<&str> {
+/// Converts the JSON value of a primary key into a valid external document id,
+/// according to the validation rule of the index.
+fn external_id_from_json(
+    document_id: Value,
+    validation: DocumentIdValidation,
+) -> StdResult<String, UserError> {
+    let invalid = |document_id| match validation {
+        DocumentIdValidation::Strict => UserError::InvalidDocumentId { document_id },
+        DocumentIdValidation::Relaxed { max_bytes } => {
+            UserError::InvalidRelaxedDocumentId { document_id, max_bytes }
+        }
+    };
+
+    match document_id {
+        Value::String(string) => match validate_document_id(&string, validation) {
+            Some(s) if s.len() == string.len() => Ok(string),
+            Some(s) => Ok(s.to_string()),
+            None => Err(invalid(Value::String(string))),
+        },
+        Value::Number(number) => Ok(number.to_string()),
+        content => Err(invalid(content)),
+    }
+}
+
+/// Converts a document of a batch into a JSON object, ignoring the fields that can't be read.
+fn document_to_json(index: &DocumentsBatchIndex, document: obkv::KvReader<FieldId>) -> Object {
+    let mut json = Map::new();
+    for (key, value) in document.iter() {
+        let key = index.name(key).cloned();
+        let value = serde_json::from_slice::<Value>(&value).ok();
+
+        if let Some((k, v)) = key.zip(value) {
+            json.insert(k, v);
+        }
+    }
+    json
+}
+
+fn validate_document_id(document_id: &str, validation: DocumentIdValidation) -> Option<&str> {
     let document_id = document_id.trim();
     Some(document_id).filter(|id| {
         !id.is_empty()
-            && id.chars().all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_'))
+            && match validation {
+                DocumentIdValidation::Strict => {
+                    id.chars().all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_'))
+                }
+                DocumentIdValidation::Relaxed { max_bytes } => {
+                    id.len() <= max_bytes && !id.chars().any(char::is_control)
+                }
+            }
     })
 }
 
+/// Returns the maximum nesting depth of the arrays and objects of a raw JSON value,
+/// a scalar has a depth of zero.
+fn json_nesting_depth(bytes: &[u8]) -> usize {
+    let mut max_depth = 0;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in bytes {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            _ if in_string => (),
+            b'[' | b'{' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    max_depth
+}
+
+/// Returns the number of keys of the objects nested in a raw JSON value, at any depth.
+fn json_object_keys_count(bytes: &[u8]) -> usize {
+    let mut count = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in bytes {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            _ if in_string => (),
+            b':' => count += 1,
+            _ => (),
+        }
+    }
+
+    count
+}
+
 /// Drops all the value of type `U` in vec, and reuses the allocation to create a `Vec<T>`.
 ///
 /// The size and alignment of T and U must match.
@@ -525,6 +791,15 @@ fn drop_and_reuse<U, T>(mut vec: Vec<U>) -> Vec<T> {
 mod test {
     use super::*;
 
+    #[test]
+    fn nesting_depth() {
+        assert_eq!(json_nesting_depth(br#"12"#), 0);
+        assert_eq!(json_nesting_depth(br#""[{hello}]""#), 0);
+        assert_eq!(json_nesting_depth(br#"["a", "b"]"#), 1);
+        assert_eq!(json_nesting_depth(br#"{"a": [{"b": "]]\"["}], "c": {}}"#), 3);
+        assert_eq!(json_nesting_depth(br#"[[], [[]], []]"#), 3);
+    }
+
     mod compute_primary_key {
         use super::{compute_primary_key_pair, FieldsIdsMap};
 
@@ -572,20 +847,48 @@ mod test {
         use bimap::BiHashMap;
 
         use crate::documents::DocumentsBatchIndex;
+        use crate::error::UserError;
         use crate::update::index_documents::transform::find_primary_key;
 
+        fn batch_index(fields: &[&str]) -> DocumentsBatchIndex {
+            let mut map = BiHashMap::new();
+            for (i, field) in fields.iter().enumerate() {
+                map.insert(i as u16, field.to_string());
+            }
+            DocumentsBatchIndex(map)
+        }
+
+        #[test]
+        fn primary_key_infered_from_id_suffix() {
+            for field in &["id", "_id", "ID", "realId", "userID", "user_id", "user-id", "user.id"] {
+                let index = batch_index(&["name", *field, "age"]);
+                assert_eq!(find_primary_key(&index).unwrap(), Some(*field));
+            }
+        }
+
+        #[test]
+        fn primary_key_not_infered_from_id_substring() {
+            let index = batch_index(&["paid", "video", "identity", "mysuperid", "Idaho"]);
+            assert_eq!(find_primary_key(&index).unwrap(), None);
+        }
+
         #[test]
-        fn primary_key_infered_on_first_field() {
+        fn exact_id_is_preferred() {
             // We run the test multiple times to change the order in which the fields are iterated upon.
             for _ in 1..50 {
-                let mut map = BiHashMap::new();
-                map.insert(1, "fakeId".to_string());
-                map.insert(2, "fakeId".to_string());
-                map.insert(3, "fakeId".to_string());
-                map.insert(4, "fakeId".to_string());
-                map.insert(0, "realId".to_string());
-
-                assert_eq!(find_primary_key(&DocumentsBatchIndex(map)), Some("realId"));
+                let index = batch_index(&["user_id", "id", "productId"]);
+                assert_eq!(find_primary_key(&index).unwrap(), Some("id"));
+            }
+        }
+
+        #[test]
+        fn multiple_candidates_are_reported() {
+            let index = batch_index(&["fakeId", "name", "realId"]);
+            match find_primary_key(&index) {
+                Err(UserError::MultiplePrimaryKeyCandidatesFound { candidates }) => {
+                    assert_eq!(candidates, vec!["fakeId".to_string(), "realId".to_string()]);
+                }
+                otherwise => panic!("unexpected result: {:?}", otherwise),
             }
         }
     }
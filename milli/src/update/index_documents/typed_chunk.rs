@@ -11,11 +11,12 @@ use super::helpers::{
     self, roaring_bitmap_from_u32s_array, serialize_roaring_bitmap, valid_lmdb_key,
     CursorClonableMmap,
 };
+use crate::error::SerializationError;
 use crate::heed_codec::facet::{decode_prefix_string, encode_prefix_string};
+use crate::index::db_name;
 use crate::update::index_documents::helpers::as_cloneable_grenad;
 use crate::{
-    lat_lng_to_xyz, BoRoaringBitmapCodec, CboRoaringBitmapCodec, DocumentId, GeoPoint, Index,
-    Result,
+    lat_lng_to_xyz, CboRoaringBitmapCodec, DeltaPositionsCodec, DocumentId, GeoPoint, Index, Result,
 };
 
 pub(crate) enum TypedChunk {
@@ -26,10 +27,12 @@ pub(crate) enum TypedChunk {
     FieldIdWordcountDocids(grenad::Reader<File>),
     NewDocumentsIds(RoaringBitmap),
     WordDocids(grenad::Reader<File>),
+    ExactWordDocids(grenad::Reader<File>),
     WordPositionDocids(grenad::Reader<File>),
     WordPairProximityDocids(grenad::Reader<File>),
     FieldIdFacetStringDocids(grenad::Reader<File>),
     FieldIdFacetNumberDocids(grenad::Reader<File>),
+    FieldIdFacetIntegerDocids(grenad::Reader<File>),
     GeoPoints(grenad::Reader<File>),
 }
 
@@ -52,24 +55,30 @@ pub(crate) fn write_typed_chunk_into_index(
                 |value, buffer| {
                     // ensure that values are unique and ordered
                     let positions = roaring_bitmap_from_u32s_array(value);
-                    BoRoaringBitmapCodec::serialize_into(&positions, buffer);
+                    DeltaPositionsCodec::serialize_into(&positions, buffer);
                     Ok(buffer)
                 },
                 |new_values, db_values, buffer| {
                     let new_values = roaring_bitmap_from_u32s_array(new_values);
-                    let positions = match BoRoaringBitmapCodec::bytes_decode(db_values) {
-                        Some(db_values) => new_values | db_values,
-                        None => new_values, // should not happen
-                    };
-                    BoRoaringBitmapCodec::serialize_into(&positions, buffer);
+                    let db_values = DeltaPositionsCodec::bytes_decode(db_values).ok_or(
+                        SerializationError::Decoding {
+                            db_name: Some(db_name::DOCID_WORD_POSITIONS),
+                        },
+                    )?;
+                    let positions = new_values | db_values;
+                    DeltaPositionsCodec::serialize_into(&positions, buffer);
                     Ok(())
                 },
             )?;
         }
         TypedChunk::Documents(obkv_documents_iter) => {
+            #[cfg(feature = "encryption")]
+            index.put_encryption_key_check(wtxn)?;
+            let codec = index.documents_codec(wtxn)?;
             let mut cursor = obkv_documents_iter.into_cursor()?;
             while let Some((key, value)) = cursor.move_on_next()? {
-                index.documents.remap_types::<ByteSlice, ByteSlice>().put(wtxn, key, value)?;
+                let value = codec.encode(value)?;
+                index.documents.remap_types::<ByteSlice, ByteSlice>().put(wtxn, key, &value)?;
             }
         }
         TypedChunk::FieldIdWordcountDocids(fid_word_count_docids_iter) => {
@@ -97,23 +106,23 @@ pub(crate) fn write_typed_chunk_into_index(
                 merge_roaring_bitmaps,
             )?;
 
-            // create fst from word docids
-            let mut builder = fst::SetBuilder::memory();
-            let mut cursor = word_docids_iter.into_cursor()?;
-            while let Some((word, _value)) = cursor.move_on_next()? {
-                // This is a lexicographically ordered word position
-                // we use the key to construct the words fst.
-                builder.insert(word)?;
-            }
-            let fst = builder.into_set().map_data(std::borrow::Cow::Owned)?;
-            let db_fst = index.words_fst(wtxn)?;
+            merge_words_into_words_fst(word_docids_iter, index, wtxn)?;
+            is_merged_database = true;
+        }
+        TypedChunk::ExactWordDocids(exact_word_docids_iter) => {
+            let exact_word_docids_iter = unsafe { as_cloneable_grenad(&exact_word_docids_iter) }?;
+            append_entries_into_database(
+                exact_word_docids_iter.clone(),
+                &index.exact_word_docids,
+                wtxn,
+                index_is_empty,
+                |value, _buffer| Ok(value),
+                merge_roaring_bitmaps,
+            )?;
 
-            // merge new fst with database fst
-            let union_stream = fst.op().add(db_fst.stream()).union();
-            let mut builder = fst::SetBuilder::memory();
-            builder.extend_stream(union_stream)?;
-            let fst = builder.into_set();
-            index.put_words_fst(wtxn, &fst)?;
+            // the exact words are also part of the words fst, they only
+            // match without typos as their documents are not in the word docids.
+            merge_words_into_words_fst(exact_word_docids_iter, index, wtxn)?;
             is_merged_database = true;
         }
         TypedChunk::WordPositionDocids(word_position_docids_iter) => {
@@ -138,6 +147,18 @@ pub(crate) fn write_typed_chunk_into_index(
             )?;
             is_merged_database = true;
         }
+        TypedChunk::FieldIdFacetIntegerDocids(facet_id_i128_docids_iter) => {
+            // Those chunks are extracted from each chunk of documents and not merged
+            // beforehand, the entries must always be merged with the database ones.
+            write_entries_into_database(
+                facet_id_i128_docids_iter,
+                &index.facet_id_i128_docids,
+                wtxn,
+                false,
+                |value, _buffer| Ok(value),
+                merge_cbo_roaring_bitmaps,
+            )?;
+        }
         TypedChunk::WordPairProximityDocids(word_pair_proximity_docids_iter) => {
             append_entries_into_database(
                 word_pair_proximity_docids_iter,
@@ -214,6 +235,32 @@ pub(crate) fn write_typed_chunk_into_index(
     Ok((RoaringBitmap::new(), is_merged_database))
 }
 
+/// Adds the keys of the chunk, the words, into the words fst of the index.
+fn merge_words_into_words_fst(
+    words_iter: grenad::Reader<CursorClonableMmap>,
+    index: &Index,
+    wtxn: &mut RwTxn,
+) -> Result<()> {
+    // create fst from the words
+    let mut builder = fst::SetBuilder::memory();
+    let mut cursor = words_iter.into_cursor()?;
+    while let Some((word, _value)) = cursor.move_on_next()? {
+        // This is a lexicographically ordered word position
+        // we use the key to construct the words fst.
+        builder.insert(word)?;
+    }
+    let fst = builder.into_set().map_data(std::borrow::Cow::Owned)?;
+    let db_fst = index.words_fst(wtxn)?;
+
+    // merge new fst with database fst
+    let union_stream = fst.op().add(db_fst.stream()).union();
+    let mut builder = fst::SetBuilder::memory();
+    builder.extend_stream(union_stream)?;
+    let fst = builder.into_set();
+    index.put_words_fst(wtxn, &fst)?;
+    Ok(())
+}
+
 fn merge_roaring_bitmaps(new_value: &[u8], db_value: &[u8], buffer: &mut Vec<u8>) -> Result<()> {
     let new_value = RoaringBitmap::deserialize_from(new_value)?;
     let db_value = RoaringBitmap::deserialize_from(db_value)?;
@@ -1,6 +1,10 @@
+use std::sync::Arc;
+
 use grenad::CompressionType;
 use rayon::ThreadPool;
 
+use crate::MetricsSink;
+
 #[derive(Debug)]
 pub struct IndexerConfig {
     pub log_every_n: Option<usize>,
@@ -11,6 +15,12 @@ pub struct IndexerConfig {
     pub chunk_compression_level: Option<u32>,
     pub thread_pool: Option<ThreadPool>,
     pub max_positions_per_attributes: Option<u32>,
+    /// Compresses the stored documents with a zstd dictionary trained on the first documents
+    /// added, once the dictionary is trained the documents of the index are always compressed.
+    #[cfg(feature = "compression")]
+    pub compress_documents: bool,
+    /// Receives the metrics of the documents additions.
+    pub metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl Default for IndexerConfig {
@@ -24,6 +34,9 @@ impl Default for IndexerConfig {
             chunk_compression_level: None,
             thread_pool: None,
             max_positions_per_attributes: None,
+            #[cfg(feature = "compression")]
+            compress_documents: false,
+            metrics: None,
         }
     }
 }
@@ -1,13 +1,20 @@
 pub use self::available_documents_ids::AvailableDocumentsIds;
-pub use self::clear_documents::ClearDocuments;
+pub use self::clear_documents::{ClearDocuments, ClearDocumentsResult};
 pub use self::delete_documents::{DeleteDocuments, DocumentDeletionResult};
 pub use self::facets::Facets;
 pub use self::index_documents::{
-    DocumentAdditionResult, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod,
+    ArrayMergePolicy, DocumentAdditionResult, DocumentIdValidation, IndexDocuments,
+    IndexDocumentsConfig, IndexDocumentsMethod, SkippedDocument,
 };
 pub use self::indexer_config::IndexerConfig;
+#[cfg(feature = "phonetic")]
+pub use self::phonetic_words_fst::PhoneticWordsFst;
+pub use self::reindex::Reindex;
 pub use self::settings::{Setting, Settings};
+pub use self::update_journal::{InterruptedUpdate, JournalRecovery, UpdateJournal};
+pub use self::update_queue::{UpdateId, UpdateQueue, UpdateStatus};
 pub use self::update_step::UpdateIndexingStep;
+pub use self::vacuum::{Vacuum, VacuumResult};
 pub use self::word_prefix_docids::WordPrefixDocids;
 pub use self::word_prefix_pair_proximity_docids::WordPrefixPairProximityDocids;
 pub use self::words_prefix_position_docids::WordPrefixPositionDocids;
@@ -19,8 +26,14 @@ mod delete_documents;
 mod facets;
 mod index_documents;
 mod indexer_config;
+#[cfg(feature = "phonetic")]
+mod phonetic_words_fst;
+mod reindex;
 mod settings;
+mod update_journal;
+mod update_queue;
 mod update_step;
+mod vacuum;
 mod word_prefix_docids;
 mod word_prefix_pair_proximity_docids;
 mod words_prefix_position_docids;
@@ -0,0 +1,47 @@
+use std::str;
+
+use fst::Streamer;
+
+use crate::phonetic::{phonetic_key, PHONETIC_SEPARATOR};
+use crate::{Index, Result};
+
+/// Builds the phonetic words FST from the words FST of the index, every word that has a
+/// phonetic key is stored after its key so that the words that sound alike are contiguous.
+pub struct PhoneticWordsFst<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+}
+
+impl<'t, 'u, 'i> PhoneticWordsFst<'t, 'u, 'i> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+    ) -> PhoneticWordsFst<'t, 'u, 'i> {
+        PhoneticWordsFst { wtxn, index }
+    }
+
+    #[logging_timer::time("PhoneticWordsFst::{}")]
+    pub fn execute(self) -> Result<()> {
+        let words_fst = self.index.words_fst(&self.wtxn)?;
+
+        let mut entries = Vec::new();
+        let mut stream = words_fst.stream();
+        while let Some(bytes) = stream.next() {
+            let word = str::from_utf8(bytes)?;
+            if let Some(key) = phonetic_key(word) {
+                let mut entry = key.into_bytes();
+                entry.push(PHONETIC_SEPARATOR);
+                entry.extend_from_slice(bytes);
+                entries.push(entry);
+            }
+        }
+
+        // The entries must be inserted in lexicographic order in the FST.
+        entries.sort_unstable();
+        let phonetic_fst = fst::Set::from_iter(entries)?;
+
+        self.index.put_phonetic_words_fst(self.wtxn, &phonetic_fst)?;
+
+        Ok(())
+    }
+}
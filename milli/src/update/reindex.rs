@@ -0,0 +1,102 @@
+use super::index_documents::{IndexDocumentsConfig, IndexDocumentsMethod, Transform};
+use super::{ClearDocuments, IndexDocuments, IndexerConfig, UpdateIndexingStep};
+use crate::{Index, Result};
+
+/// Regenerates every database derived from the stored documents, the words, facets,
+/// prefixes and geo databases, with the current settings of the index.
+///
+/// It is used to recover from an indexing bug or to upgrade the format of the derived
+/// databases without sending the documents again.
+pub struct Reindex<'t, 'u, 'i, 'a> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    indexer_config: &'a IndexerConfig,
+}
+
+impl<'t, 'u, 'i, 'a> Reindex<'t, 'u, 'i, 'a> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        indexer_config: &'a IndexerConfig,
+    ) -> Reindex<'t, 'u, 'i, 'a> {
+        Reindex { wtxn, index, indexer_config }
+    }
+
+    /// Returns the number of documents reindexed.
+    pub fn execute<F>(self, progress_callback: F) -> Result<u64>
+    where
+        F: Fn(UpdateIndexingStep) + Sync,
+    {
+        if self.index.number_of_documents(self.wtxn)? == 0 {
+            return Ok(0);
+        }
+
+        let transform = Transform::new(
+            self.index,
+            self.indexer_config,
+            IndexDocumentsMethod::ReplaceDocuments,
+            false,
+        );
+
+        // The documents are read with the same fields ids map, which keeps their fields as is.
+        let fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
+        let output =
+            transform.remap_index_documents(self.wtxn, fields_ids_map.clone(), fields_ids_map)?;
+
+        ClearDocuments::new(self.wtxn, self.index).execute()?;
+
+        let indexing_builder = IndexDocuments::new(
+            self.wtxn,
+            self.index,
+            self.indexer_config,
+            IndexDocumentsConfig::default(),
+            progress_callback,
+        );
+        indexing_builder.execute_raw(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::Filter;
+
+    #[test]
+    fn rebuild_derived_databases() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("color") }))
+            .unwrap();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "hello world", "color": "blue" },
+                { "id": 1, "title": "hello there", "color": "red" },
+                { "id": 2, "title": "goodbye", "color": "blue" }
+            ]))
+            .unwrap();
+
+        // We simulate a corruption of the derived databases.
+        let mut wtxn = index.write_txn().unwrap();
+        index.word_docids.clear(&mut wtxn).unwrap();
+        index.facet_id_string_docids.clear(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert!(index.search_external_ids("hello").unwrap().is_empty());
+
+        let mut wtxn = index.write_txn().unwrap();
+        let config = IndexerConfig::default();
+        let count = Reindex::new(&mut wtxn, &index, &config).execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(count, 3);
+
+        assert_eq!(index.search_external_ids("hello").unwrap(), vec!["0", "1"]);
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("color = blue").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), (0..3).filter(|id| *id != 1).collect());
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 3);
+    }
+}
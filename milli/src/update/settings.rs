@@ -8,11 +8,18 @@ use time::OffsetDateTime;
 
 use super::index_documents::{IndexDocumentsConfig, Transform};
 use super::IndexerConfig;
-use crate::criterion::Criterion;
+use crate::criterion::{Criterion, TermsDroppingOrder};
 use crate::error::UserError;
+use crate::facet::FacetNormalization;
 use crate::update::index_documents::IndexDocumentsMethod;
-use crate::update::{ClearDocuments, IndexDocuments, UpdateIndexingStep};
-use crate::{FieldsIdsMap, Index, Result};
+#[cfg(feature = "phonetic")]
+use crate::update::PhoneticWordsFst;
+use crate::update::{
+    ArrayMergePolicy, ClearDocuments, DocumentIdValidation, IndexDocuments, UpdateIndexingStep,
+};
+use crate::{
+    FieldsIdsMap, Filter, Index, Normalizer, QueryRule, Result, StemmingLanguage, TypoTolerance,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Setting<T> {
@@ -84,11 +91,29 @@ pub struct Settings<'a, 't, 'u, 'i> {
     displayed_fields: Setting<Vec<String>>,
     filterable_fields: Setting<HashSet<String>>,
     sortable_fields: Setting<HashSet<String>>,
+    hierarchical_facets: Setting<HashSet<String>>,
+    date_fields: Setting<HashSet<String>>,
+    facet_normalization: Setting<HashMap<String, FacetNormalization>>,
     criteria: Setting<Vec<String>>,
     stop_words: Setting<BTreeSet<String>>,
+    decompounding_dictionary: Setting<BTreeSet<String>>,
+    exact_attributes: Setting<HashSet<String>>,
+    exact_words: Setting<BTreeSet<String>>,
     distinct_field: Setting<String>,
     synonyms: Setting<HashMap<String, Vec<String>>>,
     primary_key: Setting<String>,
+    document_id_validation: Setting<DocumentIdValidation>,
+    array_merge_policies: Setting<HashMap<String, ArrayMergePolicy>>,
+    terms_dropping_order: Setting<TermsDroppingOrder>,
+    typo_tolerance: Setting<TypoTolerance>,
+    query_rules: Setting<Vec<QueryRule>>,
+    stemming_language: Setting<StemmingLanguage>,
+    field_languages: Setting<HashMap<String, StemmingLanguage>>,
+    diacritics_sensitive: Setting<bool>,
+    case_sensitive: Setting<bool>,
+    numbers_normalization: Setting<bool>,
+    #[cfg(feature = "phonetic")]
+    phonetic_matching: Setting<bool>,
 }
 
 impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
@@ -104,11 +129,29 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             displayed_fields: Setting::NotSet,
             filterable_fields: Setting::NotSet,
             sortable_fields: Setting::NotSet,
+            hierarchical_facets: Setting::NotSet,
+            date_fields: Setting::NotSet,
+            facet_normalization: Setting::NotSet,
             criteria: Setting::NotSet,
             stop_words: Setting::NotSet,
+            decompounding_dictionary: Setting::NotSet,
+            exact_attributes: Setting::NotSet,
+            exact_words: Setting::NotSet,
             distinct_field: Setting::NotSet,
             synonyms: Setting::NotSet,
             primary_key: Setting::NotSet,
+            document_id_validation: Setting::NotSet,
+            array_merge_policies: Setting::NotSet,
+            terms_dropping_order: Setting::NotSet,
+            typo_tolerance: Setting::NotSet,
+            query_rules: Setting::NotSet,
+            stemming_language: Setting::NotSet,
+            field_languages: Setting::NotSet,
+            diacritics_sensitive: Setting::NotSet,
+            case_sensitive: Setting::NotSet,
+            numbers_normalization: Setting::NotSet,
+            #[cfg(feature = "phonetic")]
+            phonetic_matching: Setting::NotSet,
             indexer_config,
         }
     }
@@ -145,6 +188,36 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.sortable_fields = Setting::Reset;
     }
 
+    /// Sets the faceted fields whose string values are paths of levels separated
+    /// by a `>`, e.g. `Books > SciFi`, each level of the paths is a facet value.
+    pub fn set_hierarchical_facets(&mut self, names: HashSet<String>) {
+        self.hierarchical_facets = Setting::Set(names);
+    }
+
+    pub fn reset_hierarchical_facets(&mut self) {
+        self.hierarchical_facets = Setting::Reset;
+    }
+
+    /// Sets the faceted fields whose values are RFC 3339 dates, they are
+    /// also indexed as numbers, the unix timestamps of the dates in seconds.
+    pub fn set_date_fields(&mut self, names: HashSet<String>) {
+        self.date_fields = Setting::Set(names);
+    }
+
+    pub fn reset_date_fields(&mut self) {
+        self.date_fields = Setting::Reset;
+    }
+
+    /// Sets how the string values of the given faceted fields are normalized,
+    /// the other fields are lowercased.
+    pub fn set_facet_normalization(&mut self, normalization: HashMap<String, FacetNormalization>) {
+        self.facet_normalization = Setting::Set(normalization);
+    }
+
+    pub fn reset_facet_normalization(&mut self) {
+        self.facet_normalization = Setting::Reset;
+    }
+
     pub fn reset_criteria(&mut self) {
         self.criteria = Setting::Reset;
     }
@@ -162,6 +235,35 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             if stop_words.is_empty() { Setting::Reset } else { Setting::Set(stop_words) }
     }
 
+    pub fn reset_decompounding_dictionary(&mut self) {
+        self.decompounding_dictionary = Setting::Reset;
+    }
+
+    pub fn set_decompounding_dictionary(&mut self, words: BTreeSet<String>) {
+        self.decompounding_dictionary =
+            if words.is_empty() { Setting::Reset } else { Setting::Set(words) }
+    }
+
+    /// Sets the searchable fields whose words only match the words of the query
+    /// that are exactly the same, without typos nor as a prefix, e.g. the SKUs.
+    pub fn set_exact_attributes(&mut self, names: HashSet<String>) {
+        self.exact_attributes = Setting::Set(names);
+    }
+
+    pub fn reset_exact_attributes(&mut self) {
+        self.exact_attributes = Setting::Reset;
+    }
+
+    /// Sets the words of the queries that only match the exactly same words
+    /// of the documents, without typos nor as a prefix.
+    pub fn set_exact_words(&mut self, words: BTreeSet<String>) {
+        self.exact_words = if words.is_empty() { Setting::Reset } else { Setting::Set(words) }
+    }
+
+    pub fn reset_exact_words(&mut self) {
+        self.exact_words = Setting::Reset;
+    }
+
     pub fn reset_distinct_field(&mut self) {
         self.distinct_field = Setting::Reset;
     }
@@ -186,6 +288,191 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.primary_key = Setting::Set(primary_key);
     }
 
+    pub fn reset_document_id_validation(&mut self) {
+        self.document_id_validation = Setting::Reset;
+    }
+
+    pub fn set_document_id_validation(&mut self, validation: DocumentIdValidation) {
+        self.document_id_validation = Setting::Set(validation);
+    }
+
+    pub fn reset_array_merge_policies(&mut self) {
+        self.array_merge_policies = Setting::Reset;
+    }
+
+    /// Sets how the arrays of the given fields are merged when the documents are updated,
+    /// documents sent several times in the same batch replace their arrays between them.
+    pub fn set_array_merge_policies(&mut self, policies: HashMap<String, ArrayMergePolicy>) {
+        self.array_merge_policies = Setting::Set(policies);
+    }
+
+    pub fn reset_terms_dropping_order(&mut self) {
+        self.terms_dropping_order = Setting::Reset;
+    }
+
+    pub fn set_terms_dropping_order(&mut self, order: TermsDroppingOrder) {
+        self.terms_dropping_order = Setting::Set(order);
+    }
+
+    pub fn reset_typo_tolerance(&mut self) {
+        self.typo_tolerance = Setting::Reset;
+    }
+
+    pub fn set_typo_tolerance(&mut self, typo_tolerance: TypoTolerance) {
+        self.typo_tolerance = Setting::Set(typo_tolerance);
+    }
+
+    pub fn reset_query_rules(&mut self) {
+        self.query_rules = Setting::Reset;
+    }
+
+    /// Sets the rules applied, in order, to the searches whose query matches their pattern,
+    /// they can pin or hide documents, force a filter or replace the query.
+    pub fn set_query_rules(&mut self, rules: Vec<QueryRule>) {
+        self.query_rules = Setting::Set(rules);
+    }
+
+    pub fn reset_stemming_language(&mut self) {
+        self.stemming_language = Setting::Reset;
+    }
+
+    pub fn set_stemming_language(&mut self, language: StemmingLanguage) {
+        self.stemming_language = Setting::Set(language);
+    }
+
+    /// Sets the languages the words of the given fields are stemmed with
+    /// instead of the stemming language of the index.
+    pub fn set_field_languages(&mut self, languages: HashMap<String, StemmingLanguage>) {
+        self.field_languages = Setting::Set(languages);
+    }
+
+    pub fn reset_field_languages(&mut self) {
+        self.field_languages = Setting::Reset;
+    }
+
+    pub fn reset_diacritics_sensitive(&mut self) {
+        self.diacritics_sensitive = Setting::Reset;
+    }
+
+    pub fn set_diacritics_sensitive(&mut self, sensitive: bool) {
+        self.diacritics_sensitive = Setting::Set(sensitive);
+    }
+
+    pub fn reset_case_sensitive(&mut self) {
+        self.case_sensitive = Setting::Reset;
+    }
+
+    pub fn set_case_sensitive(&mut self, sensitive: bool) {
+        self.case_sensitive = Setting::Set(sensitive);
+    }
+
+    pub fn reset_numbers_normalization(&mut self) {
+        self.numbers_normalization = Setting::Reset;
+    }
+
+    pub fn set_numbers_normalization(&mut self, normalization: bool) {
+        self.numbers_normalization = Setting::Set(normalization);
+    }
+
+    #[cfg(feature = "phonetic")]
+    pub fn reset_phonetic_matching(&mut self) {
+        self.phonetic_matching = Setting::Reset;
+    }
+
+    #[cfg(feature = "phonetic")]
+    pub fn set_phonetic_matching(&mut self, enabled: bool) {
+        self.phonetic_matching = Setting::Set(enabled);
+    }
+
+    /// Sets every setting of this update to the value it has in the source index,
+    /// the settings the source index doesn't define are reset.
+    ///
+    /// The searchable fields, the phonetic matching and the primary key are only
+    /// copied if they differ from the ones of this index, the searchable fields
+    /// always trigger a reindex. The synonyms are copied as they are stored in
+    /// the source index, already normalized.
+    pub fn copy_from(&mut self, source: &Index) -> Result<()> {
+        let rtxn = source.read_txn()?;
+
+        let searchable_fields = source.searchable_fields(&rtxn)?;
+        if searchable_fields != self.index.searchable_fields(self.wtxn)? {
+            self.searchable_fields = match searchable_fields {
+                Some(fields) => Setting::Set(fields.into_iter().map(String::from).collect()),
+                None => Setting::Reset,
+            };
+        }
+        self.displayed_fields = match source.displayed_fields(&rtxn)? {
+            Some(fields) => Setting::Set(fields.into_iter().map(String::from).collect()),
+            None => Setting::Reset,
+        };
+        self.filterable_fields = Setting::Set(source.filterable_fields(&rtxn)?);
+        self.sortable_fields = Setting::Set(source.sortable_fields(&rtxn)?);
+        self.hierarchical_facets = Setting::Set(source.hierarchical_facets(&rtxn)?);
+        self.date_fields = Setting::Set(source.date_fields(&rtxn)?);
+        self.facet_normalization = Setting::Set(source.facet_normalization(&rtxn)?);
+        self.criteria =
+            Setting::Set(source.criteria(&rtxn)?.iter().map(ToString::to_string).collect());
+        self.stop_words = match source.stop_words(&rtxn)? {
+            Some(stop_words) => {
+                Setting::Set(stop_words.stream().into_strs()?.into_iter().collect())
+            }
+            None => Setting::Reset,
+        };
+        self.decompounding_dictionary = match source.decompounding_dictionary(&rtxn)? {
+            Some(words) => Setting::Set(words.stream().into_strs()?.into_iter().collect()),
+            None => Setting::Reset,
+        };
+        self.exact_attributes = Setting::Set(source.exact_attributes(&rtxn)?);
+        self.exact_words = match source.exact_words(&rtxn)? {
+            Some(words) => Setting::Set(words.stream().into_strs()?.into_iter().collect()),
+            None => Setting::Reset,
+        };
+        self.distinct_field = match source.distinct_field(&rtxn)? {
+            Some(field) => Setting::Set(field.to_string()),
+            None => Setting::Reset,
+        };
+        let synonyms = source.synonyms(&rtxn)?;
+        self.synonyms = if synonyms.is_empty() {
+            Setting::Reset
+        } else {
+            Setting::Set(
+                synonyms
+                    .into_iter()
+                    .map(|(words, synonyms)| {
+                        (words.join(" "), synonyms.iter().map(|words| words.join(" ")).collect())
+                    })
+                    .collect(),
+            )
+        };
+        if let Some(primary_key) = source.primary_key(&rtxn)? {
+            if self.index.primary_key(self.wtxn)? != Some(primary_key) {
+                self.primary_key = Setting::Set(primary_key.to_string());
+            }
+        }
+        self.document_id_validation = Setting::Set(source.document_id_validation(&rtxn)?);
+        self.array_merge_policies = Setting::Set(source.array_merge_policies(&rtxn)?);
+        self.terms_dropping_order = Setting::Set(source.terms_dropping_order(&rtxn)?);
+        self.typo_tolerance = Setting::Set(source.typo_tolerance(&rtxn)?);
+        self.query_rules = Setting::Set(source.query_rules(&rtxn)?);
+        self.stemming_language = match source.stemming_language(&rtxn)? {
+            Some(language) => Setting::Set(language),
+            None => Setting::Reset,
+        };
+        self.field_languages = Setting::Set(source.field_languages(&rtxn)?);
+        self.diacritics_sensitive = Setting::Set(source.diacritics_sensitive(&rtxn)?);
+        self.case_sensitive = Setting::Set(source.case_sensitive(&rtxn)?);
+        self.numbers_normalization = Setting::Set(source.numbers_normalization(&rtxn)?);
+        #[cfg(feature = "phonetic")]
+        {
+            let phonetic_matching = source.phonetic_matching(&rtxn)?;
+            if phonetic_matching != self.index.phonetic_matching(self.wtxn)? {
+                self.phonetic_matching = Setting::Set(phonetic_matching);
+            }
+        }
+
+        Ok(())
+    }
+
     fn reindex<F>(&mut self, cb: &F, old_fields_ids_map: FieldsIdsMap) -> Result<()>
     where
         F: Fn(UpdateIndexingStep) + Sync,
@@ -256,6 +543,165 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(true)
     }
 
+    fn update_document_id_validation(&mut self) -> Result<bool> {
+        match self.document_id_validation {
+            Setting::Set(validation) => {
+                self.index.put_document_id_validation(self.wtxn, validation)?;
+            }
+            Setting::Reset => {
+                self.index.delete_document_id_validation(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn update_array_merge_policies(&mut self) -> Result<bool> {
+        match self.array_merge_policies {
+            Setting::Set(ref policies) => {
+                if self.index.array_merge_policies(self.wtxn)? != *policies {
+                    self.index.put_array_merge_policies(self.wtxn, policies)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_array_merge_policies(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_terms_dropping_order(&mut self) -> Result<bool> {
+        match self.terms_dropping_order {
+            Setting::Set(order) => {
+                self.index.put_terms_dropping_order(self.wtxn, order)?;
+            }
+            Setting::Reset => {
+                self.index.delete_terms_dropping_order(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn update_typo_tolerance(&mut self) -> Result<bool> {
+        match self.typo_tolerance {
+            Setting::Set(typo_tolerance) => {
+                self.index.put_typo_tolerance(self.wtxn, typo_tolerance)?;
+            }
+            Setting::Reset => {
+                self.index.delete_typo_tolerance(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn update_query_rules(&mut self) -> Result<bool> {
+        match self.query_rules {
+            Setting::Set(ref rules) => {
+                // The forced filters are parsed now to not fail the searches later.
+                for filter in rules.iter().filter_map(|rule| rule.filter.as_ref()) {
+                    Filter::from_str(filter)?;
+                }
+                self.index.put_query_rules(self.wtxn, rules)?;
+            }
+            Setting::Reset => {
+                self.index.delete_query_rules(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn update_stemming_language(&mut self) -> Result<bool> {
+        match self.stemming_language {
+            Setting::Set(language) => {
+                if self.index.stemming_language(self.wtxn)? != Some(language) {
+                    self.index.put_stemming_language(self.wtxn, language)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_stemming_language(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_diacritics_sensitive(&mut self) -> Result<bool> {
+        match self.diacritics_sensitive {
+            Setting::Set(sensitive) => {
+                if self.index.diacritics_sensitive(self.wtxn)? != sensitive {
+                    self.index.put_diacritics_sensitive(self.wtxn, sensitive)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => {
+                let sensitive = self.index.diacritics_sensitive(self.wtxn)?;
+                self.index.delete_diacritics_sensitive(self.wtxn)?;
+                Ok(sensitive)
+            }
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_case_sensitive(&mut self) -> Result<bool> {
+        match self.case_sensitive {
+            Setting::Set(sensitive) => {
+                if self.index.case_sensitive(self.wtxn)? != sensitive {
+                    self.index.put_case_sensitive(self.wtxn, sensitive)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => {
+                let sensitive = self.index.case_sensitive(self.wtxn)?;
+                self.index.delete_case_sensitive(self.wtxn)?;
+                Ok(sensitive)
+            }
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_numbers_normalization(&mut self) -> Result<bool> {
+        match self.numbers_normalization {
+            Setting::Set(normalization) => {
+                if self.index.numbers_normalization(self.wtxn)? != normalization {
+                    self.index.put_numbers_normalization(self.wtxn, normalization)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => {
+                let normalization = self.index.numbers_normalization(self.wtxn)?;
+                self.index.delete_numbers_normalization(self.wtxn)?;
+                Ok(normalization)
+            }
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    #[cfg(feature = "phonetic")]
+    fn update_phonetic_matching(&mut self) -> Result<bool> {
+        match self.phonetic_matching {
+            Setting::Set(true) => {
+                self.index.put_phonetic_matching(self.wtxn, true)?;
+                PhoneticWordsFst::new(self.wtxn, self.index).execute()?;
+            }
+            Setting::Set(false) | Setting::Reset => {
+                self.index.delete_phonetic_matching(self.wtxn)?;
+                self.index.delete_phonetic_words_fst(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
     /// Updates the index's searchable attributes. This causes the field map to be recomputed to
     /// reflect the order of the searchable attributes.
     fn update_searchable(&mut self) -> Result<bool> {
@@ -290,6 +736,66 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(true)
     }
 
+    fn update_hierarchical_facets(&mut self) -> Result<bool> {
+        match self.hierarchical_facets {
+            Setting::Set(ref fields) => {
+                if self.index.hierarchical_facets(self.wtxn)? != *fields {
+                    self.index.put_hierarchical_facets(self.wtxn, fields)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_hierarchical_facets(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_date_fields(&mut self) -> Result<bool> {
+        match self.date_fields {
+            Setting::Set(ref fields) => {
+                if self.index.date_fields(self.wtxn)? != *fields {
+                    self.index.put_date_fields(self.wtxn, fields)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_date_fields(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_facet_normalization(&mut self) -> Result<bool> {
+        match self.facet_normalization {
+            Setting::Set(ref normalization) => {
+                if self.index.facet_normalization(self.wtxn)? != *normalization {
+                    self.index.put_facet_normalization(self.wtxn, normalization)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_facet_normalization(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_field_languages(&mut self) -> Result<bool> {
+        match self.field_languages {
+            Setting::Set(ref languages) => {
+                if self.index.field_languages(self.wtxn)? != *languages {
+                    self.index.put_field_languages(self.wtxn, languages)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_field_languages(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
     fn update_stop_words(&mut self) -> Result<bool> {
         match self.stop_words {
             Setting::Set(ref stop_words) => {
@@ -314,48 +820,77 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         }
     }
 
+    fn update_decompounding_dictionary(&mut self) -> Result<bool> {
+        match self.decompounding_dictionary {
+            Setting::Set(ref words) => {
+                let current = self.index.decompounding_dictionary(self.wtxn)?;
+                // The dictionary words are compared with the lowercased words of the documents.
+                let words: BTreeSet<_> = words.iter().map(|word| word.to_lowercase()).collect();
+                let fst = fst::Set::from_iter(words)?;
+
+                if current
+                    .map_or(true, |current| current.as_fst().as_bytes() != fst.as_fst().as_bytes())
+                {
+                    self.index.put_decompounding_dictionary(self.wtxn, &fst)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_decompounding_dictionary(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_exact_attributes(&mut self) -> Result<bool> {
+        match self.exact_attributes {
+            Setting::Set(ref fields) => {
+                if self.index.exact_attributes(self.wtxn)? != *fields {
+                    self.index.put_exact_attributes(self.wtxn, fields)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_exact_attributes(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    /// The exact words are only read when the query tree is built,
+    /// they don't require a reindex.
+    fn update_exact_words(&mut self) -> Result<()> {
+        match self.exact_words {
+            Setting::Set(ref words) => {
+                // The exact words are compared with the lowercased words of the query.
+                let words: BTreeSet<_> = words
+                    .iter()
+                    .map(|word| word.trim().to_lowercase())
+                    .filter(|word| !word.is_empty())
+                    .collect();
+                let fst = fst::Set::from_iter(words)?;
+                self.index.put_exact_words(self.wtxn, &fst)?;
+            }
+            Setting::Reset => {
+                self.index.delete_exact_words(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
     fn update_synonyms(&mut self) -> Result<bool> {
         match self.synonyms {
             Setting::Set(ref synonyms) => {
-                fn normalize(analyzer: &Analyzer<&[u8]>, text: &str) -> Vec<String> {
-                    analyzer
-                        .analyze(text)
-                        .tokens()
-                        .filter_map(|token| {
-                            if token.is_word() {
-                                Some(token.text().to_string())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                }
-
                 let mut config = AnalyzerConfig::default();
                 let stop_words = self.index.stop_words(self.wtxn)?;
                 if let Some(stop_words) = &stop_words {
                     config.stop_words(stop_words);
                 }
                 let analyzer = Analyzer::new(config);
-
-                let mut new_synonyms = HashMap::new();
-                for (word, synonyms) in synonyms {
-                    // Normalize both the word and associated synonyms.
-                    let normalized_word = normalize(&analyzer, word);
-                    let normalized_synonyms =
-                        synonyms.iter().map(|synonym| normalize(&analyzer, synonym));
-
-                    // Store the normalized synonyms under the normalized word,
-                    // merging the possible duplicate words.
-                    let entry = new_synonyms.entry(normalized_word).or_insert_with(Vec::new);
-                    entry.extend(normalized_synonyms);
-                }
-
-                // Make sure that we don't have duplicate synonyms.
-                new_synonyms.iter_mut().for_each(|(_, synonyms)| {
-                    synonyms.sort_unstable();
-                    synonyms.dedup();
-                });
+                // The synonyms are matched against the stemmed query words.
+                let normalizer = Normalizer::from_index(self.index, self.wtxn)?;
+                let new_synonyms = normalizer.normalize_synonyms(&analyzer, synonyms);
 
                 let old_synonyms = self.index.synonyms(self.wtxn)?;
 
@@ -465,18 +1000,51 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.update_distinct_field()?;
         self.update_criteria()?;
         self.update_primary_key()?;
+        self.update_document_id_validation()?;
+        self.update_array_merge_policies()?;
+        self.update_terms_dropping_order()?;
+        self.update_typo_tolerance()?;
+        self.update_query_rules()?;
+        self.update_exact_words()?;
+        #[cfg(feature = "phonetic")]
+        self.update_phonetic_matching()?;
 
         // If there is new faceted fields we indicate that we must reindex as we must
         // index new fields as facets. It means that the distinct attribute,
         // an Asc/Desc criterion or a filtered attribute as be added or removed.
         let new_faceted_fields = self.index.faceted_fields(&self.wtxn)?;
         let faceted_updated = old_faceted_fields != new_faceted_fields;
-
+        let hierarchical_updated = self.update_hierarchical_facets()?;
+        let date_fields_updated = self.update_date_fields()?;
+        let facet_normalization_updated = self.update_facet_normalization()?;
+
+        // The stemmed words are the ones stored in the index, it must be reindexed.
+        let stemming_updated = self.update_stemming_language()?;
+        let field_languages_updated = self.update_field_languages()?;
+        let diacritics_updated = self.update_diacritics_sensitive()?;
+        let case_updated = self.update_case_sensitive()?;
+        let numbers_updated = self.update_numbers_normalization()?;
         let stop_words_updated = self.update_stop_words()?;
+        let decompounding_updated = self.update_decompounding_dictionary()?;
+        let exact_attributes_updated = self.update_exact_attributes()?;
         let synonyms_updated = self.update_synonyms()?;
         let searchable_updated = self.update_searchable()?;
 
-        if stop_words_updated || faceted_updated || synonyms_updated || searchable_updated {
+        if stop_words_updated
+            || faceted_updated
+            || hierarchical_updated
+            || date_fields_updated
+            || facet_normalization_updated
+            || synonyms_updated
+            || searchable_updated
+            || stemming_updated
+            || field_languages_updated
+            || decompounding_updated
+            || exact_attributes_updated
+            || diacritics_updated
+            || case_updated
+            || numbers_updated
+        {
             self.reindex(&progress_callback, old_fields_ids_map)?;
         }
 
@@ -489,10 +1057,11 @@ mod tests {
     use big_s::S;
     use heed::types::ByteSlice;
     use heed::EnvOpenOptions;
-    use maplit::{btreeset, hashmap, hashset};
+    use maplit::{btreemap, btreeset, hashmap, hashset};
 
     use super::*;
     use crate::error::Error;
+    use crate::facet::DateGranularity;
     use crate::update::IndexDocuments;
     use crate::{Criterion, Filter, SearchResult};
 
@@ -724,8 +1293,9 @@ mod tests {
         println!("fidmap: {:?}", fidmap);
         for document in index.all_documents(&rtxn).unwrap() {
             let document = document.unwrap();
-            let json = crate::obkv_to_json(&fidmap.ids().collect::<Vec<_>>(), &fidmap, document.1)
-                .unwrap();
+            let json =
+                crate::obkv_to_json(&fidmap.ids().collect::<Vec<_>>(), &fidmap, document.1.obkv())
+                    .unwrap();
             println!("json: {:?}", json);
         }
         let count = index
@@ -802,7 +1372,7 @@ mod tests {
 
         // Fetch the documents "age" field in the ordre in which the documents appear.
         let age_field_id = index.fields_ids_map(&rtxn).unwrap().id("age").unwrap();
-        let iter = documents.into_iter().map(|(_, doc)| {
+        let iter = documents.iter().map(|(_, doc)| {
             let bytes = doc.get(age_field_id).unwrap();
             let string = std::str::from_utf8(bytes).unwrap();
             string.parse::<u32>().unwrap()
@@ -1026,6 +1596,825 @@ mod tests {
         assert!(result.documents_ids.is_empty());
     }
 
+    #[test]
+    fn query_synonyms() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "name": "kevin", "maxim": "I love dogs"},
+            { "name": "kevina", "maxim": "Doggos are the best"},
+            { "name": "benoit", "maxim": "The crepes are really good"},
+        ]);
+        let config = IndexerConfig::default();
+        let indexing_config =
+            IndexDocumentsConfig { autogenerate_docids: true, ..Default::default() };
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_synonyms(hashmap! {
+            "puppies".to_string() => vec!["dogs".to_string()],
+        });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let synonyms = hashmap! {
+            "Blini".to_string() => vec!["crepes".to_string()],
+            "puppies".to_string() => vec!["doggos".to_string()],
+        };
+
+        // The query synonyms are merged with the synonyms of the index.
+        let result = index.search(&rtxn).query("blini").synonyms(synonyms.clone()).execute();
+        assert_eq!(result.unwrap().documents_ids.len(), 1);
+        let result = index.search(&rtxn).query("puppies").synonyms(synonyms).execute();
+        assert_eq!(result.unwrap().documents_ids.len(), 2);
+
+        // They are only used by the search they are given to.
+        let result = index.search(&rtxn).query("blini").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        let result = index.search(&rtxn).query("puppies").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 1);
+        assert_eq!(index.synonyms(&rtxn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn copy_settings_from_index() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::index::tests::TempIndex;
+
+        let config = IndexerConfig::default();
+        let source = TempIndex::new();
+        let mut wtxn = source.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &source, &config);
+        builder.set_searchable_fields(vec![S("name"), S("maxim")]);
+        builder.set_filterable_fields(hashset! { S("age") });
+        builder.set_criteria(vec![S("words"), S("age:desc")]);
+        builder.set_stop_words(btreeset! { S("the") });
+        builder.set_distinct_field(S("name"));
+        builder.set_synonyms(hashmap! { S("puppies") => vec![S("dogs")] });
+        builder.set_primary_key(S("id"));
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let target = TempIndex::new();
+        let mut wtxn = target.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin", "maxim": "I love dogs", "age": 20 },
+            { "id": 1, "name": "kevina", "maxim": "The dogs are the best", "age": 21 }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &target, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &target, &config);
+        builder.set_displayed_fields(vec![S("name")]);
+        builder.execute(|_| ()).unwrap();
+
+        let reindexing_steps = AtomicUsize::new(0);
+        let mut builder = Settings::new(&mut wtxn, &target, &config);
+        builder.copy_from(&source).unwrap();
+        builder
+            .execute(|_| {
+                reindexing_steps.fetch_add(1, Ordering::Relaxed);
+            })
+            .unwrap();
+        assert_ne!(reindexing_steps.swap(0, Ordering::Relaxed), 0);
+        wtxn.commit().unwrap();
+
+        let rtxn = target.read_txn().unwrap();
+        assert_eq!(target.searchable_fields(&rtxn).unwrap(), Some(vec!["name", "maxim"]));
+        assert_eq!(target.displayed_fields(&rtxn).unwrap(), None);
+        assert_eq!(target.filterable_fields(&rtxn).unwrap(), hashset! { S("age") });
+        assert_eq!(target.criteria(&rtxn).unwrap()[1], Criterion::Desc(S("age")));
+        assert_eq!(target.distinct_field(&rtxn).unwrap(), Some("name"));
+        assert_eq!(
+            target.synonyms(&rtxn).unwrap(),
+            source.synonyms(&source.read_txn().unwrap()).unwrap()
+        );
+        let stop_words = target.stop_words(&rtxn).unwrap().unwrap();
+        assert_eq!(stop_words.stream().into_strs().unwrap(), vec![S("the")]);
+        let result = target.search(&rtxn).query("puppies").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 2);
+        drop(rtxn);
+
+        // Copying the same settings again doesn't reindex the documents.
+        let mut wtxn = target.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &target, &config);
+        builder.copy_from(&source).unwrap();
+        builder
+            .execute(|_| {
+                reindexing_steps.fetch_add(1, Ordering::Relaxed);
+            })
+            .unwrap();
+        assert_eq!(reindexing_steps.load(Ordering::Relaxed), 0);
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn set_typo_tolerance() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "email": "kevin gmail" },
+            { "id": 2, "email": "kevina hotmail" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // A typo on the first character is accepted by default.
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("fmail ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_typo_tolerance(TypoTolerance { first_char_typo: false, ..Default::default() });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.typo_tolerance(&rtxn).unwrap().first_char_typo);
+        let result = index.search(&rtxn).query("fmail ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        // The typos on the other characters are still accepted.
+        let result = index.search(&rtxn).query("gmaul ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        // A transposition counts as a single typo by default, as two typos otherwise.
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("kevni ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_typo_tolerance(TypoTolerance { transpositions: false, ..Default::default() });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("kevni ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_typo_tolerance();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.typo_tolerance(&rtxn).unwrap(), TypoTolerance::default());
+    }
+
+    #[test]
+    fn set_query_rules() {
+        use serde_json::json;
+
+        use crate::index::tests::TempIndex;
+        use crate::QueryPattern;
+
+        let index = TempIndex::new();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "red shoes", "color": "red" },
+                { "id": 1, "title": "blue shoes", "color": "blue" },
+                { "id": 2, "title": "red boots", "color": "red" }
+            ]))
+            .unwrap();
+
+        let rule = QueryRule {
+            pattern: QueryPattern::Is(S("shoes")),
+            pin: vec![S("2")],
+            hide: Vec::new(),
+            filter: Some(S("(color = red")),
+            replace_query: None,
+        };
+        let error = index.update_settings(|settings| settings.set_query_rules(vec![rule.clone()]));
+        assert!(error.is_err());
+
+        let rule = QueryRule { filter: Some(S("color = red")), ..rule };
+        index.update_settings(|settings| settings.set_query_rules(vec![rule.clone()])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.query_rules(&rtxn).unwrap(), vec![rule]);
+        drop(rtxn);
+
+        index.update_settings(|settings| settings.reset_query_rules()).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.query_rules(&rtxn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_exact_attributes_and_exact_words() {
+        use serde_json::json;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_exact_attributes(hashset! { S("sku") }))
+            .unwrap();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "blue shirt", "sku": "zorblax" },
+                { "id": 1, "title": "red zorblax", "sku": "quux" },
+                { "id": 2, "title": "green shirts", "sku": "quuz" }
+            ]))
+            .unwrap();
+
+        let search = |query: &str| {
+            let mut ids = index.search_external_ids(query).unwrap();
+            ids.sort_unstable();
+            ids
+        };
+
+        // The words of the exact attributes don't match through typos nor as a prefix.
+        assert_eq!(search("zorblax"), vec![S("0"), S("1")]);
+        assert_eq!(search("zorblix"), vec![S("1")]);
+        assert_eq!(search("zorbl"), vec![S("1")]);
+        assert_eq!(search("shirt"), vec![S("0"), S("2")]);
+
+        // The exact words of the queries only match the same words.
+        index
+            .update_settings(|settings| settings.set_exact_words(btreeset! { S("Shirt") }))
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let exact_words = index.exact_words(&rtxn).unwrap().unwrap();
+        assert_eq!(exact_words.stream().into_strs().unwrap(), vec![S("shirt")]);
+        drop(rtxn);
+        assert_eq!(search("shirt"), vec![S("0")]);
+
+        // The documents are reindexed without the exact attributes.
+        index
+            .update_settings(|settings| {
+                settings.reset_exact_attributes();
+                settings.reset_exact_words();
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.exact_attributes(&rtxn).unwrap().is_empty());
+        assert!(index.exact_words(&rtxn).unwrap().is_none());
+        assert!(index.exact_word_docids.is_empty(&rtxn).unwrap());
+        drop(rtxn);
+        assert_eq!(search("zorbl"), vec![S("0"), S("1")]);
+        assert_eq!(search("shirt"), vec![S("0"), S("2")]);
+    }
+
+    #[test]
+    fn set_decompounding_dictionary() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "Hundefutter" },
+            { "id": 2, "title": "Katzenstreu" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // The compound words are not split by default.
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("futter ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_decompounding_dictionary(btreeset! { S("Hunde"), S("futter") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The documents are reindexed with the parts of the compound words.
+        let rtxn = index.read_txn().unwrap();
+        let dictionary = index.decompounding_dictionary(&rtxn).unwrap().unwrap();
+        assert!(dictionary.contains("hunde"));
+        let result = index.search(&rtxn).query("futter ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        let result = index.search(&rtxn).query("hunde ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        let result = index.search(&rtxn).query("hundefutter ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_decompounding_dictionary();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.decompounding_dictionary(&rtxn).unwrap().is_none());
+        let result = index.search(&rtxn).query("futter ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+    }
+
+    #[test]
+    fn set_stemming_language() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "running shoes" },
+            { "id": 2, "title": "walking sticks" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // The words are not stemmed by default.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.stemming_language(&rtxn).unwrap(), None);
+        let result = index.search(&rtxn).query("run ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_stemming_language(StemmingLanguage::English);
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The documents are reindexed with the stemmed words.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.stemming_language(&rtxn).unwrap(), Some(StemmingLanguage::English));
+        let result = index.search(&rtxn).query("run ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        let result = index.search(&rtxn).query("runs ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        let result = index.search(&rtxn).query("walked stick").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_stemming_language();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("run ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+    }
+
+    #[test]
+    fn set_diacritics_sensitive() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "péché" },
+            { "id": 2, "title": "pêche" },
+            { "id": 3, "title": "peche" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // The diacritics are removed by default.
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.diacritics_sensitive(&rtxn).unwrap());
+        let result = index.search(&rtxn).query("péché ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1, 2]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_diacritics_sensitive(true);
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The documents are reindexed with the diacritics of the words.
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.diacritics_sensitive(&rtxn).unwrap());
+        let result = index.search(&rtxn).query("péché ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        // The words with other diacritics are matched as typos.
+        let result = index.search(&rtxn).query("PECHE ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2, 1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_diacritics_sensitive();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("péché ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn set_case_sensitive() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "title": "us and them" },
+            { "id": 2, "title": "US army" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // The words are lowercased by default.
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.case_sensitive(&rtxn).unwrap());
+        let result = index.search(&rtxn).query("US ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_case_sensitive(true);
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The documents containing the word with the same case are ranked first,
+        // the lowercased words are still matched.
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.case_sensitive(&rtxn).unwrap());
+        let result = index.search(&rtxn).query("US ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1, 0]);
+        let result = index.search(&rtxn).query("us ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_case_sensitive();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("US ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn set_numbers_normalization() {
+        use serde_json::json;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "agent 007" },
+                { "id": 1, "title": "chapter 7" },
+                { "id": 2, "title": "1,000 miles" }
+            ]))
+            .unwrap();
+
+        // The numbers are kept as is by default.
+        assert_eq!(index.search_external_ids("7 ").unwrap(), vec!["1"]);
+        assert!(index.search_external_ids("1000 ").unwrap().is_empty());
+
+        index.update_settings(|settings| settings.set_numbers_normalization(true)).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.numbers_normalization(&rtxn).unwrap());
+        drop(rtxn);
+        assert_eq!(index.search_external_ids("7 ").unwrap(), vec!["0", "1"]);
+        assert_eq!(index.search_external_ids("0007 ").unwrap(), vec!["0", "1"]);
+        assert_eq!(index.search_external_ids("1000 ").unwrap(), vec!["2"]);
+
+        index.update_settings(|settings| settings.reset_numbers_normalization()).unwrap();
+        assert_eq!(index.search_external_ids("7 ").unwrap(), vec!["1"]);
+    }
+
+    #[test]
+    fn set_field_languages() {
+        use serde_json::json;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_field_languages(hashmap! { S("title_fr") => StemmingLanguage::French })
+            })
+            .unwrap();
+        index
+            .add_documents(json!([
+                { "id": 0, "title_fr": "chanteuses" },
+                { "id": 1, "title": "chanteuses" }
+            ]))
+            .unwrap();
+
+        let search = |query: &str, language: Option<StemmingLanguage>| {
+            let rtxn = index.read_txn().unwrap();
+            let mut search = index.search(&rtxn);
+            search.query(query).authorize_typos(false).query_language(language);
+            search.execute().unwrap().documents_ids
+        };
+
+        // Only the words of the french field are stemmed in french.
+        assert_eq!(search("chanteuses ", None), vec![1]);
+        assert!(search("chanteuse ", None).is_empty());
+        assert_eq!(search("chanteuse ", Some(StemmingLanguage::French)), vec![0]);
+
+        index.update_settings(|settings| settings.reset_field_languages()).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.field_languages(&rtxn).unwrap().is_empty());
+        drop(rtxn);
+        assert_eq!(search("chanteuses ", None), vec![0, 1]);
+    }
+
+    #[test]
+    fn set_facet_normalization() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("genre") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 1, "genre": "Café" },
+            { "id": 2, "genre": "CAFÉ" },
+            { "id": 3, "genre": "cafe" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let set_normalization = |normalization| {
+            let mut wtxn = index.write_txn().unwrap();
+            let mut builder = Settings::new(&mut wtxn, &index, &config);
+            builder.set_facet_normalization(hashmap! { S("genre") => normalization });
+            builder.execute(|_| ()).unwrap();
+            wtxn.commit().unwrap();
+        };
+        let filter_and_distribution = |expression: &str| {
+            let rtxn = index.read_txn().unwrap();
+            let mut search = index.search(&rtxn);
+            search.filter(Filter::from_str(expression).unwrap().unwrap());
+            let documents_ids = search.execute().unwrap().documents_ids;
+            let mut distribution = index.facets_distribution(&rtxn).execute().unwrap();
+            (documents_ids, distribution.remove("genre").unwrap())
+        };
+
+        // The values are lowercased by default.
+        let (documents_ids, distribution) = filter_and_distribution("genre = café");
+        assert_eq!(documents_ids, vec![0, 1]);
+        assert_eq!(distribution.values().copied().collect::<Vec<_>>(), vec![2, 1]);
+
+        set_normalization(FacetNormalization::Preserve);
+        let (documents_ids, distribution) = filter_and_distribution("genre = Café");
+        assert_eq!(documents_ids, vec![0]);
+        let expected = btreemap! { S("CAFÉ") => 1, S("Café") => 1, S("cafe") => 1 };
+        assert_eq!(distribution, expected);
+
+        set_normalization(FacetNormalization::Fold);
+        let (documents_ids, distribution) = filter_and_distribution("genre = cafe");
+        assert_eq!(documents_ids, vec![0, 1, 2]);
+        assert_eq!(distribution.values().copied().collect::<Vec<_>>(), vec![3]);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_facet_normalization();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let (documents_ids, _) = filter_and_distribution("genre = cafe");
+        assert_eq!(documents_ids, vec![2]);
+    }
+
+    #[test]
+    fn set_date_fields() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("published_at") });
+        builder.set_date_fields(hashset! { S("published_at") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 1, "published_at": "2020-12-31T23:30:00Z" },
+            { "id": 2, "published_at": ["2021-01-01T10:00:00Z", "2021-01-01T11:00:00Z"] },
+            { "id": 3, "published_at": "2021-03-14T10:00:00+02:00" },
+            { "id": 4, "published_at": "not a date" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.date_fields(&rtxn).unwrap(), hashset! { S("published_at") });
+
+        let distribution_by = |granularity| {
+            let date_granularity = Some(granularity);
+            let options = crate::FacetOptions { date_granularity, ..Default::default() };
+            let mut distribution = index.facets_distribution(&rtxn);
+            distribution.facet_options("published_at", options);
+            distribution.execute().unwrap().remove("published_at").unwrap()
+        };
+        let expected = btreemap! { S("2020") => 1, S("2021") => 2 };
+        assert_eq!(distribution_by(DateGranularity::Year), expected);
+        let expected = btreemap! { S("2020-12") => 1, S("2021-01") => 1, S("2021-03") => 1 };
+        assert_eq!(distribution_by(DateGranularity::Month), expected);
+        let expected = btreemap! {
+            S("2020-12-31T23") => 1,
+            S("2021-01-01T10") => 1,
+            S("2021-01-01T11") => 1,
+            S("2021-03-14T08") => 1,
+        };
+        assert_eq!(distribution_by(DateGranularity::Hour), expected);
+
+        // The dates are filtered by their timestamps.
+        let mut search = index.search(&rtxn);
+        search.filter(Filter::from_str("published_at > 1609459200").unwrap().unwrap());
+        assert_eq!(search.execute().unwrap().documents_ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn set_hierarchical_facets() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_filterable_fields(hashset! { S("category") });
+        builder.execute(|_| ()).unwrap();
+
+        let content = documents!([
+            { "id": 1, "category": "Books > SciFi > Cyberpunk" },
+            { "id": 2, "category": "Books > SciFi > Space Opera" },
+            { "id": 3, "category": ["Books > History", "Movies > SciFi"] },
+            { "id": 4, "category": "Movies" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_hierarchical_facets(hashset! { S("category") });
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.hierarchical_facets(&rtxn).unwrap(), hashset! { S("category") });
+
+        // A filter on a level selects its whole subtree.
+        let filter_ids = |expression: &str| {
+            let mut search = index.search(&rtxn);
+            search.filter(Filter::from_str(expression).unwrap().unwrap());
+            search.execute().unwrap().documents_ids
+        };
+        assert_eq!(filter_ids("category = Books"), vec![0, 1, 2]);
+        assert_eq!(filter_ids("category = \"books > scifi\""), vec![0, 1]);
+        assert_eq!(filter_ids("category = Movies"), vec![2, 3]);
+
+        let distribution_at = |depth| {
+            let options = crate::FacetOptions { depth: Some(depth), ..Default::default() };
+            let mut distribution = index.facets_distribution(&rtxn);
+            distribution.facet_options("category", options);
+            distribution.execute().unwrap().remove("category").unwrap()
+        };
+        assert_eq!(distribution_at(1), btreemap! { S("Books") => 3, S("Movies") => 2 });
+        let expected = btreemap! {
+            S("Books > History") => 1,
+            S("Books > SciFi") => 2,
+            S("Movies > SciFi") => 1,
+        };
+        assert_eq!(distribution_at(2), expected);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_hierarchical_facets();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The paths are indexed as they are again.
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.filter(Filter::from_str("category = Books").unwrap().unwrap());
+        assert!(search.execute().unwrap().documents_ids.is_empty());
+    }
+
+    #[cfg(feature = "phonetic")]
+    #[test]
+    fn set_phonetic_matching() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kathryn smyth" },
+            { "id": 2, "name": "john doe" }
+        ]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // The phonetic matching is disabled by default.
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.phonetic_matching(&rtxn).unwrap());
+        let result = index.search(&rtxn).query("catherine ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.set_phonetic_matching(true);
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("catherine ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        // The words of the new documents are added to the phonetic words.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 3, "name": "steven" }]);
+        let indexing_config = IndexDocumentsConfig::default();
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ());
+        builder.add_documents(content).unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("stephen ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, &config);
+        builder.reset_phonetic_matching();
+        builder.execute(|_| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("catherine ").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+    }
+
     #[test]
     fn setting_searchable_recomputes_other_settings() {
         let path = tempfile::tempdir().unwrap();
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use crate::error::InternalError;
+use crate::update::UpdateId;
+use crate::{Index, Result};
+
+/// The name of the journal file, stored in the directory of the index.
+pub const JOURNAL_FILE_NAME: &str = "updates.journal";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JournalState {
+    Started,
+    Committed,
+    Aborted,
+    /// The update was found started but not finished when the journal was opened.
+    Interrupted,
+}
+
+/// A line of the journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntry {
+    update_id: UpdateId,
+    state: JournalState,
+    at: OffsetDateTime,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    metadata: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// An update that was started but never committed nor aborted, the process
+/// stopped while it was being processed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptedUpdate {
+    pub update_id: UpdateId,
+    /// The metadata the update was registered with.
+    pub metadata: Value,
+    pub started_at: OffsetDateTime,
+    /// Whether the write transaction of the update was committed in the index before
+    /// the process stopped, if not the index is in the state preceding the update.
+    pub applied: bool,
+}
+
+/// What was found in the journal when it was opened.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalRecovery {
+    /// The id the next update must be registered with.
+    pub next_update_id: UpdateId,
+    /// The updates interrupted since the journal was last opened.
+    pub interrupted: Vec<InterruptedUpdate>,
+}
+
+impl JournalRecovery {
+    /// Returns whether every update of the journal was committed or aborted.
+    pub fn is_clean(&self) -> bool {
+        self.interrupted.is_empty()
+    }
+}
+
+/// A write-ahead journal of the updates of an index, the metadata of an update is written
+/// and synced to disk before its write transaction is started, its outcome once it is done.
+///
+/// When the journal is opened the updates that were started but not finished are reported,
+/// the ones whose write transaction was committed are told apart by the id of the last
+/// journaled update that is stored in the index with the update itself.
+pub struct UpdateJournal {
+    file: File,
+    path: PathBuf,
+}
+
+impl UpdateJournal {
+    /// Opens the journal of the index, creating it if it doesn't exist, and reports
+    /// the updates that were interrupted.
+    ///
+    /// Once the interrupted updates are known every update of the journal is finished,
+    /// the journal is compacted to the last entry, the one giving the next update id.
+    pub fn open(index: &Index) -> Result<(UpdateJournal, JournalRecovery)> {
+        let path = index.path().join(JOURNAL_FILE_NAME);
+        let entries = read_entries(&path)?;
+
+        let last_applied = index.last_journaled_update(&index.read_txn()?)?;
+        let mut started = BTreeMap::new();
+        let mut next_update_id = last_applied.map_or(0, |id| id + 1);
+        let mut last_entry: Option<JournalEntry> = None;
+        for entry in entries {
+            next_update_id = next_update_id.max(entry.update_id + 1);
+            if last_entry.as_ref().map_or(true, |last| last.update_id <= entry.update_id) {
+                last_entry = Some(entry.clone());
+            }
+            match entry.state {
+                JournalState::Started => {
+                    started.insert(entry.update_id, entry);
+                }
+                _ => {
+                    started.remove(&entry.update_id);
+                }
+            }
+        }
+
+        let mut interrupted = Vec::with_capacity(started.len());
+        for (update_id, entry) in started {
+            let applied = last_applied.map_or(false, |last| last >= update_id);
+            warn!(
+                "Update {} was interrupted, it was {}applied to the index",
+                update_id,
+                if applied { "" } else { "not " }
+            );
+            interrupted.push(InterruptedUpdate {
+                update_id,
+                metadata: entry.metadata,
+                started_at: entry.at,
+                applied,
+            });
+        }
+
+        // The interrupted updates are only reported once, the last entry is kept
+        // as interrupted if its update was.
+        if let Some(entry) = last_entry.as_mut() {
+            if entry.state == JournalState::Started {
+                entry.state = JournalState::Interrupted;
+                entry.at = OffsetDateTime::now_utc();
+                entry.metadata = Value::Null;
+            }
+        }
+        let file = compact(&path, last_entry.as_ref())?;
+
+        Ok((UpdateJournal { file, path }, JournalRecovery { next_update_id, interrupted }))
+    }
+
+    /// Returns the path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes that the update is started, must be called before its write transaction.
+    pub fn start(&mut self, update_id: UpdateId, metadata: Value) -> Result<()> {
+        self.write(update_id, JournalState::Started, metadata, None)
+    }
+
+    /// Writes that the write transaction of the update was committed.
+    pub fn commit(&mut self, update_id: UpdateId) -> Result<()> {
+        self.write(update_id, JournalState::Committed, Value::Null, None)
+    }
+
+    /// Writes that the write transaction of the update was aborted.
+    pub fn abort(&mut self, update_id: UpdateId, error: &str) -> Result<()> {
+        self.write(update_id, JournalState::Aborted, Value::Null, Some(error))
+    }
+
+    fn write(
+        &mut self,
+        update_id: UpdateId,
+        state: JournalState,
+        metadata: Value,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let at = OffsetDateTime::now_utc();
+        let error = error.map(String::from);
+        let entry = JournalEntry { update_id, state, at, metadata, error };
+        let mut line = serde_json::to_vec(&entry).map_err(InternalError::SerdeJson)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Reads the entries of the journal, the lines that can't be read are ignored,
+/// the last one can be partially written if the process stopped while writing it.
+fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut entries = Vec::new();
+    for line in bytes.split(|&b| b == b'\n').filter(|line| !line.is_empty()) {
+        match serde_json::from_slice(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Ignoring an invalid line of the update journal: {}", e),
+        }
+    }
+    Ok(entries)
+}
+
+/// Replaces the journal by a journal only containing the given entry and opens it
+/// to append the next entries. The new journal is written next to the current one
+/// and renamed, a crash while compacting leaves one of them complete.
+fn compact(path: &Path, entry: Option<&JournalEntry>) -> Result<File> {
+    let compacted_path = path.with_extension("journal.compacted");
+    let mut file = File::create(&compacted_path)?;
+    if let Some(entry) = entry {
+        let mut line = serde_json::to_vec(entry).map_err(InternalError::SerdeJson)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+    }
+    file.sync_data()?;
+    std::fs::rename(&compacted_path, path)?;
+
+    Ok(OpenOptions::new().append(true).open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use heed::EnvOpenOptions;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn interrupted_updates() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let (mut journal, recovery) = UpdateJournal::open(&index).unwrap();
+        assert_eq!(recovery, JournalRecovery::default());
+
+        journal.start(0, json!({ "kind": "settings" })).unwrap();
+        journal.commit(0).unwrap();
+        journal.start(1, json!({ "kind": "documentsAddition" })).unwrap();
+        journal.abort(1, "invalid document").unwrap();
+
+        // The update 2 is committed in the index but the process stops before
+        // it is journaled, the update 3 stops before its commit.
+        journal.start(2, json!({ "kind": "documentsAddition" })).unwrap();
+        let mut wtxn = index.write_txn().unwrap();
+        index.put_last_journaled_update(&mut wtxn, 2).unwrap();
+        wtxn.commit().unwrap();
+        journal.start(3, json!({ "kind": "documentsDeletion" })).unwrap();
+        drop(journal);
+
+        let (journal, recovery) = UpdateJournal::open(&index).unwrap();
+        assert!(!recovery.is_clean());
+        assert_eq!(recovery.next_update_id, 4);
+        let interrupted: Vec<_> = recovery
+            .interrupted
+            .iter()
+            .map(|update| (update.update_id, update.metadata.clone(), update.applied))
+            .collect();
+        assert_eq!(
+            interrupted,
+            vec![
+                (2, json!({ "kind": "documentsAddition" }), true),
+                (3, json!({ "kind": "documentsDeletion" }), false),
+            ]
+        );
+        drop(journal);
+
+        // The interrupted updates are only reported once.
+        let (_journal, recovery) = UpdateJournal::open(&index).unwrap();
+        assert!(recovery.is_clean());
+        assert_eq!(recovery.next_update_id, 4);
+    }
+
+    #[test]
+    fn compact_the_journal_when_opened() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let (mut journal, _) = UpdateJournal::open(&index).unwrap();
+        for update_id in 0..10 {
+            journal.start(update_id, json!({ "kind": "settings" })).unwrap();
+            journal.commit(update_id).unwrap();
+        }
+        // The last update is not stored in the index, the journal must remember it.
+        journal.start(10, json!({ "kind": "documentsAddition" })).unwrap();
+        journal.abort(10, "invalid document").unwrap();
+        let journal_path = journal.path().to_path_buf();
+        drop(journal);
+
+        let (mut journal, recovery) = UpdateJournal::open(&index).unwrap();
+        assert!(recovery.is_clean());
+        assert_eq!(recovery.next_update_id, 11);
+        let entries = read_entries(&journal_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].update_id, 10);
+        assert_eq!(entries[0].state, JournalState::Aborted);
+
+        // The compacted journal is appended to and compacted again.
+        journal.start(11, json!({ "kind": "documentsDeletion" })).unwrap();
+        drop(journal);
+
+        let (_journal, recovery) = UpdateJournal::open(&index).unwrap();
+        assert_eq!(recovery.next_update_id, 12);
+        assert_eq!(recovery.interrupted.len(), 1);
+        let entries = read_entries(&journal_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].update_id, 11);
+        assert_eq!(entries[0].state, JournalState::Interrupted);
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crossbeam_channel::{unbounded, Sender};
+use log::{error, info};
+use serde_json::Value;
+
+use crate::update::{IndexerConfig, JournalRecovery, UpdateJournal};
+use crate::{Index, Result};
+
+pub type UpdateId = u64;
+
+type Update = Box<
+    dyn for<'a> FnOnce(&mut heed::RwTxn<'a, 'a>, &'a Index, &IndexerConfig) -> Result<()> + Send,
+>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateStatus {
+    /// The update is waiting for the previous ones to be processed.
+    Enqueued,
+    /// The update is being processed in its write transaction.
+    Processing,
+    /// The update has been processed and its write transaction committed.
+    Processed,
+    /// The update failed with the given error and its write transaction was aborted.
+    Failed(String),
+}
+
+impl UpdateStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, UpdateStatus::Processed | UpdateStatus::Failed(_))
+    }
+}
+
+#[derive(Default)]
+struct Statuses {
+    next_update_id: UpdateId,
+    statuses: HashMap<UpdateId, UpdateStatus>,
+}
+
+/// Serializes the write operations (documents additions, deletions, settings...) that are
+/// registered from any thread and processes them one by one, each in its own write
+/// transaction, on a dedicated thread.
+///
+/// The status of the updates can be retrieved with their update id, the updates that
+/// are registered in the queue are processed in the order they were registered.
+///
+/// The queue can keep a journal of the updates, see [`UpdateJournal`], to know after a crash
+/// which updates were interrupted and whether they were applied to the index.
+pub struct UpdateQueue {
+    sender: Option<Sender<(UpdateId, Value, Update)>>,
+    statuses: Arc<(Mutex<Statuses>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl UpdateQueue {
+    pub fn new(index: Index, config: IndexerConfig) -> UpdateQueue {
+        UpdateQueue::start(index, config, None, 0)
+    }
+
+    /// Creates a queue journaling its updates in the journal of the index and returns
+    /// what the journal reported when it was opened, the interrupted updates.
+    pub fn with_journal(
+        index: Index,
+        config: IndexerConfig,
+    ) -> Result<(UpdateQueue, JournalRecovery)> {
+        let (journal, recovery) = UpdateJournal::open(&index)?;
+        let queue = UpdateQueue::start(index, config, Some(journal), recovery.next_update_id);
+        Ok((queue, recovery))
+    }
+
+    fn start(
+        index: Index,
+        config: IndexerConfig,
+        mut journal: Option<UpdateJournal>,
+        next_update_id: UpdateId,
+    ) -> UpdateQueue {
+        let (sender, receiver) = unbounded::<(UpdateId, Value, Update)>();
+        let statuses = Statuses { next_update_id, statuses: HashMap::new() };
+        let statuses = Arc::new((Mutex::new(statuses), Condvar::new()));
+
+        let worker_statuses = statuses.clone();
+        let handle = thread::spawn(move || {
+            for (update_id, metadata, update) in receiver {
+                set_status(&worker_statuses, update_id, UpdateStatus::Processing);
+                info!("Processing update {}", update_id);
+
+                let result = match journal.as_mut() {
+                    Some(journal) => process_journaled_update(
+                        &index, &config, journal, update_id, metadata, update,
+                    ),
+                    None => process_update(&index, &config, None, update),
+                };
+                let status = match result {
+                    Ok(()) => UpdateStatus::Processed,
+                    Err(e) => {
+                        error!("Update {} failed: {}", update_id, e);
+                        UpdateStatus::Failed(e)
+                    }
+                };
+
+                set_status(&worker_statuses, update_id, status);
+            }
+        });
+
+        UpdateQueue { sender: Some(sender), statuses, handle: Some(handle) }
+    }
+
+    /// Registers an update in the queue and returns its update id.
+    ///
+    /// The update function is given a write transaction that is committed if it returns
+    /// successfully and aborted otherwise.
+    pub fn register<F>(&self, update: F) -> UpdateId
+    where
+        F: for<'a> FnOnce(&mut heed::RwTxn<'a, 'a>, &'a Index, &IndexerConfig) -> Result<()>,
+        F: Send + 'static,
+    {
+        self.register_with_metadata(Value::Null, update)
+    }
+
+    /// Registers an update in the queue like `register`, the metadata describing the
+    /// update, e.g. its kind and the name of its payload, is written in the journal.
+    pub fn register_with_metadata<F>(&self, metadata: Value, update: F) -> UpdateId
+    where
+        F: for<'a> FnOnce(&mut heed::RwTxn<'a, 'a>, &'a Index, &IndexerConfig) -> Result<()>,
+        F: Send + 'static,
+    {
+        let (lock, _) = &*self.statuses;
+        let mut statuses = lock.lock().unwrap();
+        let update_id = statuses.next_update_id;
+        statuses.next_update_id += 1;
+        statuses.statuses.insert(update_id, UpdateStatus::Enqueued);
+
+        // The lock is held while sending to keep the updates ordered by update id.
+        // The worker thread only stops when the queue is dropped, the channel is never closed.
+        let sender = self.sender.as_ref().unwrap();
+        sender.send((update_id, metadata, Box::new(update))).unwrap();
+
+        update_id
+    }
+
+    /// Returns the status of the update, `None` if it was never registered.
+    pub fn status(&self, update_id: UpdateId) -> Option<UpdateStatus> {
+        let (lock, _) = &*self.statuses;
+        lock.lock().unwrap().statuses.get(&update_id).cloned()
+    }
+
+    /// Blocks until the update is processed or failed and returns its status,
+    /// `None` if it was never registered.
+    pub fn wait(&self, update_id: UpdateId) -> Option<UpdateStatus> {
+        let (lock, condvar) = &*self.statuses;
+        let mut statuses = lock.lock().unwrap();
+        loop {
+            match statuses.statuses.get(&update_id) {
+                Some(status) if status.is_finished() => return Some(status.clone()),
+                Some(_) => statuses = condvar.wait(statuses).unwrap(),
+                None => return None,
+            }
+        }
+    }
+
+    /// Removes the statuses of the finished updates and returns them.
+    pub fn remove_finished(&self) -> Vec<(UpdateId, UpdateStatus)> {
+        let (lock, _) = &*self.statuses;
+        let mut statuses = lock.lock().unwrap();
+        let mut finished = Vec::new();
+        statuses.statuses.retain(|update_id, status| {
+            if status.is_finished() {
+                finished.push((*update_id, status.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        finished.sort_unstable_by_key(|(update_id, _)| *update_id);
+        finished
+    }
+}
+
+impl Drop for UpdateQueue {
+    /// Waits for the registered updates to be processed.
+    fn drop(&mut self) {
+        // Dropping the sender stops the worker once the channel is empty.
+        drop(self.sender.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn set_status(statuses: &(Mutex<Statuses>, Condvar), update_id: UpdateId, status: UpdateStatus) {
+    let (lock, condvar) = statuses;
+    lock.lock().unwrap().statuses.insert(update_id, status);
+    condvar.notify_all();
+}
+
+/// Writes the update in the journal before processing it and its outcome after.
+fn process_journaled_update(
+    index: &Index,
+    config: &IndexerConfig,
+    journal: &mut UpdateJournal,
+    update_id: UpdateId,
+    metadata: Value,
+    update: Update,
+) -> std::result::Result<(), String> {
+    // An update that can't be journaled is not processed.
+    journal.start(update_id, metadata).map_err(|e| e.to_string())?;
+    let result = process_update(index, config, Some(update_id), update);
+    let journaled = match &result {
+        Ok(()) => journal.commit(update_id),
+        Err(e) => journal.abort(update_id, e),
+    };
+    if let Err(e) = journaled {
+        error!("The outcome of the update {} could not be journaled: {}", update_id, e);
+    }
+    result
+}
+
+/// Runs the update in a write transaction and commits it if it succeeded,
+/// a panicking update is reported as failed.
+///
+/// The id of a journaled update is stored in the index with the update.
+fn process_update(
+    index: &Index,
+    config: &IndexerConfig,
+    journaled_update_id: Option<UpdateId>,
+    update: Update,
+) -> std::result::Result<(), String> {
+    let mut wtxn = index.write_txn().map_err(|e| e.to_string())?;
+    let result = catch_unwind(AssertUnwindSafe(|| update(&mut wtxn, index, config)));
+    match result {
+        Ok(Ok(())) => {
+            if let Some(update_id) = journaled_update_id {
+                index.put_last_journaled_update(&mut wtxn, update_id).map_err(|e| e.to_string())?;
+            }
+            wtxn.commit().map_err(|e| e.to_string())
+        }
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(String::from("the update panicked")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use heed::EnvOpenOptions;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::update::{DeleteDocuments, IndexDocuments, IndexDocumentsConfig, Settings};
+
+    #[test]
+    fn updates_from_multiple_threads() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let queue = Arc::new(UpdateQueue::new(index.clone(), IndexerConfig::default()));
+
+        let settings_id = queue.register(|wtxn, index, config| {
+            let mut builder = Settings::new(wtxn, index, config);
+            builder.set_filterable_fields(hashset! { S("name") });
+            builder.execute(|_| ())
+        });
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    queue.register(move |wtxn, index, config| {
+                        let content = documents!([{ "id": i, "name": "kevin" }]);
+                        let indexing_config = IndexDocumentsConfig::default();
+                        let mut builder =
+                            IndexDocuments::new(wtxn, index, config, indexing_config, |_| ());
+                        builder.add_documents(content)?;
+                        builder.execute()?;
+                        Ok(())
+                    })
+                })
+            })
+            .collect();
+
+        let update_ids: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(queue.wait(settings_id), Some(UpdateStatus::Processed));
+        for update_id in update_ids {
+            assert_eq!(queue.wait(update_id), Some(UpdateStatus::Processed));
+        }
+
+        let deletion_id = queue.register(|wtxn, index, _config| {
+            let mut builder = DeleteDocuments::new(wtxn, index)?;
+            builder.delete_external_id("0");
+            builder.execute()?;
+            Ok(())
+        });
+        assert_eq!(queue.wait(deletion_id), Some(UpdateStatus::Processed));
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 3);
+        assert_eq!(index.filterable_fields(&rtxn).unwrap(), hashset! { S("name") });
+    }
+
+    #[test]
+    fn failed_update_is_aborted() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let queue = UpdateQueue::new(index.clone(), IndexerConfig::default());
+
+        let update_id = queue.register(|wtxn, index, config| {
+            let content = documents!([{ "id": 0, "name": "kevin" }]);
+            let indexing_config = IndexDocumentsConfig::default();
+            let mut builder = IndexDocuments::new(wtxn, index, config, indexing_config, |_| ());
+            builder.add_documents(content)?;
+            builder.execute()?;
+            // The documents have been indexed but the update must not be committed.
+            let content = documents!([{ "name": "kevina" }]);
+            let indexing_config = IndexDocumentsConfig::default();
+            let mut builder = IndexDocuments::new(wtxn, index, config, indexing_config, |_| ());
+            builder.add_documents(content)?;
+            Ok(())
+        });
+
+        assert!(matches!(queue.wait(update_id), Some(UpdateStatus::Failed(_))));
+        assert_eq!(queue.wait(update_id + 1), None);
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 0);
+        drop(rtxn);
+
+        assert_eq!(queue.remove_finished().len(), 1);
+        assert_eq!(queue.status(update_id), None);
+    }
+
+    #[test]
+    fn journaled_updates() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let (queue, recovery) =
+            UpdateQueue::with_journal(index.clone(), IndexerConfig::default()).unwrap();
+        assert!(recovery.is_clean());
+        let metadata = serde_json::json!({ "kind": "settings" });
+        let settings_id = queue.register_with_metadata(metadata, |wtxn, index, config| {
+            let mut builder = Settings::new(wtxn, index, config);
+            builder.set_filterable_fields(hashset! { S("name") });
+            builder.execute(|_| ())
+        });
+        let failed_id = queue.register(|_wtxn, _index, _config| {
+            Err(crate::error::UserError::MissingPrimaryKey.into())
+        });
+        assert_eq!(queue.wait(settings_id), Some(UpdateStatus::Processed));
+        assert!(matches!(queue.wait(failed_id), Some(UpdateStatus::Failed(_))));
+        drop(queue);
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.last_journaled_update(&rtxn).unwrap(), Some(settings_id));
+        drop(rtxn);
+
+        // The ids of the updates continue after the ones of the journal.
+        let (queue, recovery) =
+            UpdateQueue::with_journal(index.clone(), IndexerConfig::default()).unwrap();
+        assert!(recovery.is_clean());
+        assert_eq!(recovery.next_update_id, failed_id + 1);
+        let update_id = queue.register(|_wtxn, _index, _config| Ok(()));
+        assert_eq!(update_id, failed_id + 1);
+        assert_eq!(queue.wait(update_id), Some(UpdateStatus::Processed));
+    }
+}
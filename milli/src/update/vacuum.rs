@@ -0,0 +1,342 @@
+use fst::IntoStreamer;
+use heed::types::{ByteSlice, DecodeIgnore};
+use heed::{BytesDecode, BytesEncode};
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::error::SerializationError;
+use crate::heed_codec::facet::{
+    FacetLevelValueU32Codec, FacetStringLevelZeroValueCodec, FacetStringZeroBoundsValueCodec,
+};
+use crate::heed_codec::CboRoaringBitmapCodec;
+use crate::index::db_name;
+use crate::{Index, Result};
+
+/// Removes, from the word and facet databases, the documents ids that are no longer
+/// in the documents ids of the index and deletes the entries left empty.
+pub struct Vacuum<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+}
+
+/// What was reclaimed by a [`Vacuum`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VacuumResult {
+    /// The number of documents ids that were referenced but are not in the index.
+    pub orphan_documents: u64,
+    /// The number of entries from which orphan documents ids were removed.
+    pub cleaned_entries: u64,
+    /// The number of entries that only referenced orphan documents and were deleted.
+    pub deleted_entries: u64,
+}
+
+impl<'t, 'u, 'i> Vacuum<'t, 'u, 'i> {
+    pub fn new(wtxn: &'t mut heed::RwTxn<'i, 'u>, index: &'i Index) -> Vacuum<'t, 'u, 'i> {
+        Vacuum { wtxn, index }
+    }
+
+    pub fn execute(self) -> Result<VacuumResult> {
+        self.index.set_updated_at(self.wtxn, &OffsetDateTime::now_utc())?;
+        let Index {
+            word_docids,
+            exact_word_docids,
+            word_prefix_docids,
+            docid_word_positions,
+            word_pair_proximity_docids,
+            word_prefix_pair_proximity_docids,
+            word_position_docids,
+            field_id_word_count_docids,
+            word_prefix_position_docids,
+            facet_id_f64_docids,
+            facet_id_i128_docids,
+            facet_id_string_docids,
+            field_id_docid_facet_f64s,
+            field_id_docid_facet_strings,
+            ..
+        } = self.index;
+
+        let documents_ids = self.index.documents_ids(self.wtxn)?;
+        let mut orphans = RoaringBitmap::new();
+        let mut result = VacuumResult::default();
+
+        let deleted_words = prune_docids(
+            self.wtxn,
+            word_docids.remap_key_type(),
+            &documents_ids,
+            &mut orphans,
+            &mut result,
+        )?;
+        prune_docids(
+            self.wtxn,
+            exact_word_docids.remap_key_type(),
+            &documents_ids,
+            &mut orphans,
+            &mut result,
+        )?;
+        let deleted_prefixes = prune_docids(
+            self.wtxn,
+            word_prefix_docids.remap_key_type(),
+            &documents_ids,
+            &mut orphans,
+            &mut result,
+        )?;
+        let cbo_databases = [
+            word_pair_proximity_docids.remap_key_type::<ByteSlice>(),
+            word_prefix_pair_proximity_docids.remap_key_type(),
+            word_position_docids.remap_key_type(),
+            word_prefix_position_docids.remap_key_type(),
+            field_id_word_count_docids.remap_key_type(),
+            facet_id_f64_docids.remap_key_type(),
+            facet_id_i128_docids.remap_key_type(),
+        ];
+        for db in cbo_databases.iter().copied() {
+            prune_docids(self.wtxn, db, &documents_ids, &mut orphans, &mut result)?;
+        }
+        prune_facet_string_docids(
+            self.wtxn,
+            facet_id_string_docids,
+            &documents_ids,
+            &mut orphans,
+            &mut result,
+        )?;
+
+        // The databases keyed by the documents ids only contain the entries of one document.
+        let mut iter =
+            docid_word_positions.remap_data_type::<DecodeIgnore>().iter_mut(self.wtxn)?;
+        while let Some(((docid, _), ())) = iter.next().transpose()? {
+            if !documents_ids.contains(docid) {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+                orphans.insert(docid);
+                result.deleted_entries += 1;
+            }
+        }
+        drop(iter);
+
+        let mut iter = field_id_docid_facet_f64s.iter_mut(self.wtxn)?;
+        while let Some(((_, docid, _), ())) = iter.next().transpose()? {
+            if !documents_ids.contains(docid) {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+                orphans.insert(docid);
+                result.deleted_entries += 1;
+            }
+        }
+        drop(iter);
+
+        let mut iter =
+            field_id_docid_facet_strings.remap_data_type::<DecodeIgnore>().iter_mut(self.wtxn)?;
+        while let Some(((_, docid, _), ())) = iter.next().transpose()? {
+            if !documents_ids.contains(docid) {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+                orphans.insert(docid);
+                result.deleted_entries += 1;
+            }
+        }
+        drop(iter);
+
+        for field_id in self.index.faceted_fields_ids(self.wtxn)? {
+            let mut docids = self.index.number_faceted_documents_ids(self.wtxn, field_id)?;
+            orphans |= &docids - &documents_ids;
+            docids &= &documents_ids;
+            self.index.put_number_faceted_documents_ids(self.wtxn, field_id, &docids)?;
+
+            let mut docids = self.index.string_faceted_documents_ids(self.wtxn, field_id)?;
+            orphans |= &docids - &documents_ids;
+            docids &= &documents_ids;
+            self.index.put_string_faceted_documents_ids(self.wtxn, field_id, &docids)?;
+        }
+
+        if let Some(mut rtree) = self.index.geo_rtree(self.wtxn)? {
+            let points: Vec<_> = rtree
+                .iter()
+                .filter(|point| !documents_ids.contains(point.data.0))
+                .cloned()
+                .collect();
+            for point in &points {
+                rtree.remove(point);
+                orphans.insert(point.data.0);
+            }
+            if !points.is_empty() {
+                self.index.put_geo_rtree(self.wtxn, &rtree)?;
+            }
+
+            let mut geo_faceted_documents_ids = self.index.geo_faceted_documents_ids(self.wtxn)?;
+            orphans |= &geo_faceted_documents_ids - &documents_ids;
+            geo_faceted_documents_ids &= &documents_ids;
+            self.index.put_geo_faceted_documents_ids(self.wtxn, &geo_faceted_documents_ids)?;
+        }
+
+        // The words and prefixes whose entries were deleted are removed from the fsts.
+        if !deleted_words.is_empty() {
+            let deleted_words = fst::Set::from_iter(deleted_words)?;
+            let new_words_fst = {
+                let words_fst = self.index.words_fst(self.wtxn)?;
+                let difference = words_fst.op().add(&deleted_words).difference();
+                let mut builder = fst::SetBuilder::memory();
+                builder.extend_stream(difference.into_stream())?;
+                builder.into_set()
+            };
+            self.index.put_words_fst(self.wtxn, &new_words_fst)?;
+        }
+
+        if !deleted_prefixes.is_empty() {
+            let deleted_prefixes = fst::Set::from_iter(deleted_prefixes)?;
+            let new_words_prefixes_fst = {
+                let words_prefixes_fst = self.index.words_prefixes_fst(self.wtxn)?;
+                let difference = words_prefixes_fst.op().add(&deleted_prefixes).difference();
+                let mut builder = fst::SetBuilder::memory();
+                builder.extend_stream(difference.into_stream())?;
+                builder.into_set()
+            };
+            self.index.put_words_prefixes_fst(self.wtxn, &new_words_prefixes_fst)?;
+        }
+
+        result.orphan_documents = orphans.len();
+        Ok(result)
+    }
+}
+
+/// Keeps the documents ids of the index in the bitmaps of the database,
+/// returns the keys of the deleted entries, in order.
+fn prune_docids<C>(
+    wtxn: &mut heed::RwTxn,
+    db: heed::Database<ByteSlice, C>,
+    documents_ids: &RoaringBitmap,
+    orphans: &mut RoaringBitmap,
+    result: &mut VacuumResult,
+) -> heed::Result<Vec<Vec<u8>>>
+where
+    C: for<'a> BytesDecode<'a, DItem = RoaringBitmap>
+        + for<'a> BytesEncode<'a, EItem = RoaringBitmap>,
+{
+    let mut deleted_keys = Vec::new();
+    let mut iter = db.iter_mut(wtxn)?;
+    while let Some((key, mut docids)) = iter.next().transpose()? {
+        let previous_len = docids.len();
+        orphans.extend(docids.iter().filter(|docid| !documents_ids.contains(*docid)));
+        docids &= documents_ids;
+        if docids.is_empty() {
+            let key = key.to_owned();
+            // safety: we don't keep references from inside the LMDB database.
+            unsafe { iter.del_current()? };
+            deleted_keys.push(key);
+            result.deleted_entries += 1;
+        } else if docids.len() != previous_len {
+            let key = key.to_owned();
+            // safety: we don't keep references from inside the LMDB database.
+            unsafe { iter.put_current(&key, &docids)? };
+            result.cleaned_entries += 1;
+        }
+    }
+
+    Ok(deleted_keys)
+}
+
+/// Keeps the documents ids of the index in the facet string database,
+/// whose level zero and group entries store their bitmaps along other values.
+fn prune_facet_string_docids<C, D>(
+    wtxn: &mut heed::RwTxn,
+    db: &heed::Database<C, D>,
+    documents_ids: &RoaringBitmap,
+    orphans: &mut RoaringBitmap,
+    result: &mut VacuumResult,
+) -> Result<()> {
+    let db_name = Some(db_name::FACET_ID_STRING_DOCIDS);
+    let mut iter = db.remap_types::<ByteSlice, ByteSlice>().iter_mut(wtxn)?;
+    while let Some((key, val)) = iter.next().transpose()? {
+        // The group level keys can be parsed, the level zero ones can't.
+        let is_group = FacetLevelValueU32Codec::bytes_decode(key).is_some();
+        let (original_or_group, mut docids) = if is_group {
+            let (group, docids) =
+                FacetStringZeroBoundsValueCodec::<CboRoaringBitmapCodec>::bytes_decode(val)
+                    .ok_or(SerializationError::Decoding { db_name })?;
+            (Err(group), docids)
+        } else {
+            let (original, docids) = FacetStringLevelZeroValueCodec::bytes_decode(val)
+                .ok_or(SerializationError::Decoding { db_name })?;
+            (Ok(original), docids)
+        };
+
+        let previous_len = docids.len();
+        orphans.extend(docids.iter().filter(|docid| !documents_ids.contains(*docid)));
+        docids &= documents_ids;
+        if docids.is_empty() {
+            // safety: we don't keep references from inside the LMDB database.
+            unsafe { iter.del_current()? };
+            result.deleted_entries += 1;
+        } else if docids.len() != previous_len {
+            let value_bytes = match original_or_group {
+                Ok(original) => FacetStringLevelZeroValueCodec::bytes_encode(&(original, docids))
+                    .map(|bytes| bytes.into_owned()),
+                Err(group) => {
+                    FacetStringZeroBoundsValueCodec::<CboRoaringBitmapCodec>::bytes_encode(&(
+                        group, docids,
+                    ))
+                    .map(|bytes| bytes.into_owned())
+                }
+            }
+            .ok_or(SerializationError::Encoding { db_name })?;
+            let key = key.to_owned();
+            // safety: we don't keep references from inside the LMDB database.
+            unsafe { iter.put_current(&key, &value_bytes)? };
+            result.cleaned_entries += 1;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn prune_orphan_documents() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("color") }))
+            .unwrap();
+        index
+            .add_documents(json!([
+                { "id": 0, "title": "hello world", "color": "blue" },
+                { "id": 1, "title": "hello zebra", "color": "red" },
+            ]))
+            .unwrap();
+
+        // A document that is no longer in the documents ids, but still in the other databases.
+        let mut wtxn = index.write_txn().unwrap();
+        let documents_ids = (0..1).collect();
+        index.put_documents_ids(&mut wtxn, &documents_ids).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let result = Vacuum::new(&mut wtxn, &index).execute().unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(result.orphan_documents, 1);
+        assert!(result.cleaned_entries > 0);
+        assert!(result.deleted_entries > 0);
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.word_docids.get(&rtxn, "hello").unwrap(), Some(documents_ids));
+        assert_eq!(index.word_docids.get(&rtxn, "zebra").unwrap(), None);
+        assert!(!index.words_fst(&rtxn).unwrap().contains("zebra"));
+        assert!(index.docid_word_positions.get(&rtxn, &(1, "zebra")).unwrap().is_none());
+        let color = index.fields_ids_map(&rtxn).unwrap().id("color").unwrap();
+        let docids = index.string_faceted_documents_ids(&rtxn, color).unwrap();
+        assert_eq!(docids.len(), 1);
+        drop(rtxn);
+
+        // There is nothing left to reclaim.
+        let mut wtxn = index.write_txn().unwrap();
+        let result = Vacuum::new(&mut wtxn, &index).execute().unwrap();
+        assert_eq!(result, VacuumResult::default());
+    }
+}
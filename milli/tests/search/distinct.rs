@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use big_s::S;
 use milli::update::Settings;
@@ -71,3 +71,39 @@ test_distinct!(distinct_string_criterion_words_attribute, tag, vec![Words, Attri
 test_distinct!(distinct_number_criterion_words_attribute, asc_desc_rank, vec![Words, Attribute]);
 test_distinct!(distinct_string_criterion_words_exactness, tag, vec![Words, Exactness]);
 test_distinct!(distinct_number_criterion_words_exactness, asc_desc_rank, vec![Words, Exactness]);
+
+#[test]
+fn group_by_tag() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.group_by("tag", 2);
+
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+
+    let mut groups = HashMap::new();
+    let expected_external_ids: Vec<_> = search::expected_order(&criteria, true, true, &[])
+        .into_iter()
+        .filter_map(|d| {
+            let count = groups.entry(d.tag.clone()).or_insert(0);
+            *count += 1;
+            if *count <= 2 {
+                Some(d.id)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let documents_ids = search::internal_to_external_ids(&index, &documents_ids);
+    assert_eq!(documents_ids, expected_external_ids);
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.group_by("title", 2);
+    assert!(search.execute().is_err());
+}
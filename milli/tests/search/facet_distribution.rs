@@ -0,0 +1,160 @@
+use big_s::S;
+use maplit::{btreemap, hashset};
+use milli::test_utils::TempIndex;
+use milli::{
+    Criterion, FacetOptions, FacetOrder, Filter, HistogramBucket, HistogramBuckets, Search,
+    SearchResult,
+};
+
+use crate::search;
+
+#[test]
+fn facet_options_limit_and_order_values() {
+    let index = search::setup_search_index_with_criteria(&[Criterion::Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let distribution = index.facets_distribution(&rtxn).facets(Some("tag")).execute_ordered();
+    let expected = vec![(S("blue"), 6), (S("green"), 5), (S("red"), 6)];
+    assert_eq!(distribution.unwrap()["tag"], expected);
+
+    let options = FacetOptions { order: FacetOrder::Count, ..Default::default() };
+    let distribution =
+        index.facets_distribution(&rtxn).facet_options("tag", options).execute_ordered();
+    let expected = vec![(S("blue"), 6), (S("red"), 6), (S("green"), 5)];
+    assert_eq!(distribution.unwrap()["tag"], expected);
+
+    let options = FacetOptions {
+        max_values: Some(1),
+        order: FacetOrder::Count,
+        include: vec![S("green"), S("purple")],
+        ..Default::default()
+    };
+    let distribution = index
+        .facets_distribution(&rtxn)
+        .facets(Some("asc_desc_rank"))
+        .facet_options("tag", options)
+        .execute_ordered()
+        .unwrap();
+    let expected = vec![(S("blue"), 6), (S("green"), 5), (S("purple"), 0)];
+    assert_eq!(distribution["tag"], expected);
+    assert_eq!(distribution.len(), 2);
+}
+
+#[test]
+fn facet_options_on_unknown_facet() {
+    let index = search::setup_search_index_with_criteria(&[Criterion::Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let result = index
+        .facets_distribution(&rtxn)
+        .facet_options("title", FacetOptions::default())
+        .execute_ordered();
+    assert!(result.is_err());
+}
+
+#[test]
+fn numeric_histogram_buckets() {
+    let index = search::setup_search_index_with_criteria(&[Criterion::Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let buckets = HistogramBuckets::FixedWidth { start: 0.0, width: 2.0, count: 3 };
+    let histogram = index.facet_histogram(&rtxn, "asc_desc_rank", buckets.clone());
+    let counts: Vec<_> = histogram.execute().unwrap().into_iter().map(|b| b.count).collect();
+    assert_eq!(counts, vec![5, 6, 6]);
+
+    // The histogram of the red documents only.
+    let mut search = Search::new(&rtxn, &index);
+    search.filter(Filter::from_str("tag = red").unwrap().unwrap());
+    let SearchResult { candidates, .. } = search.execute().unwrap();
+    let mut histogram = index.facet_histogram(&rtxn, "asc_desc_rank", buckets);
+    let histogram = histogram.candidates(candidates).execute().unwrap();
+    let expected = vec![
+        HistogramBucket { from: 0.0, to: 2.0, count: 3 },
+        HistogramBucket { from: 2.0, to: 4.0, count: 2 },
+        HistogramBucket { from: 4.0, to: 6.0, count: 1 },
+    ];
+    assert_eq!(histogram, expected);
+
+    let buckets = HistogramBuckets::Bounds(vec![1.0, 3.0, 5.0]);
+    let histogram = index.facet_histogram(&rtxn, "asc_desc_rank", buckets);
+    let counts: Vec<_> = histogram.execute().unwrap().into_iter().map(|b| b.count).collect();
+    assert_eq!(counts, vec![5, 8]);
+
+    let buckets = HistogramBuckets::Bounds(vec![3.0, 1.0]);
+    assert!(index.facet_histogram(&rtxn, "asc_desc_rank", buckets).execute().is_err());
+    let buckets = HistogramBuckets::FixedWidth { start: 0.0, width: 0.0, count: 3 };
+    assert!(index.facet_histogram(&rtxn, "asc_desc_rank", buckets).execute().is_err());
+    let buckets = HistogramBuckets::Bounds(vec![0.0, 1.0]);
+    assert!(index.facet_histogram(&rtxn, "title", buckets).execute().is_err());
+}
+
+#[test]
+fn facets_distribution_during_search() {
+    let index = search::setup_search_index_with_criteria(&[Criterion::Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.filter(Filter::from_str("asc_desc_rank < 3").unwrap().unwrap());
+    search.facets_distribution(Some(hashset! { S("tag") }));
+    let options = FacetOptions { order: FacetOrder::Count, ..Default::default() };
+    search.facet_options("asc_desc_rank", options);
+    let SearchResult { candidates, facets_distribution, .. } = search.execute().unwrap();
+
+    // The same distribution as the one computed in a second pass over the candidates.
+    let options = FacetOptions { order: FacetOrder::Count, ..Default::default() };
+    let expected = index
+        .facets_distribution(&rtxn)
+        .facets(Some("tag"))
+        .facet_options("asc_desc_rank", options)
+        .candidates(candidates)
+        .execute_ordered()
+        .unwrap();
+    let facets_distribution = facets_distribution.unwrap();
+    assert_eq!(facets_distribution, expected);
+    assert_eq!(facets_distribution.len(), 2);
+
+    let mut search = Search::new(&rtxn, &index);
+    let SearchResult { facets_distribution, .. } = search.execute().unwrap();
+    assert!(facets_distribution.is_none());
+    let SearchResult { facets_distribution, .. } =
+        search.facets_distribution(None).execute().unwrap();
+    assert_eq!(facets_distribution.unwrap().len(), 3);
+}
+
+#[test]
+fn cached_facets_distribution() {
+    let mut index = TempIndex::new();
+    index.set_facet_distribution_cache_capacity(10);
+    index
+        .update_settings(|settings| settings.set_filterable_fields(hashset! { S("color") }))
+        .unwrap();
+    index
+        .add_documents(serde_json::json!([
+            { "id": 1, "color": "blue" },
+            { "id": 2, "color": "red" },
+            { "id": 3, "color": "blue" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let expected = btreemap! { S("blue") => 2, S("red") => 1 };
+    for _ in 0..2 {
+        let distribution = index.facets_distribution(&rtxn).facets(Some("color")).execute();
+        assert_eq!(distribution.unwrap()["color"], expected);
+    }
+    // The distribution over a subset of the documents is never cached.
+    let distribution = index
+        .facets_distribution(&rtxn)
+        .facets(Some("color"))
+        .candidates((0..2).collect())
+        .execute();
+    assert_eq!(distribution.unwrap()["color"], btreemap! { S("blue") => 1, S("red") => 1 });
+    drop(rtxn);
+
+    // The cached distribution is dropped when the documents are updated.
+    index.add_documents(serde_json::json!({ "id": 4, "color": "red" })).unwrap();
+    let rtxn = index.read_txn().unwrap();
+    let distribution = index.facets_distribution(&rtxn).facets(Some("color")).execute();
+    assert_eq!(distribution.unwrap()["color"], btreemap! { S("blue") => 2, S("red") => 2 });
+}
@@ -77,3 +77,32 @@ test_filter!(
     lower_complex_filter_2,
     vec![Left(vec!["tag=red", "tag=green"]), Left(vec!["asc_desc_rank<3", "asc_desc_rank<1"])]
 );
+
+#[test]
+fn geo_distances_with_geo_radius() {
+    let index = search::setup_search_index_with_criteria(&[Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { geo_distances, .. } = search.execute().unwrap();
+    assert_eq!(geo_distances, None);
+
+    let filter =
+        Filter::from_str("_geoRadius(50.630010347667806, 3.086251829166809, 100000)").unwrap();
+    search.filter(filter.unwrap());
+    let SearchResult { documents_ids, geo_distances, .. } = search.execute().unwrap();
+    let geo_distances = geo_distances.unwrap();
+    assert_eq!(geo_distances.len(), documents_ids.len());
+
+    let dataset: Vec<search::TestDocument> = serde_json::Deserializer::from_str(search::CONTENT)
+        .into_iter()
+        .map(|r| r.unwrap())
+        .collect();
+    let external_ids = search::internal_to_external_ids(&index, &documents_ids);
+    for (docid, external_id) in documents_ids.iter().zip(external_ids) {
+        let document = dataset.iter().find(|d| d.id == external_id).unwrap();
+        let distance = geo_distances[docid];
+        assert!((distance - document.geo_rank as f64).abs() <= 1.0 + distance / 100.0);
+    }
+}
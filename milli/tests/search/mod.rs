@@ -13,6 +13,7 @@ use serde::Deserialize;
 use slice_group_by::GroupBy;
 
 mod distinct;
+mod facet_distribution;
 mod filters;
 mod query_criteria;
 mod sort;
@@ -144,7 +145,7 @@ pub fn expected_order(
                     new_groups
                         .extend(group.linear_group_by_key(|d| d.asc_desc_rank).map(Vec::from));
                 }
-                Criterion::Asc(_) | Criterion::Desc(_) | Criterion::Sort => {
+                Criterion::Asc(_) | Criterion::Desc(_) | Criterion::Sort | Criterion::Custom(_) => {
                     new_groups.push(group.clone())
                 }
             }
@@ -1,5 +1,7 @@
 use std::cmp::Reverse;
 use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use big_s::S;
 use heed::EnvOpenOptions;
@@ -7,7 +9,10 @@ use itertools::Itertools;
 use maplit::hashset;
 use milli::documents::{DocumentBatchBuilder, DocumentBatchReader};
 use milli::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
-use milli::{AscDesc, Criterion, Index, Member, Search, SearchResult};
+use milli::{
+    AscDesc, Criterion, Filter, Index, Member, QueryPattern, QueryRule, Search, SearchContext,
+    SearchQuery, SearchResult, SlowQuery,
+};
 use rand::Rng;
 use Criterion::*;
 
@@ -427,6 +432,8 @@ fn criteria_ascdesc() {
 
     let rtxn = index.read_txn().unwrap();
     let documents = index.all_documents(&rtxn).unwrap().map(|doc| doc.unwrap()).collect::<Vec<_>>();
+    let documents =
+        documents.iter().map(|(id, document)| (*id, document.obkv())).collect::<Vec<_>>();
 
     for criterion in [Asc(S("name")), Desc(S("name")), Asc(S("age")), Desc(S("age"))] {
         eprintln!("Testing with criterion: {:?}", &criterion);
@@ -465,3 +472,242 @@ fn criteria_ascdesc() {
         assert_eq!(documents_ids, expected_document_ids);
     }
 }
+
+#[test]
+fn slow_query_log() {
+    let criteria = vec![Words, Typo, Proximity];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let cloned_reports = reports.clone();
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.filter(Filter::from_str("tag = blue").unwrap().unwrap());
+    search.slow_query_log(Duration::ZERO, move |report| {
+        cloned_reports.lock().unwrap().push(report.clone())
+    });
+    let SearchResult { candidates, .. } = search.execute().unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    let SlowQuery { query, filter, filtered_candidates, criteria_timings, .. } = &reports[0];
+    assert_eq!(query.as_deref(), Some(search::TEST_QUERY));
+    assert_eq!(filter.as_deref(), Some("\"tag\" = \"blue\""));
+    assert_eq!(*filtered_candidates, Some(6));
+    assert_eq!(reports[0].candidates, candidates.len());
+    let names: Vec<_> = criteria_timings.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["words", "typo", "proximity"]);
+
+    // The fast queries are not reported.
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.slow_query_log(Duration::from_secs(3600), |_| panic!("the search is not slow"));
+    search.execute().unwrap();
+}
+
+#[test]
+fn search_query() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SearchQuery>();
+
+    let criteria = vec![Words, Typo, Proximity];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let query = SearchQuery {
+        query: Some(S(search::TEST_QUERY)),
+        filter: Some(S("tag = blue OR tag = red")),
+        limit: EXTERNAL_DOCUMENTS_IDS.len(),
+        facets_distribution: Some(vec![S("tag")]),
+        ..Default::default()
+    };
+    // the query can be serialized, e.g. to be logged or cached
+    let json = serde_json::to_string(&query).unwrap();
+    let query: SearchQuery = serde_json::from_str(&json).unwrap();
+
+    let result = std::thread::spawn(move || query).join().unwrap().execute(&index, &rtxn);
+    let SearchResult { documents_ids, facets_distribution, .. } = result.unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.filter(Filter::from_str("tag = blue OR tag = red").unwrap().unwrap());
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.facets_distribution(Some(hashset! { S("tag") }));
+    let expected = search.execute().unwrap();
+    assert_eq!(documents_ids, expected.documents_ids);
+    assert_eq!(facets_distribution, expected.facets_distribution);
+}
+
+#[test]
+fn search_many() {
+    let criteria = vec![Words, Typo, Proximity];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let queries = vec![
+        SearchQuery { query: Some(S(search::TEST_QUERY)), ..Default::default() },
+        SearchQuery { filter: Some(S("tag = green")), ..Default::default() },
+        SearchQuery { query: Some(S("hello")), offset: 1, limit: 3, ..Default::default() },
+    ];
+    let results = index.search_many(&rtxn, &queries).unwrap();
+    assert_eq!(results.len(), queries.len());
+
+    for (query, result) in queries.iter().zip(results) {
+        let expected = query.execute(&index, &rtxn).unwrap();
+        assert_eq!(result.documents_ids, expected.documents_ids);
+        assert_eq!(result.candidates, expected.candidates);
+    }
+}
+
+#[test]
+fn execute_iter() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+    let context = SearchContext::new(&rtxn, &index).unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+
+    let iter = search.execute_iter(&context).unwrap();
+    let streamed: Vec<_> = iter.collect::<milli::Result<_>>().unwrap();
+    assert_eq!(streamed, documents_ids);
+
+    // the caller can stop at the first hit, or start later
+    let mut iter = search.execute_iter(&context).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap(), documents_ids[0]);
+    search.offset(2);
+    let streamed: Vec<_> =
+        search.execute_iter(&context).unwrap().take(3).map(Result::unwrap).collect();
+    assert_eq!(streamed, documents_ids[2..5]);
+}
+
+#[test]
+fn pinned_and_boosted_documents() {
+    let index = milli::test_utils::TempIndex::new();
+    index
+        .add_documents(serde_json::json!([
+            { "id": 0, "title": "hello world" },
+            { "id": 1, "title": "hello" },
+            { "id": 2, "title": "world" },
+            { "id": 3, "title": "goodbye" },
+            { "id": 4, "title": "hello there world" },
+        ]))
+        .unwrap();
+    let rtxn = index.read_txn().unwrap();
+    let context = SearchContext::new(&rtxn, &index).unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello");
+    // The pinned documents don't have to match the query, unlike the boosted ones.
+    search.pin_documents(&["3", "unknown"]);
+    search.boost_documents(&["2", "1", "3"]);
+    let SearchResult { documents_ids, candidates, .. } = search.execute().unwrap();
+    assert_eq!(documents_ids[..2], [3, 1]);
+    assert_eq!(documents_ids[2..].iter().copied().sorted().collect::<Vec<_>>(), vec![0, 4]);
+    assert_eq!(candidates, [0, 1, 3, 4].iter().copied().collect());
+
+    let streamed: Vec<_> = search.execute_iter(&context).unwrap().map(Result::unwrap).collect();
+    assert_eq!(streamed, documents_ids);
+
+    search.offset(1).limit(2);
+    let SearchResult { documents_ids: page, .. } = search.execute().unwrap();
+    assert_eq!(page, documents_ids[1..3]);
+    let streamed: Vec<_> =
+        search.execute_iter(&context).unwrap().take(2).map(Result::unwrap).collect();
+    assert_eq!(streamed, page);
+}
+
+#[test]
+fn query_rules() {
+    let index = milli::test_utils::TempIndex::new();
+    index
+        .update_settings(|settings| settings.set_filterable_fields(hashset! { S("color") }))
+        .unwrap();
+    index
+        .add_documents(serde_json::json!([
+            { "id": 0, "title": "red shoes", "color": "red" },
+            { "id": 1, "title": "blue shoes", "color": "blue" },
+            { "id": 2, "title": "red sneakers", "color": "red" },
+            { "id": 3, "title": "sandals", "color": "red" },
+            { "id": 4, "title": "sneakers", "color": "blue" },
+        ]))
+        .unwrap();
+
+    let rule = |pattern| QueryRule {
+        pattern,
+        pin: Vec::new(),
+        hide: Vec::new(),
+        filter: None,
+        replace_query: None,
+    };
+    let rules = vec![
+        QueryRule { pin: vec![S("3")], hide: vec![S("1")], ..rule(QueryPattern::Is(S("Shoes"))) },
+        QueryRule {
+            filter: Some(S("color = red")),
+            replace_query: Some(S("sneakers")),
+            ..rule(QueryPattern::Contains(S("trainers")))
+        },
+    ];
+    index.update_settings(|settings| settings.set_query_rules(rules)).unwrap();
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("shoes");
+    let SearchResult { documents_ids, candidates, .. } = search.execute().unwrap();
+    assert_eq!(documents_ids, vec![3, 0]);
+    assert_eq!(candidates, [0, 3].iter().copied().collect());
+
+    // The rules pins come after the pins of the search.
+    search.pin_documents(&["4"]);
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert_eq!(documents_ids, vec![4, 3, 0]);
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("cheap trainers");
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert_eq!(documents_ids, vec![2]);
+
+    // The rules only apply to the queries matching their pattern.
+    let mut search = Search::new(&rtxn, &index);
+    search.query("blue shoes");
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert_eq!(documents_ids[0], 1);
+    assert!(!documents_ids.contains(&3));
+}
+
+#[test]
+fn external_ids_without_documents() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.filter(
+        Filter::from_str("_geoRadius(50.630010347667806, 3.086251829166809, 1000000)")
+            .unwrap()
+            .unwrap(),
+    );
+    search.show_external_ids(true);
+    search.show_matches_position(true);
+    let SearchResult { documents_ids, external_ids, geo_distances, matches_position, .. } =
+        search.execute().unwrap();
+    assert!(!documents_ids.is_empty());
+    assert_eq!(external_ids.unwrap(), search::internal_to_external_ids(&index, &documents_ids));
+    assert!(geo_distances.is_some() && matches_position.is_some());
+
+    // The matches positions can't be computed without the documents.
+    search.skip_documents_fetching(true);
+    assert!(search.execute().is_err());
+
+    search.show_matches_position(false);
+    let SearchResult { documents_ids: ids, external_ids, geo_distances, matches_position, .. } =
+        search.execute().unwrap();
+    assert_eq!(ids, documents_ids);
+    assert_eq!(external_ids.unwrap(), search::internal_to_external_ids(&index, &documents_ids));
+    assert!(geo_distances.is_none() && matches_position.is_none());
+}
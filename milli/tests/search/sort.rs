@@ -1,8 +1,15 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+
 use big_s::S;
-use milli::Criterion::{Attribute, Exactness, Proximity, Typo, Words};
-use milli::{AscDesc, Error, Member, Search, UserError};
+use milli::Criterion::{Attribute, Custom, Exactness, Proximity, Sort, Typo, Words};
+use milli::{
+    parse_sort_criteria, AscDesc, CriterionError, CustomCriterion, Error, Index, Member, Search,
+    UserError,
+};
+use roaring::RoaringBitmap;
 
-use crate::search::{self, EXTERNAL_DOCUMENTS_IDS};
+use crate::search::{self, TestDocument, EXTERNAL_DOCUMENTS_IDS};
 
 #[test]
 fn sort_ranking_rule_missing() {
@@ -21,3 +28,92 @@ fn sort_ranking_rule_missing() {
     let result = search.execute();
     assert!(matches!(result, Err(Error::UserError(UserError::SortRankingRuleMissing))));
 }
+
+#[test]
+fn sort_on_unsortable_field() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    // sortables: `tag` and `asc_desc_rank`
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.sort_criteria(vec![AscDesc::Desc(Member::Field(S("tags")))]);
+
+    match search.execute() {
+        Err(Error::UserError(UserError::InvalidSortableAttribute {
+            field,
+            expression,
+            suggestion,
+            ..
+        })) => {
+            assert_eq!(field, "tags");
+            assert_eq!(expression, "tags:desc");
+            assert_eq!(suggestion.as_deref(), Some("tag"));
+        }
+        Err(error) => panic!("unexpected error {}", error),
+        Ok(_) => panic!("sorting on an unsortable field must fail"),
+    }
+}
+
+#[test]
+fn sort_on_multiple_fields() {
+    let criteria = vec![Sort];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.sort_criteria(parse_sort_criteria("tag:asc,asc_desc_rank:desc").unwrap());
+    let result = search.execute().unwrap();
+
+    // The documents with the same tag are sorted by decreasing rank,
+    // the ones with the same tag and rank stay in the order of their ids.
+    let mut expected: Vec<TestDocument> = serde_json::Deserializer::from_str(search::CONTENT)
+        .into_iter()
+        .map(|document| document.unwrap())
+        .collect();
+    expected.sort_by_key(|document| (document.tag.clone(), Reverse(document.asc_desc_rank)));
+    let expected: Vec<_> = expected.into_iter().map(|document| document.id).collect();
+    let documents_ids = search::internal_to_external_ids(&index, &result.documents_ids);
+    assert_eq!(documents_ids, expected);
+}
+
+/// Ranks the documents by decreasing internal document id.
+struct ReverseDocids;
+
+impl CustomCriterion for ReverseDocids {
+    fn buckets(
+        &self,
+        _rtxn: &heed::RoTxn,
+        _index: &Index,
+        candidates: &RoaringBitmap,
+    ) -> milli::Result<Vec<RoaringBitmap>> {
+        let mut docids: Vec<_> = candidates.iter().collect();
+        docids.reverse();
+        Ok(docids.into_iter().map(|docid| std::iter::once(docid).collect()).collect())
+    }
+}
+
+#[test]
+fn custom_criterion() {
+    let criteria = vec![Custom(S("reverse"))];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    // The search fails when no custom criterion is registered for the ranking rule.
+    let result = search.execute();
+    assert!(matches!(
+        result,
+        Err(Error::UserError(UserError::CriterionError(
+            CriterionError::UnknownCustomCriterion { name }
+        ))) if name == "reverse"
+    ));
+
+    search.custom_criterion("reverse", Arc::new(ReverseDocids));
+    let result = search.execute().unwrap();
+    let expected: Vec<_> = (0..EXTERNAL_DOCUMENTS_IDS.len() as u32).rev().collect();
+    assert_eq!(result.documents_ids, expected);
+}